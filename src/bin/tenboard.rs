@@ -0,0 +1,642 @@
+//! Command line interface for scoring and generating Tenboard layouts.
+
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  process::ExitCode,
+};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use rand::prelude::*;
+use tenboard::{
+  corpus,
+  export::{self, Board},
+  keyboard::{
+    effort::Effort,
+    hands::HandsState,
+    layout::{constraints::Constraints, tenboard::TenboardUnconstrained},
+    metric::{
+      FingerAlternation,
+      FingerBalance,
+      FingerUsage,
+      HandAlternation,
+      HandBalance,
+      HandRuns,
+      HandUsage,
+      MaxFingerLoad,
+      Metric,
+      Redirects,
+      SameHandStreaks,
+      Skipgrams,
+      ThumbUsage,
+      Trigrams,
+    },
+    objective::{Objective, Preset, UnknownPreset},
+    optimize::{text_score, Annealing},
+    report::Report,
+    Keyboard,
+  },
+  render::SvgRenderer,
+};
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// Types a corpus with a layout and prints a table of metric scores.
+  Analyze(AnalyzeArgs),
+  /// Optimizes a layout for a corpus and writes it as JSON.
+  Generate(GenerateArgs),
+  /// Types a corpus with several layouts and prints their scores side by
+  /// side.
+  Compare(CompareArgs),
+  /// Converts a layout into a firmware or key remapper config, or draws it.
+  Export(ExportArgs),
+}
+
+#[derive(Args)]
+struct AnalyzeArgs {
+  /// Layout JSON file.
+  layout: PathBuf,
+  /// Text file to type.
+  corpus: PathBuf,
+  /// Metrics to print: built-in report entries and objective presets.
+  /// All built-in metrics are printed by default.
+  #[arg(short, long, value_delimiter = ',')]
+  metrics: Vec<String>,
+}
+
+#[derive(Args)]
+struct GenerateArgs {
+  /// Text file to optimize the layout for.
+  corpus: PathBuf,
+  /// File to write the layout JSON to.
+  #[arg(short, long)]
+  output: PathBuf,
+  /// File to write the JSON report of the layout to.
+  #[arg(long)]
+  report: Option<PathBuf>,
+  /// Layout JSON file to start from. A random layout is used by default.
+  #[arg(long)]
+  start: Option<PathBuf>,
+  /// Objective preset. Used by default if no weights are given.
+  #[arg(short, long, value_parser = parse_preset)]
+  preset: Option<Preset>,
+  /// Weighted metric to optimize, like `redirects=0.5`. Counting metrics
+  /// are scored per press. May be repeated.
+  #[arg(short, long, value_parser = parse_weight)]
+  weight: Vec<(String, f32)>,
+  /// Binds a char to a chord, like `e=LT` or ` =RT+RI`. May be repeated.
+  #[arg(long, value_parser = parse_pin)]
+  pin: Vec<(char, HandsState)>,
+  /// Forbids chords containing the fingers, like `LP+LR`. May be repeated.
+  #[arg(long, value_parser = parse_chord)]
+  forbid: Vec<HandsState>,
+  /// Chars that must be typed with single key chords.
+  #[arg(long, default_value = "")]
+  single_key: String,
+  /// Keeps thumbs pressed alone free of chars.
+  #[arg(long)]
+  reserve_thumbs: bool,
+  #[arg(short, long, default_value_t = 10_000)]
+  iterations: u32,
+  /// Seed for reproducible runs.
+  #[arg(short, long)]
+  seed: Option<u64>,
+}
+
+#[derive(Args)]
+struct CompareArgs {
+  /// Text file to type.
+  corpus: PathBuf,
+  /// Layout JSON files. Differences are relative to the first one.
+  #[arg(required = true, num_args = 2..)]
+  layouts: Vec<PathBuf>,
+  /// Metrics to compare: built-in report entries and objective presets.
+  /// All built-in metrics are compared by default.
+  #[arg(short, long, value_delimiter = ',')]
+  metrics: Vec<String>,
+}
+
+#[derive(Args)]
+struct ExportArgs {
+  /// Layout JSON file.
+  layout: PathBuf,
+  #[arg(short, long, value_enum)]
+  format: ExportFormat,
+  /// File to write the config to. It's printed by default.
+  #[arg(short, long)]
+  output: Option<PathBuf>,
+  /// Board JSON file with keycodes and key positions pressed by fingers.
+  /// Home row keys with V and N under thumbs are used by default.
+  #[arg(short, long)]
+  board: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+  /// QMK `keymap.c` combos.
+  Qmk,
+  /// ZMK `.keymap` combos.
+  Zmk,
+  /// Kanata config with chords.
+  Kanata,
+  /// SVG diagram of chords.
+  Svg,
+}
+
+fn main() -> ExitCode {
+  let cli = Cli::parse();
+  let result = match cli.command {
+    Command::Analyze(args) => analyze(args),
+    Command::Generate(args) => generate(args),
+    Command::Compare(args) => compare(args),
+    Command::Export(args) => export(args),
+  };
+  match result {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(e) => {
+      eprintln!("error: {e}");
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn analyze(args: AnalyzeArgs) -> Result<(), String> {
+  let layout = read_layout(&args.layout)?;
+  let text = read_corpus(&args.corpus)?;
+  let handstates = layout.try_type_text(&text).map_err(|e| e.to_string())?;
+  let report = select_metrics(&handstates, &args.metrics)?;
+  print!("{}", score_table(&report));
+  Ok(())
+}
+
+fn compare(args: CompareArgs) -> Result<(), String> {
+  let text = read_corpus(&args.corpus)?;
+  let mut columns = Vec::new();
+  for path in &args.layouts {
+    let handstates = read_layout(path)?
+      .try_type_text(&text)
+      .map_err(|e| format!("{}: {e}", path.display()))?;
+    let name = path.file_stem().map_or_else(
+      || path.display().to_string(),
+      |s| s.to_string_lossy().into(),
+    );
+    columns.push((name, select_metrics(&handstates, &args.metrics)?));
+  }
+  print!("{}", comparison_table(&columns));
+  Ok(())
+}
+
+fn export(args: ExportArgs) -> Result<(), String> {
+  let layout = read_layout(&args.layout)?;
+  let board = match &args.board {
+    Some(path) => read_board(path)?,
+    None => Board::home_row(),
+  };
+  let config = match args.format {
+    ExportFormat::Qmk => export::to_qmk(&layout, &board),
+    ExportFormat::Zmk => export::to_zmk(&layout, &board),
+    ExportFormat::Kanata => export::to_kanata(&layout, &board),
+    ExportFormat::Svg => Ok(SvgRenderer::new().render(&layout)),
+  }
+  .map_err(|e| e.to_string())?;
+  match args.output {
+    Some(path) => write_file(&path, &config),
+    None => {
+      print!("{config}");
+      Ok(())
+    }
+  }
+}
+
+fn generate(args: GenerateArgs) -> Result<(), String> {
+  let text = read_corpus(&args.corpus)?;
+  let mut constraints = Constraints::new();
+  for (ch, hs) in &args.pin {
+    constraints.pin(*ch, *hs);
+  }
+  for hs in &args.forbid {
+    constraints.forbid(*hs);
+  }
+  constraints.single_key(&args.single_key);
+  if args.reserve_thumbs {
+    constraints.reserve_thumbs();
+  }
+  let mut rng = match args.seed {
+    Some(seed) => StdRng::seed_from_u64(seed),
+    None => StdRng::from_entropy(),
+  };
+  let layout = match &args.start {
+    // optimizers only keep a starting layout within the constraints
+    Some(path) => {
+      let layout = read_layout(path)?;
+      let violations = constraints.violations(&layout);
+      if !violations.is_empty() {
+        return Err(format!(
+          "{}: chords of {violations:?} break the constraints",
+          path.display()
+        ));
+      }
+      layout
+    }
+    None => TenboardUnconstrained::new_random_with_constraints_and_rng(
+      &constraints,
+      &mut rng,
+    )
+    .map_err(|e| e.to_string())?,
+  };
+  // fail early on unknown metrics and untypable corpora
+  let objective = || new_objective(args.preset, &args.weight);
+  objective()?;
+  layout.try_type_text(&text).map_err(|e| e.to_string())?;
+
+  let mut optimizer = Annealing::new();
+  optimizer
+    .set_iterations(args.iterations)
+    .set_constraints(constraints);
+  if let Some(seed) = args.seed {
+    optimizer.set_seed(seed);
+  }
+  let layout = optimizer.optimize(
+    layout,
+    text_score(&text, || objective().expect("metrics are checked")),
+  );
+
+  let json =
+    serde_json::to_string_pretty(&layout).map_err(|e| e.to_string())?;
+  write_file(&args.output, &json)?;
+  let handstates = layout.try_type_text(&text).map_err(|e| e.to_string())?;
+  let mut report = Report::new(&handstates);
+  report.add_metric("objective", &objective()?.updated(&handstates));
+  if let Some(path) = &args.report {
+    let json =
+      serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+    write_file(path, &json)?;
+  }
+  print!("{}", score_table(&report));
+  Ok(())
+}
+
+/// Creates an objective of `preset` and weighted metrics. If neither is
+/// given, the balanced preset is used.
+fn new_objective(
+  preset: Option<Preset>,
+  weights: &[(String, f32)],
+) -> Result<Objective, String> {
+  let mut objective = match (preset, weights.is_empty()) {
+    (Some(preset), _) => Objective::from_preset(preset),
+    (None, true) => Objective::from_preset(Preset::Balanced),
+    (None, false) => Objective::new(),
+  };
+  for (name, w) in weights {
+    match name.as_str() {
+      "finger_usage" => {
+        objective.add_metric(*w, FingerUsage::new().per_press())
+      }
+      "hand_usage" => objective.add_metric(*w, HandUsage::new().per_press()),
+      "finger_alternation" => {
+        objective.add_metric(*w, FingerAlternation::new().per_press())
+      }
+      "skipgrams" => objective.add_metric(*w, Skipgrams::new().per_press()),
+      "hand_alternation" => {
+        objective.add_metric(*w, HandAlternation::new().per_press())
+      }
+      "redirects" => objective.add_metric(*w, Redirects::new().per_press()),
+      "hand_runs" => objective.add_metric(*w, HandRuns::new().per_press()),
+      "same_hand_streaks" => {
+        objective.add_metric(*w, SameHandStreaks::default().per_press())
+      }
+      "trigrams" => objective.add_metric(*w, Trigrams::new().per_press()),
+      "thumb_usage" => objective.add_metric(*w, ThumbUsage::new().per_press()),
+      "finger_balance" => objective.add_metric(*w, FingerBalance::new()),
+      "max_finger_load" => objective.add_metric(*w, MaxFingerLoad::new()),
+      "hand_balance" => objective.add_metric(*w, HandBalance::new()),
+      "effort" => objective.add_metric(*w, Effort::default().per_press()),
+      _ => return Err(format!("unknown metric '{name}'")),
+    };
+  }
+  Ok(objective)
+}
+
+fn parse_preset(s: &str) -> Result<Preset, String> {
+  s.parse().map_err(|e: UnknownPreset| e.to_string())
+}
+
+fn parse_weight(s: &str) -> Result<(String, f32), String> {
+  let (name, weight) = s
+    .split_once('=')
+    .ok_or_else(|| format!("expected NAME=WEIGHT, got '{s}'"))?;
+  let weight = weight.parse().map_err(|e| format!("bad weight: {e}"))?;
+  Ok((name.to_owned(), weight))
+}
+
+fn parse_pin(s: &str) -> Result<(char, HandsState), String> {
+  let mut chars = s.chars();
+  match (chars.next(), chars.next()) {
+    (Some(ch), Some('=')) => Ok((ch, parse_chord(chars.as_str())?)),
+    _ => Err(format!("expected CHAR=CHORD, got '{s}'")),
+  }
+}
+
+fn parse_chord(s: &str) -> Result<HandsState, String> {
+  HandsState::from_chord_name(s).map_err(|e| e.to_string())
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+  fs::write(path, contents).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn read_layout(path: &Path) -> Result<TenboardUnconstrained, String> {
+  let json =
+    fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+  serde_json::from_str(&json).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn read_board(path: &Path) -> Result<Board, String> {
+  let json =
+    fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+  serde_json::from_str(&json).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn read_corpus(path: &Path) -> Result<String, String> {
+  corpus::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Computes a report of `metrics` over `handstates`. Names of objective
+/// presets are accepted along with built-in report entries.
+fn select_metrics(
+  handstates: &[HandsState],
+  metrics: &[String],
+) -> Result<Report, String> {
+  let full = Report::new(handstates);
+  if metrics.is_empty() {
+    return Ok(full);
+  }
+  let mut report = Report::empty(full.presses);
+  for name in metrics {
+    match (full.get(name), name.parse::<Preset>()) {
+      (Some(entry), _) => report.metrics.push(entry.clone()),
+      (None, Ok(preset)) => {
+        report.add_metric(
+          name,
+          &Objective::from_preset(preset).updated(handstates),
+        );
+      }
+      (None, Err(_)) => return Err(format!("unknown metric '{name}'")),
+    }
+  }
+  Ok(report)
+}
+
+/// Returns a table with a row per metric and a column per report. Scores
+/// are followed by their difference with the first report and the best
+/// score of a row is marked with `*`, unless the metric has no direction.
+fn comparison_table(columns: &[(String, Report)]) -> String {
+  let Some((_, first)) = columns.first() else {
+    return String::new();
+  };
+  let name_width = first
+    .metrics
+    .iter()
+    .map(|m| m.name.len())
+    .max()
+    .unwrap_or(0)
+    .max("metric".len());
+  let width = columns
+    .iter()
+    .map(|(name, _)| name.len())
+    .max()
+    .unwrap_or(0)
+    .max(22);
+  let mut table = format!("{:name_width$}", "metric");
+  for (name, _) in columns {
+    table.push_str(&format!("  {name:>width$}"));
+  }
+  table.push('\n');
+  for (row, base) in first.metrics.iter().enumerate() {
+    let scores: Vec<_> = columns
+      .iter()
+      .map(|(_, report)| report.metrics[row].score)
+      .collect();
+    let best = match base.lower_is_better {
+      Some(true) => Some(scores.iter().copied().fold(f32::INFINITY, f32::min)),
+      Some(false) => {
+        Some(scores.iter().copied().fold(f32::NEG_INFINITY, f32::max))
+      }
+      None => None,
+    };
+    table.push_str(&format!("{:name_width$}", base.name));
+    for (col, &score) in scores.iter().enumerate() {
+      let diff = if col == 0 || base.score == 0.0 {
+        String::new()
+      } else {
+        format!(
+          " ({:+.1}%)",
+          (score - base.score) / base.score.abs() * 100.0
+        )
+      };
+      let mark = if Some(score) == best { "*" } else { " " };
+      let cell = format!("{score:.4}{diff}{mark}");
+      table.push_str(&format!("  {cell:>width$}"));
+    }
+    table.push('\n');
+  }
+  table
+}
+
+fn score_table(report: &Report) -> String {
+  let width = report
+    .metrics
+    .iter()
+    .map(|m| m.name.len())
+    .max()
+    .unwrap_or(0)
+    .max("metric".len());
+  let mut table = format!(
+    "{:width$}  {:>12}  {:>12}\n",
+    "metric", "score", "per press"
+  );
+  for m in &report.metrics {
+    table.push_str(&format!(
+      "{:width$}  {:>12.4}  {:>12.6}\n",
+      m.name, m.score, m.score_per_press
+    ));
+  }
+  table
+}
+
+#[cfg(test)]
+mod tests {
+  use tenboard::keyboard::{layout::tenboard::Tenboard, metric::ChordEntropy};
+
+  use super::*;
+
+  const CORPUS: &str = "the quick brown fox jumps over the lazy dog\n";
+
+  fn handstates() -> Vec<HandsState> {
+    TenboardUnconstrained::new_random_with_constraints_and_rng(
+      &Constraints::new(),
+      &mut StdRng::seed_from_u64(0),
+    )
+    .unwrap()
+    .type_chars(CORPUS.chars())
+  }
+
+  fn report(entropy: f32, redirects: f32) -> Report {
+    let mut report = Report::empty(10);
+    report
+      .add_metric("entropy", &ChordEntropy::new())
+      .add_metric("redirects", &Redirects::new())
+      .add_metric("hand_usage", &HandUsage::new());
+    report.metrics[0].score = entropy;
+    report.metrics[1].score = redirects;
+    report
+  }
+
+  #[test]
+  fn test_select_metrics() {
+    let handstates = handstates();
+    let full = select_metrics(&handstates, &[]).unwrap();
+    assert_eq!(full, Report::new(&handstates));
+    let names = ["redirects".to_owned(), "balanced".to_owned()];
+    let report = select_metrics(&handstates, &names).unwrap();
+    assert_eq!(report.presses, full.presses);
+    assert_eq!(report.metrics.len(), 2);
+    assert_eq!(report.metrics[0], *full.get("redirects").unwrap());
+    assert_eq!(report.metrics[1].name, "balanced");
+    assert_eq!(
+      select_metrics(&handstates, &["nope".to_owned()]),
+      Err("unknown metric 'nope'".to_owned())
+    );
+  }
+
+  #[test]
+  fn test_round_trip() {
+    let dir = std::env::temp_dir()
+      .join(format!("tenboard-cli-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let corpus = dir.join("corpus.txt");
+    let (layout, report, other) = (
+      dir.join("layout.json"),
+      dir.join("report.json"),
+      dir.join("other.json"),
+    );
+    fs::write(&corpus, CORPUS).unwrap();
+    let generate_args = |output: &Path, start: Option<PathBuf>| GenerateArgs {
+      corpus: corpus.clone(),
+      output: output.to_owned(),
+      report: Some(report.clone()),
+      start,
+      preset: None,
+      weight: vec![("redirects".to_owned(), 1.0)],
+      pin: vec![parse_pin("e=LT").unwrap()],
+      forbid: Vec::new(),
+      single_key: String::new(),
+      reserve_thumbs: false,
+      iterations: 50,
+      seed: Some(0),
+    };
+    let result = generate(generate_args(&layout, None))
+      .and_then(|()| generate(generate_args(&other, Some(layout.clone()))))
+      .and_then(|()| {
+        analyze(AnalyzeArgs {
+          layout: layout.clone(),
+          corpus: corpus.clone(),
+          metrics: vec!["redirects".to_owned()],
+        })
+      })
+      .and_then(|()| {
+        compare(CompareArgs {
+          corpus: corpus.clone(),
+          layouts: vec![layout.clone(), other.clone()],
+          metrics: Vec::new(),
+        })
+      })
+      .and_then(|()| Ok((read_layout(&layout)?, fs::read_to_string(&report))));
+    let unknown = analyze(AnalyzeArgs {
+      layout: layout.clone(),
+      corpus: corpus.clone(),
+      metrics: vec!["nope".to_owned()],
+    });
+    fs::remove_dir_all(&dir).unwrap();
+
+    let (layout, report) = result.unwrap();
+    assert_eq!(layout.try_type_char('e'), Ok(HandsState::left_thumb()));
+    let report: Report = serde_json::from_str(&report.unwrap()).unwrap();
+    assert!(report.get("objective").is_some());
+    assert_eq!(unknown, Err("unknown metric 'nope'".to_owned()));
+  }
+
+  #[test]
+  fn test_start_breaking_constraints() {
+    let dir = std::env::temp_dir()
+      .join(format!("tenboard-cli-start-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (corpus, start) = (dir.join("corpus.txt"), dir.join("start.json"));
+    fs::write(&corpus, CORPUS).unwrap();
+    let mut constraints = Constraints::new();
+    constraints.pin('e', HandsState::left_thumb());
+    let layout = TenboardUnconstrained::new_random_with_constraints_and_rng(
+      &constraints,
+      &mut StdRng::seed_from_u64(0),
+    )
+    .unwrap();
+    fs::write(&start, serde_json::to_string(&layout).unwrap()).unwrap();
+    let result = generate(GenerateArgs {
+      corpus: corpus.clone(),
+      output: dir.join("layout.json"),
+      report: None,
+      start: Some(start.clone()),
+      preset: None,
+      weight: Vec::new(),
+      pin: vec![parse_pin("e=RT").unwrap()],
+      forbid: Vec::new(),
+      single_key: String::new(),
+      reserve_thumbs: false,
+      iterations: 10,
+      seed: Some(0),
+    });
+    let written = dir.join("layout.json").exists();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(
+      result,
+      Err(format!(
+        "{}: chords of ['e'] break the constraints",
+        start.display()
+      ))
+    );
+    assert!(!written);
+  }
+
+  #[test]
+  fn test_comparison_table() {
+    let columns = [
+      ("first".to_owned(), report(2.0, 4.0)),
+      ("second".to_owned(), report(3.0, 2.0)),
+    ];
+    let table = comparison_table(&columns);
+    let rows: Vec<Vec<_>> = table
+      .lines()
+      .skip(1)
+      .map(|row| row.split_whitespace().collect())
+      .collect();
+    assert_eq!(rows[0], ["entropy", "2.0000", "3.0000", "(+50.0%)*"]);
+    assert_eq!(rows[1], ["redirects", "4.0000", "2.0000", "(-50.0%)*"]);
+    assert!(!rows[2].concat().contains('*'));
+    let columns = [
+      ("first".to_owned(), report(3.0, 2.0)),
+      ("second".to_owned(), report(2.0, 4.0)),
+    ];
+    let table = comparison_table(&columns);
+    let rows: Vec<_> = table.lines().collect();
+    assert!(rows[1].contains("3.0000*"));
+    assert!(rows[2].contains("2.0000*"));
+  }
+}
@@ -0,0 +1,332 @@
+//! Contains a compact binary cache of corpora converted to chords or
+//! n-gram tables, so repeated analysis sessions can skip the conversion.
+//!
+//! Every cache file stores a hash of its source: the layout and the text
+//! for typed corpora, the text for n-gram tables. Loading a cache with a
+//! different hash returns `None`, so stale caches are never used.
+//!
+//! ```no_run
+//! use tenboard::{
+//!   cache::TypedCorpus,
+//!   corpus,
+//!   keyboard::layout::tenboard::{Tenboard, TenboardUnconstrained},
+//! };
+//!
+//! let layout = TenboardUnconstrained::new_random();
+//! let text = corpus::read_to_string("corpus.txt").unwrap();
+//! let corpus = match TypedCorpus::load("corpus.cache", &layout, &text) {
+//!   Ok(Some(corpus)) => corpus,
+//!   _ => {
+//!     let corpus = TypedCorpus::new(&layout, &text).unwrap();
+//!     corpus.save("corpus.cache").unwrap();
+//!     corpus
+//!   }
+//! };
+//! println!("{} chords", corpus.handstates().len());
+//! ```
+
+use std::{
+  fs::File,
+  io::{self, BufReader, BufWriter, Read, Write},
+  path::Path,
+};
+
+pub use crate::corpus::text_hash;
+use crate::{
+  corpus::{fnv1a, NgramTable},
+  keyboard::{
+    hands::HandsState,
+    layout::tenboard::Tenboard,
+    Keyboard,
+    NoSuchChar,
+    TypingError,
+  },
+};
+
+const MAGIC: &[u8; 7] = b"TBCACHE";
+const VERSION: u8 = 1;
+const KIND_TYPED: u8 = 0;
+const KIND_NGRAMS: u8 = 1;
+
+/// Returns hash of chords of all chars of `layout` combined with
+/// `text_hash`.
+pub fn layout_hash(layout: &dyn Tenboard, text_hash: u64) -> u64 {
+  let mut hash = text_hash;
+  for ch in layout.charset().chars() {
+    let mut bytes = [0; 6];
+    bytes[..4].copy_from_slice(&u32::from(ch).to_le_bytes());
+    if let Ok(hs) = layout.try_type_char(ch) {
+      bytes[4..].copy_from_slice(&pack(&hs).to_le_bytes());
+    }
+    hash = fnv1a(hash, &bytes);
+  }
+  hash
+}
+
+fn pack(hs: &HandsState) -> u16 {
+  hs.iter()
+    .enumerate()
+    .fold(0, |bits, (i, fs)| bits | (u16::from(fs.is_pressed()) << i))
+}
+
+fn unpack(bits: u16) -> HandsState {
+  let mut hs = HandsState::default();
+  for i in 0..10 {
+    hs[i] = (bits & (1 << i) != 0).into();
+  }
+  hs
+}
+
+/// Corpus typed with a layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypedCorpus {
+  hash: u64,
+  handstates: Vec<HandsState>,
+}
+
+impl TypedCorpus {
+  /// Types `text` with `layout`.
+  pub fn new(layout: &dyn Tenboard, text: &str) -> Result<Self, TypingError> {
+    let handstates = Layout(layout).try_type_text(text)?;
+    Ok(Self {
+      hash: layout_hash(layout, text_hash(text)),
+      handstates,
+    })
+  }
+
+  pub fn handstates(&self) -> &[HandsState] {
+    &self.handstates
+  }
+
+  pub fn into_handstates(self) -> Vec<HandsState> {
+    self.handstates
+  }
+
+  /// Returns `true` if the corpus is `text` typed with `layout`.
+  pub fn is_valid_for(&self, layout: &dyn Tenboard, text: &str) -> bool {
+    self.hash == layout_hash(layout, text_hash(text))
+  }
+
+  pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+    write_header(&mut writer, KIND_TYPED, self.hash)?;
+    writer.write_all(&(self.handstates.len() as u64).to_le_bytes())?;
+    for hs in &self.handstates {
+      writer.write_all(&pack(hs).to_le_bytes())?;
+    }
+    Ok(())
+  }
+
+  pub fn read(mut reader: impl Read) -> io::Result<Self> {
+    let hash = read_header(&mut reader, KIND_TYPED)?;
+    let len = read_u64(&mut reader)?;
+    let mut handstates = Vec::new();
+    for _ in 0..len {
+      let mut bytes = [0; 2];
+      reader.read_exact(&mut bytes)?;
+      handstates.push(unpack(u16::from_le_bytes(bytes)));
+    }
+    Ok(Self { hash, handstates })
+  }
+
+  pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    self.write(&mut writer)?;
+    writer.flush()
+  }
+
+  /// Loads a corpus from `path`. Returns `None` if it isn't `text` typed
+  /// with `layout`.
+  pub fn load(
+    path: impl AsRef<Path>,
+    layout: &dyn Tenboard,
+    text: &str,
+  ) -> io::Result<Option<Self>> {
+    let corpus = Self::read(BufReader::new(File::open(path)?))?;
+    Ok(corpus.is_valid_for(layout, text).then_some(corpus))
+  }
+}
+
+/// Adapts `dyn Tenboard` to [Keyboard], which isn't object safe.
+struct Layout<'a>(&'a dyn Tenboard);
+
+impl Keyboard for Layout<'_> {
+  fn try_type_chars(
+    &self,
+    chars: impl Iterator<Item = char>,
+  ) -> Result<Vec<HandsState>, NoSuchChar> {
+    chars.map(|ch| self.0.try_type_char(ch)).collect()
+  }
+}
+
+/// N-gram table counted from a text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedNgrams {
+  hash: u64,
+  table: NgramTable,
+}
+
+impl CachedNgrams {
+  /// Counts n-grams of `text` with [NgramTable::from_text].
+  pub fn new(text: &str) -> Self {
+    Self {
+      hash: text_hash(text),
+      table: NgramTable::from_text(text),
+    }
+  }
+
+  pub fn table(&self) -> &NgramTable {
+    &self.table
+  }
+
+  pub fn into_table(self) -> NgramTable {
+    self.table
+  }
+
+  /// Returns `true` if the table was counted from `text`.
+  pub fn is_valid_for(&self, text: &str) -> bool {
+    self.hash == text_hash(text)
+  }
+
+  pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+    write_header(&mut writer, KIND_NGRAMS, self.hash)?;
+    writer.write_all(&self.table.word_len().to_le_bytes())?;
+    let chars: Vec<_> = self.table.char_frequencies().collect();
+    writer.write_all(&(chars.len() as u64).to_le_bytes())?;
+    for (ch, freq) in chars {
+      writer.write_all(&u32::from(ch).to_le_bytes())?;
+      writer.write_all(&freq.to_le_bytes())?;
+    }
+    let bigrams: Vec<_> = self.table.bigram_frequencies().collect();
+    writer.write_all(&(bigrams.len() as u64).to_le_bytes())?;
+    for ([first, second], freq) in bigrams {
+      writer.write_all(&u32::from(first).to_le_bytes())?;
+      writer.write_all(&u32::from(second).to_le_bytes())?;
+      writer.write_all(&freq.to_le_bytes())?;
+    }
+    Ok(())
+  }
+
+  pub fn read(mut reader: impl Read) -> io::Result<Self> {
+    let hash = read_header(&mut reader, KIND_NGRAMS)?;
+    let word_len = read_f32(&mut reader)?;
+    let mut chars = Vec::new();
+    for _ in 0..read_u64(&mut reader)? {
+      chars.push((read_char(&mut reader)?, read_f32(&mut reader)?));
+    }
+    let mut bigrams = Vec::new();
+    for _ in 0..read_u64(&mut reader)? {
+      let bigram = [read_char(&mut reader)?, read_char(&mut reader)?];
+      bigrams.push((bigram, read_f32(&mut reader)?));
+    }
+    Ok(Self {
+      hash,
+      table: NgramTable::from_fractions(chars, bigrams, word_len),
+    })
+  }
+
+  pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    self.write(&mut writer)?;
+    writer.flush()
+  }
+
+  /// Loads a table from `path`. Returns `None` if it wasn't counted from
+  /// `text`.
+  pub fn load(path: impl AsRef<Path>, text: &str) -> io::Result<Option<Self>> {
+    let ngrams = Self::read(BufReader::new(File::open(path)?))?;
+    Ok(ngrams.is_valid_for(text).then_some(ngrams))
+  }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_header(
+  writer: &mut impl Write,
+  kind: u8,
+  hash: u64,
+) -> io::Result<()> {
+  writer.write_all(MAGIC)?;
+  writer.write_all(&[VERSION, kind])?;
+  writer.write_all(&hash.to_le_bytes())
+}
+
+fn read_header(reader: &mut impl Read, kind: u8) -> io::Result<u64> {
+  let mut magic = [0; 7];
+  reader.read_exact(&mut magic)?;
+  if &magic != MAGIC {
+    return Err(invalid_data("not a tenboard cache file"));
+  }
+  let mut header = [0; 2];
+  reader.read_exact(&mut header)?;
+  if header[0] != VERSION {
+    return Err(invalid_data("unsupported cache version"));
+  }
+  if header[1] != kind {
+    return Err(invalid_data("unexpected cache kind"));
+  }
+  read_u64(reader)
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+  let mut bytes = [0; 8];
+  reader.read_exact(&mut bytes)?;
+  Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+  let mut bytes = [0; 4];
+  reader.read_exact(&mut bytes)?;
+  Ok(f32::from_le_bytes(bytes))
+}
+
+fn read_char(reader: &mut impl Read) -> io::Result<char> {
+  let mut bytes = [0; 4];
+  reader.read_exact(&mut bytes)?;
+  char::from_u32(u32::from_le_bytes(bytes))
+    .ok_or_else(|| invalid_data("invalid char in cache"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::keyboard::layout::tenboard::TenboardUnconstrained;
+
+  #[test]
+  fn test_typed_corpus() -> io::Result<()> {
+    let mut layout = TenboardUnconstrained::new_random();
+    let text = "Hello, world!\n";
+    let corpus = TypedCorpus::new(&layout, text).unwrap();
+    assert_eq!(corpus.handstates(), layout.type_chars(text.chars()));
+
+    let mut bytes = Vec::new();
+    corpus.write(&mut bytes)?;
+    assert_eq!(bytes.len(), 7 + 2 + 8 + 8 + 2 * 14);
+    let read = TypedCorpus::read(&bytes[..])?;
+    assert_eq!(read, corpus);
+    assert!(read.is_valid_for(&layout, text));
+    assert!(!read.is_valid_for(&layout, "Hello"));
+    layout.swap_states('a', 'b');
+    assert!(!read.is_valid_for(&layout, text));
+
+    assert!(CachedNgrams::read(&bytes[..]).is_err());
+    assert!(TypedCorpus::read(&b"garbage"[..]).is_err());
+    assert!(TypedCorpus::new(&layout, "é").is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_cached_ngrams() -> io::Result<()> {
+    let text = "the cat ate the hat";
+    let ngrams = CachedNgrams::new(text);
+    let mut bytes = Vec::new();
+    ngrams.write(&mut bytes)?;
+    let read = CachedNgrams::read(&bytes[..])?;
+    assert_eq!(read, ngrams);
+    assert!(read.is_valid_for(text));
+    assert!(!read.is_valid_for("the dog"));
+    assert_eq!(read.table().bigram_frequency(['t', 'h']), 0.2);
+    Ok(())
+  }
+}
@@ -0,0 +1,21 @@
+//! Contains items that come from `std`, or from `hashbrown` and `libm`
+//! when the crate is built without the `std` feature.
+
+#[cfg(not(feature = "std"))]
+pub(crate) use hashbrown::HashMap;
+#[cfg(feature = "std")]
+pub(crate) use std::collections::HashMap;
+
+pub(crate) fn log2(x: f32) -> f32 {
+  #[cfg(feature = "std")]
+  return x.log2();
+  #[cfg(not(feature = "std"))]
+  return libm::log2f(x);
+}
+
+pub(crate) fn round(x: f32) -> f32 {
+  #[cfg(feature = "std")]
+  return x.round();
+  #[cfg(not(feature = "std"))]
+  return libm::roundf(x);
+}
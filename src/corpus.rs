@@ -0,0 +1,1008 @@
+//! Contains utilities for reading text corpora that may not fit in memory.
+
+use std::{
+  collections::HashMap,
+  fmt::Display,
+  fs::File,
+  io::{self, BufRead, BufReader},
+  path::Path,
+};
+
+use rand::{distributions::WeightedIndex, prelude::*};
+
+use crate::keyboard::{charset::CharSet, metric::Metric, Keyboard, NoSuchChar};
+
+/// Frequencies of English letters in percents, from Peter Norvig's "English
+/// Letter Frequency Counts: Mayzner Revisited".
+#[allow(clippy::approx_constant)] // 'r' is 6.28%, not τ
+const ENGLISH_LETTERS: [(char, f32); 26] = [
+  ('e', 12.49),
+  ('t', 9.28),
+  ('a', 8.04),
+  ('o', 7.64),
+  ('i', 7.57),
+  ('n', 7.23),
+  ('s', 6.51),
+  ('r', 6.28),
+  ('h', 5.05),
+  ('l', 4.07),
+  ('d', 3.82),
+  ('c', 3.34),
+  ('u', 2.73),
+  ('m', 2.51),
+  ('f', 2.40),
+  ('p', 2.14),
+  ('g', 1.87),
+  ('w', 1.68),
+  ('y', 1.66),
+  ('b', 1.48),
+  ('v', 1.05),
+  ('k', 0.54),
+  ('x', 0.23),
+  ('j', 0.16),
+  ('q', 0.12),
+  ('z', 0.09),
+];
+
+/// Frequencies of the most common English bigrams in percents, from the
+/// same source as [ENGLISH_LETTERS].
+const ENGLISH_BIGRAMS: [(&str, f32); 50] = [
+  ("th", 3.56),
+  ("he", 3.07),
+  ("in", 2.43),
+  ("er", 2.05),
+  ("an", 1.99),
+  ("re", 1.85),
+  ("on", 1.76),
+  ("at", 1.49),
+  ("en", 1.45),
+  ("nd", 1.35),
+  ("ti", 1.34),
+  ("es", 1.34),
+  ("or", 1.28),
+  ("te", 1.20),
+  ("of", 1.17),
+  ("ed", 1.17),
+  ("is", 1.13),
+  ("it", 1.12),
+  ("al", 1.09),
+  ("ar", 1.07),
+  ("st", 1.05),
+  ("to", 1.04),
+  ("nt", 1.04),
+  ("ng", 0.95),
+  ("se", 0.93),
+  ("ha", 0.93),
+  ("as", 0.87),
+  ("ou", 0.87),
+  ("io", 0.83),
+  ("le", 0.83),
+  ("ve", 0.83),
+  ("co", 0.79),
+  ("me", 0.79),
+  ("de", 0.76),
+  ("hi", 0.76),
+  ("ri", 0.73),
+  ("ro", 0.73),
+  ("ic", 0.70),
+  ("ne", 0.69),
+  ("ea", 0.69),
+  ("ra", 0.69),
+  ("ce", 0.65),
+  ("li", 0.62),
+  ("ch", 0.60),
+  ("ll", 0.58),
+  ("be", 0.58),
+  ("ma", 0.57),
+  ("si", 0.55),
+  ("om", 0.55),
+  ("ur", 0.54),
+];
+
+/// Mean length of English words in letters.
+const ENGLISH_WORD_LEN: f32 = 4.79;
+
+/// Approximate frequencies of punctuation in Rust source code in percents.
+const RUST_SYMBOLS: [(char, f32); 24] = [
+  ('(', 12.0),
+  (')', 12.0),
+  ('.', 9.0),
+  (':', 9.0),
+  (';', 8.0),
+  (',', 8.0),
+  ('_', 8.0),
+  ('=', 4.0),
+  ('{', 4.0),
+  ('}', 4.0),
+  ('>', 3.5),
+  ('&', 3.0),
+  ('"', 3.0),
+  ('<', 2.5),
+  ('-', 2.0),
+  ('/', 2.0),
+  ('!', 1.5),
+  ('[', 1.5),
+  (']', 1.5),
+  ('\'', 1.0),
+  ('#', 1.0),
+  ('?', 1.0),
+  ('*', 0.5),
+  ('|', 0.5),
+];
+
+/// Approximate frequencies of the most common punctuation bigrams in Rust
+/// source code in percents.
+const RUST_BIGRAMS: [(&str, f32); 12] = [
+  ("::", 6.0),
+  (");", 4.0),
+  ("()", 3.0),
+  ("->", 1.5),
+  ("=>", 1.2),
+  ("\")", 1.0),
+  ("),", 1.0),
+  ("#[", 0.8),
+  ("?;", 0.5),
+  ("&&", 0.3),
+  ("||", 0.2),
+  ("![", 0.3),
+];
+
+/// Approximate frequencies of punctuation in Python source code in
+/// percents.
+const PYTHON_SYMBOLS: [(char, f32); 23] = [
+  ('(', 13.0),
+  (')', 13.0),
+  ('.', 12.0),
+  ('_', 12.0),
+  (',', 9.0),
+  ('=', 7.0),
+  (':', 6.0),
+  ('"', 4.0),
+  ('\'', 4.0),
+  ('[', 3.0),
+  (']', 3.0),
+  ('#', 2.0),
+  ('-', 1.5),
+  ('+', 1.0),
+  ('*', 1.0),
+  ('{', 0.8),
+  ('}', 0.8),
+  ('<', 0.5),
+  ('>', 0.5),
+  ('/', 0.5),
+  ('%', 0.3),
+  ('@', 0.3),
+  ('!', 0.2),
+];
+
+/// Approximate frequencies of the most common punctuation bigrams in
+/// Python source code in percents.
+const PYTHON_BIGRAMS: [(&str, f32); 9] = [
+  ("__", 4.0),
+  ("()", 3.0),
+  ("):", 2.0),
+  ("))", 1.0),
+  ("),", 1.0),
+  ("('", 1.0),
+  ("')", 1.0),
+  ("==", 0.8),
+  ("[\"", 0.5),
+];
+
+/// Approximate frequencies of punctuation in C source code in percents.
+const C_SYMBOLS: [(char, f32); 26] = [
+  ('(', 11.0),
+  (')', 11.0),
+  (';', 10.0),
+  (',', 8.0),
+  ('_', 6.0),
+  ('*', 5.0),
+  ('=', 5.0),
+  ('-', 3.0),
+  ('>', 3.0),
+  ('.', 3.0),
+  ('{', 3.0),
+  ('}', 3.0),
+  ('"', 3.0),
+  ('[', 2.0),
+  (']', 2.0),
+  ('&', 2.0),
+  ('/', 2.0),
+  ('#', 1.5),
+  ('<', 1.5),
+  ('+', 1.5),
+  ('!', 0.8),
+  ('%', 0.8),
+  ('\'', 0.7),
+  ('|', 0.5),
+  (':', 0.5),
+  ('?', 0.3),
+];
+
+/// Approximate frequencies of the most common punctuation bigrams in C
+/// source code in percents.
+const C_BIGRAMS: [(&str, f32); 10] = [
+  (");", 5.0),
+  ("->", 3.0),
+  ("()", 2.0),
+  ("++", 0.8),
+  ("==", 0.8),
+  ("/*", 0.6),
+  ("*/", 0.6),
+  ("!=", 0.5),
+  ("//", 0.5),
+  ("&&", 0.4),
+];
+
+/// Char and bigram frequencies of a language or a corpus. Frequencies are
+/// fractions: char frequencies sum up to 1 and so do bigram frequencies, so
+/// tables of any source can be compared and mixed. Char frequencies include
+/// whitespace between words, while bigrams are counted within words only.
+///
+/// A table can stand in for a corpus: [NgramTable::sample_text] generates
+/// text with the same statistics.
+///
+/// ```
+/// use rand::{rngs::StdRng, SeedableRng};
+/// use tenboard::{
+///   corpus::NgramTable,
+///   keyboard::{layout::asetniop::Asetniop, Keyboard},
+/// };
+///
+/// let text = NgramTable::english()
+///   .sample_text(1000, &mut StdRng::seed_from_u64(0));
+/// assert!(Asetniop::default().try_type_chars(text.chars()).is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct NgramTable {
+  chars: HashMap<char, f32>,
+  bigrams: HashMap<[char; 2], f32>,
+  word_len: f32,
+}
+
+impl NgramTable {
+  /// Returns frequencies of English lowercase letters and the 50 most
+  /// common bigrams.
+  pub fn english() -> Self {
+    Self::from_preset(&ENGLISH_LETTERS, &ENGLISH_BIGRAMS, ENGLISH_WORD_LEN)
+  }
+
+  /// Returns approximate frequencies of punctuation and punctuation bigrams
+  /// of Rust source code. Letters aren't included, so the table is meant
+  /// to be mixed with a language table with [NgramTable::merge] to bias
+  /// layouts toward code.
+  ///
+  /// ```
+  /// use tenboard::corpus::NgramTable;
+  ///
+  /// let english = NgramTable::english();
+  /// let rust = NgramTable::rust();
+  /// let mix = NgramTable::merge([(&english, 0.7), (&rust, 0.3)]);
+  /// assert!(mix.char_frequency(':') > mix.char_frequency('z'));
+  /// ```
+  pub fn rust() -> Self {
+    Self::from_preset(&RUST_SYMBOLS, &RUST_BIGRAMS, 5.5)
+  }
+
+  /// Same as [NgramTable::rust], but for Python source code.
+  pub fn python() -> Self {
+    Self::from_preset(&PYTHON_SYMBOLS, &PYTHON_BIGRAMS, 6.0)
+  }
+
+  /// Same as [NgramTable::rust], but for C source code.
+  pub fn c() -> Self {
+    Self::from_preset(&C_SYMBOLS, &C_BIGRAMS, 5.0)
+  }
+
+  /// Creates a table from frequencies in percents of chars within words.
+  /// Spaces are added as one per word of `word_len` chars.
+  fn from_preset(
+    chars: &[(char, f32)],
+    bigrams: &[(&str, f32)],
+    word_len: f32,
+  ) -> Self {
+    let letters: f32 = chars.iter().map(|(_, freq)| freq).sum();
+    let bigrams = bigrams.iter().map(|(bigram, freq)| {
+      let mut chars = bigram.chars();
+      ([chars.next().unwrap(), chars.next().unwrap()], *freq)
+    });
+    Self::from_frequencies(
+      chars
+        .iter()
+        .copied()
+        .chain([(' ', letters / word_len)]),
+      bigrams,
+      word_len,
+    )
+  }
+
+  /// Counts chars of `text`, whitespace included, and bigrams of its words.
+  pub fn from_text(text: &str) -> Self {
+    let mut chars = HashMap::new();
+    let mut bigrams = HashMap::new();
+    let mut prev = None;
+    for ch in text.chars() {
+      *chars.entry(ch).or_default() += 1.0;
+      if ch.is_whitespace() {
+        prev = None;
+        continue;
+      }
+      if let Some(prev) = prev {
+        *bigrams.entry([prev, ch]).or_default() += 1.0;
+      }
+      prev = Some(ch);
+    }
+    let words = text.split_whitespace().count();
+    let letters = text.chars().filter(|ch| !ch.is_whitespace()).count();
+    let word_len = letters as f32 / (words as f32).max(1.0);
+    Self::from_frequencies(chars, bigrams, word_len)
+  }
+
+  /// Creates a table from char and bigram frequencies and mean word length.
+  /// Frequencies may be given in any units, e.g. counts or percents: they
+  /// are normalized to sum up to 1.
+  pub fn from_frequencies(
+    chars: impl IntoIterator<Item = (char, f32)>,
+    bigrams: impl IntoIterator<Item = ([char; 2], f32)>,
+    word_len: f32,
+  ) -> Self {
+    Self {
+      chars: normalized(chars),
+      bigrams: normalized(bigrams),
+      word_len,
+    }
+  }
+
+  /// Same as [NgramTable::from_frequencies], but frequencies must already
+  /// sum up to 1, e.g. if they come from another table.
+  #[cfg(feature = "cache")]
+  pub(crate) fn from_fractions(
+    chars: impl IntoIterator<Item = (char, f32)>,
+    bigrams: impl IntoIterator<Item = ([char; 2], f32)>,
+    word_len: f32,
+  ) -> Self {
+    Self {
+      chars: chars.into_iter().collect(),
+      bigrams: bigrams.into_iter().collect(),
+      word_len,
+    }
+  }
+
+  /// Mixes `tables` with their weights, e.g. 0.7 of English prose and 0.3
+  /// of source code. Weights are normalized, so tables counted from corpora
+  /// of different sizes weigh as given. Frequencies of the result sum up to
+  /// 1 and its word length is the weighted mean.
+  ///
+  /// ```
+  /// use tenboard::corpus::NgramTable;
+  ///
+  /// let prose = NgramTable::from_text("the cat");
+  /// let code = NgramTable::from_text("fn x() {}");
+  /// let mix = NgramTable::merge([(&prose, 0.7), (&code, 0.3)]);
+  /// assert!((mix.char_frequency('t') - 0.7 * 2.0 / 7.0).abs() < 1e-6);
+  /// assert!((mix.char_frequency('{') - 0.3 / 9.0).abs() < 1e-6);
+  /// ```
+  pub fn merge<'a>(
+    tables: impl IntoIterator<Item = (&'a NgramTable, f32)>,
+  ) -> Self {
+    let tables: Vec<_> = tables.into_iter().collect();
+    let weights: f32 = tables.iter().map(|(_, weight)| weight).sum();
+    let mut merged = Self {
+      chars: HashMap::new(),
+      bigrams: HashMap::new(),
+      word_len: 0.0,
+    };
+    if weights <= 0.0 {
+      return merged;
+    }
+    for (table, weight) in tables {
+      let weight = weight / weights;
+      for (&ch, &freq) in &table.chars {
+        *merged.chars.entry(ch).or_default() += weight * freq;
+      }
+      for (&bigram, &freq) in &table.bigrams {
+        *merged.bigrams.entry(bigram).or_default() += weight * freq;
+      }
+      merged.word_len += weight * table.word_len;
+    }
+    merged
+  }
+
+  /// Returns relative frequency of `ch`.
+  pub fn char_frequency(&self, ch: char) -> f32 {
+    self.chars.get(&ch).copied().unwrap_or_default()
+  }
+
+  /// Returns relative frequency of `bigram`.
+  pub fn bigram_frequency(&self, bigram: [char; 2]) -> f32 {
+    self.bigrams.get(&bigram).copied().unwrap_or_default()
+  }
+
+  /// Returns chars with their relative frequencies.
+  pub fn char_frequencies(&self) -> impl Iterator<Item = (char, f32)> + '_ {
+    self.chars.iter().map(|(&ch, &freq)| (ch, freq))
+  }
+
+  /// Returns bigrams with their relative frequencies.
+  pub fn bigram_frequencies(
+    &self,
+  ) -> impl Iterator<Item = ([char; 2], f32)> + '_ {
+    self.bigrams.iter().map(|(&bigram, &freq)| (bigram, freq))
+  }
+
+  /// Returns mean length of words in chars.
+  pub fn word_len(&self) -> f32 {
+    self.word_len
+  }
+
+  /// Generates text of `len` chars. Words have random lengths with the
+  /// table's mean and are separated by spaces. Their first chars follow
+  /// frequencies of chars other than whitespace and the next ones follow
+  /// frequencies of bigrams starting with the previous char, or char
+  /// frequencies if there are no such bigrams.
+  pub fn sample_text(&self, len: usize, rng: &mut impl Rng) -> String {
+    let mut text = String::with_capacity(len);
+    let letters = self.chars.iter().filter(|(ch, _)| !ch.is_whitespace());
+    let Some(first) = CharChoice::new(letters) else {
+      return text;
+    };
+    let mut grouped: HashMap<char, Vec<(&char, &f32)>> = HashMap::new();
+    for ([prev, next], freq) in &self.bigrams {
+      grouped.entry(*prev).or_default().push((next, freq));
+    }
+    let next: HashMap<_, _> = grouped
+      .into_iter()
+      .filter_map(|(ch, freqs)| CharChoice::new(freqs).map(|c| (ch, c)))
+      .collect();
+    let end_word = 1.0 / self.word_len.max(1.0) as f64;
+    let mut prev = None;
+    for _ in 0..len {
+      let ch = match prev {
+        Some(_) if rng.gen_bool(end_word) => ' ',
+        Some(prev) => next.get(&prev).unwrap_or(&first).sample(rng),
+        None => first.sample(rng),
+      };
+      text.push(ch);
+      prev = (ch != ' ').then_some(ch);
+    }
+    text
+  }
+}
+
+/// Returns `frequencies` divided by their sum, or as they are if the sum
+/// isn't positive.
+fn normalized<K: Eq + std::hash::Hash>(
+  frequencies: impl IntoIterator<Item = (K, f32)>,
+) -> HashMap<K, f32> {
+  let frequencies: HashMap<_, _> = frequencies.into_iter().collect();
+  let sum: f32 = frequencies.values().sum();
+  if sum <= 0.0 {
+    return frequencies;
+  }
+  frequencies
+    .into_iter()
+    .map(|(key, freq)| (key, freq / sum))
+    .collect()
+}
+
+/// Weighted random choice of a char.
+struct CharChoice {
+  chars: Vec<char>,
+  index: WeightedIndex<f32>,
+}
+
+impl CharChoice {
+  /// Returns `None` if there are no chars with positive weights.
+  fn new<'a>(
+    weights: impl IntoIterator<Item = (&'a char, &'a f32)>,
+  ) -> Option<Self> {
+    let mut weights: Vec<_> = weights.into_iter().collect();
+    // a fixed order makes sampling with a seeded rng reproducible
+    weights.sort_by_key(|(ch, _)| **ch);
+    Some(Self {
+      index: WeightedIndex::new(weights.iter().map(|(_, w)| **w)).ok()?,
+      chars: weights.into_iter().map(|(ch, _)| *ch).collect(),
+    })
+  }
+
+  fn sample(&self, rng: &mut impl Rng) -> char {
+    self.chars[self.index.sample(rng)]
+  }
+}
+
+/// Number of chars typed at once by [update_metric].
+pub const DEFAULT_CHUNK_LEN: usize = 1 << 16;
+
+/// Iterator over chars of a UTF-8 stream. The stream is decoded in buffered
+/// chunks, so memory usage doesn't depend on its size.
+///
+/// Iteration stops at the first I/O or decoding error. Check
+/// [TextReader::take_error] after iterating to tell an error from the end
+/// of the stream.
+pub struct TextReader<R> {
+  reader: R,
+  /// Bytes of an incomplete char at the end of the last read chunk.
+  pending: Vec<u8>,
+  decoded: String,
+  pos: usize,
+  error: Option<io::Error>,
+}
+
+impl<R: BufRead> TextReader<R> {
+  pub fn new(reader: R) -> Self {
+    Self {
+      reader,
+      pending: Vec::new(),
+      decoded: String::new(),
+      pos: 0,
+      error: None,
+    }
+  }
+
+  /// Returns an error that stopped iteration, if any.
+  pub fn take_error(&mut self) -> Option<io::Error> {
+    self.error.take()
+  }
+
+  /// Decodes the next chunk of the stream. Returns `false` if the stream
+  /// is over.
+  fn fill(&mut self) -> bool {
+    self.decoded.clear();
+    self.pos = 0;
+    while self.decoded.is_empty() {
+      let buf = match self.reader.fill_buf() {
+        Ok(buf) => buf,
+        Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+        Err(e) => {
+          self.error = Some(e);
+          return false;
+        }
+      };
+      if buf.is_empty() {
+        if !self.pending.is_empty() {
+          self.error = Some(invalid_utf8());
+        }
+        return false;
+      }
+      self.pending.extend_from_slice(buf);
+      let len = buf.len();
+      self.reader.consume(len);
+      let valid = match std::str::from_utf8(&self.pending) {
+        Ok(s) => s.len(),
+        Err(e) => {
+          // chars before an invalid sequence are still returned
+          if e.error_len().is_some() {
+            self.error = Some(invalid_utf8());
+          }
+          e.valid_up_to()
+        }
+      };
+      let tail = self.pending.split_off(valid);
+      // the prefix was validated above
+      self.decoded =
+        String::from_utf8(std::mem::replace(&mut self.pending, tail))
+          .expect("prefix is valid UTF-8");
+      if self.error.is_some() {
+        return !self.decoded.is_empty();
+      }
+    }
+    true
+  }
+}
+
+impl<R: BufRead> Iterator for TextReader<R> {
+  type Item = char;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.pos == self.decoded.len() && (self.error.is_some() || !self.fill())
+    {
+      return None;
+    }
+    let ch = self.decoded[self.pos..].chars().next()?;
+    self.pos += ch.len_utf8();
+    Some(ch)
+  }
+}
+
+fn invalid_utf8() -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, "stream is not valid UTF-8")
+}
+
+/// Opens a text file for streaming reading. Files with `.gz` extension are
+/// decompressed on the fly if `gzip` feature is enabled.
+pub fn read_text(
+  path: impl AsRef<Path>,
+) -> io::Result<TextReader<Box<dyn BufRead>>> {
+  let path = path.as_ref();
+  let file = File::open(path)?;
+  let reader: Box<dyn BufRead> =
+    if path.extension().is_some_and(|ext| ext == "gz") {
+      gzip_reader(file)?
+    } else {
+      Box::new(BufReader::new(file))
+    };
+  Ok(TextReader::new(reader))
+}
+
+#[cfg(feature = "gzip")]
+fn gzip_reader(file: File) -> io::Result<Box<dyn BufRead>> {
+  Ok(Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(
+    file,
+  ))))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip_reader(_: File) -> io::Result<Box<dyn BufRead>> {
+  Err(io::Error::new(
+    io::ErrorKind::Unsupported,
+    "reading gzip files requires `gzip` feature",
+  ))
+}
+
+/// Reads a whole text file like [read_text] does.
+pub fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+  let mut reader = read_text(path)?;
+  let text = reader.by_ref().collect();
+  match reader.take_error() {
+    Some(e) => Err(e),
+    None => Ok(text),
+  }
+}
+
+/// Returns a hash of `text` that is the same on every platform and
+/// version, so layouts and caches can be matched with corpora they were made
+/// for. It isn't cryptographic.
+pub fn text_hash(text: &str) -> u64 {
+  fnv1a(0xcbf29ce484222325, text.as_bytes())
+}
+
+/// Continues 64-bit FNV-1a `hash` with `bytes`.
+pub(crate) fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+  for &b in bytes {
+    hash ^= b as u64;
+    hash = hash.wrapping_mul(0x100000001b3);
+  }
+  hash
+}
+
+/// Types `chars` with `keyboard` in chunks of `chunk_len` chars and updates
+/// `metric` with each chunk, so that hand states of the whole corpus are
+/// never kept in memory. Returns the number of typed chords.
+///
+/// ```no_run
+/// use tenboard::{
+///   corpus,
+///   keyboard::{layout::asetniop::Asetniop, metric::{FingerUsage, Metric}},
+/// };
+///
+/// let mut chars = corpus::read_text("corpus.txt.gz").unwrap();
+/// let mut metric = FingerUsage::new();
+/// let presses = corpus::update_metric(
+///   &Asetniop::default(),
+///   &mut metric,
+///   chars.by_ref(),
+///   corpus::DEFAULT_CHUNK_LEN,
+/// )
+/// .unwrap();
+/// assert!(chars.take_error().is_none());
+/// println!("{presses} presses, score {}", metric.score());
+/// ```
+pub fn update_metric(
+  keyboard: &impl Keyboard,
+  metric: &mut impl Metric,
+  mut chars: impl Iterator<Item = char>,
+  chunk_len: usize,
+) -> Result<u64, NoSuchChar> {
+  let mut presses = 0;
+  loop {
+    let mut chunk = chars.by_ref().take(chunk_len.max(1)).peekable();
+    if chunk.peek().is_none() {
+      return Ok(presses);
+    }
+    let handstates = keyboard.try_type_chars(chunk)?;
+    presses += handstates.len() as u64;
+    metric.update(&handstates);
+  }
+}
+
+/// Summary of a corpus with respect to a keyboard: how often every char
+/// occurs and whether the keyboard can type it. Computing it before a long
+/// scoring run shows what share of the corpus a layout can represent.
+///
+/// ```
+/// use tenboard::{corpus::Stats, keyboard::layout::asetniop::Asetniop};
+///
+/// let stats = Stats::from_text(&Asetniop::default(), "café, naïve café");
+/// assert_eq!(stats.total(), 16);
+/// assert_eq!(stats.top_untypable(1), [('é', 2)]);
+/// assert_eq!(stats.coverage(), 13.0 / 16.0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Stats {
+  /// Count of every char and how many of its occurrences are untypable.
+  chars: HashMap<char, (u64, u64)>,
+  total: u64,
+  typable: u64,
+}
+
+impl Stats {
+  /// Counts `chars` and checks which of them `keyboard` can type. Chars are
+  /// typed in runs like in [Keyboard::try_type_chars_collect_errors], so
+  /// the same char may be typable in one place and not in another.
+  pub fn new(
+    keyboard: &impl Keyboard,
+    mut chars: impl Iterator<Item = char>,
+  ) -> Self {
+    let mut stats = Self::default();
+    loop {
+      let chunk: Vec<_> = chars.by_ref().take(DEFAULT_CHUNK_LEN).collect();
+      if chunk.is_empty() {
+        return stats;
+      }
+      for &ch in &chunk {
+        stats.chars.entry(ch).or_default().0 += 1;
+      }
+      stats.total += chunk.len() as u64;
+      stats.typable += chunk.len() as u64;
+      let (_, missing) =
+        keyboard.try_type_chars_collect_errors(chunk.into_iter());
+      for (ch, count) in missing {
+        stats.chars.entry(ch).or_default().1 += count as u64;
+        stats.typable -= count as u64;
+      }
+    }
+  }
+
+  pub fn from_text(keyboard: &impl Keyboard, text: &str) -> Self {
+    Self::new(keyboard, text.chars())
+  }
+
+  /// Returns the number of chars in the corpus.
+  pub fn total(&self) -> u64 {
+    self.total
+  }
+
+  /// Returns the number of chars the keyboard can type.
+  pub fn typable(&self) -> u64 {
+    self.typable
+  }
+
+  /// Returns the number of chars the keyboard can't type.
+  pub fn untypable(&self) -> u64 {
+    self.total - self.typable
+  }
+
+  /// Returns share of chars of the corpus the keyboard can type. It's 1
+  /// for an empty corpus.
+  pub fn coverage(&self) -> f32 {
+    if self.total == 0 {
+      return 1.0;
+    }
+    self.typable as f32 / self.total as f32
+  }
+
+  /// Returns the number of occurrences of `ch`.
+  pub fn count(&self, ch: char) -> u64 {
+    self.chars.get(&ch).map_or(0, |&(count, _)| count)
+  }
+
+  /// Returns at most `n` chars the keyboard can't type with their counts,
+  /// most frequent first.
+  pub fn top_untypable(&self, n: usize) -> Vec<(char, u64)> {
+    let mut untypable: Vec<_> = self
+      .chars
+      .iter()
+      .filter(|(_, &(_, untypable))| untypable > 0)
+      .map(|(&ch, &(_, untypable))| (ch, untypable))
+      .collect();
+    untypable.sort_by_key(|&(ch, count)| (std::cmp::Reverse(count), ch));
+    untypable.truncate(n);
+    untypable
+  }
+
+  /// Returns share of every char in the corpus, most frequent first.
+  pub fn frequencies(&self) -> Vec<(char, f32)> {
+    let mut chars: Vec<_> = self
+      .chars
+      .iter()
+      .map(|(&ch, &(count, _))| (ch, count))
+      .collect();
+    chars.sort_by_key(|&(ch, count)| (std::cmp::Reverse(count), ch));
+    chars
+      .into_iter()
+      .map(|(ch, count)| (ch, count as f32 / self.total as f32))
+      .collect()
+  }
+
+  /// Returns chars of `charset` that don't occur in the corpus, e.g. chars
+  /// a layout spends chords on for nothing.
+  pub fn unused(&self, charset: &CharSet) -> Vec<char> {
+    charset
+      .chars()
+      .filter(|ch| !self.chars.contains_key(ch))
+      .collect()
+  }
+}
+
+impl Display for Stats {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(
+      f,
+      "{} chars, {} typable ({:.2}%)",
+      self.total,
+      self.typable,
+      self.coverage() * 100.0
+    )?;
+    for (ch, count) in self.top_untypable(10) {
+      writeln!(f, "  {ch:?}\t{count}")?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    keyboard::{
+      hands::HandsState,
+      layout::modifiers::{ModifierKeyboard, ModifierMode},
+      metric::FingerAlternation,
+    },
+    testing::SyntheticKeyboard,
+  };
+
+  #[test]
+  fn test_text_reader() {
+    // a tiny buffer splits multibyte chars between reads
+    let text = "añb€c😀\n";
+    let reader = BufReader::with_capacity(1, text.as_bytes());
+    assert_eq!(TextReader::new(reader).collect::<String>(), text);
+
+    let bytes: &[u8] = b"ab\xffc";
+    for capacity in [2, 4] {
+      let reader = BufReader::with_capacity(capacity, bytes);
+      let mut reader = TextReader::new(reader);
+      assert_eq!(reader.by_ref().collect::<String>(), "ab");
+      assert_eq!(
+        reader.take_error().map(|e| e.kind()),
+        Some(io::ErrorKind::InvalidData)
+      );
+    }
+  }
+
+  #[cfg(feature = "gzip")]
+  #[test]
+  fn test_read_gzip() {
+    use std::io::Write;
+
+    let path = std::env::temp_dir()
+      .join(format!("tenboard-corpus-{}.txt.gz", std::process::id()));
+    let mut encoder = flate2::write::GzEncoder::new(
+      File::create(&path).unwrap(),
+      flate2::Compression::default(),
+    );
+    encoder.write_all("hello, мир".as_bytes()).unwrap();
+    encoder.finish().unwrap();
+    let text = read_to_string(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(text.unwrap(), "hello, мир");
+  }
+
+  #[test]
+  fn test_ngram_table() {
+    let sum = |table: &NgramTable| -> [f32; 2] {
+      [
+        table.char_frequencies().map(|(_, freq)| freq).sum(),
+        table.bigram_frequencies().map(|(_, freq)| freq).sum(),
+      ]
+    };
+    let english = NgramTable::english();
+    let [chars, bigrams] = sum(&english);
+    assert!((chars - 1.0).abs() < 1e-5 && (bigrams - 1.0).abs() < 1e-5);
+    let ratio = english.char_frequency('e') / english.char_frequency('t');
+    assert!((ratio - 12.49 / 9.28).abs() < 1e-5);
+    let ratio = english.char_frequency(' ') / english.char_frequency('e');
+    assert!((ratio - 100.0 / ENGLISH_WORD_LEN / 12.49).abs() < 1e-3);
+    assert_eq!(english.bigram_frequency(['q', 'x']), 0.0);
+    for code in [NgramTable::rust(), NgramTable::python(), NgramTable::c()] {
+      let [chars, bigrams] = sum(&code);
+      assert!((chars - 1.0).abs() < 1e-5 && (bigrams - 1.0).abs() < 1e-5);
+      assert!(code.char_frequency('(') > 0.05);
+      assert!(code
+        .char_frequencies()
+        .all(|(ch, _)| ch.is_ascii_punctuation() || ch == ' '));
+      assert!(code.bigram_frequencies().all(|(bigram, _)| bigram
+        .iter()
+        .all(|&ch| code.char_frequency(ch) > 0.0)));
+    }
+
+    let table = NgramTable::from_text("aab ab\nbb");
+    assert_eq!(table.char_frequency('b'), 4.0 / 9.0);
+    assert_eq!(table.char_frequency(' '), 1.0 / 9.0);
+    assert_eq!(table.char_frequency('\n'), 1.0 / 9.0);
+    assert_eq!(table.bigram_frequency(['a', 'b']), 0.5);
+    assert_eq!(table.bigram_frequency(['b', 'a']), 0.0);
+    assert_eq!(table.word_len(), 7.0 / 3.0);
+    assert_eq!(
+      NgramTable::from_frequencies([('a', 3.0), ('b', 1.0)], [], 1.0)
+        .char_frequency('a'),
+      0.75
+    );
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let text = english.sample_text(10_000, &mut rng);
+    assert_eq!(text.chars().count(), 10_000);
+    assert!(!text.contains("  "));
+    let sampled = NgramTable::from_text(&text);
+    assert!((sampled.word_len() - english.word_len()).abs() < 0.5);
+    assert!(sampled.char_frequency('e') > sampled.char_frequency('z'));
+    let spaces = sampled.char_frequency(' ');
+    assert!((spaces - english.char_frequency(' ')).abs() < 0.02);
+    assert_eq!(
+      text,
+      english.sample_text(10_000, &mut StdRng::seed_from_u64(0))
+    );
+    assert_eq!(NgramTable::from_text("").sample_text(10, &mut rng), "");
+  }
+
+  #[test]
+  fn test_merge() {
+    let first = NgramTable::from_text("abab");
+    let second = NgramTable::from_text("ccc");
+    let merged = NgramTable::merge([(&first, 3.0), (&second, 1.0)]);
+    assert_eq!(merged.char_frequency('a'), 0.375);
+    assert_eq!(merged.char_frequency('c'), 0.25);
+    assert_eq!(merged.bigram_frequency(['a', 'b']), 0.5);
+    assert_eq!(merged.bigram_frequency(['c', 'c']), 0.25);
+    assert_eq!(merged.word_len(), 0.75 * 4.0 + 0.25 * 3.0);
+    let sum: f32 = merged.char_frequencies().map(|(_, freq)| freq).sum();
+    assert!((sum - 1.0).abs() < 1e-6);
+    assert_eq!(
+      NgramTable::merge([(&first, 0.0)]),
+      NgramTable::from_frequencies([], [], 0.0)
+    );
+  }
+
+  #[test]
+  fn test_update_metric() {
+    let kb = SyntheticKeyboard::new_one_finger("abc");
+    let text = "aabbcabcaab";
+    let mut chunked = FingerAlternation::new();
+    let presses = update_metric(&kb, &mut chunked, text.chars(), 3).unwrap();
+    let whole = FingerAlternation::new().updated(&kb.type_chars(text.chars()));
+    assert_eq!(presses, 11);
+    assert_eq!(chunked.values(), whole.values());
+    assert_eq!(
+      update_metric(&kb, &mut FingerAlternation::new(), "abX".chars(), 2),
+      Err(NoSuchChar { ch: 'X' })
+    );
+  }
+
+  #[test]
+  fn test_stats() {
+    let kb = SyntheticKeyboard::new_one_finger("abc");
+    let stats = Stats::from_text(&kb, "abxaayyz");
+    assert_eq!(stats.total(), 8);
+    assert_eq!(stats.typable(), 4);
+    assert_eq!(stats.untypable(), 4);
+    assert_eq!(stats.coverage(), 0.5);
+    assert_eq!(stats.count('a'), 3);
+    assert_eq!(stats.count('c'), 0);
+    assert_eq!(stats.top_untypable(2), [('y', 2), ('x', 1)]);
+    assert_eq!(stats.frequencies()[0], ('a', 3.0 / 8.0));
+    assert_eq!(stats.unused(&"abc".chars().collect()), ['c']);
+    assert_eq!(
+      stats.to_string(),
+      "8 chars, 4 typable (50.00%)\n  'y'\t2\n  'x'\t1\n  'z'\t1\n"
+    );
+    assert_eq!(Stats::from_text(&kb, "").coverage(), 1.0);
+
+    let mut kb =
+      ModifierKeyboard::new(kb.with_chord('A', [1, 0, 0, 0, 1, 0, 0, 0, 0, 0]));
+    kb.add_modifier(HandsState::left_thumb(), ModifierMode::Locked);
+    let stats = Stats::from_text(&kb, "AAxAyA");
+    assert_eq!(stats.typable(), 4);
+    assert_eq!(stats.top_untypable(2), [('x', 1), ('y', 1)]);
+  }
+}
@@ -0,0 +1,454 @@
+//! Contains conversion of Tenboard layouts into firmware and key remapper
+//! configs, so that generated layouts can be used on real keyboards.
+
+use std::fmt::{Display, Write};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::keyboard::layout::tenboard::Tenboard;
+
+/// ASCII chars that aren't letters or digits with their QMK keycodes, ZMK
+/// and kanata key names.
+const KEYCODES: [(char, &str, &str, &str); 35] = [
+  ('`', "KC_GRV", "GRAVE", "grv"),
+  ('-', "KC_MINS", "MINUS", "min"),
+  ('=', "KC_EQL", "EQUAL", "eql"),
+  ('[', "KC_LBRC", "LBKT", "lbrc"),
+  (']', "KC_RBRC", "RBKT", "rbrc"),
+  ('\\', "KC_BSLS", "BSLH", "bksl"),
+  (';', "KC_SCLN", "SEMI", "scln"),
+  ('\'', "KC_QUOT", "SQT", "apos"),
+  (',', "KC_COMM", "COMMA", "comm"),
+  ('.', "KC_DOT", "DOT", "dot"),
+  ('/', "KC_SLSH", "FSLH", "slsh"),
+  ('~', "KC_TILD", "TILDE", "S-grv"),
+  ('!', "KC_EXLM", "EXCL", "S-1"),
+  ('@', "KC_AT", "AT", "S-2"),
+  ('#', "KC_HASH", "HASH", "S-3"),
+  ('$', "KC_DLR", "DLLR", "S-4"),
+  ('%', "KC_PERC", "PRCNT", "S-5"),
+  ('^', "KC_CIRC", "CARET", "S-6"),
+  ('&', "KC_AMPR", "AMPS", "S-7"),
+  ('*', "KC_ASTR", "STAR", "S-8"),
+  ('(', "KC_LPRN", "LPAR", "S-9"),
+  (')', "KC_RPRN", "RPAR", "S-0"),
+  ('_', "KC_UNDS", "UNDER", "S-min"),
+  ('+', "KC_PLUS", "PLUS", "S-eql"),
+  ('{', "KC_LCBR", "LBRC", "S-lbrc"),
+  ('}', "KC_RCBR", "RBRC", "S-rbrc"),
+  ('|', "KC_PIPE", "PIPE", "S-bksl"),
+  (':', "KC_COLN", "COLON", "S-scln"),
+  ('"', "KC_DQUO", "DQT", "S-apos"),
+  ('<', "KC_LT", "LT", "S-comm"),
+  ('>', "KC_GT", "GT", "S-dot"),
+  ('?', "KC_QUES", "QMARK", "S-slsh"),
+  (' ', "KC_SPC", "SPACE", "spc"),
+  ('\t', "KC_TAB", "TAB", "tab"),
+  ('\n', "KC_ENT", "RET", "ret"),
+];
+
+/// Physical keys pressed by each finger of a board. Fingers go in
+/// [HandsState](crate::keyboard::hands::HandsState) order, from left pinky
+/// to right pinky.
+///
+/// Boards are read from JSON objects with `keycodes` and `positions` of the
+/// fingers. `key_names` are guessed from `keycodes` and `combo_timeout`
+/// defaults to 50 if they are missing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "BoardDescription"))]
+pub struct Board {
+  keycodes: [String; 10],
+  positions: [u32; 10],
+  key_names: [String; 10],
+  combo_timeout: u32,
+}
+
+impl Board {
+  /// Creates a board where fingers press keys with QMK `keycodes` at ZMK
+  /// key `positions`. Kanata key names are guessed from `keycodes`, use
+  /// [Board::set_key_names] if they're wrong.
+  pub fn new(keycodes: [&str; 10], positions: [u32; 10]) -> Self {
+    Self {
+      keycodes: keycodes.map(str::to_owned),
+      positions,
+      key_names: keycodes
+        .map(|keycode| kanata_key_name(keycode.trim_start_matches("KC_"))),
+      combo_timeout: 50,
+    }
+  }
+
+  /// Creates a board of a regular keyboard where fingers rest on the home
+  /// row and thumbs press V and N. ZMK positions are numbered from 0.
+  pub fn home_row() -> Self {
+    Self::new(
+      [
+        "KC_A", "KC_S", "KC_D", "KC_F", "KC_V", "KC_N", "KC_J", "KC_K", "KC_L",
+        "KC_SCLN",
+      ],
+      [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+    )
+  }
+
+  /// Sets names of keys in kanata, like `a` or `spc`.
+  pub fn set_key_names(&mut self, names: [&str; 10]) -> &mut Self {
+    self.key_names = names.map(str::to_owned);
+    self
+  }
+
+  /// Sets time in milliseconds within which keys of a chord must be
+  /// pressed. Defaults to 50.
+  pub fn set_combo_timeout(&mut self, timeout: u32) -> &mut Self {
+    self.combo_timeout = timeout;
+    self
+  }
+
+  pub fn keycodes(&self) -> &[String; 10] {
+    &self.keycodes
+  }
+
+  pub fn positions(&self) -> [u32; 10] {
+    self.positions
+  }
+
+  pub fn key_names(&self) -> &[String; 10] {
+    &self.key_names
+  }
+
+  pub fn combo_timeout(&self) -> u32 {
+    self.combo_timeout
+  }
+}
+
+impl Default for Board {
+  fn default() -> Self {
+    Self::home_row()
+  }
+}
+
+/// Serialized [Board] with optional settings.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct BoardDescription {
+  keycodes: [String; 10],
+  positions: [u32; 10],
+  key_names: Option<[String; 10]>,
+  combo_timeout: Option<u32>,
+}
+
+#[cfg(feature = "serde")]
+impl From<BoardDescription> for Board {
+  fn from(value: BoardDescription) -> Self {
+    let keycodes = value.keycodes.each_ref().map(String::as_str);
+    let mut board = Self::new(keycodes, value.positions);
+    if let Some(names) = &value.key_names {
+      board.set_key_names(names.each_ref().map(String::as_str));
+    }
+    if let Some(timeout) = value.combo_timeout {
+      board.set_combo_timeout(timeout);
+    }
+    board
+  }
+}
+
+/// This error means that a char of a layout has no keycode in firmware.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct NoKeycode {
+  pub ch: char,
+}
+
+impl Display for NoKeycode {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "char '{}' has no keycode", self.ch)
+  }
+}
+
+/// Returns QMK keycode that types `ch`.
+pub fn qmk_keycode(ch: char) -> Result<String, NoKeycode> {
+  match ch {
+    'a'..='z' | '0'..='9' => Ok(format!("KC_{}", ch.to_ascii_uppercase())),
+    'A'..='Z' => Ok(format!("LSFT(KC_{ch})")),
+    _ => KEYCODES
+      .iter()
+      .find(|(c, ..)| *c == ch)
+      .map(|(_, qmk, ..)| qmk.to_string())
+      .ok_or(NoKeycode { ch }),
+  }
+}
+
+/// Returns ZMK key name that types `ch`.
+pub fn zmk_keycode(ch: char) -> Result<String, NoKeycode> {
+  match ch {
+    'a'..='z' => Ok(ch.to_ascii_uppercase().to_string()),
+    'A'..='Z' => Ok(format!("LS({ch})")),
+    '0'..='9' => Ok(format!("N{ch}")),
+    _ => KEYCODES
+      .iter()
+      .find(|(c, ..)| *c == ch)
+      .map(|(_, _, zmk, _)| zmk.to_string())
+      .ok_or(NoKeycode { ch }),
+  }
+}
+
+/// Returns kanata action that types `ch`.
+pub fn kanata_keycode(ch: char) -> Result<String, NoKeycode> {
+  match ch {
+    'a'..='z' | '0'..='9' => Ok(ch.to_string()),
+    'A'..='Z' => Ok(format!("S-{}", ch.to_ascii_lowercase())),
+    _ => KEYCODES
+      .iter()
+      .find(|(c, ..)| *c == ch)
+      .map(|(.., kanata)| kanata.to_string())
+      .ok_or(NoKeycode { ch }),
+  }
+}
+
+/// Returns kanata name of a key with QMK keycode `keycode` stripped of
+/// `KC_` prefix.
+fn kanata_key_name(keycode: &str) -> String {
+  match keycode {
+    "SCLN" => "scln".to_owned(),
+    "SPC" => "spc".to_owned(),
+    "ENT" => "ret".to_owned(),
+    "LSFT" => "lsft".to_owned(),
+    "RSFT" => "rsft".to_owned(),
+    "LALT" => "lalt".to_owned(),
+    "RALT" => "ralt".to_owned(),
+    _ => keycode.to_lowercase(),
+  }
+}
+
+/// Returns chars of `layout` with their chords and indices of pressed
+/// fingers.
+fn chords(layout: &dyn Tenboard) -> Vec<(char, Vec<usize>)> {
+  layout
+    .charset()
+    .chars()
+    .filter_map(|ch| {
+      let hs = layout.try_type_char(ch).ok()?;
+      let fingers = (0..10).filter(|&i| hs[i].is_pressed()).collect();
+      Some((ch, fingers))
+    })
+    .collect()
+}
+
+/// Returns a printable name of `ch` for comments.
+fn char_name(ch: char) -> String {
+  format!("{ch:?}")
+}
+
+/// Converts `layout` into a QMK `keymap.c` block of combos. Single key
+/// chords can't be combos, so they're listed in a comment and should be
+/// put into the keymap by hand.
+///
+/// ```
+/// use tenboard::{
+///   export::{to_qmk, Board},
+///   keyboard::layout::tenboard::{Tenboard, TenboardUnconstrained},
+/// };
+///
+/// let layout = TenboardUnconstrained::new_random();
+/// let keymap = to_qmk(&layout, &Board::home_row()).unwrap();
+/// assert!(keymap.contains("combo_t key_combos[] = {"));
+/// ```
+pub fn to_qmk(
+  layout: &dyn Tenboard,
+  board: &Board,
+) -> Result<String, NoKeycode> {
+  let chords = chords(layout);
+  let mut out = String::new();
+  writeln!(out, "#define COMBO_TERM {}", board.combo_timeout).unwrap();
+  writeln!(out).unwrap();
+  writeln!(out, "// single key chords:").unwrap();
+  for (ch, fingers) in chords.iter().filter(|(_, f)| f.len() == 1) {
+    let key = &board.keycodes[fingers[0]];
+    let keycode = qmk_keycode(*ch)?;
+    writeln!(out, "//   {key} -> {keycode} {}", char_name(*ch)).unwrap();
+  }
+  writeln!(out).unwrap();
+  let combos: Vec<_> = chords.iter().filter(|(_, f)| f.len() > 1).collect();
+  for (i, (ch, fingers)) in combos.iter().enumerate() {
+    let keys: Vec<_> = fingers
+      .iter()
+      .map(|&f| board.keycodes[f].as_str())
+      .collect();
+    writeln!(
+      out,
+      "const uint16_t PROGMEM combo_{i}[] = {{{}, COMBO_END}}; // {}",
+      keys.join(", "),
+      char_name(*ch)
+    )
+    .unwrap();
+  }
+  writeln!(out).unwrap();
+  writeln!(out, "combo_t key_combos[] = {{").unwrap();
+  for (i, (ch, _)) in combos.iter().enumerate() {
+    writeln!(out, "  COMBO(combo_{i}, {}),", qmk_keycode(*ch)?).unwrap();
+  }
+  writeln!(out, "}};").unwrap();
+  Ok(out)
+}
+
+/// Converts `layout` into a ZMK `.keymap` combos section. Single key chords
+/// can't be combos, so they're listed in a comment and should be put into
+/// the keymap by hand.
+pub fn to_zmk(
+  layout: &dyn Tenboard,
+  board: &Board,
+) -> Result<String, NoKeycode> {
+  let chords = chords(layout);
+  let mut out = String::new();
+  writeln!(out, "/* single key chords:").unwrap();
+  for (ch, fingers) in chords.iter().filter(|(_, f)| f.len() == 1) {
+    let position = board.positions[fingers[0]];
+    let keycode = zmk_keycode(*ch)?;
+    writeln!(out, " *   {position} -> &kp {keycode} {}", char_name(*ch))
+      .unwrap();
+  }
+  writeln!(out, " */").unwrap();
+  writeln!(out, "combos {{").unwrap();
+  writeln!(out, "  compatible = \"zmk,combos\";").unwrap();
+  let combos = chords.iter().filter(|(_, f)| f.len() > 1);
+  for (i, (ch, fingers)) in combos.enumerate() {
+    let positions: Vec<_> = fingers
+      .iter()
+      .map(|&f| board.positions[f].to_string())
+      .collect();
+    writeln!(out, "  combo_{i} {{ // {}", char_name(*ch)).unwrap();
+    writeln!(out, "    timeout-ms = <{}>;", board.combo_timeout).unwrap();
+    writeln!(out, "    key-positions = <{}>;", positions.join(" ")).unwrap();
+    writeln!(out, "    bindings = <&kp {}>;", zmk_keycode(*ch)?).unwrap();
+    writeln!(out, "  }};").unwrap();
+  }
+  writeln!(out, "}};").unwrap();
+  Ok(out)
+}
+
+/// Converts `layout` into a kanata config with chords, so that it can be
+/// tried on a regular keyboard before flashing firmware. Single key chords
+/// are mapped in the layer, and keys of the board that type nothing are
+/// left as they are. `defchordsv2` needs `concurrent-tap-hold`, so it is
+/// enabled in `defcfg`. KMonad has no chords, so it isn't supported.
+pub fn to_kanata(
+  layout: &dyn Tenboard,
+  board: &Board,
+) -> Result<String, NoKeycode> {
+  let chords = chords(layout);
+  let mut actions = [const { None }; 10];
+  for (ch, fingers) in chords.iter().filter(|(_, f)| f.len() == 1) {
+    actions[fingers[0]] = Some(kanata_keycode(*ch)?);
+  }
+  let mut out = String::new();
+  writeln!(out, "(defcfg concurrent-tap-hold yes)").unwrap();
+  writeln!(out, "(defsrc {})", board.key_names.join(" ")).unwrap();
+  let actions: Vec<_> = actions
+    .iter()
+    .map(|action| action.as_deref().unwrap_or("_"))
+    .collect();
+  writeln!(out, "(deflayer tenboard {})", actions.join(" ")).unwrap();
+  writeln!(out, "(defchordsv2").unwrap();
+  for (ch, fingers) in chords.iter().filter(|(_, f)| f.len() > 1) {
+    let keys: Vec<_> = fingers
+      .iter()
+      .map(|&f| board.key_names[f].as_str())
+      .collect();
+    writeln!(
+      out,
+      "  ({}) {} {} all-released () ;; {}",
+      keys.join(" "),
+      kanata_keycode(*ch)?,
+      board.combo_timeout,
+      char_name(*ch)
+    )
+    .unwrap();
+  }
+  writeln!(out, ")").unwrap();
+  Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::keyboard::{
+    hands::HandsState,
+    layout::tenboard::TenboardUnconstrained,
+    TYPABLE_CHARS,
+  };
+
+  fn layout() -> TenboardUnconstrained {
+    let mut handstates = HandsState::iterate_one_two_key_all_states();
+    TYPABLE_CHARS
+      .chars()
+      .map(|ch| (ch, handstates.next().unwrap()))
+      .collect()
+  }
+
+  #[test]
+  fn test_keycodes() {
+    assert_eq!(qmk_keycode('a'), Ok("KC_A".to_owned()));
+    assert_eq!(qmk_keycode('A'), Ok("LSFT(KC_A)".to_owned()));
+    assert_eq!(qmk_keycode('7'), Ok("KC_7".to_owned()));
+    assert_eq!(zmk_keycode('7'), Ok("N7".to_owned()));
+    assert_eq!(zmk_keycode('\n'), Ok("RET".to_owned()));
+    assert_eq!(zmk_keycode('é'), Err(NoKeycode { ch: 'é' }));
+    assert_eq!(kanata_keycode('A'), Ok("S-a".to_owned()));
+    assert_eq!(kanata_keycode('?'), Ok("S-slsh".to_owned()));
+    for ch in TYPABLE_CHARS.chars() {
+      assert!(qmk_keycode(ch).is_ok() && zmk_keycode(ch).is_ok());
+      assert!(kanata_keycode(ch).is_ok());
+    }
+  }
+
+  #[test]
+  fn test_qmk() {
+    let keymap = to_qmk(&layout(), &Board::home_row()).unwrap();
+    assert!(keymap.contains("//   KC_A -> KC_A 'a'\n"));
+    assert!(keymap.contains(
+      "const uint16_t PROGMEM combo_0[] = {KC_A, KC_S, COMBO_END}; // 'i'\n"
+    ));
+    assert!(keymap.contains("  COMBO(combo_0, KC_I),\n"));
+    assert_eq!(keymap.matches("COMBO(").count(), TYPABLE_CHARS.len() - 8);
+  }
+
+  #[test]
+  fn test_zmk() {
+    let mut board = Board::home_row();
+    board.set_combo_timeout(30);
+    let keymap = to_zmk(&layout(), &board).unwrap();
+    assert!(keymap.contains(" *   0 -> &kp A 'a'\n"));
+    assert!(keymap.contains(
+      "  combo_0 { // 'i'\n    timeout-ms = <30>;\n    key-positions = <0 \
+       1>;\n    bindings = <&kp I>;\n  };\n"
+    ));
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_board_serialization() -> Result<(), serde_json::Error> {
+    let board: Board = serde_json::from_str(
+      r#"{
+        "keycodes": ["KC_Q", "KC_W", "KC_E", "KC_R", "KC_SPC", "KC_ENT",
+                     "KC_U", "KC_I", "KC_O", "KC_P"],
+        "positions": [10, 11, 12, 13, 30, 31, 16, 17, 18, 19]
+      }"#,
+    )?;
+    assert_eq!(board.positions()[4], 30);
+    assert_eq!(board.key_names()[4], "spc");
+    assert_eq!(board.combo_timeout(), 50);
+    let mut home_row = Board::home_row();
+    home_row.set_combo_timeout(30);
+    let json = serde_json::to_string(&home_row)?;
+    assert_eq!(serde_json::from_str::<Board>(&json)?, home_row);
+    Ok(())
+  }
+
+  #[test]
+  fn test_kanata() {
+    let config = to_kanata(&layout(), &Board::home_row()).unwrap();
+    assert!(config.starts_with(
+      "(defcfg concurrent-tap-hold yes)\n(defsrc a s d f v n j k l scln)\n(deflayer tenboard a b c d _ _ e f g \
+       h)\n(defchordsv2\n  (a s) i 50 all-released () ;; 'i'\n"
+    ));
+  }
+}
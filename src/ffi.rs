@@ -0,0 +1,262 @@
+//! Contains C bindings for scoring layouts from other languages. Build a
+//! shared library with
+//! `cargo rustc --release --features ffi --crate-type cdylib`.
+//!
+//! Functions that fail return a null pointer or a negative number. Use
+//! [tenboard_last_error] to get a description of the last error that
+//! happened in the calling thread.
+
+use std::{
+  cell::RefCell,
+  ffi::{c_char, c_int, CStr, CString},
+  fmt::Display,
+  ptr,
+  slice,
+};
+
+use crate::keyboard::{
+  hands::HandsState,
+  layout::tenboard::TenboardUnconstrained,
+  metric::Metric,
+  objective::{Objective, Preset},
+  report::Report,
+  Keyboard,
+};
+
+thread_local! {
+  static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(e: impl Display) {
+  let message = CString::new(e.to_string().replace('\0', " "))
+    .expect("nul bytes are replaced");
+  LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Returns description of the last error in the calling thread or null if
+/// there were no errors. The string is valid until the next failing call.
+#[no_mangle]
+pub extern "C" fn tenboard_last_error() -> *const c_char {
+  LAST_ERROR
+    .with(|last| last.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// Parses a layout from a JSON string. Returns null on error. The layout
+/// must be freed with [tenboard_layout_free].
+///
+/// # Safety
+///
+/// `json` must be a valid nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn tenboard_layout_from_json(
+  json: *const c_char,
+) -> *mut TenboardUnconstrained {
+  let json = match CStr::from_ptr(json).to_str() {
+    Ok(json) => json,
+    Err(e) => {
+      set_last_error(e);
+      return ptr::null_mut();
+    }
+  };
+  match serde_json::from_str(json) {
+    Ok(layout) => Box::into_raw(Box::new(layout)),
+    Err(e) => {
+      set_last_error(e);
+      ptr::null_mut()
+    }
+  }
+}
+
+/// Frees a layout created by [tenboard_layout_from_json].
+///
+/// # Safety
+///
+/// `layout` must be null or a pointer returned by
+/// [tenboard_layout_from_json] that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn tenboard_layout_free(
+  layout: *mut TenboardUnconstrained,
+) {
+  if !layout.is_null() {
+    drop(Box::from_raw(layout));
+  }
+}
+
+/// Reads `len` bytes of UTF-8 text and types it. `text` may be null if
+/// `len` is 0.
+unsafe fn type_text(
+  layout: *const TenboardUnconstrained,
+  text: *const u8,
+  len: usize,
+) -> Option<Vec<HandsState>> {
+  let bytes = match len {
+    0 => &[],
+    _ => slice::from_raw_parts(text, len),
+  };
+  let text = match std::str::from_utf8(bytes) {
+    Ok(text) => text,
+    Err(e) => {
+      set_last_error(e);
+      return None;
+    }
+  };
+  (*layout).try_type_text(text).map_err(set_last_error).ok()
+}
+
+/// Types `len` bytes of UTF-8 `text` and writes ten finger states, 0 or
+/// 1, per chord to `out` of `out_len` bytes. Returns the number of chords,
+/// even if `out` can't fit all of them, or -1 on error. `out` may be null
+/// to only count chords.
+///
+/// # Safety
+///
+/// `layout` must be a valid layout, `text` must point to `len` bytes or be
+/// null if `len` is 0 and `out` must be null or point to `out_len` writable
+/// bytes.
+#[no_mangle]
+pub unsafe extern "C" fn tenboard_type_text(
+  layout: *const TenboardUnconstrained,
+  text: *const u8,
+  len: usize,
+  out: *mut u8,
+  out_len: usize,
+) -> isize {
+  let Some(handstates) = type_text(layout, text, len) else {
+    return -1;
+  };
+  if !out.is_null() && out_len > 0 {
+    let out = slice::from_raw_parts_mut(out, out_len);
+    let states = handstates.iter().flat_map(|hs| hs.iter());
+    for (byte, fs) in out.iter_mut().zip(states) {
+      *byte = u32::from(*fs) as u8;
+    }
+  }
+  handstates.len() as isize
+}
+
+/// Writes score of the objective `preset`, like `"speed"`, for typing
+/// `len` bytes of UTF-8 `text` to `score`. Returns 0 on success or -1 on
+/// error.
+///
+/// # Safety
+///
+/// `layout` must be a valid layout, `text` must point to `len` bytes or be
+/// null if `len` is 0, `preset` must be a valid nul-terminated string and
+/// `score` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn tenboard_score(
+  layout: *const TenboardUnconstrained,
+  text: *const u8,
+  len: usize,
+  preset: *const c_char,
+  score: *mut f32,
+) -> c_int {
+  let preset: Preset = match CStr::from_ptr(preset).to_string_lossy().parse() {
+    Ok(preset) => preset,
+    Err(e) => {
+      set_last_error(e);
+      return -1;
+    }
+  };
+  let Some(handstates) = type_text(layout, text, len) else {
+    return -1;
+  };
+  *score = Objective::from_preset(preset).updated(&handstates).score();
+  0
+}
+
+/// Returns JSON report of typing `len` bytes of UTF-8 `text` or null on
+/// error. The string must be freed with [tenboard_string_free].
+///
+/// # Safety
+///
+/// `layout` must be a valid layout and `text` must point to `len` bytes
+/// or be null if `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn tenboard_report_json(
+  layout: *const TenboardUnconstrained,
+  text: *const u8,
+  len: usize,
+) -> *mut c_char {
+  let Some(handstates) = type_text(layout, text, len) else {
+    return ptr::null_mut();
+  };
+  let json = serde_json::to_string(&Report::new(&handstates))
+    .expect("report is serializable");
+  CString::new(json)
+    .expect("JSON has no nul bytes")
+    .into_raw()
+}
+
+/// Frees a string returned by this library.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this library that hasn't been
+/// freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn tenboard_string_free(s: *mut c_char) {
+  if !s.is_null() {
+    drop(CString::from_raw(s));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::keyboard::layout::tenboard::Tenboard;
+
+  #[test]
+  fn test_ffi() {
+    let json =
+      serde_json::to_string(&TenboardUnconstrained::new_random()).unwrap();
+    let json = CString::new(json).unwrap();
+    unsafe {
+      let layout = tenboard_layout_from_json(json.as_ptr());
+      assert!(!layout.is_null());
+
+      let text = "ab";
+      let mut out = [0; 20];
+      let chords =
+        tenboard_type_text(layout, text.as_ptr(), 2, out.as_mut_ptr(), 20);
+      assert_eq!(chords, 2);
+      let a = (*layout).try_type_char('a').unwrap();
+      assert!(out[..10]
+        .iter()
+        .zip(a.iter())
+        .all(|(b, fs)| u32::from(*b) == u32::from(*fs)));
+      assert_eq!(
+        tenboard_type_text(layout, text.as_ptr(), 2, ptr::null_mut(), 0),
+        2
+      );
+
+      assert_eq!(
+        tenboard_type_text(layout, ptr::null(), 0, ptr::null_mut(), 0),
+        0
+      );
+
+      let mut score = -1.0;
+      let preset = CString::new("comfort").unwrap();
+      let code =
+        tenboard_score(layout, text.as_ptr(), 2, preset.as_ptr(), &mut score);
+      assert_eq!(code, 0);
+      assert!(score >= 0.0);
+
+      let report = tenboard_report_json(layout, text.as_ptr(), 2);
+      assert!(CStr::from_ptr(report).to_str().unwrap().contains("presses"));
+      tenboard_string_free(report);
+
+      let bad = "é";
+      assert_eq!(
+        tenboard_type_text(layout, bad.as_ptr(), 2, ptr::null_mut(), 0),
+        -1
+      );
+      let error = CStr::from_ptr(tenboard_last_error()).to_str().unwrap();
+      assert!(error.starts_with("char 'é' was not found"));
+      tenboard_layout_free(layout);
+
+      let invalid = CString::new("{").unwrap();
+      assert!(tenboard_layout_from_json(invalid.as_ptr()).is_null());
+    }
+  }
+}
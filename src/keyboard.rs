@@ -1,10 +1,48 @@
+#[cfg(feature = "std")]
+pub mod annotation;
+#[cfg(feature = "std")]
+pub mod badge;
+#[cfg(feature = "std")]
+pub mod benchmark;
+#[cfg(feature = "std")]
+pub mod charset;
+#[cfg(feature = "std")]
+pub mod decoder;
+#[cfg(feature = "std")]
+pub mod effort;
+#[cfg(feature = "std")]
+pub mod geometry;
+#[cfg(feature = "std")]
+pub mod graph;
 pub mod hands;
+#[cfg(feature = "std")]
+pub mod incremental;
+#[cfg(feature = "std")]
 pub mod layout;
+#[cfg(feature = "std")]
+pub mod mapper;
 pub mod metric;
+#[cfg(feature = "std")]
+pub mod objective;
+#[cfg(feature = "std")]
+pub mod optimize;
+pub mod profile;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg(feature = "std")]
+pub mod sampling;
+#[cfg(feature = "std")]
+pub mod schedule;
+pub mod shift;
+pub mod tokenizer;
 
-use std::fmt::Display;
+use alloc::{string::String, vec::Vec};
+use core::fmt::Display;
 
 use hands::HandsState;
+use tokenizer::Tokenizer;
+
+use crate::compat::HashMap;
 
 pub const LOWERCASE_CHARS: &str = "abcdefghijklmnopqrstuvwxyz";
 pub const UPPERCASE_CHARS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
@@ -17,16 +55,20 @@ pub const TYPABLE_CHARS: &str = concat!(
   "~!@#$%^&*()_+{}|:\"<>?",
   " \t\n"
 );
+/// Char standing for a backspace press in typed text, e.g. in keystroke
+/// logs. Only layouts with a backspace chord can type it.
+pub const BACKSPACE: char = '\u{8}';
 
-/// Represents a generic keyboard.
-pub trait Keyboard {
+/// Represents a generic keyboard. `N` is the number of its inputs, see
+/// [HandsState].
+pub trait Keyboard<const N: usize = 10> {
   /// Returns a sequence of hand states that describe necessary finger presses
   /// for given char sequence to be typed or an error if a char can't be
   /// typed with this keyboard.
   fn try_type_chars(
     &self,
     chars: impl Iterator<Item = char>,
-  ) -> Result<Vec<HandsState>, NoSuchChar>;
+  ) -> Result<Vec<HandsState<N>>, NoSuchChar>;
 
   /// Returns a sequence of hand states that describe necessary finger presses
   /// for given char sequence to be typed.
@@ -35,9 +77,278 @@ pub trait Keyboard {
   ///
   /// Panics if any char in the sequence cannot be typed with this keyboard.
   /// To avoid panic, use [Keyboard::try_type_chars].
-  fn type_chars(&self, text: impl Iterator<Item = char>) -> Vec<HandsState> {
+  fn type_chars(&self, text: impl Iterator<Item = char>) -> Vec<HandsState<N>> {
     self.try_type_chars(text).unwrap_or_else(|e| panic!("{e}"))
   }
+
+  /// Same as [Keyboard::try_type_chars], but calls `on_progress` with the
+  /// number of consumed chars every `every` chars and once more after the
+  /// last char, so long conversions can report progress. If `every` is 0,
+  /// `on_progress` is only called after the last char.
+  fn try_type_chars_with_progress(
+    &self,
+    chars: impl Iterator<Item = char>,
+    every: usize,
+    mut on_progress: impl FnMut(usize),
+  ) -> Result<Vec<HandsState<N>>, NoSuchChar> {
+    let mut consumed = 0;
+    let chars = chars.inspect(|_| {
+      consumed += 1;
+      if every != 0 && consumed % every == 0 {
+        on_progress(consumed);
+      }
+    });
+    let handstates = self.try_type_chars(chars)?;
+    if every == 0 || consumed % every != 0 {
+      on_progress(consumed);
+    }
+    Ok(handstates)
+  }
+
+  /// Same as [Keyboard::type_chars], but reports progress like
+  /// [Keyboard::try_type_chars_with_progress].
+  ///
+  /// # Panics
+  ///
+  /// Panics if any char in the sequence cannot be typed with this keyboard.
+  fn type_chars_with_progress(
+    &self,
+    chars: impl Iterator<Item = char>,
+    every: usize,
+    on_progress: impl FnMut(usize),
+  ) -> Vec<HandsState<N>> {
+    self
+      .try_type_chars_with_progress(chars, every, on_progress)
+      .unwrap_or_else(|e| panic!("{e}"))
+  }
+
+  /// Types all chars that can be typed with this keyboard and skips the
+  /// others. Returns typed hand states and untypable chars with their
+  /// counts, most frequent first. Runs of chars between untypable ones are
+  /// typed at once, so keyboards that keep state between chars, like
+  /// locked modifiers, type them as usual.
+  fn try_type_chars_collect_errors(
+    &self,
+    chars: impl Iterator<Item = char>,
+  ) -> (Vec<HandsState<N>>, Vec<(char, usize)>) {
+    let text: String = chars.collect();
+    let mut handstates = Vec::new();
+    let mut missing: HashMap<char, usize> = HashMap::new();
+    let (mut rest, mut end) = (text.as_str(), text.len());
+    loop {
+      match self.try_type_text(&rest[..end]) {
+        Ok(hs) => {
+          handstates.extend(hs);
+          let Some(ch) = rest[end..].chars().next() else {
+            break;
+          };
+          *missing.entry(ch).or_default() += 1;
+          rest = &rest[end + ch.len_utf8()..];
+          end = rest.len();
+        }
+        // retry with the run cut before the char the keyboard failed on
+        Err(e) => end = e.byte,
+      }
+    }
+    let mut missing: Vec<_> = missing.into_iter().collect();
+    missing.sort_by_key(|&(ch, count)| (core::cmp::Reverse(count), ch));
+    (handstates, missing)
+  }
+
+  /// Types `words` with separators between them chosen by `policy`, e.g.
+  /// for corpora that are tokenized before scoring. Returns an error if a
+  /// char of a word or a separator can't be typed with this keyboard.
+  fn try_type_words<'a>(
+    &self,
+    words: impl Iterator<Item = &'a str>,
+    policy: &SeparatorPolicy,
+  ) -> Result<Vec<HandsState<N>>, NoSuchChar> {
+    let chars = words
+      .enumerate()
+      .flat_map(|(i, word)| policy.separator(i).chars().chain(word.chars()));
+    self.try_type_chars(chars)
+  }
+
+  /// Same as [Keyboard::try_type_words].
+  ///
+  /// # Panics
+  ///
+  /// Panics if any char of the words or separators cannot be typed with
+  /// this keyboard.
+  fn type_words<'a>(
+    &self,
+    words: impl Iterator<Item = &'a str>,
+    policy: &SeparatorPolicy,
+  ) -> Vec<HandsState<N>> {
+    self
+      .try_type_words(words, policy)
+      .unwrap_or_else(|e| panic!("{e}"))
+  }
+
+  /// Types tokens of `text` split by `tokenizer` with its separators
+  /// between them. Returns an error if a char of a token or a separator
+  /// can't be typed with this keyboard.
+  fn try_type_tokens(
+    &self,
+    text: &str,
+    tokenizer: &impl Tokenizer,
+  ) -> Result<Vec<HandsState<N>>, NoSuchChar> {
+    let tokens = tokenizer.tokens(text);
+    self.try_type_words(
+      tokens.iter().map(|token| token.as_ref()),
+      &tokenizer.separator_policy(),
+    )
+  }
+
+  /// Same as [Keyboard::try_type_tokens].
+  ///
+  /// # Panics
+  ///
+  /// Panics if any char of the tokens or separators cannot be typed with
+  /// this keyboard.
+  fn type_tokens(
+    &self,
+    text: &str,
+    tokenizer: &impl Tokenizer,
+  ) -> Vec<HandsState<N>> {
+    self
+      .try_type_tokens(text, tokenizer)
+      .unwrap_or_else(|e| panic!("{e}"))
+  }
+
+  /// Same as [Keyboard::try_type_chars], but the error tells where in
+  /// `text` the char that couldn't be typed is. The default implementation
+  /// takes the last consumed char for it, so keyboards that read chars
+  /// ahead of the typed ones override it.
+  fn try_type_text(
+    &self,
+    text: &str,
+  ) -> Result<Vec<HandsState<N>>, TypingError> {
+    let mut consumed: usize = 0;
+    let chars = text.chars().inspect(|_| consumed += 1);
+    self
+      .try_type_chars(chars)
+      .map_err(|e| TypingError::locate(e.ch, text, consumed.saturating_sub(1)))
+  }
+}
+
+/// Tells what [Keyboard::type_words] types between words.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum SeparatorPolicy {
+  /// Words are typed back to back.
+  None,
+  /// Space between every two words.
+  #[default]
+  Space,
+  /// Newline between every two words.
+  Newline,
+  /// Space between words, but newline after every `n` words, like lines
+  /// of a text of `n` words each. `0` means no newlines.
+  Lines(usize),
+  /// Given string between every two words.
+  Custom(String),
+}
+
+impl SeparatorPolicy {
+  /// Returns chars typed before the `index`-th word.
+  pub fn separator(&self, index: usize) -> &str {
+    match self {
+      _ if index == 0 => "",
+      SeparatorPolicy::None => "",
+      SeparatorPolicy::Space => " ",
+      SeparatorPolicy::Newline => "\n",
+      SeparatorPolicy::Lines(n) if index.is_multiple_of(*n) => "\n",
+      SeparatorPolicy::Lines(_) => " ",
+      SeparatorPolicy::Custom(separator) => separator,
+    }
+  }
+}
+
+/// Object safe counterpart of [Keyboard], implemented for every keyboard.
+/// It allows keeping keyboards of different types in one collection, e.g.
+/// `Vec<Box<dyn DynKeyboard>>`. `dyn DynKeyboard` implements [Keyboard]
+/// itself, so boxed keyboards are used like any other.
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use tenboard::keyboard::{
+///   layout::{
+///     asetniop::Asetniop,
+///     standard::Qwerty,
+///     tenboard::{Tenboard, TenboardUnconstrained},
+///   },
+///   DynKeyboard,
+///   Keyboard,
+/// };
+///
+/// let keyboards: Vec<Box<dyn DynKeyboard>> = vec![
+///   Box::new(Qwerty::new()),
+///   Box::new(Asetniop::default()),
+///   Box::new(TenboardUnconstrained::new_random()),
+/// ];
+/// for keyboard in &keyboards {
+///   assert!(!keyboard.type_chars("hello".chars()).is_empty());
+/// }
+/// # }
+/// ```
+pub trait DynKeyboard {
+  /// Same as [Keyboard::try_type_chars].
+  fn try_type_dyn(
+    &self,
+    chars: &mut dyn Iterator<Item = char>,
+  ) -> Result<Vec<HandsState>, NoSuchChar>;
+
+  /// Same as [Keyboard::try_type_text].
+  fn try_type_text_dyn(
+    &self,
+    text: &str,
+  ) -> Result<Vec<HandsState>, TypingError>;
+}
+
+impl<K: Keyboard> DynKeyboard for K {
+  fn try_type_dyn(
+    &self,
+    chars: &mut dyn Iterator<Item = char>,
+  ) -> Result<Vec<HandsState>, NoSuchChar> {
+    self.try_type_chars(chars)
+  }
+
+  fn try_type_text_dyn(
+    &self,
+    text: &str,
+  ) -> Result<Vec<HandsState>, TypingError> {
+    self.try_type_text(text)
+  }
+}
+
+impl Keyboard for dyn DynKeyboard + '_ {
+  fn try_type_chars(
+    &self,
+    mut chars: impl Iterator<Item = char>,
+  ) -> Result<Vec<HandsState>, NoSuchChar> {
+    self.try_type_dyn(&mut chars)
+  }
+
+  fn try_type_text(
+    &self,
+    text: &str,
+  ) -> Result<Vec<HandsState>, TypingError> {
+    self.try_type_text_dyn(text)
+  }
+}
+
+/// Types `chars` with `keyboard` like [Keyboard::try_type_text], but the
+/// error holds the index of the char in `chars`. Keyboards typing runs of
+/// chars with other keyboards use it to locate errors.
+#[cfg(feature = "std")]
+fn try_type_run<K: Keyboard<N>, const N: usize>(
+  keyboard: &K,
+  chars: &[char],
+) -> Result<Vec<HandsState<N>>, (NoSuchChar, usize)> {
+  let text: String = chars.iter().collect();
+  keyboard
+    .try_type_text(&text)
+    .map_err(|e| (NoSuchChar { ch: e.ch }, e.index))
 }
 
 /// This error means that a character couldn't be typed with a `Keyboard`.
@@ -47,40 +358,83 @@ pub struct NoSuchChar {
 }
 
 impl Display for NoSuchChar {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     write!(f, "char '{}' was not found in keyboard", self.ch)
   }
 }
 
-#[cfg(test)]
-mod tests {
-  use super::*;
-
-  struct TestKeyboard {}
+/// This error means that a character of a text couldn't be typed with a
+/// `Keyboard`. Besides the char, it holds its position in the text.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TypingError {
+  pub ch: char,
+  /// Index of the char among chars of the text.
+  pub index: usize,
+  /// Byte offset of the char in the text.
+  pub byte: usize,
+  /// Line number starting from 1.
+  pub line: usize,
+  /// Column number in chars starting from 1.
+  pub column: usize,
+}
 
-  impl TestKeyboard {
-    fn try_type_char(&self, ch: char) -> Result<HandsState, NoSuchChar> {
-      match ch {
-        'a' => Ok([1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into()),
-        'b' => Ok([0, 1, 0, 0, 0, 0, 0, 0, 0, 0].into()),
-        'c' => Ok([0, 0, 1, 0, 0, 0, 0, 0, 0, 0].into()),
-        _ => Err(NoSuchChar { ch }),
+impl TypingError {
+  /// Creates an error for `ch` being the `index`-th char of `text`.
+  fn locate(ch: char, text: &str, index: usize) -> Self {
+    let mut error = Self {
+      ch,
+      index,
+      byte: text.len(),
+      line: 1,
+      column: 1,
+    };
+    for (i, (byte, c)) in text.char_indices().enumerate() {
+      if i == index {
+        error.byte = byte;
+        break;
+      }
+      if c == '\n' {
+        error.line += 1;
+        error.column = 1;
+      } else {
+        error.column += 1;
       }
     }
+    error
   }
+}
 
-  impl Keyboard for TestKeyboard {
-    fn try_type_chars(
-      &self,
-      chars: impl Iterator<Item = char>,
-    ) -> Result<Vec<HandsState>, NoSuchChar> {
-      chars.map(|ch| self.try_type_char(ch)).collect()
-    }
+impl Display for TypingError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(
+      f,
+      "{} at line {} column {}",
+      NoSuchChar { ch: self.ch },
+      self.line,
+      self.column
+    )
+  }
+}
+
+impl From<TypingError> for NoSuchChar {
+  fn from(value: TypingError) -> Self {
+    NoSuchChar { ch: value.ch }
   }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  #[cfg(feature = "std")]
+  use crate::keyboard::layout::{
+    modifiers::{ModifierKeyboard, ModifierMode},
+    word_chords::WordChordKeyboard,
+  };
+  use crate::testing::SyntheticKeyboard;
 
   #[test]
   fn test_typing() {
-    let tk = TestKeyboard {};
+    let tk = SyntheticKeyboard::new_one_finger("abc");
     let text = "cabcab";
     assert_eq!(tk.type_chars(text.chars()), vec![
       [0, 0, 1, 0, 0, 0, 0, 0, 0, 0].into(),
@@ -92,17 +446,192 @@ mod tests {
     ]);
   }
 
+  #[test]
+  fn test_custom_finger_count() {
+    use metric::{FnMetric, Metric};
+
+    struct Pedals;
+
+    impl Keyboard<6> for Pedals {
+      fn try_type_chars(
+        &self,
+        chars: impl Iterator<Item = char>,
+      ) -> Result<Vec<HandsState<6>>, NoSuchChar> {
+        chars
+          .map(|ch| match ch {
+            'a' => Ok([1, 0, 0, 0, 0, 0].into()),
+            'b' => Ok([1, 0, 0, 1, 0, 0].into()),
+            _ => Err(NoSuchChar { ch }),
+          })
+          .collect()
+      }
+    }
+
+    let handstates = Pedals.type_chars("aab".chars());
+    assert_eq!(handstates[2], [1, 0, 0, 1, 0, 0].into());
+    assert_eq!(Pedals.try_type_text("ac").unwrap_err().column, 2);
+    let both_feet = FnMetric::new(
+      0,
+      |n: &mut u32, hs: &HandsState<6>| {
+        *n +=
+          hs.hand_iter()
+            .all(|foot| foot.iter().any(|fs| fs.is_pressed())) as u32
+      },
+      |n: &u32| *n as f32,
+    );
+    assert_eq!(
+      both_feet.per_press().updated(&handstates).score(),
+      1.0 / 3.0
+    );
+  }
+
   #[test]
   fn test_char_not_found() {
-    let tk = TestKeyboard {};
+    let tk = SyntheticKeyboard::new_one_finger("abc");
     let text = "abcX";
     assert_eq!(tk.try_type_chars(text.chars()), Err(NoSuchChar { ch: 'X' }));
   }
 
   #[test]
-  #[should_panic(expected = "char X was not found in keyboard")]
+  fn test_dyn_keyboard() {
+    let keyboards: Vec<Box<dyn DynKeyboard>> = vec![
+      Box::new(SyntheticKeyboard::new_one_finger("abc")),
+      Box::new(SyntheticKeyboard::new_one_finger("cba")),
+    ];
+    assert_ne!(
+      keyboards[0].type_chars("ab".chars()),
+      keyboards[1].type_chars("ab".chars())
+    );
+    assert_eq!(keyboards[1].try_type_text("ab\nX").unwrap_err().ch, '\n');
+  }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn test_dyn_keyboard_read_ahead() {
+    // keyboards reading ahead locate errors through `dyn` as well
+    let keyboard: Box<dyn DynKeyboard> =
+      Box::new(WordChordKeyboard::new(SyntheticKeyboard::new_one_finger("a")));
+    assert_eq!(keyboard.try_type_text("aXa").unwrap_err().index, 1);
+  }
+
+  #[test]
+  fn test_type_words() {
+    let tk = SyntheticKeyboard::new_one_finger("abc \n-");
+    let words = ["ab", "c", "a", "b"];
+    let typed = |policy| tk.type_words(words.into_iter(), &policy);
+    assert_eq!(typed(SeparatorPolicy::None), tk.type_chars("abcab".chars()));
+    assert_eq!(
+      typed(SeparatorPolicy::default()),
+      tk.type_chars("ab c a b".chars())
+    );
+    assert_eq!(
+      typed(SeparatorPolicy::Newline),
+      tk.type_chars("ab\nc\na\nb".chars())
+    );
+    assert_eq!(
+      typed(SeparatorPolicy::Lines(2)),
+      tk.type_chars("ab c\na b".chars())
+    );
+    assert_eq!(
+      typed(SeparatorPolicy::Custom("--".to_owned())),
+      tk.type_chars("ab--c--a--b".chars())
+    );
+    assert_eq!(
+      tk.try_type_words(words.into_iter(), &SeparatorPolicy::Lines(0)),
+      Ok(tk.type_chars("ab c a b".chars()))
+    );
+    let policy = SeparatorPolicy::Custom(", ".to_owned());
+    assert_eq!(
+      tk.try_type_words(words.into_iter(), &policy),
+      Err(NoSuchChar { ch: ',' })
+    );
+  }
+
+  #[test]
+  fn test_progress() {
+    let tk = SyntheticKeyboard::new_one_finger("abc");
+    let mut progress = Vec::new();
+    let handstates =
+      tk.type_chars_with_progress("abcabca".chars(), 3, |n| progress.push(n));
+    assert_eq!(handstates, tk.type_chars("abcabca".chars()));
+    assert_eq!(progress, [3, 6, 7]);
+
+    progress.clear();
+    tk.type_chars_with_progress("abcabc".chars(), 3, |n| progress.push(n));
+    assert_eq!(progress, [3, 6]);
+
+    progress.clear();
+    tk.type_chars_with_progress("abc".chars(), 0, |n| progress.push(n));
+    assert_eq!(progress, [3]);
+
+    progress.clear();
+    let result =
+      tk.try_type_chars_with_progress("abXc".chars(), 1, |n| progress.push(n));
+    assert_eq!(result, Err(NoSuchChar { ch: 'X' }));
+    assert_eq!(progress, [1, 2, 3]);
+  }
+
+  #[test]
+  fn test_collect_errors() {
+    let tk = SyntheticKeyboard::new_one_finger("abc");
+    let (handstates, missing) =
+      tk.try_type_chars_collect_errors("aXbYcX".chars());
+    assert_eq!(handstates, tk.type_chars("abc".chars()));
+    assert_eq!(missing, vec![('X', 2), ('Y', 1)]);
+  }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn test_collect_errors_with_modifiers() {
+    let tk = SyntheticKeyboard::new_one_finger("abc");
+    let mut mk = ModifierKeyboard::new(
+      tk.with_chord('A', [1, 0, 0, 0, 1, 0, 0, 0, 0, 0])
+        .with_chord('B', [0, 1, 0, 0, 1, 0, 0, 0, 0, 0]),
+    );
+    mk.add_modifier(HandsState::left_thumb(), ModifierMode::Locked);
+    let (handstates, missing) =
+      mk.try_type_chars_collect_errors("ABXAB".chars());
+    assert_eq!(handstates.len(), 6);
+    assert_eq!(handstates[..3], mk.type_chars("AB".chars()));
+    assert_eq!(missing, vec![('X', 1)]);
+  }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn test_collect_errors_reading_ahead() {
+    // 'h' is typed with the word chord only, so the error is at its second
+    // occurrence
+    let mut kb =
+      WordChordKeyboard::new(SyntheticKeyboard::new_one_finger("tea "));
+    kb.add_word("the", HandsState::left_thumb());
+    let (handstates, missing) =
+      kb.try_type_chars_collect_errors("the hat".chars());
+    assert_eq!(handstates, kb.type_chars("the at".chars()));
+    assert_eq!(missing, vec![('h', 1)]);
+  }
+
+  #[test]
+  fn test_typing_error_position() {
+    let tk = SyntheticKeyboard::new_one_finger("abc\n");
+    let error = tk.try_type_text("ab\ncaé\nX").unwrap_err();
+    assert_eq!(error, TypingError {
+      ch: 'é',
+      index: 5,
+      byte: 5,
+      line: 2,
+      column: 3,
+    });
+    assert_eq!(
+      error.to_string(),
+      "char 'é' was not found in keyboard at line 2 column 3"
+    );
+    assert_eq!(tk.try_type_text("abc").unwrap().len(), 3);
+  }
+
+  #[test]
+  #[should_panic(expected = "char 'X' was not found in keyboard")]
   fn test_char_not_found_panic() {
-    let tk = TestKeyboard {};
+    let tk = SyntheticKeyboard::new_one_finger("abc");
     let text = "abcX";
     tk.type_chars(text.chars());
   }
@@ -0,0 +1,351 @@
+//! Contains per-chord metadata of layouts, e.g. for training tools or for
+//! chords a particular user finds hard.
+
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+  effort::{EffortModel, FingerEffort},
+  hands::HandsState,
+  shift::{Shift, ShiftPolicy},
+  Keyboard,
+  NoSuchChar,
+  TypingError,
+};
+
+/// Metadata of a chord. All fields are optional.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChordMeta {
+  /// How hard the chord is to press, used as its cost by [ChordCost].
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, skip_serializing_if = "Option::is_none")
+  )]
+  pub difficulty: Option<f32>,
+  /// Whether the chord is learned. `None` if it's unknown.
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, skip_serializing_if = "Option::is_none")
+  )]
+  pub learned: Option<bool>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, skip_serializing_if = "Option::is_none")
+  )]
+  pub comment: Option<String>,
+}
+
+/// Metadata of chords of a layout. It's serialized as a list of chords with
+/// their metadata.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(from = "Vec<ChordEntry>", into = "Vec<ChordEntry>")
+)]
+pub struct ChordAnnotations {
+  chords: HashMap<HandsState, ChordMeta>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct ChordEntry {
+  chord: HandsState,
+  #[serde(flatten)]
+  meta: ChordMeta,
+}
+
+impl ChordAnnotations {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn get(&self, chord: &HandsState) -> Option<&ChordMeta> {
+    self.chords.get(chord)
+  }
+
+  /// Returns metadata of `chord`, adding empty metadata if it has none.
+  pub fn entry(&mut self, chord: HandsState) -> &mut ChordMeta {
+    self.chords.entry(chord).or_default()
+  }
+
+  pub fn set_difficulty(
+    &mut self,
+    chord: HandsState,
+    difficulty: f32,
+  ) -> &mut Self {
+    self.entry(chord).difficulty = Some(difficulty);
+    self
+  }
+
+  pub fn set_learned(&mut self, chord: HandsState, learned: bool) -> &mut Self {
+    self.entry(chord).learned = Some(learned);
+    self
+  }
+
+  pub fn set_comment(&mut self, chord: HandsState, comment: &str) -> &mut Self {
+    self.entry(chord).comment = Some(comment.to_owned());
+    self
+  }
+
+  /// Removes metadata of `chord` and returns it.
+  pub fn remove(&mut self, chord: &HandsState) -> Option<ChordMeta> {
+    self.chords.remove(chord)
+  }
+
+  /// Returns annotated chords with their metadata in a stable order.
+  pub fn iter(&self) -> impl Iterator<Item = (&HandsState, &ChordMeta)> {
+    let mut chords: Vec<_> = self.chords.iter().collect();
+    chords.sort_unstable_by_key(|(hs, _)| hs.map(u32::from));
+    chords.into_iter()
+  }
+
+  /// Returns chords marked as not learned in a stable order.
+  pub fn unlearned(&self) -> impl Iterator<Item = &HandsState> {
+    self
+      .iter()
+      .filter(|(_, meta)| meta.learned == Some(false))
+      .map(|(hs, _)| hs)
+  }
+
+  pub fn len(&self) -> usize {
+    self.chords.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.chords.is_empty()
+  }
+}
+
+#[cfg(feature = "serde")]
+impl From<Vec<ChordEntry>> for ChordAnnotations {
+  fn from(value: Vec<ChordEntry>) -> Self {
+    Self {
+      chords: value.into_iter().map(|e| (e.chord, e.meta)).collect(),
+    }
+  }
+}
+
+#[cfg(feature = "serde")]
+impl From<ChordAnnotations> for Vec<ChordEntry> {
+  fn from(value: ChordAnnotations) -> Self {
+    value
+      .iter()
+      .map(|(&chord, meta)| ChordEntry {
+        chord,
+        meta: meta.clone(),
+      })
+      .collect()
+  }
+}
+
+/// Layout carrying metadata of its chords. It types like the wrapped
+/// layout, and both are serialized together.
+///
+/// ```
+/// use tenboard::keyboard::{
+///   annotation::{Annotated, ChordCost},
+///   effort::Effort,
+///   layout::standard::Qwerty,
+///   Keyboard,
+///   metric::Metric,
+/// };
+///
+/// let mut layout = Annotated::new(Qwerty::new());
+/// let q = layout.type_chars("q".chars())[0];
+/// layout.annotations_mut().set_difficulty(q, 10.0).set_learned(q, false);
+/// let cost = ChordCost::new(layout.annotations());
+/// let effort = Effort::new(cost).updated(&layout.type_chars("qq".chars()));
+/// assert_eq!(effort.score(), 20.0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Annotated<L> {
+  layout: L,
+  #[cfg_attr(feature = "serde", serde(default))]
+  annotations: ChordAnnotations,
+}
+
+impl<L> Annotated<L> {
+  /// Wraps `layout` without any chord metadata.
+  pub fn new(layout: L) -> Self {
+    Self {
+      layout,
+      annotations: ChordAnnotations::new(),
+    }
+  }
+
+  pub fn layout(&self) -> &L {
+    &self.layout
+  }
+
+  pub fn layout_mut(&mut self) -> &mut L {
+    &mut self.layout
+  }
+
+  pub fn annotations(&self) -> &ChordAnnotations {
+    &self.annotations
+  }
+
+  pub fn annotations_mut(&mut self) -> &mut ChordAnnotations {
+    &mut self.annotations
+  }
+
+  /// Returns the wrapped layout and its chord metadata.
+  pub fn into_parts(self) -> (L, ChordAnnotations) {
+    (self.layout, self.annotations)
+  }
+}
+
+impl<L: Keyboard> Keyboard for Annotated<L> {
+  fn try_type_chars(
+    &self,
+    chars: impl Iterator<Item = char>,
+  ) -> Result<Vec<HandsState>, NoSuchChar> {
+    self.layout.try_type_chars(chars)
+  }
+
+  fn try_type_text(
+    &self,
+    text: &str,
+  ) -> Result<Vec<HandsState>, TypingError> {
+    self.layout.try_type_text(text)
+  }
+}
+
+impl<L: Shift> Shift for Annotated<L> {
+  fn shift_policy(&self) -> ShiftPolicy {
+    self.layout.shift_policy()
+  }
+}
+
+/// Effort model taking chord costs from [ChordAnnotations]: a chord with a
+/// difficulty costs the difficulty, others cost as much as in the fallback
+/// model. Costs of unlearned chords are multiplied by a penalty.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChordCost<E = FingerEffort> {
+  annotations: ChordAnnotations,
+  fallback: E,
+  unlearned_penalty: f32,
+}
+
+impl ChordCost {
+  /// Creates a model falling back to [FingerEffort::new] without a
+  /// penalty for unlearned chords.
+  pub fn new(annotations: &ChordAnnotations) -> Self {
+    Self::new_with_fallback(annotations, FingerEffort::new())
+  }
+}
+
+impl<E: EffortModel> ChordCost<E> {
+  pub fn new_with_fallback(
+    annotations: &ChordAnnotations,
+    fallback: E,
+  ) -> Self {
+    Self {
+      annotations: annotations.clone(),
+      fallback,
+      unlearned_penalty: 1.0,
+    }
+  }
+
+  /// Sets multiplier of costs of chords marked as not learned.
+  pub fn set_unlearned_penalty(&mut self, penalty: f32) -> &mut Self {
+    self.unlearned_penalty = penalty;
+    self
+  }
+
+  pub fn annotations(&self) -> &ChordAnnotations {
+    &self.annotations
+  }
+}
+
+impl<E: EffortModel> EffortModel for ChordCost<E> {
+  fn cost(&self, handstate: &HandsState) -> f32 {
+    let meta = self.annotations.get(handstate);
+    let cost = meta
+      .and_then(|meta| meta.difficulty)
+      .unwrap_or_else(|| self.fallback.cost(handstate));
+    match meta.and_then(|meta| meta.learned) {
+      Some(false) => cost * self.unlearned_penalty,
+      _ => cost,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testing::SyntheticKeyboard;
+
+  #[test]
+  fn test_chord_cost() {
+    let a: HandsState = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into();
+    let b: HandsState = [0, 1, 0, 0, 0, 0, 0, 0, 0, 0].into();
+    let c: HandsState = [0, 0, 1, 0, 0, 0, 0, 0, 0, 0].into();
+    let mut annotations = ChordAnnotations::new();
+    annotations
+      .set_difficulty(a, 5.0)
+      .set_learned(b, false)
+      .set_learned(c, true)
+      .set_comment(c, "easy");
+    assert_eq!(annotations.len(), 3);
+    assert_eq!(annotations.unlearned().collect::<Vec<_>>(), [&b]);
+    assert_eq!(
+      annotations.get(&c).unwrap().comment.as_deref(),
+      Some("easy")
+    );
+
+    let mut cost =
+      ChordCost::new_with_fallback(&annotations, |_: &HandsState| 1.0);
+    cost.set_unlearned_penalty(3.0);
+    assert_eq!(cost.cost(&a), 5.0);
+    assert_eq!(cost.cost(&b), 3.0);
+    assert_eq!(cost.cost(&c), 1.0);
+    assert_eq!(cost.cost(&HandsState::left_thumb()), 1.0);
+  }
+
+  #[test]
+  fn test_annotated() {
+    let mut layout = Annotated::new(SyntheticKeyboard::new_one_finger("ab"));
+    let b = layout.type_chars("b".chars())[0];
+    layout.annotations_mut().set_learned(b, false);
+    assert_eq!(
+      layout.try_type_chars("c".chars()),
+      Err(NoSuchChar { ch: 'c' })
+    );
+    let (_, mut annotations) = layout.into_parts();
+    assert_eq!(annotations.get(&b).unwrap().learned, Some(false));
+    assert!(annotations.remove(&b).is_some());
+    assert!(annotations.is_empty());
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_annotations_serialization() -> Result<(), serde_json::Error> {
+    let mut annotations = ChordAnnotations::new();
+    annotations
+      .set_difficulty(HandsState::left_thumb(), 2.0)
+      .set_comment(HandsState::right_thumb(), "space");
+    let json = serde_json::to_string(&annotations)?;
+    assert_eq!(
+      json,
+      "[{\"chord\":[0,0,0,0,0,1,0,0,0,0],\"comment\":\"space\"},\
+       {\"chord\":[0,0,0,0,1,0,0,0,0,0],\"difficulty\":2.0}]"
+    );
+    let annotations: ChordAnnotations =
+      serde_json::from_str(r#"[{"chord": ["LP"], "learned": true}]"#)?;
+    assert_eq!(
+      annotations.get(&[1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into()),
+      Some(&ChordMeta {
+        learned: Some(true),
+        ..ChordMeta::default()
+      })
+    );
+    Ok(())
+  }
+}
@@ -0,0 +1,119 @@
+//! Contains small score summaries of a layout to embed in repositories where
+//! layouts are published.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+  report::Report,
+  Keyboard,
+  NoSuchChar,
+};
+
+/// Name of the report entry used as the overall score of a badge. It is
+/// added by [Report::new].
+pub const BADGE_SCORE_METRIC: &str = "balanced";
+
+/// Summary of a layout's efficiency on a corpus.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Badge {
+  /// Score of the [Preset::Balanced](super::objective::Preset::Balanced)
+  /// objective. The lower - the better.
+  pub score: f32,
+  /// Keystrokes (chords) per character.
+  pub kspc: f32,
+  /// Shares of presses made by left and right hands.
+  pub balance: [f32; 2],
+}
+
+impl Badge {
+  /// Types `text` with `keyboard` and computes a badge from its report.
+  pub fn new(keyboard: &impl Keyboard, text: &str) -> Result<Self, NoSuchChar> {
+    let handstates = keyboard.try_type_chars(text.chars())?;
+    let report = Report::new(&handstates);
+    Ok(
+      Self::from_report(&report, text.chars().count())
+        .expect("report contains all badge metrics"),
+    )
+  }
+
+  /// Creates a badge from `report` of a corpus of `chars` characters.
+  /// Returns `None` if the report lacks `hand_usage` or
+  /// [BADGE_SCORE_METRIC] entries.
+  pub fn from_report(report: &Report, chars: usize) -> Option<Self> {
+    let score = report.get(BADGE_SCORE_METRIC)?.score;
+    let [left, right] = report.get("hand_usage")?.hands?;
+    let total = (left + right).max(1.0);
+    Some(Self {
+      score,
+      kspc: report.presses as f32 / chars.max(1) as f32,
+      balance: [left / total, right / total],
+    })
+  }
+
+  /// Returns badge as a JSON object.
+  #[cfg(feature = "serde")]
+  pub fn to_json(&self) -> String {
+    serde_json::to_string(self).expect("badge is always serializable")
+  }
+
+  /// Returns badge as an SVG image similar to shields.io badges.
+  pub fn to_svg(&self) -> String {
+    let label = "tenboard";
+    let value = format!(
+      "score {:.3} | kspc {:.2} | L/R {:.0}/{:.0}",
+      self.score,
+      self.kspc,
+      self.balance[0] * 100.0,
+      self.balance[1] * 100.0
+    );
+    // rough width estimation for 11px Verdana
+    let label_width = label.len() * 7 + 10;
+    let value_width = value.len() * 7 + 10;
+    let width = label_width + value_width;
+    format!(
+      concat!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" ",
+        "height=\"20\" role=\"img\" aria-label=\"{l}: {v}\">",
+        "<rect width=\"{lw}\" height=\"20\" fill=\"#555\"/>",
+        "<rect x=\"{lw}\" width=\"{vw}\" height=\"20\" fill=\"#4c1\"/>",
+        "<g fill=\"#fff\" font-family=\"Verdana,sans-serif\" ",
+        "font-size=\"11\" text-anchor=\"middle\">",
+        "<text x=\"{lx}\" y=\"14\">{l}</text>",
+        "<text x=\"{vx}\" y=\"14\">{v}</text>",
+        "</g></svg>\n"
+      ),
+      w = width,
+      l = label,
+      v = value,
+      lw = label_width,
+      vw = value_width,
+      lx = label_width / 2,
+      vx = label_width + value_width / 2,
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testing::SyntheticKeyboard;
+
+  #[test]
+  fn test_badge() {
+    let kb = SyntheticKeyboard::new_one_finger("abcpqrsdef");
+    let badge = Badge::new(&kb, "abcd").unwrap();
+    assert_eq!(badge.kspc, 1.0);
+    assert_eq!(badge.balance, [0.75, 0.25]);
+    #[cfg(feature = "serde")]
+    assert!(badge.to_json().starts_with("{\"score\":"));
+    let svg = badge.to_svg();
+    assert!(svg.contains("kspc 1.00 | L/R 75/25"));
+    assert_eq!(Badge::new(&kb, "x"), Err(NoSuchChar { ch: 'x' }));
+    assert_eq!(Badge::from_report(&Report::empty(0), 0), None);
+    let handstates = kb.type_chars("abcd".chars());
+    let report = Report::new(&handstates);
+    assert_eq!(Badge::from_report(&report, 4), Some(badge));
+  }
+}
@@ -0,0 +1,265 @@
+//! Contains benchmarks that score many layouts over many corpora at once.
+
+use super::{
+  hands::HandsState,
+  metric::Metric,
+  report::Report,
+  DynKeyboard,
+  Keyboard,
+  TypingError,
+};
+
+type NewMetric<'a> = Box<dyn Fn(&[HandsState]) -> Box<dyn Metric> + 'a>;
+
+/// Scores every layout over every corpus with all metrics of
+/// [Report::new] and additionally registered metrics. Layouts may be of
+/// different types, e.g. generated Tenboard layouts along with
+/// [Qwerty](super::layout::standard::Qwerty).
+///
+/// ```
+/// use tenboard::keyboard::{
+///   benchmark::Benchmark,
+///   layout::{
+///     standard::Qwerty,
+///     tenboard::{Tenboard, TenboardUnconstrained},
+///   },
+///   metric::Skipgrams,
+/// };
+///
+/// let qwerty = Qwerty::new();
+/// let tenboard = TenboardUnconstrained::new_random();
+/// let result = Benchmark::new()
+///   .add_layout("qwerty", &qwerty)
+///   .add_layout("tenboard", &tenboard)
+///   .add_corpus("pangram", "the quick brown fox jumps over the lazy dog")
+///   .add_metric("skipgrams", Skipgrams::new)
+///   .run();
+/// let ranking = result.ranking("finger_alternation");
+/// assert_eq!(ranking.len(), 2);
+/// assert!(result.score("qwerty", "pangram", "skipgrams").is_some());
+/// ```
+pub struct Benchmark<'a> {
+  layouts: Vec<(String, &'a dyn DynKeyboard)>,
+  corpora: Vec<(String, &'a str)>,
+  metrics: Vec<(String, NewMetric<'a>)>,
+}
+
+impl<'a> Benchmark<'a> {
+  pub fn new() -> Self {
+    Self {
+      layouts: Vec::new(),
+      corpora: Vec::new(),
+      metrics: Vec::new(),
+    }
+  }
+
+  pub fn add_layout(
+    &mut self,
+    name: &str,
+    layout: &'a dyn DynKeyboard,
+  ) -> &mut Self {
+    self.layouts.push((name.to_owned(), layout));
+    self
+  }
+
+  pub fn add_corpus(&mut self, name: &str, text: &'a str) -> &mut Self {
+    self.corpora.push((name.to_owned(), text));
+    self
+  }
+
+  /// Registers a metric created by `new_metric` for every pair of a layout
+  /// and a corpus. It's added to reports with [Report::add_metric].
+  pub fn add_metric<M: Metric + 'static>(
+    &mut self,
+    name: &str,
+    new_metric: impl Fn() -> M + 'a,
+  ) -> &mut Self {
+    self.metrics.push((
+      name.to_owned(),
+      Box::new(move |handstates| Box::new(new_metric().updated(handstates))),
+    ));
+    self
+  }
+
+  /// Types every corpus with every layout and reports their scores.
+  pub fn run(&self) -> BenchmarkResult {
+    let reports = self
+      .layouts
+      .iter()
+      .map(|(_, layout)| {
+        self
+          .corpora
+          .iter()
+          .map(|(_, text)| {
+            let handstates = layout.try_type_text(text)?;
+            let mut report = Report::new(&handstates);
+            for (name, new_metric) in &self.metrics {
+              report.add_metric(name, new_metric(&handstates).as_ref());
+            }
+            Ok(report)
+          })
+          .collect()
+      })
+      .collect();
+    BenchmarkResult {
+      layouts: self.layouts.iter().map(|(name, _)| name.clone()).collect(),
+      corpora: self.corpora.iter().map(|(name, _)| name.clone()).collect(),
+      reports,
+    }
+  }
+}
+
+impl Default for Benchmark<'_> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Matrix of reports of a [Benchmark] with a row per layout and a column
+/// per corpus, in order they were added.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkResult {
+  layouts: Vec<String>,
+  corpora: Vec<String>,
+  reports: Vec<Vec<Result<Report, TypingError>>>,
+}
+
+impl BenchmarkResult {
+  pub fn layouts(&self) -> &[String] {
+    &self.layouts
+  }
+
+  pub fn corpora(&self) -> &[String] {
+    &self.corpora
+  }
+
+  /// Returns the report of `layout` over `corpus` or an error if the
+  /// corpus couldn't be typed with the layout. Returns `None` if there is
+  /// no such layout or corpus.
+  pub fn get(
+    &self,
+    layout: &str,
+    corpus: &str,
+  ) -> Option<&Result<Report, TypingError>> {
+    let row = self.layouts.iter().position(|name| name == layout)?;
+    let column = self.corpora.iter().position(|name| name == corpus)?;
+    Some(&self.reports[row][column])
+  }
+
+  /// Returns score per press of `metric` for `layout` over `corpus`.
+  pub fn score(&self, layout: &str, corpus: &str, metric: &str) -> Option<f32> {
+    let report = self.get(layout, corpus)?.as_ref().ok()?;
+    Some(report.get(metric)?.score_per_press)
+  }
+
+  /// Returns mean score per press of `metric` of `layout` over all
+  /// corpora, so long and short corpora weigh the same. It's infinite if
+  /// any corpus couldn't be typed with the layout.
+  pub fn mean_score(&self, layout: &str, metric: &str) -> Option<f32> {
+    let row = self.layouts.iter().position(|name| name == layout)?;
+    let mut sum = 0.0;
+    for report in &self.reports[row] {
+      match report {
+        Ok(report) => sum += report.get(metric)?.score_per_press,
+        Err(_) => return Some(f32::INFINITY),
+      }
+    }
+    Some(sum / self.corpora.len().max(1) as f32)
+  }
+
+  /// Returns layouts with their [BenchmarkResult::mean_score] of `metric`,
+  /// the best score first as told by
+  /// [lower_is_better](super::report::MetricReport::lower_is_better) of
+  /// reports. The lowest score goes first if the metric has no direction and
+  /// layouts that couldn't type some corpus go last. Layouts that typed some
+  /// corpus, but whose reports lack the metric, are skipped.
+  pub fn ranking(&self, metric: &str) -> Vec<(&str, f32)> {
+    let mut ranking: Vec<_> = self
+      .layouts
+      .iter()
+      .filter_map(|name| Some((name.as_str(), self.mean_score(name, metric)?)))
+      .collect();
+    let higher_is_better = self
+      .reports
+      .iter()
+      .flatten()
+      .filter_map(|report| report.as_ref().ok()?.get(metric))
+      .any(|report| report.lower_is_better == Some(false));
+    let failed = |score: &f32| *score == f32::INFINITY;
+    ranking.sort_by(|(_, a), (_, b)| {
+      failed(a).cmp(&failed(b)).then_with(|| match higher_is_better {
+        true => b.total_cmp(a),
+        false => a.total_cmp(b),
+      })
+    });
+    ranking
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    keyboard::metric::{ChordEntropy, FingerAlternation, Skipgrams},
+    testing::SyntheticKeyboard,
+  };
+
+  #[test]
+  fn test_benchmark() {
+    let one = SyntheticKeyboard::new_one_finger("abcpqrsdef");
+    let same = SyntheticKeyboard::new_one_finger("aaaaaaaaaa");
+    let result = Benchmark::new()
+      .add_layout("one", &one)
+      .add_layout("same", &same)
+      .add_corpus("short", "abab")
+      .add_corpus("long", "abcabcabcdef")
+      .add_metric("custom", FingerAlternation::new)
+      .add_metric("skipgrams", Skipgrams::new)
+      .run();
+    assert_eq!(result.layouts(), ["one", "same"]);
+    assert_eq!(result.corpora(), ["short", "long"]);
+    assert_eq!(
+      result.score("one", "short", "finger_alternation"),
+      Some(0.0)
+    );
+    assert_eq!(
+      result.score("one", "long", "custom"),
+      result.score("one", "long", "finger_alternation")
+    );
+    assert_eq!(result.score("one", "short", "skipgrams"), Some(0.5));
+    assert!(result.get("same", "short").unwrap().is_err());
+    assert_eq!(result.mean_score("same", "custom"), Some(f32::INFINITY));
+    assert_eq!(result.ranking("custom"), [
+      ("one", 0.0),
+      ("same", f32::INFINITY)
+    ]);
+    assert_eq!(result.mean_score("one", "missing"), None);
+    assert_eq!(result.get("other", "short"), None);
+  }
+
+  #[test]
+  fn test_ranking_direction() {
+    let one = SyntheticKeyboard::new_one_finger("abcdef");
+    // `a` and `c` share a chord
+    let merged = SyntheticKeyboard::new_one_finger("abcdefxxxxc");
+    let same = SyntheticKeyboard::new_one_finger("aaaaaaaaaa");
+    let result = Benchmark::new()
+      .add_layout("same", &same)
+      .add_layout("merged", &merged)
+      .add_layout("one", &one)
+      .add_corpus("corpus", "abcabcabcdef")
+      .add_metric("chord_entropy", ChordEntropy::new)
+      .run();
+    let names = |ranking: Vec<(&str, f32)>| -> Vec<String> {
+      ranking.into_iter().map(|(name, _)| name.to_owned()).collect()
+    };
+    assert_eq!(names(result.ranking("chord_entropy")), [
+      "one", "merged", "same"
+    ]);
+    assert_eq!(names(result.ranking("finger_alternation")), [
+      "one", "merged", "same"
+    ]);
+    let ranking = result.ranking("chord_entropy");
+    assert!(ranking[0].1 > ranking[1].1);
+  }
+}
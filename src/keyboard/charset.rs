@@ -0,0 +1,157 @@
+//! Contains sets of chars that layouts are generated for.
+
+use std::fmt::Display;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+  layout::tenboard::Tenboard,
+  DIGIT_CHARS,
+  PUNCTUATION_CHARS,
+  TYPABLE_CHARS,
+};
+
+const GERMAN_CHARS: &str = "äöüÄÖÜß";
+const RUSSIAN_LOWERCASE_CHARS: &str = "абвгдеёжзийклмнопрстуфхцчшщъыьэюя";
+const RUSSIAN_UPPERCASE_CHARS: &str = "АБВГДЕЁЖЗИЙКЛМНОПРСТУФХЦЧШЩЪЫЬЭЮЯ";
+
+/// Ordered set of chars a layout must be able to type.
+///
+/// ```
+/// use tenboard::keyboard::charset::CharSet;
+///
+/// let mut charset = CharSet::ascii();
+/// charset.add_chars("€£").remove_chars("\t");
+/// assert!(charset.contains('€'));
+/// assert!(!charset.contains('\t'));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CharSet {
+  chars: Vec<char>,
+}
+
+impl CharSet {
+  /// Creates an empty set.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Creates a set of ASCII letters, digits, punctuation, space, tab and
+  /// newline, the chars layouts are generated for by default.
+  pub fn ascii() -> Self {
+    TYPABLE_CHARS.chars().collect()
+  }
+
+  /// Creates a set of ASCII chars with umlauts and eszett.
+  pub fn german() -> Self {
+    let mut charset = Self::ascii();
+    charset.add_chars(GERMAN_CHARS);
+    charset
+  }
+
+  /// Creates a set of Cyrillic letters of Russian alphabet, digits, ASCII
+  /// punctuation, space, tab and newline.
+  pub fn russian() -> Self {
+    let mut charset = Self::new();
+    charset
+      .add_chars(RUSSIAN_LOWERCASE_CHARS)
+      .add_chars(RUSSIAN_UPPERCASE_CHARS)
+      .add_chars(DIGIT_CHARS)
+      .add_chars(PUNCTUATION_CHARS)
+      .add_chars("№");
+    charset
+  }
+
+  /// Appends `chars` that aren't in the set yet.
+  pub fn add_chars(&mut self, chars: &str) -> &mut Self {
+    for ch in chars.chars() {
+      if !self.contains(ch) {
+        self.chars.push(ch);
+      }
+    }
+    self
+  }
+
+  pub fn remove_chars(&mut self, chars: &str) -> &mut Self {
+    self.chars.retain(|&ch| !chars.contains(ch));
+    self
+  }
+
+  pub fn contains(&self, ch: char) -> bool {
+    self.chars.contains(&ch)
+  }
+
+  pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+    self.chars.iter().copied()
+  }
+
+  pub fn len(&self) -> usize {
+    self.chars.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.chars.is_empty()
+  }
+
+  /// Returns chars of the set that `layout` can't type.
+  pub fn missing(&self, layout: &dyn Tenboard) -> Vec<char> {
+    self
+      .chars()
+      .filter(|&ch| layout.try_type_char(ch).is_err())
+      .collect()
+  }
+}
+
+impl FromIterator<char> for CharSet {
+  fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
+    let mut charset = Self::new();
+    for ch in iter {
+      if !charset.contains(ch) {
+        charset.chars.push(ch);
+      }
+    }
+    charset
+  }
+}
+
+impl From<&str> for CharSet {
+  fn from(value: &str) -> Self {
+    value.chars().collect()
+  }
+}
+
+impl Display for CharSet {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    self.chars().try_for_each(|ch| write!(f, "{ch}"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::keyboard::{
+    hands::ThumbPolicy,
+    layout::tenboard::TenboardUnconstrained,
+  };
+
+  #[test]
+  fn test_charset() {
+    assert_eq!(CharSet::ascii().len(), TYPABLE_CHARS.len());
+    assert_eq!(CharSet::german().len(), TYPABLE_CHARS.len() + 7);
+    assert_eq!(CharSet::russian().len(), 66 + 10 + 35 + 1);
+    assert_eq!(CharSet::from("abca").to_string(), "abc");
+  }
+
+  #[test]
+  fn test_missing() {
+    let layout = TenboardUnconstrained::new_random_with_charset(
+      &CharSet::russian(),
+      ThumbPolicy::ANY,
+    );
+    assert!(CharSet::russian().missing(&layout).is_empty());
+    assert_eq!(CharSet::from("aжq").missing(&layout), vec!['a', 'q']);
+    assert_eq!(layout.charset().len(), CharSet::russian().len());
+  }
+}
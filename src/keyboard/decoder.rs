@@ -0,0 +1,142 @@
+//! Contains reconstruction of text from chords typed with a layout.
+
+use std::{collections::HashMap, fmt::Display};
+
+use super::{hands::HandsState, layout::tenboard::Tenboard};
+
+/// Inverse of [Keyboard::type_chars](super::Keyboard::type_chars) for
+/// Tenboard layouts.
+///
+/// ```
+/// use tenboard::keyboard::{
+///   decoder::Decoder,
+///   layout::tenboard::{Tenboard, TenboardUnconstrained},
+///   Keyboard,
+/// };
+///
+/// let layout = TenboardUnconstrained::new_random();
+/// let handstates = layout.type_chars("Hello, world!".chars());
+/// assert_eq!(Decoder::new(&layout).decode(&handstates), "Hello, world!");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoder {
+  chars: HashMap<HandsState, char>,
+}
+
+impl Decoder {
+  /// Creates a decoder of chords of `layout`. If several chars share a
+  /// chord, the first one of the layout's char set is decoded.
+  pub fn new(layout: &dyn Tenboard) -> Self {
+    let mut chars = HashMap::new();
+    for ch in layout.charset().chars() {
+      if let Ok(hs) = layout.try_type_char(ch) {
+        chars.entry(hs).or_insert(ch);
+      }
+    }
+    Self { chars }
+  }
+
+  /// Returns the char typed with `handstate`, if there is one.
+  pub fn decode_chord(&self, handstate: &HandsState) -> Option<char> {
+    self.chars.get(handstate).copied()
+  }
+
+  /// Decodes `handstates` into text. If a chord has no char, returns an
+  /// error.
+  pub fn try_decode(
+    &self,
+    handstates: &[HandsState],
+  ) -> Result<String, UnknownChord> {
+    handstates
+      .iter()
+      .enumerate()
+      .map(|(index, hs)| {
+        self.decode_chord(hs).ok_or(UnknownChord {
+          handstate: *hs,
+          index,
+        })
+      })
+      .collect()
+  }
+
+  /// Decodes `handstates` into text.
+  ///
+  /// # Panics
+  ///
+  /// Panics if any chord has no char. To avoid panic, use
+  /// [Decoder::try_decode].
+  pub fn decode(&self, handstates: &[HandsState]) -> String {
+    self
+      .try_decode(handstates)
+      .unwrap_or_else(|e| panic!("{e}"))
+  }
+}
+
+/// This error means that a chord doesn't type anything in a layout.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct UnknownChord {
+  pub handstate: HandsState,
+  /// Index of the chord in the decoded sequence.
+  pub index: usize,
+}
+
+impl Display for UnknownChord {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "chord {} at {} has no char in layout",
+      self.handstate, self.index
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::keyboard::{
+    layout::tenboard::{
+      TenboardHomeRowConstrained,
+      TenboardModifierConstrained,
+      TenboardThumbConstrained,
+      TenboardUnconstrained,
+    },
+    Keyboard,
+    TYPABLE_CHARS,
+  };
+
+  #[test]
+  fn test_round_trip() {
+    fn assert_round_trip<T: Tenboard>() {
+      let layout = T::new_random();
+      let handstates = layout.type_chars(TYPABLE_CHARS.chars());
+      let decoder = Decoder::new(&layout);
+      assert_eq!(decoder.decode(&handstates), TYPABLE_CHARS);
+    }
+    assert_round_trip::<TenboardUnconstrained>();
+    assert_round_trip::<TenboardThumbConstrained>();
+    assert_round_trip::<TenboardModifierConstrained>();
+    assert_round_trip::<TenboardHomeRowConstrained>();
+  }
+
+  #[test]
+  fn test_unknown_chord() {
+    let layout: TenboardUnconstrained = TYPABLE_CHARS
+      .chars()
+      .zip(HandsState::iterate_one_two_key_all_states())
+      .collect();
+    let decoder = Decoder::new(&layout);
+    let unknown = HandsState::both_thumbs();
+    let handstates = [
+      HandsState::iterate_one_key_no_thumbs().next().unwrap(),
+      unknown,
+    ];
+    assert_eq!(
+      decoder.try_decode(&handstates),
+      Err(UnknownChord {
+        handstate: unknown,
+        index: 1
+      })
+    );
+    assert_eq!(decoder.decode(&handstates[..1]), "a");
+  }
+}
@@ -0,0 +1,175 @@
+//! Contains models of physical effort of pressing chords.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+  hands::{FingerState, HandsState},
+  metric::{Metric, MetricInfo},
+  profile::FingerProfile,
+};
+
+/// Describes how hard it is to press a chord.
+pub trait EffortModel {
+  /// Returns cost of pressing `handstate`. The lower - the easier.
+  fn cost(&self, handstate: &HandsState) -> f32;
+}
+
+impl<F: Fn(&HandsState) -> f32> EffortModel for F {
+  fn cost(&self, handstate: &HandsState) -> f32 {
+    self(handstate)
+  }
+}
+
+/// Effort model where a chord costs the sum of base costs of its fingers
+/// multiplied by a factor depending on chord size.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FingerEffort {
+  finger_costs: [f32; 10],
+  size_multipliers: Vec<f32>,
+}
+
+impl FingerEffort {
+  /// Creates a model where pinkies cost twice as much as index fingers and
+  /// thumbs, ring fingers cost 1.5 and middle fingers cost 1.2. Chords of
+  /// 2, 3 and 4+ keys have 1.2, 1.5 and 2 multipliers.
+  pub fn new() -> Self {
+    Self {
+      finger_costs: [2.0, 1.5, 1.2, 1.0, 1.0, 1.0, 1.0, 1.2, 1.5, 2.0],
+      size_multipliers: vec![1.0, 1.2, 1.5, 2.0],
+    }
+  }
+
+  /// Same as [FingerEffort::new], but finger costs are taken from
+  /// `profile`.
+  pub fn new_with_profile(profile: &FingerProfile) -> Self {
+    let mut model = Self::new();
+    model.set_finger_costs(profile.costs());
+    model
+  }
+
+  pub fn set_finger_costs(&mut self, finger_costs: [f32; 10]) -> &mut Self {
+    self.finger_costs = finger_costs;
+    self
+  }
+
+  /// Sets multipliers of chords of 1, 2, 3... keys. Bigger chords use the
+  /// last multiplier. Without multipliers chord size doesn't matter.
+  pub fn set_size_multipliers(&mut self, multipliers: &[f32]) -> &mut Self {
+    self.size_multipliers = multipliers.to_vec();
+    self
+  }
+
+  pub fn finger_costs(&self) -> [f32; 10] {
+    self.finger_costs
+  }
+
+  pub fn size_multipliers(&self) -> &[f32] {
+    &self.size_multipliers
+  }
+}
+
+impl Default for FingerEffort {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl EffortModel for FingerEffort {
+  fn cost(&self, handstate: &HandsState) -> f32 {
+    let base: f32 = handstate
+      .iter()
+      .zip(self.finger_costs)
+      .filter(|(fs, _)| **fs == FingerState::Pressed)
+      .map(|(_, cost)| cost)
+      .sum();
+    let size = handstate.count_pressed();
+    let multiplier = match size {
+      0 => 1.0,
+      _ => self
+        .size_multipliers
+        .get(size - 1)
+        .or(self.size_multipliers.last())
+        .copied()
+        .unwrap_or(1.0),
+    };
+    base * multiplier
+  }
+}
+
+/// Measures total effort of typed chords according to an [EffortModel].
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Effort<E = FingerEffort> {
+  model: E,
+  total: f32,
+}
+
+impl<E: EffortModel> Effort<E> {
+  pub fn new(model: E) -> Self {
+    Self { model, total: 0.0 }
+  }
+
+  pub fn model(&self) -> &E {
+    &self.model
+  }
+
+  pub fn values(self) -> f32 {
+    self.total
+  }
+}
+
+impl Default for Effort {
+  fn default() -> Self {
+    Self::new(FingerEffort::new())
+  }
+}
+
+impl<E: EffortModel> Metric for Effort<E> {
+  fn update_once(&mut self, handstate: &HandsState) {
+    self.total += self.model.cost(handstate);
+  }
+
+  fn score(&self) -> f32 {
+    self.total
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::new("effort")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_finger_effort() {
+    let model = FingerEffort::new();
+    assert_eq!(model.cost(&[1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into()), 2.0);
+    assert_eq!(model.cost(&[0, 0, 0, 1, 0, 0, 1, 0, 0, 0].into()), 2.4);
+    assert_eq!(model.cost(&[1, 1, 1, 1, 1, 1, 0, 0, 0, 0].into()), 15.4);
+    assert_eq!(model.cost(&HandsState::default()), 0.0);
+
+    let model = FingerEffort::new_with_profile(&FingerProfile::weak_pinkies());
+    let pinky = model.cost(&[1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into());
+    assert!((pinky - 3.0).abs() < 1e-6);
+
+    let mut model = FingerEffort::new();
+    model.set_size_multipliers(&[]);
+    assert_eq!(model.cost(&[0, 0, 0, 1, 0, 0, 1, 0, 0, 0].into()), 2.0);
+  }
+
+  #[test]
+  fn test_effort_metric() {
+    let handstates: [HandsState; 2] = [
+      [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+      [0, 0, 0, 0, 0, 0, 0, 0, 1, 1].into(),
+    ];
+    let effort = Effort::default().updated(&handstates).values();
+    assert!((effort - 6.2).abs() < 1e-5);
+    let sizes = Effort::new(|hs: &HandsState| hs.count_pressed() as f32);
+    assert_eq!(sizes.updated(&handstates).score(), 3.0);
+  }
+}
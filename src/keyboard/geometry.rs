@@ -0,0 +1,214 @@
+//! Contains physical model of keyboards where fingers own keys in several
+//! rows, and metrics of finger movement between them.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::hands::{FingerState, HandsState};
+
+/// Row of a key pressed by a finger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Row {
+  Top,
+  #[default]
+  Home,
+  Bottom,
+}
+
+impl Row {
+  /// All rows from top to bottom.
+  pub const ALL: [Row; 3] = [Row::Top, Row::Home, Row::Bottom];
+
+  fn index(self) -> usize {
+    match self {
+      Row::Top => 0,
+      Row::Home => 1,
+      Row::Bottom => 2,
+    }
+  }
+}
+
+/// Chord where every pressed finger presses a key in some row. Fingers are
+/// indexed like in [HandsState].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RowChord(pub [Option<Row>; 10]);
+
+impl RowChord {
+  /// Creates a chord where fingers pressed in `handstate` press keys in
+  /// `row`.
+  pub fn new(handstate: &HandsState, row: Row) -> Self {
+    Self(handstate.map(|fs| fs.is_pressed().then_some(row)))
+  }
+
+  /// Returns fingers pressed by the chord regardless of rows.
+  pub fn handstate(&self) -> HandsState {
+    HandsState(self.0.map(|row| FingerState::from(row.is_some())))
+  }
+}
+
+impl From<HandsState> for RowChord {
+  /// Presses keys of the home row.
+  fn from(value: HandsState) -> Self {
+    Self::new(&value, Row::Home)
+  }
+}
+
+/// Positions of keys of every finger, in key widths.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Geometry {
+  positions: [[(f32, f32); 3]; 10],
+}
+
+impl Geometry {
+  /// Creates an ortholinear geometry: fingers own columns one key apart and
+  /// rows are one key apart.
+  pub fn new() -> Self {
+    Self::with_row_offsets([0.0; 3])
+  }
+
+  /// Creates a geometry of a row-staggered keyboard, where the top row is
+  /// shifted a quarter of a key left and the bottom row half a key right of
+  /// the home row.
+  pub fn staggered() -> Self {
+    Self::with_row_offsets([-0.25, 0.0, 0.5])
+  }
+
+  fn with_row_offsets(offsets: [f32; 3]) -> Self {
+    let mut positions = [[(0.0, 0.0); 3]; 10];
+    for (finger, keys) in positions.iter_mut().enumerate() {
+      for (row, key) in keys.iter_mut().enumerate() {
+        *key = (finger as f32 + offsets[row], row as f32 - 1.0);
+      }
+    }
+    Self { positions }
+  }
+
+  /// Places the key of `finger` in `row` at `position`.
+  pub fn set_position(
+    &mut self,
+    finger: usize,
+    row: Row,
+    position: (f32, f32),
+  ) -> &mut Self {
+    self.positions[finger][row.index()] = position;
+    self
+  }
+
+  pub fn position(&self, finger: usize, row: Row) -> (f32, f32) {
+    self.positions[finger][row.index()]
+  }
+
+  /// Returns distance `finger` moves from its key in `from` row to the key
+  /// in `to` row.
+  pub fn distance(&self, finger: usize, from: Row, to: Row) -> f32 {
+    let (x1, y1) = self.position(finger, from);
+    let (x2, y2) = self.position(finger, to);
+    (x2 - x1).hypot(y2 - y1)
+  }
+}
+
+impl Default for Geometry {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Measures total distance fingers travel between keys. Fingers start on
+/// the home row and stay on the last pressed key.
+///
+/// It isn't a [Metric](super::metric::Metric), so it can't be added to
+/// objectives and reports: [HandsState]s don't tell rows of keys, and
+/// fingers would never leave the home row. Feed it chords typed with
+/// `try_type_row_chars` of [TenboardRows](super::layout::rows::TenboardRows)
+/// instead.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TravelDistance {
+  geometry: Geometry,
+  rows: [Row; 10],
+  distances: [f32; 10],
+}
+
+impl TravelDistance {
+  pub fn new(geometry: Geometry) -> Self {
+    Self {
+      geometry,
+      rows: [Row::Home; 10],
+      distances: [0.0; 10],
+    }
+  }
+
+  /// Updates metric's state with a chord pressing keys in several rows.
+  pub fn update_rows(&mut self, chord: &RowChord) {
+    for (finger, row) in chord.0.iter().enumerate() {
+      if let Some(row) = *row {
+        self.distances[finger] +=
+          self.geometry.distance(finger, self.rows[finger], row);
+        self.rows[finger] = row;
+      }
+    }
+  }
+
+  /// Consumes `self`, then [TravelDistance::update_rows] with every chord
+  /// and returns it.
+  pub fn updated_rows(mut self, chords: &[RowChord]) -> Self {
+    for chord in chords {
+      self.update_rows(chord);
+    }
+    self
+  }
+
+  /// Returns total distance traveled by fingers.
+  pub fn score(&self) -> f32 {
+    self.distances.iter().sum()
+  }
+
+  /// Returns distance traveled by every finger.
+  pub fn values(self) -> [f32; 10] {
+    self.distances
+  }
+}
+
+impl Default for TravelDistance {
+  fn default() -> Self {
+    Self::new(Geometry::new())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_geometry() {
+    let geometry = Geometry::staggered();
+    assert_eq!(geometry.position(0, Row::Top), (-0.25, -1.0));
+    assert_eq!(geometry.distance(3, Row::Home, Row::Home), 0.0);
+    assert!(
+      (geometry.distance(3, Row::Top, Row::Bottom) - 2.1360).abs() < 1e-4
+    );
+    let mut geometry = Geometry::new();
+    geometry.set_position(4, Row::Home, (4.0, 1.0));
+    assert_eq!(geometry.distance(4, Row::Top, Row::Home), 2.0);
+  }
+
+  #[test]
+  fn test_travel_distance() {
+    let index: HandsState = [0, 0, 0, 1, 0, 0, 0, 0, 0, 0].into();
+    let chords = [
+      RowChord::new(&index, Row::Top),
+      RowChord::new(&index, Row::Top),
+      RowChord::new(&index, Row::Bottom),
+    ];
+    let metric = TravelDistance::default().updated_rows(&chords);
+    assert_eq!(metric.score(), 3.0);
+    let metric = metric.updated_rows(&[index.into()]);
+    assert_eq!(metric.score(), 4.0);
+    assert_eq!(metric.values()[3], 4.0);
+    assert_eq!(chords[0].handstate(), index);
+  }
+}
@@ -0,0 +1,159 @@
+//! Contains chord transition graph that can be exported to DOT and GraphML
+//! formats for analysis and visualization with external tools.
+
+use std::{collections::HashMap, fmt::Write};
+
+use super::hands::HandsState;
+
+/// Weighted directed graph of typed text. Nodes are chords weighted by
+/// number of their uses, edges are pairs of consecutive chords weighted by
+/// number of their occurrences.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct TransitionGraph {
+  last_handstate: Option<HandsState>,
+  nodes: HashMap<HandsState, u32>,
+  edges: HashMap<(HandsState, HandsState), u32>,
+}
+
+impl TransitionGraph {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Updates graph with given `handstate` following the previous one.
+  pub fn update_once(&mut self, handstate: &HandsState) {
+    *self.nodes.entry(*handstate).or_default() += 1;
+    if let Some(last_hs) = self.last_handstate {
+      *self.edges.entry((last_hs, *handstate)).or_default() += 1;
+    }
+    self.last_handstate = Some(*handstate);
+  }
+
+  /// Updates graph with given `handstates`.
+  pub fn update(&mut self, handstates: &[HandsState]) {
+    for hs in handstates {
+      self.update_once(hs);
+    }
+  }
+
+  /// Consumes `self`, then `update`s and returns it.
+  pub fn updated(mut self, handstates: &[HandsState]) -> Self {
+    self.update(handstates);
+    self
+  }
+
+  /// Returns chords with their use counts in a stable order.
+  pub fn nodes(&self) -> Vec<(HandsState, u32)> {
+    let mut nodes: Vec<_> = self.nodes.iter().map(|(k, v)| (*k, *v)).collect();
+    nodes.sort_by_key(|(hs, _)| sort_key(hs));
+    nodes
+  }
+
+  /// Returns transitions between chords with their counts in a stable order.
+  pub fn edges(&self) -> Vec<(HandsState, HandsState, u32)> {
+    let mut edges: Vec<_> =
+      self.edges.iter().map(|((a, b), v)| (*a, *b, *v)).collect();
+    edges.sort_by_key(|(a, b, _)| (sort_key(a), sort_key(b)));
+    edges
+  }
+
+  /// Returns graph description in Graphviz DOT format.
+  pub fn to_dot(&self) -> String {
+    let ids = self.node_ids();
+    let mut dot = String::from("digraph chords {\n");
+    for (hs, uses) in self.nodes() {
+      writeln!(dot, "  n{} [label=\"{hs}\", uses={uses}];", ids[&hs]).unwrap();
+    }
+    for (a, b, count) in self.edges() {
+      writeln!(
+        dot,
+        "  n{} -> n{} [weight={count}, label=\"{count}\"];",
+        ids[&a], ids[&b]
+      )
+      .unwrap();
+    }
+    dot.push_str("}\n");
+    dot
+  }
+
+  /// Returns graph description in GraphML format.
+  pub fn to_graphml(&self) -> String {
+    let ids = self.node_ids();
+    let mut xml = String::from(concat!(
+      "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+      "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+      "  <key id=\"label\" for=\"node\" attr.name=\"label\" ",
+      "attr.type=\"string\"/>\n",
+      "  <key id=\"uses\" for=\"node\" attr.name=\"uses\" attr.type=\"int\"/>\n",
+      "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" ",
+      "attr.type=\"int\"/>\n",
+      "  <graph id=\"chords\" edgedefault=\"directed\">\n",
+    ));
+    for (hs, uses) in self.nodes() {
+      writeln!(
+        xml,
+        "    <node id=\"n{}\"><data key=\"label\">{hs}</data>\
+         <data key=\"uses\">{uses}</data></node>",
+        ids[&hs]
+      )
+      .unwrap();
+    }
+    for (a, b, count) in self.edges() {
+      writeln!(
+        xml,
+        "    <edge source=\"n{}\" target=\"n{}\">\
+         <data key=\"weight\">{count}</data></edge>",
+        ids[&a], ids[&b]
+      )
+      .unwrap();
+    }
+    xml.push_str("  </graph>\n</graphml>\n");
+    xml
+  }
+
+  fn node_ids(&self) -> HashMap<HandsState, usize> {
+    self
+      .nodes()
+      .into_iter()
+      .enumerate()
+      .map(|(i, (hs, _))| (hs, i))
+      .collect()
+  }
+}
+
+fn sort_key(handstate: &HandsState) -> [u32; 10] {
+  handstate.map(u32::from)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{keyboard::Keyboard, testing::SyntheticKeyboard};
+
+  #[test]
+  fn test_transition_graph() {
+    let kb = SyntheticKeyboard::new_one_finger("ab");
+    let tg = TransitionGraph::new().updated(&kb.type_chars("abba".chars()));
+    let a: HandsState = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into();
+    let b: HandsState = [0, 1, 0, 0, 0, 0, 0, 0, 0, 0].into();
+    assert_eq!(tg.nodes(), vec![(b, 2), (a, 2)]);
+    assert_eq!(tg.edges(), vec![(b, b, 1), (b, a, 1), (a, b, 1)]);
+    assert_eq!(
+      tg.to_dot(),
+      concat!(
+        "digraph chords {\n",
+        "  n0 [label=\".|... .....\", uses=2];\n",
+        "  n1 [label=\"|.... .....\", uses=2];\n",
+        "  n0 -> n0 [weight=1, label=\"1\"];\n",
+        "  n0 -> n1 [weight=1, label=\"1\"];\n",
+        "  n1 -> n0 [weight=1, label=\"1\"];\n",
+        "}\n",
+      )
+    );
+    let xml = tg.to_graphml();
+    assert!(xml.contains(
+      "<edge source=\"n1\" target=\"n0\"><data key=\"weight\">1</data></edge>"
+    ));
+    assert_eq!(xml.matches("<node ").count(), 2);
+  }
+}
@@ -1,27 +1,23 @@
 //! Contains description of hands' and fingers' actions used to type stuff on a
 //! keyboard.
 
-use std::{
+#[cfg(feature = "serde")]
+use alloc::{format, string::ToString};
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use core::{
   fmt::Display,
-  ops::{Deref, DerefMut},
+  ops::{BitAnd, BitOr, Deref, DerefMut, Sub},
   slice::Chunks,
 };
 
-use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "serde")]
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
 /// Represents a finger state. Can be either pressed or released.
-#[derive(
-  Default,
-  Debug,
-  Eq,
-  PartialEq,
-  Clone,
-  Copy,
-  Hash,
-  Serialize_repr,
-  Deserialize_repr,
-)]
+#[derive(Default, Debug, Eq, PartialEq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize_repr, Deserialize_repr))]
 #[repr(u8)]
 pub enum FingerState {
   Pressed = 1,
@@ -64,7 +60,7 @@ impl From<FingerState> for u32 {
 }
 
 impl Display for FingerState {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     match self {
       FingerState::Pressed => write!(f, "|"),
       FingerState::Released => write!(f, "."),
@@ -81,10 +77,24 @@ impl Display for FingerState {
 /// | | | | |_  _|       |
 /// |        /  \        |
 /// </pre>
-#[derive(
-  Default, Debug, Eq, PartialEq, Clone, Copy, Hash, Serialize, Deserialize,
-)]
-pub struct HandsState(pub [FingerState; 10]);
+///
+/// `HandsState` is serialized as an array of 10 finger states. It can also be
+/// deserialized from an array of finger names, see
+/// [HandsState::from_finger_names].
+///
+/// Devices with another number of inputs, e.g. 8-key boards or pedal rigs,
+/// use `HandsState<N>`, where the first half of inputs belongs to the left
+/// hand. Named fingers and chord iterators are only defined for 10 fingers.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+pub struct HandsState<const N: usize = 10>(pub [FingerState; N]);
+
+/// Short names of fingers in order of their indices: left pinky, ring,
+/// middle, index, thumb, then right thumb, index, middle, ring, pinky.
+pub const FINGER_NAMES: [&str; 10] =
+  ["LP", "LR", "LM", "LI", "LT", "RT", "RI", "RM", "RR", "RP"];
+
+/// Short names of the left and the right hands.
+pub const HAND_NAMES: [&str; 2] = ["L", "R"];
 
 impl HandsState {
   #[inline]
@@ -97,6 +107,11 @@ impl HandsState {
     [0, 0, 0, 0, 0, 1, 0, 0, 0, 0].into()
   }
 
+  #[inline]
+  pub fn both_thumbs() -> Self {
+    [0, 0, 0, 0, 1, 1, 0, 0, 0, 0].into()
+  }
+
   /// Returns iterator over unique one key `HandsState`s without left and
   /// right thumbs.
   ///
@@ -143,6 +158,61 @@ impl HandsState {
     Self::iterate_one_key_no_thumbs().chain(Self::iterate_two_key_no_thumbs())
   }
 
+  /// Returns iterator over unique three key `HandsState`s without left and
+  /// right thumbs.
+  ///
+  /// - `|||.. .....`, `||.|. .....`, ..., `..... .|.||`, `..... ..|||`
+  pub fn iterate_three_key_no_thumbs() -> impl Iterator<Item = HandsState> {
+    combinations(8, 3).map(|idx| {
+      let mut fs = [0; 10];
+      for i in idx {
+        fs[if i < 4 { i } else { i + 2 }] = 1;
+      }
+      fs.into()
+    })
+  }
+
+  /// Returns iterator over unique `HandsState`s where exactly `n` fingers
+  /// are pressed, thumbs included. `HandsState`s are ordered
+  /// lexicographically by indices of pressed fingers.
+  ///
+  /// - `|||.. .....`, `||.|. .....`, ..., `..... .|.||`, `..... ..|||`
+  ///   *(n = 3)*
+  pub fn iterate_n_key(n: usize) -> impl Iterator<Item = HandsState> {
+    combinations(10, n).map(|idx| {
+      let mut fs = [0; 10];
+      for i in idx {
+        fs[i] = 1;
+      }
+      fs.into()
+    })
+  }
+
+  /// Returns iterator over unique `HandsState`s where exactly `n` fingers of
+  /// one hand are pressed, thumb included. `hand` is 0 for the left hand and
+  /// 1 for the right one. `HandsState`s are ordered lexicographically by
+  /// indices of pressed fingers.
+  ///
+  /// - `|||.. .....`, `||.|. .....`, ..., `.|.|| .....`, `..||| .....`
+  ///   *(hand = 0, n = 3)*
+  ///
+  /// # Panics
+  ///
+  /// Panics if `hand` isn't 0 or 1.
+  pub fn iterate_n_key_one_hand(
+    hand: usize,
+    n: usize,
+  ) -> impl Iterator<Item = HandsState> {
+    assert!(hand < 2, "hand must be 0 or 1, got {hand}");
+    combinations(5, n).map(move |idx| {
+      let mut fs = [0; 10];
+      for i in idx {
+        fs[hand * 5 + i] = 1;
+      }
+      fs.into()
+    })
+  }
+
   /// Returns iterator over two key `HandsState`s with and without left and
   /// right thumbs modifiers.
   /// `HandsState`s with left and right thumbs pressed alone aren't inlcuded.
@@ -155,15 +225,7 @@ impl HandsState {
   /// - `||..| .....`, `|.|.| .....`, ..., `..... |.|.|`, `..... |..||`
   ///   *(three keys with left and right thumbs)*
   pub fn iterate_one_two_key_with_thumbs() -> impl Iterator<Item = HandsState> {
-    Self::iterate_one_two_key_no_thumbs()
-      .chain(
-        Self::iterate_one_two_key_no_thumbs()
-          .map(|hs| hs.combine(&HandsState::left_thumb())),
-      )
-      .chain(
-        Self::iterate_one_two_key_no_thumbs()
-          .map(|hs| hs.combine(&HandsState::right_thumb())),
-      )
+    Self::iterate_one_two_key(ThumbPolicy::MODIFIERS)
   }
 
   /// Returns iterator over one and two key `HandsState`s with and without
@@ -179,24 +241,87 @@ impl HandsState {
   /// - `||..| .....`, `|.|.| .....`, ..., `..... |.|.|`, `..... |..||`
   ///   *(three keys with left and right thumbs)*
   pub fn iterate_one_two_key_all_states() -> impl Iterator<Item = HandsState> {
-    Self::iterate_one_two_key_with_thumbs()
-      .chain([HandsState::left_thumb(), HandsState::right_thumb()])
+    Self::iterate_one_two_key(ThumbPolicy::default())
+  }
+
+  /// Returns iterator over one and two key `HandsState`s, optionally
+  /// combined with thumbs, that are allowed by `policy`. `HandsState`s come
+  /// in the following order:
+  ///
+  /// - one and two keys without thumbs
+  /// - the same with left thumb, right thumb, then both thumbs
+  /// - left thumb, right thumb, then both thumbs alone
+  pub fn iterate_one_two_key(
+    policy: ThumbPolicy,
+  ) -> impl Iterator<Item = HandsState> {
+    let thumbs = [
+      HandsState::left_thumb(),
+      HandsState::right_thumb(),
+      HandsState::both_thumbs(),
+    ];
+    Self::iterate_one_two_key_no_thumbs()
+      .chain(thumbs.into_iter().flat_map(|thumb| {
+        Self::iterate_one_two_key_no_thumbs().map(move |hs| hs.combine(&thumb))
+      }))
+      .chain(thumbs)
+      .filter(move |hs| policy.allows(hs))
+  }
+
+  /// Creates a `HandsState` where fingers with given names are pressed.
+  /// See [FINGER_NAMES] for the list of names. Names are case insensitive.
+  pub fn from_finger_names<T: AsRef<str>>(
+    names: impl IntoIterator<Item = T>,
+  ) -> Result<Self, BadFingerName> {
+    let mut handstate = HandsState::default();
+    for name in names {
+      let name = name.as_ref().trim();
+      let idx = FINGER_NAMES
+        .iter()
+        .position(|n| n.eq_ignore_ascii_case(name))
+        .ok_or_else(|| BadFingerName {
+          name: name.to_owned(),
+        })?;
+      handstate[idx] = FingerState::Pressed;
+    }
+    Ok(handstate)
   }
 
+  /// Creates a `HandsState` from a chord name like `"LP+RI"`, which is a list
+  /// of finger names joined with `+`.
+  pub fn from_chord_name(name: &str) -> Result<Self, BadFingerName> {
+    Self::from_finger_names(name.split('+'))
+  }
+}
+
+impl<const N: usize> HandsState<N> {
   /// Returns iterator over finger states for left then right hand.
-  pub fn hand_iter(&self) -> Chunks<FingerState> {
-    self.0.chunks(5)
+  pub fn hand_iter(&self) -> Chunks<'_, FingerState> {
+    self.0.chunks(N.div_ceil(2))
   }
 
   /// Creates a new `HandsState` where fingers from `self` and `other` are in
   /// `Pressed` state.
   pub fn combine(&self, other: &Self) -> Self {
-    let mut handstate = self.to_owned();
-    handstate.iter_mut().zip(other.iter()).for_each(|(s, o)| {
-      if o == &FingerState::Pressed {
-        *s = FingerState::Pressed;
-      }
-    });
+    *self | *other
+  }
+
+  /// Returns `true` if every finger pressed in `self` is pressed in `other`.
+  pub fn is_subset_of(&self, other: &Self) -> bool {
+    (*self - *other).count_pressed() == 0
+  }
+
+  /// Returns `true` if some finger is pressed in both `self` and `other`.
+  pub fn overlaps(&self, other: &Self) -> bool {
+    (*self & *other).count_pressed() > 0
+  }
+
+  /// Returns `HandsState` with fingers of both `self` and `other` mapped by
+  /// `f`.
+  fn zip_with(self, other: Self, f: impl Fn(bool, bool) -> bool) -> Self {
+    let mut handstate = self;
+    for (s, o) in handstate.iter_mut().zip(other.iter()) {
+      *s = f(s.is_pressed(), o.is_pressed()).into();
+    }
     handstate
   }
 
@@ -209,37 +334,363 @@ impl HandsState {
   }
 }
 
-impl From<[i32; 10]> for HandsState {
-  fn from(value: [i32; 10]) -> Self {
+/// Returns iterator over `k`-combinations of `0..n` in lexicographic order.
+fn combinations(n: usize, k: usize) -> impl Iterator<Item = Vec<usize>> {
+  let first = (k <= n).then(|| (0..k).collect());
+  core::iter::successors(first, move |prev: &Vec<usize>| {
+    let i = (0..k).rev().find(|&i| prev[i] < n - k + i)?;
+    let mut next = prev.clone();
+    next[i] += 1;
+    for j in i + 1..k {
+      next[j] = next[j - 1] + 1;
+    }
+    Some(next)
+  })
+}
+
+impl<const N: usize> Default for HandsState<N> {
+  fn default() -> Self {
+    Self([FingerState::Released; N])
+  }
+}
+
+impl<const N: usize> From<[i32; N]> for HandsState<N> {
+  fn from(value: [i32; N]) -> Self {
     HandsState(value.map(FingerState::from))
   }
 }
 
-impl Deref for HandsState {
-  type Target = [FingerState; 10];
+/// Presses fingers pressed in either chord, like [HandsState::combine].
+impl<const N: usize> BitOr for HandsState<N> {
+  type Output = Self;
+
+  fn bitor(self, rhs: Self) -> Self {
+    self.zip_with(rhs, |a, b| a || b)
+  }
+}
+
+/// Presses fingers pressed in both chords.
+impl<const N: usize> BitAnd for HandsState<N> {
+  type Output = Self;
+
+  fn bitand(self, rhs: Self) -> Self {
+    self.zip_with(rhs, |a, b| a && b)
+  }
+}
+
+/// Releases fingers of `rhs`.
+impl<const N: usize> Sub for HandsState<N> {
+  type Output = Self;
+
+  fn sub(self, rhs: Self) -> Self {
+    self.zip_with(rhs, |a, b| a && !b)
+  }
+}
+
+impl<const N: usize> Deref for HandsState<N> {
+  type Target = [FingerState; N];
 
   fn deref(&self) -> &Self::Target {
     &self.0
   }
 }
 
-impl DerefMut for HandsState {
+impl<const N: usize> DerefMut for HandsState<N> {
   fn deref_mut(&mut self) -> &mut Self::Target {
     &mut self.0
   }
 }
 
-impl Display for HandsState {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    let (lh, rh) = self.split_at(5);
+impl<const N: usize> Display for HandsState<N> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let (lh, rh) = self.split_at(N.div_ceil(2));
     lh.iter().try_for_each(|fs| write!(f, "{}", fs))?;
     write!(f, " ")?;
     rh.iter().try_for_each(|fs| write!(f, "{}", fs))
   }
 }
 
+#[cfg(feature = "serde")]
+impl<const N: usize> Serialize for HandsState<N> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(self.iter())
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const N: usize> Deserialize<'de> for HandsState<N> {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    let human_readable = deserializer.is_human_readable();
+    deserializer.deserialize_seq(HandsStateVisitor { human_readable })
+  }
+}
+
+/// Element of a serialized chord: a finger state or a finger name.
+#[cfg(feature = "serde")]
+enum Entry {
+  State(u64),
+  Name(String),
+}
+
+#[cfg(feature = "serde")]
+impl Display for Entry {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Entry::State(s) => write!(f, "{s}"),
+      Entry::Name(n) => write!(f, "{n:?}"),
+    }
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Entry {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    struct EntryVisitor;
+
+    impl de::Visitor<'_> for EntryVisitor {
+      type Value = Entry;
+
+      fn expecting(
+        &self,
+        formatter: &mut core::fmt::Formatter,
+      ) -> core::fmt::Result {
+        write!(formatter, "a finger state of 0 or 1 or a finger name")
+      }
+
+      fn visit_u64<E: de::Error>(self, v: u64) -> Result<Entry, E> {
+        Ok(Entry::State(v))
+      }
+
+      fn visit_i64<E: de::Error>(self, v: i64) -> Result<Entry, E> {
+        u64::try_from(v)
+          .map(Entry::State)
+          .map_err(|_| E::invalid_value(de::Unexpected::Signed(v), &self))
+      }
+
+      fn visit_str<E: de::Error>(self, v: &str) -> Result<Entry, E> {
+        Ok(Entry::Name(v.to_owned()))
+      }
+    }
+
+    deserializer.deserialize_any(EntryVisitor)
+  }
+}
+
+/// Deserializes a chord from a sequence of finger states or finger names.
+/// The first element tells which of them the chord consists of. Formats that
+/// aren't human readable hold finger states only, since they can't tell an
+/// element's type without a hint.
+#[cfg(feature = "serde")]
+struct HandsStateVisitor<const N: usize> {
+  human_readable: bool,
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const N: usize> de::Visitor<'de> for HandsStateVisitor<N> {
+  type Value = HandsState<N>;
+
+  fn expecting(
+    &self,
+    formatter: &mut core::fmt::Formatter,
+  ) -> core::fmt::Result {
+    write!(formatter, "an array of {N} finger states or of finger names")
+  }
+
+  fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+  where
+    A: de::SeqAccess<'de>,
+  {
+    let pressing = |handstate: HandsState<N>, chord: &str| {
+      match handstate.count_pressed() {
+        0 => Err(de::Error::custom(format!(
+          "invalid chord [{chord}]: a chord must press at least one finger"
+        ))),
+        _ => Ok(handstate),
+      }
+    };
+    if !self.human_readable {
+      let mut states = Vec::with_capacity(N);
+      while let Some(state) = seq.next_element::<FingerState>()? {
+        states.push(state);
+      }
+      let chord = states
+        .iter()
+        .map(|&s| (s as u8).to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+      let found = states.len();
+      let handstate = states.try_into().map(HandsState).map_err(|_| {
+        de::Error::custom(format!(
+          "invalid chord [{chord}]: expected {N} finger states, found {found}"
+        ))
+      })?;
+      return pressing(handstate, &chord);
+    }
+    let mut entries = Vec::new();
+    let chord = |entries: &[Entry]| {
+      entries
+        .iter()
+        .map(Entry::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+    };
+    while let Some(entry) = seq.next_element::<Entry>()? {
+      let error = match (entries.first(), &entry) {
+        (None | Some(Entry::State(_)), Entry::State(0 | 1)) => None,
+        (None | Some(Entry::State(_)), Entry::State(_)) => {
+          Some("finger states must be 0 or 1")
+        }
+        (Some(Entry::State(_)), Entry::Name(_)) => {
+          Some("finger names can't follow finger states")
+        }
+        (Some(Entry::Name(_)), Entry::State(_)) => {
+          Some("finger states can't follow finger names")
+        }
+        (None | Some(Entry::Name(_)), Entry::Name(_)) => None,
+      };
+      entries.push(entry);
+      if let Some(error) = error {
+        return Err(de::Error::custom(format!(
+          "invalid chord [{}]: {error}",
+          chord(&entries)
+        )));
+      }
+    }
+    match entries.first() {
+      None => pressing(HandsState::default(), ""),
+      Some(Entry::Name(_)) => {
+        let names = entries.iter().filter_map(|e| match e {
+          Entry::Name(n) => Some(n),
+          Entry::State(_) => None,
+        });
+        let handstate = HandsState::from_finger_names(names).map_err(|e| {
+          de::Error::custom(format!("invalid chord [{}]: {e}", chord(&entries)))
+        })?;
+        handstate.as_slice().try_into().map(HandsState).map_err(|_| {
+          de::Error::custom(format!(
+            "invalid chord [{}]: finger names need 10 fingers, not {N}",
+            chord(&entries)
+          ))
+        })
+      }
+      Some(Entry::State(_)) => {
+        let states: Vec<_> = entries
+          .iter()
+          .map(|e| FingerState::from(matches!(e, Entry::State(1))))
+          .collect();
+        let handstate = states.try_into().map(HandsState).map_err(|_| {
+          de::Error::custom(format!(
+            "invalid chord [{}]: expected {N} finger states, found {}",
+            chord(&entries),
+            entries.len()
+          ))
+        })?;
+        pressing(handstate, &chord(&entries))
+      }
+    }
+  }
+}
+
+/// This error means that a finger name is not one of [FINGER_NAMES].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BadFingerName {
+  pub name: String,
+}
+
+impl Display for BadFingerName {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(
+      f,
+      "unknown finger name '{}', expected one of {}",
+      self.name,
+      FINGER_NAMES.join(", ")
+    )
+  }
+}
+
+/// Describes how thumbs may be used in chords. Used by chord enumeration,
+/// layout generators and layout validation.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ThumbPolicy {
+  /// Thumbs may be pressed along with other fingers.
+  pub with_fingers: bool,
+  /// A thumb may be pressed along with fingers of the same hand. Has no
+  /// effect if `with_fingers` is `false`.
+  pub same_hand: bool,
+  /// Both thumbs may be pressed in one chord.
+  pub both_thumbs: bool,
+  /// Thumbs may be pressed without other fingers.
+  pub alone: bool,
+}
+
+impl ThumbPolicy {
+  /// Thumbs are never used.
+  pub const NONE: ThumbPolicy = ThumbPolicy {
+    with_fingers: false,
+    same_hand: false,
+    both_thumbs: false,
+    alone: false,
+  };
+
+  /// A single thumb may be used as a modifier with fingers of any hand.
+  pub const MODIFIERS: ThumbPolicy = ThumbPolicy {
+    with_fingers: true,
+    same_hand: true,
+    both_thumbs: false,
+    alone: false,
+  };
+
+  /// Any thumb usage is allowed.
+  pub const ANY: ThumbPolicy = ThumbPolicy {
+    with_fingers: true,
+    same_hand: true,
+    both_thumbs: true,
+    alone: true,
+  };
+
+  /// Returns `true` if thumb usage in `handstate` is allowed by the policy.
+  pub fn allows(&self, handstate: &HandsState) -> bool {
+    let (left_thumb, right_thumb) = (handstate[4], handstate[5]);
+    if left_thumb.is_released() && right_thumb.is_released() {
+      return true;
+    }
+    if left_thumb.is_pressed() && right_thumb.is_pressed() && !self.both_thumbs
+    {
+      return false;
+    }
+    let left_fingers = handstate[0..4].contains(&FingerState::Pressed);
+    let right_fingers = handstate[6..10].contains(&FingerState::Pressed);
+    if !left_fingers && !right_fingers {
+      return self.alone;
+    }
+    let same_hand = (left_thumb.is_pressed() && left_fingers)
+      || (right_thumb.is_pressed() && right_fingers);
+    self.with_fingers && (self.same_hand || !same_hand)
+  }
+}
+
+impl Default for ThumbPolicy {
+  /// A single thumb may be used alone or as a modifier with fingers of any
+  /// hand.
+  fn default() -> Self {
+    ThumbPolicy {
+      alone: true,
+      ..ThumbPolicy::MODIFIERS
+    }
+  }
+}
+
 #[cfg(test)]
 mod tests {
+  use std::collections::HashSet;
+
   use super::*;
 
   #[test]
@@ -281,6 +732,28 @@ mod tests {
     assert_eq!(handstate[5], FingerState::Pressed);
   }
 
+  #[test]
+  fn test_handsstate_ops() {
+    let a: HandsState = [1, 1, 0, 0, 1, 0, 0, 0, 0, 0].into();
+    let b: HandsState = [0, 1, 1, 0, 0, 0, 0, 0, 0, 1].into();
+    assert_eq!(a | b, [1, 1, 1, 0, 1, 0, 0, 0, 0, 1].into());
+    assert_eq!(a & b, [0, 1, 0, 0, 0, 0, 0, 0, 0, 0].into());
+    assert_eq!(a - b, [1, 0, 0, 0, 1, 0, 0, 0, 0, 0].into());
+    assert_eq!(a - a, HandsState::default());
+    assert!(a.overlaps(&b));
+    assert!(!a.overlaps(&HandsState::right_thumb()));
+    assert!(HandsState::left_thumb().is_subset_of(&a));
+    assert!(HandsState::default().is_subset_of(&a));
+    assert!(!a.is_subset_of(&b));
+    assert!((a & b).is_subset_of(&a));
+
+    let pedals: HandsState<6> = [1, 0, 0, 0, 1, 1].into();
+    assert_eq!(pedals.to_string(), "|.. .||");
+    assert_eq!(pedals.hand_iter().count(), 2);
+    assert_eq!((pedals - [0, 0, 0, 0, 0, 1].into()).count_pressed(), 2);
+    assert_eq!(HandsState::<6>::default().count_pressed(), 0);
+  }
+
   #[test]
   fn test_iterate_one_key_no_thumbs() {
     let handstates: Vec<_> = HandsState::iterate_one_key_no_thumbs().collect();
@@ -315,6 +788,51 @@ mod tests {
     );
   }
 
+  #[test]
+  fn test_iterate_three_key_no_thumbs() {
+    let handstates: Vec<_> =
+      HandsState::iterate_three_key_no_thumbs().collect();
+    assert_eq!(handstates.len(), 56);
+    assert_eq!(handstates[0], [1, 1, 1, 0, 0, 0, 0, 0, 0, 0].into());
+    assert_eq!(handstates[1], [1, 1, 0, 1, 0, 0, 0, 0, 0, 0].into());
+    assert_eq!(handstates[55], [0, 0, 0, 0, 0, 0, 0, 1, 1, 1].into());
+    assert!(handstates.iter().all(|hs| hs.count_pressed() == 3
+      && hs[4].is_released()
+      && hs[5].is_released()));
+    assert_eq!(handstates.iter().collect::<HashSet<_>>().len(), 56);
+  }
+
+  #[test]
+  fn test_iterate_n_key() {
+    let counts: Vec<_> = (0..=11)
+      .map(|n| HandsState::iterate_n_key(n).count())
+      .collect();
+    assert_eq!(counts, [1, 10, 45, 120, 210, 252, 210, 120, 45, 10, 1, 0]);
+    assert!(HandsState::iterate_n_key(4).all(|hs| hs.count_pressed() == 4));
+    assert_eq!(
+      HandsState::iterate_n_key(2).nth(9),
+      Some([0, 1, 1, 0, 0, 0, 0, 0, 0, 0].into())
+    );
+  }
+
+  #[test]
+  fn test_iterate_n_key_one_hand() {
+    let counts: Vec<_> = (0..=6)
+      .map(|n| HandsState::iterate_n_key_one_hand(1, n).count())
+      .collect();
+    assert_eq!(counts, [1, 5, 10, 10, 5, 1, 0]);
+    assert!(HandsState::iterate_n_key_one_hand(0, 3).all(|hs| hs
+      .hand_iter()
+      .nth(1)
+      .unwrap()
+      .iter()
+      .all(|fs| fs.is_released())));
+    assert_eq!(
+      HandsState::iterate_n_key_one_hand(1, 2).next(),
+      Some([0, 0, 0, 0, 0, 1, 1, 0, 0, 0].into())
+    );
+  }
+
   #[test]
   fn test_iterate_one_two_key_no_thumbs() {
     let handstates: Vec<_> =
@@ -374,4 +892,220 @@ mod tests {
       .iter()
       .all(|hs| matches!(hs.count_pressed(), 1..=3)));
   }
+
+  #[test]
+  fn test_thumb_policy() {
+    let left_fingers_left_thumb = [1, 0, 0, 0, 1, 0, 0, 0, 0, 0].into();
+    let left_fingers_right_thumb = [1, 0, 0, 0, 0, 1, 0, 0, 0, 0].into();
+    let both_thumbs_fingers = [1, 0, 0, 0, 1, 1, 0, 0, 0, 0].into();
+    assert!(ThumbPolicy::NONE.allows(&[1, 1, 0, 0, 0, 0, 0, 0, 0, 0].into()));
+    assert!(!ThumbPolicy::NONE.allows(&HandsState::left_thumb()));
+    assert!(ThumbPolicy::MODIFIERS.allows(&left_fingers_left_thumb));
+    assert!(!ThumbPolicy::MODIFIERS.allows(&both_thumbs_fingers));
+    assert!(!ThumbPolicy::MODIFIERS.allows(&HandsState::right_thumb()));
+    assert!(ThumbPolicy::ANY.allows(&HandsState::both_thumbs()));
+    assert!(ThumbPolicy::ANY.allows(&both_thumbs_fingers));
+    let crossed_only = ThumbPolicy {
+      same_hand: false,
+      ..ThumbPolicy::MODIFIERS
+    };
+    assert!(!crossed_only.allows(&left_fingers_left_thumb));
+    assert!(crossed_only.allows(&left_fingers_right_thumb));
+  }
+
+  #[test]
+  fn test_iterate_one_two_key() {
+    let count = (1..=8).sum::<usize>();
+    assert_eq!(
+      HandsState::iterate_one_two_key(ThumbPolicy::NONE).count(),
+      count
+    );
+    assert_eq!(
+      HandsState::iterate_one_two_key(ThumbPolicy::ANY).count(),
+      count * 4 + 3
+    );
+    let crossed_only = ThumbPolicy {
+      same_hand: false,
+      ..ThumbPolicy::MODIFIERS
+    };
+    let handstates: Vec<_> =
+      HandsState::iterate_one_two_key(crossed_only).collect();
+    assert!(handstates.iter().all(|hs| crossed_only.allows(hs)));
+    // one finger chords of each hand combined with thumb of the other hand
+    // and two finger chords that use only fingers of the other hand
+    assert_eq!(handstates.len(), count + (4 + 6) * 2);
+  }
+
+  #[test]
+  fn test_from_finger_names() {
+    assert_eq!(
+      HandsState::from_finger_names(["LP", "ri"]),
+      Ok([1, 0, 0, 0, 0, 0, 1, 0, 0, 0].into())
+    );
+    assert_eq!(
+      HandsState::from_chord_name("LT+RP"),
+      Ok([0, 0, 0, 0, 1, 0, 0, 0, 0, 1].into())
+    );
+    assert_eq!(
+      HandsState::from_chord_name("LT+XX"),
+      Err(BadFingerName {
+        name: "XX".to_owned()
+      })
+    );
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_deserialize_handsstate() -> Result<(), serde_json::Error> {
+    let hs: HandsState = serde_json::from_str("[1,0,0,0,0,0,1,0,0,0]")?;
+    assert_eq!(hs, [1, 0, 0, 0, 0, 0, 1, 0, 0, 0].into());
+    let hs: HandsState = serde_json::from_str(r#"["LP", "RI"]"#)?;
+    assert_eq!(hs, [1, 0, 0, 0, 0, 0, 1, 0, 0, 0].into());
+    assert_eq!(serde_json::to_string(&hs)?, "[1,0,0,0,0,0,1,0,0,0]");
+
+    let err = serde_json::from_str::<HandsState>(r#"["LP", "XI"]"#)
+      .unwrap_err()
+      .to_string();
+    assert!(err
+      .starts_with("invalid chord [\"LP\",\"XI\"]: unknown finger name 'XI'"));
+    let err = serde_json::from_str::<HandsState>("[1, 0]")
+      .unwrap_err()
+      .to_string();
+    assert!(err.contains("expected 10 finger states, found 2"));
+    let err = serde_json::from_str::<HandsState>(r#"[1, "RI"]"#)
+      .unwrap_err()
+      .to_string();
+    assert!(err.starts_with(
+      "invalid chord [1,\"RI\"]: finger names can't follow finger states"
+    ));
+    let err = serde_json::from_str::<HandsState>(r#"["RI", 0]"#)
+      .unwrap_err()
+      .to_string();
+    assert!(err.starts_with(
+      "invalid chord [\"RI\",0]: finger states can't follow finger names"
+    ));
+    let err = serde_json::from_str::<HandsState>("[0, 2]")
+      .unwrap_err()
+      .to_string();
+    assert!(err.starts_with("invalid chord [0,2]: finger states must be 0 or 1"));
+    let err = serde_json::from_str::<HandsState>("[true]")
+      .unwrap_err()
+      .to_string();
+    assert!(err.starts_with(
+      "invalid type: boolean `true`, expected a finger state of 0 or 1 or a \
+       finger name"
+    ));
+    let err = serde_json::from_str::<HandsState>("[]")
+      .unwrap_err()
+      .to_string();
+    assert!(err.starts_with(
+      "invalid chord []: a chord must press at least one finger"
+    ));
+    let err = serde_json::from_str::<HandsState>("[0,0,0,0,0,0,0,0,0,0]")
+      .unwrap_err()
+      .to_string();
+    assert!(err.starts_with(
+      "invalid chord [0,0,0,0,0,0,0,0,0,0]: a chord must press at least one \
+       finger"
+    ));
+    assert!(serde_json::from_str::<HandsState>("{}").is_err());
+
+    let hs: HandsState<6> = serde_json::from_str("[0,1,0,0,0,1]")?;
+    assert_eq!(serde_json::to_string(&hs)?, "[0,1,0,0,0,1]");
+    let err = serde_json::from_str::<HandsState<6>>(r#"["LP"]"#)
+      .unwrap_err()
+      .to_string();
+    assert!(err.contains("finger names need 10 fingers, not 6"));
+    Ok(())
+  }
+
+  /// Reads a byte sequence prefixed with its length. Like binary formats, it
+  /// can't tell the type of a value without a hint.
+  #[cfg(feature = "serde")]
+  struct Binary<'a>(&'a [u8]);
+
+  #[cfg(feature = "serde")]
+  impl<'de> Deserializer<'de> for &mut Binary<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(
+      self,
+      _: V,
+    ) -> Result<V::Value, Self::Error> {
+      Err(de::Error::custom("a type hint is required"))
+    }
+
+    fn deserialize_u8<V: de::Visitor<'de>>(
+      self,
+      visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+      let (&byte, rest) = self
+        .0
+        .split_first()
+        .ok_or_else(|| de::Error::custom("unexpected end of input"))?;
+      self.0 = rest;
+      visitor.visit_u8(byte)
+    }
+
+    fn deserialize_seq<V: de::Visitor<'de>>(
+      self,
+      visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+      let len = u8::deserialize(&mut *self)?;
+      visitor.visit_seq(Elements(self, len))
+    }
+
+    fn is_human_readable(&self) -> bool {
+      false
+    }
+
+    serde::forward_to_deserialize_any! {
+      bool i8 i16 i32 i64 u16 u32 u64 f32 f64 char str string bytes byte_buf
+      option unit unit_struct newtype_struct tuple tuple_struct map struct
+      enum identifier ignored_any
+    }
+  }
+
+  #[cfg(feature = "serde")]
+  struct Elements<'a, 'b>(&'a mut Binary<'b>, u8);
+
+  #[cfg(feature = "serde")]
+  impl<'de> de::SeqAccess<'de> for Elements<'_, '_> {
+    type Error = de::value::Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+      &mut self,
+      seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+      if self.1 == 0 {
+        return Ok(None);
+      }
+      self.1 -= 1;
+      seed.deserialize(&mut *self.0).map(Some)
+    }
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_deserialize_handsstate_binary() -> Result<(), de::value::Error> {
+    let mut binary = Binary(&[10, 1, 0, 0, 0, 0, 0, 1, 0, 0, 0]);
+    let hs = HandsState::deserialize(&mut binary)?;
+    assert_eq!(hs, [1, 0, 0, 0, 0, 0, 1, 0, 0, 0].into());
+    assert!(binary.0.is_empty());
+
+    let mut binary = Binary(&[10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    let err = HandsState::<10>::deserialize(&mut binary)
+      .unwrap_err()
+      .to_string();
+    assert!(err.starts_with(
+      "invalid chord [0,0,0,0,0,0,0,0,0,0]: a chord must press at least one \
+       finger"
+    ));
+    let err = HandsState::<10>::deserialize(&mut Binary(&[2, 1, 0]))
+      .unwrap_err()
+      .to_string();
+    assert!(err.contains("expected 10 finger states, found 2"));
+    assert!(HandsState::<10>::deserialize(&mut Binary(&[2, 1, 2])).is_err());
+    Ok(())
+  }
 }
@@ -0,0 +1,402 @@
+//! Contains scoring of layouts from cached char and bigram counts, which
+//! can be updated after a swap without retyping the corpus.
+
+use std::collections::HashMap;
+
+use super::{
+  hands::{FingerState, HandsState},
+  layout::tenboard::{BadSwap, Tenboard},
+};
+use crate::corpus::NgramTable;
+
+pub(crate) type UnaryCost = Box<dyn Fn(&HandsState) -> f32>;
+pub(crate) type PairCost = Box<dyn Fn(&HandsState, &HandsState) -> f32>;
+
+/// Scores a layout as a weighted sum of per-chord and per-bigram costs over
+/// cached char and bigram counts of a corpus. Swapping chords of two chars
+/// only recomputes entries containing them, which makes it much faster than
+/// retyping the corpus in optimizers like
+/// [Annealing](super::optimize::Annealing).
+///
+/// Only metrics that depend on single chords and pairs of consecutive
+/// chords can be expressed this way. See [same_finger] and [same_hand] for
+/// costs matching [FingerAlternation](super::metric::FingerAlternation) and
+/// [HandAlternation](super::metric::HandAlternation).
+///
+/// ```
+/// use tenboard::keyboard::{
+///   incremental::{same_finger, IncrementalScorer},
+///   layout::tenboard::{Tenboard, TenboardUnconstrained},
+/// };
+///
+/// let layout = TenboardUnconstrained::new_random();
+/// let mut scorer = IncrementalScorer::from_text(&layout, "hello world");
+/// scorer.add_pair_cost(1.0, same_finger);
+/// let before = scorer.score();
+/// let delta = scorer.swap_delta('l', 'w').unwrap();
+/// let after = scorer.try_swap('l', 'w').unwrap();
+/// assert!((after - before - delta).abs() < 1e-4);
+/// ```
+pub struct IncrementalScorer {
+  chars: Vec<char>,
+  indices: HashMap<char, usize>,
+  chords: Vec<HandsState>,
+  char_counts: Vec<f32>,
+  /// Indices of the first and second chars with the bigram's count.
+  bigrams: Vec<(usize, usize, f32)>,
+  /// Indices of bigrams containing every char.
+  char_bigrams: Vec<Vec<usize>>,
+  unary_costs: Vec<(f32, UnaryCost)>,
+  pair_costs: Vec<(f32, PairCost)>,
+  score: f64,
+}
+
+impl IncrementalScorer {
+  /// Creates a scorer without costs from char and bigram counts. Chars
+  /// missing in `layout` are ignored.
+  pub fn from_counts(
+    layout: &dyn Tenboard,
+    chars: impl IntoIterator<Item = (char, f32)>,
+    bigrams: impl IntoIterator<Item = ([char; 2], f32)>,
+  ) -> Self {
+    let mut scorer = Self {
+      chars: Vec::new(),
+      indices: HashMap::new(),
+      chords: Vec::new(),
+      char_counts: Vec::new(),
+      bigrams: Vec::new(),
+      char_bigrams: Vec::new(),
+      unary_costs: Vec::new(),
+      pair_costs: Vec::new(),
+      score: 0.0,
+    };
+    for (ch, count) in chars {
+      if let Some(i) = scorer.index(layout, ch) {
+        scorer.char_counts[i] += count;
+      }
+    }
+    let mut bigram_indices = HashMap::new();
+    for ([first, second], count) in bigrams {
+      let (Some(i), Some(j)) =
+        (scorer.index(layout, first), scorer.index(layout, second))
+      else {
+        continue;
+      };
+      let idx = *bigram_indices.entry((i, j)).or_insert_with(|| {
+        scorer.bigrams.push((i, j, 0.0));
+        scorer.char_bigrams[i].push(scorer.bigrams.len() - 1);
+        if i != j {
+          scorer.char_bigrams[j].push(scorer.bigrams.len() - 1);
+        }
+        scorer.bigrams.len() - 1
+      });
+      scorer.bigrams[idx].2 += count;
+    }
+    scorer
+  }
+
+  /// Creates a scorer without costs from chars and bigrams of `text`.
+  pub fn from_text(layout: &dyn Tenboard, text: &str) -> Self {
+    let chars: Vec<_> = text.chars().collect();
+    Self::from_counts(
+      layout,
+      chars.iter().map(|&ch| (ch, 1.0)),
+      chars.windows(2).map(|w| ([w[0], w[1]], 1.0)),
+    )
+  }
+
+  /// Creates a scorer without costs from frequencies of `table`.
+  pub fn from_ngrams(layout: &dyn Tenboard, table: &NgramTable) -> Self {
+    Self::from_counts(
+      layout,
+      table.char_frequencies(),
+      table.bigram_frequencies(),
+    )
+  }
+
+  /// Returns index of `ch`, adding it if it's in `layout`.
+  fn index(&mut self, layout: &dyn Tenboard, ch: char) -> Option<usize> {
+    if let Some(&i) = self.indices.get(&ch) {
+      return Some(i);
+    }
+    let chord = layout.try_type_char(ch).ok()?;
+    self.indices.insert(ch, self.chars.len());
+    self.chars.push(ch);
+    self.chords.push(chord);
+    self.char_counts.push(0.0);
+    self.char_bigrams.push(Vec::new());
+    Some(self.chars.len() - 1)
+  }
+
+  /// Adds cost of typing a single chord multiplied by `weight`.
+  pub fn add_unary_cost(
+    &mut self,
+    weight: f32,
+    cost: impl Fn(&HandsState) -> f32 + 'static,
+  ) -> &mut Self {
+    self.unary_costs.push((weight, Box::new(cost)));
+    self.rescore();
+    self
+  }
+
+  /// Adds cost of typing a chord right after another one multiplied by
+  /// `weight`.
+  pub fn add_pair_cost(
+    &mut self,
+    weight: f32,
+    cost: impl Fn(&HandsState, &HandsState) -> f32 + 'static,
+  ) -> &mut Self {
+    self.pair_costs.push((weight, Box::new(cost)));
+    self.rescore();
+    self
+  }
+
+  pub fn score(&self) -> f32 {
+    self.score as f32
+  }
+
+  /// Returns current chord of `ch` if it's in the corpus.
+  pub fn chord(&self, ch: char) -> Option<HandsState> {
+    self.indices.get(&ch).map(|&i| self.chords[i])
+  }
+
+  /// Recomputes the score from scratch.
+  pub fn rescore(&mut self) {
+    let unary: f64 = (0..self.chars.len()).map(|i| self.char_cost(i)).sum();
+    let pairs: f64 = (0..self.bigrams.len()).map(|b| self.bigram_cost(b)).sum();
+    self.score = unary + pairs;
+  }
+
+  /// Returns how the score changes if chords of `ch1` and `ch2` are
+  /// swapped. Returns an error if any of them isn't in the corpus.
+  pub fn swap_delta(&mut self, ch1: char, ch2: char) -> Result<f32, BadSwap> {
+    let (i, j) = self.indices_of(ch1, ch2)?;
+    let delta = self.swap_indices(i, j);
+    self.chords.swap(i, j);
+    Ok(delta as f32)
+  }
+
+  /// Swaps chords of `ch1` and `ch2` and returns the new score. Returns an
+  /// error if any of them isn't in the corpus.
+  pub fn try_swap(&mut self, ch1: char, ch2: char) -> Result<f32, BadSwap> {
+    let (i, j) = self.indices_of(ch1, ch2)?;
+    self.score += self.swap_indices(i, j);
+    Ok(self.score())
+  }
+
+  fn indices_of(
+    &self,
+    ch1: char,
+    ch2: char,
+  ) -> Result<(usize, usize), BadSwap> {
+    match (self.indices.get(&ch1), self.indices.get(&ch2)) {
+      (Some(&i), Some(&j)) => Ok((i, j)),
+      _ => Err(BadSwap { ch1, ch2 }),
+    }
+  }
+
+  /// Swaps chords of chars `i` and `j` and returns the score delta.
+  fn swap_indices(&mut self, i: usize, j: usize) -> f64 {
+    let chars = if i == j { vec![i] } else { vec![i, j] };
+    self.reassign(&chars, |chords| chords.swap(i, j))
+  }
+
+  /// Changes chords of `chars` with `apply` and returns the score delta.
+  /// Only costs of `chars` and bigrams containing them are recomputed.
+  fn reassign(
+    &mut self,
+    chars: &[usize],
+    apply: impl FnOnce(&mut [HandsState]),
+  ) -> f64 {
+    let mut affected: Vec<_> = chars
+      .iter()
+      .flat_map(|&c| &self.char_bigrams[c])
+      .copied()
+      .collect();
+    affected.sort_unstable();
+    affected.dedup();
+    let cost = |scorer: &Self| {
+      chars.iter().map(|&c| scorer.char_cost(c)).sum::<f64>()
+        + affected.iter().map(|&b| scorer.bigram_cost(b)).sum::<f64>()
+    };
+    let before = cost(self);
+    apply(&mut self.chords);
+    cost(self) - before
+  }
+
+  fn char_cost(&self, i: usize) -> f64 {
+    let chord = &self.chords[i];
+    let cost: f32 = self.unary_costs.iter().map(|(w, c)| w * c(chord)).sum();
+    (cost * self.char_counts[i]) as f64
+  }
+
+  fn bigram_cost(&self, b: usize) -> f64 {
+    let (i, j, count) = self.bigrams[b];
+    let (first, second) = (&self.chords[i], &self.chords[j]);
+    let cost: f32 = self
+      .pair_costs
+      .iter()
+      .map(|(w, c)| w * c(first, second))
+      .sum();
+    (cost * count) as f64
+  }
+}
+
+/// Scores whole layouts with an [IncrementalScorer], so it can be used as
+/// a score function of optimizers that don't report their swaps, like
+/// [Annealing](super::optimize::Annealing) and
+/// [HillClimb](super::optimize::HillClimb). Every call compares chords of
+/// the layout with the previously scored one and recomputes only costs of
+/// chars whose chords changed and bigrams containing them.
+///
+/// ```
+/// use tenboard::keyboard::{
+///   incremental::{same_finger, CachedScorer, IncrementalScorer},
+///   layout::tenboard::{Tenboard, TenboardUnconstrained},
+///   optimize::Annealing,
+/// };
+///
+/// let layout = TenboardUnconstrained::new_random();
+/// let mut scorer = IncrementalScorer::from_text(&layout, "hello world");
+/// scorer.add_pair_cost(1.0, same_finger);
+/// let mut cached = CachedScorer::new(scorer);
+/// let optimized = Annealing::new()
+///   .set_iterations(100)
+///   .optimize(layout, |layout| cached.score(layout));
+/// ```
+pub struct CachedScorer {
+  scorer: IncrementalScorer,
+}
+
+impl CachedScorer {
+  pub fn new(scorer: IncrementalScorer) -> Self {
+    Self { scorer }
+  }
+
+  /// Returns score of `layout`. It's infinite if some char of the corpus
+  /// can't be typed with `layout`.
+  pub fn score(&mut self, layout: &dyn Tenboard) -> f32 {
+    let scorer = &mut self.scorer;
+    let mut changed = Vec::new();
+    let mut chords = Vec::new();
+    for (i, &ch) in scorer.chars.iter().enumerate() {
+      let Ok(chord) = layout.try_type_char(ch) else {
+        return f32::INFINITY;
+      };
+      if chord != scorer.chords[i] {
+        changed.push(i);
+        chords.push(chord);
+      }
+    }
+    if !changed.is_empty() {
+      let apply = |all: &mut [HandsState]| {
+        for (&i, chord) in changed.iter().zip(chords) {
+          all[i] = chord;
+        }
+      };
+      scorer.score += scorer.reassign(&changed, apply);
+    }
+    scorer.score()
+  }
+
+  pub fn scorer(&self) -> &IncrementalScorer {
+    &self.scorer
+  }
+
+  pub fn into_scorer(self) -> IncrementalScorer {
+    self.scorer
+  }
+}
+
+/// Returns the number of fingers pressed in both chords, like
+/// [FingerAlternation](super::metric::FingerAlternation) counts.
+pub fn same_finger(first: &HandsState, second: &HandsState) -> f32 {
+  (*first & *second).count_pressed() as f32
+}
+
+/// Returns the number of hands used in both chords, like
+/// [HandAlternation](super::metric::HandAlternation) counts.
+pub fn same_hand(first: &HandsState, second: &HandsState) -> f32 {
+  first
+    .hand_iter()
+    .zip(second.hand_iter())
+    .filter(|(a, b)| {
+      a.contains(&FingerState::Pressed) && b.contains(&FingerState::Pressed)
+    })
+    .count() as f32
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+  use super::*;
+  use crate::keyboard::{
+    effort::{Effort, EffortModel, FingerEffort},
+    layout::tenboard::TenboardUnconstrained,
+    metric::{FingerAlternation, HandAlternation, Metric},
+    Keyboard,
+  };
+
+  #[test]
+  fn test_matches_full_scoring() {
+    let text = "the quick brown fox jumps over the lazy dog. Hello, world!";
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut layout = TenboardUnconstrained::new_random_with_rng(&mut rng);
+    let mut scorer = IncrementalScorer::from_text(&layout, text);
+    let effort = FingerEffort::new();
+    scorer
+      .add_pair_cost(1.0, same_finger)
+      .add_pair_cost(0.5, same_hand)
+      .add_unary_cost(0.1, move |hs| effort.cost(hs));
+    let full = |layout: &TenboardUnconstrained| {
+      let hss = layout.type_chars(text.chars());
+      FingerAlternation::new().updated(&hss).score()
+        + 0.5 * HandAlternation::new().updated(&hss).score()
+        + 0.1 * Effort::default().updated(&hss).score()
+    };
+    assert!((scorer.score() - full(&layout)).abs() < 1e-3);
+
+    let chars: Vec<_> = "thequickbrownfxjmpsvlazydg.,H!".chars().collect();
+    for _ in 0..50 {
+      let (ch1, ch2) = (
+        *chars.choose(&mut rng).unwrap(),
+        *chars.choose(&mut rng).unwrap(),
+      );
+      let before = scorer.score();
+      let delta = scorer.swap_delta(ch1, ch2).unwrap();
+      assert_eq!(scorer.score(), before);
+      let score = scorer.try_swap(ch1, ch2).unwrap();
+      layout.swap_states(ch1, ch2);
+      assert!((score - before - delta).abs() < 1e-3);
+      assert!((score - full(&layout)).abs() < 1e-3);
+    }
+    assert_eq!(scorer.chord('t'), layout.try_type_char('t').ok());
+    assert!(scorer.try_swap('t', 'Z').is_err());
+  }
+
+  #[test]
+  fn test_cached_scorer() {
+    let text = "the quick brown fox jumps over the lazy dog";
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut layout = TenboardUnconstrained::new_random_with_rng(&mut rng);
+    let mut scorer = IncrementalScorer::from_text(&layout, text);
+    scorer.add_pair_cost(1.0, same_finger);
+    let mut cached = CachedScorer::new(scorer);
+    let full = |layout: &TenboardUnconstrained| {
+      let hss = layout.type_chars(text.chars());
+      FingerAlternation::new().updated(&hss).score()
+    };
+    assert!((cached.score(&layout) - full(&layout)).abs() < 1e-3);
+    let chars: Vec<_> = "thequickbrownfxjmpsvlazydg".chars().collect();
+    for _ in 0..20 {
+      for _ in 0..3 {
+        let mut pair = chars.choose_multiple(&mut rng, 2);
+        layout.swap_states(*pair.next().unwrap(), *pair.next().unwrap());
+      }
+      assert!((cached.score(&layout) - full(&layout)).abs() < 1e-3);
+    }
+    let other = TenboardUnconstrained::new_random_with_rng(&mut rng);
+    assert!((cached.score(&other) - full(&other)).abs() < 1e-3);
+  }
+}
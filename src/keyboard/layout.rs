@@ -1,2 +1,13 @@
+pub mod artsey;
 pub mod asetniop;
+mod chord_map;
+pub mod constraints;
+pub mod dead_keys;
+pub mod grapheme;
+pub mod layered;
+pub mod metadata;
+pub mod modifiers;
+pub mod rows;
+pub mod standard;
 pub mod tenboard;
+pub mod word_chords;
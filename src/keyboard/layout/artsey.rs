@@ -0,0 +1,158 @@
+//! Describes ARTSEY keyboard layout.
+//!
+//! ARTSEY is a one-handed layout for 8 keys arranged in two rows of four:
+//! <pre>
+//! A R T S
+//! E Y I O
+//! </pre>
+//! Keys are numbered in reading order, so chords are `HandsState<8>`s and
+//! [hand_iter](HandsState::hand_iter) yields the top and the bottom rows.
+//! Shift is a one-shot chord, A+R+T+O, typed before a letter chord.
+
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+
+use crate::keyboard::{
+  hands::HandsState,
+  shift::{unshifted, Shift, ShiftPolicy},
+  Keyboard,
+  NoSuchChar,
+};
+
+lazy_static! {
+static ref LAYOUT: HashMap<char, HandsState<8>> = HashMap::from([
+  // lowercase
+  ('a', [1, 0, 0, 0, 0, 0, 0, 0].into()),
+  ('b', [0, 0, 0, 0, 1, 0, 0, 1].into()),
+  ('c', [0, 0, 0, 0, 1, 1, 0, 0].into()),
+  ('d', [1, 1, 1, 0, 0, 0, 0, 0].into()),
+  ('e', [0, 0, 0, 0, 1, 0, 0, 0].into()),
+  ('f', [1, 1, 0, 0, 0, 0, 0, 0].into()),
+  ('g', [0, 1, 1, 0, 0, 0, 0, 0].into()),
+  ('h', [0, 0, 0, 0, 1, 0, 1, 0].into()),
+  ('i', [0, 0, 0, 0, 0, 0, 1, 0].into()),
+  ('j', [0, 0, 1, 1, 0, 0, 0, 0].into()),
+  ('k', [0, 0, 0, 0, 0, 1, 0, 1].into()),
+  ('l', [0, 0, 0, 0, 1, 1, 1, 0].into()),
+  ('m', [0, 0, 0, 0, 0, 1, 1, 1].into()),
+  ('n', [0, 0, 0, 0, 0, 0, 1, 1].into()),
+  ('o', [0, 0, 0, 0, 0, 0, 0, 1].into()),
+  ('p', [0, 0, 0, 0, 1, 0, 1, 1].into()),
+  ('q', [1, 0, 1, 1, 0, 0, 0, 0].into()),
+  ('r', [0, 1, 0, 0, 0, 0, 0, 0].into()),
+  ('s', [0, 0, 0, 1, 0, 0, 0, 0].into()),
+  ('t', [0, 0, 1, 0, 0, 0, 0, 0].into()),
+  ('u', [0, 0, 0, 0, 0, 1, 1, 0].into()),
+  ('v', [0, 1, 0, 1, 0, 0, 0, 0].into()),
+  ('w', [1, 0, 0, 1, 0, 0, 0, 0].into()),
+  ('x', [0, 1, 1, 1, 0, 0, 0, 0].into()),
+  ('y', [0, 0, 0, 0, 0, 1, 0, 0].into()),
+  ('z', [1, 1, 1, 1, 0, 0, 0, 0].into()),
+  // whitespace
+  (' ', [0, 0, 0, 0, 1, 1, 1, 1].into()),
+]);
+static ref SHIFT: HandsState<8> = [1, 1, 1, 0, 0, 0, 0, 1].into();
+}
+
+/// ARTSEY layout on its own 8 keys.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct Artsey {}
+
+impl Artsey {
+  pub fn new() -> Self {
+    Self {}
+  }
+
+  /// Returns a hand state that describes necessary key combination for
+  /// given unshifted char to be typed.
+  pub fn try_type_char(&self, ch: char) -> Result<HandsState<8>, NoSuchChar> {
+    LAYOUT.get(&ch).copied().ok_or(NoSuchChar { ch })
+  }
+}
+
+/// Uppercase letters are typed with the one-shot shift chord first.
+impl Shift<8> for Artsey {
+  fn shift_policy(&self) -> ShiftPolicy<8> {
+    ShiftPolicy::Sticky(*SHIFT)
+  }
+}
+
+impl Keyboard<8> for Artsey {
+  fn try_type_chars(
+    &self,
+    chars: impl Iterator<Item = char>,
+  ) -> Result<Vec<HandsState<8>>, NoSuchChar> {
+    let mut handstates = Vec::new();
+    for ch in chars {
+      match (self.try_type_char(ch), unshifted(ch)) {
+        (Ok(handstate), _) => handstates.push(handstate),
+        (Err(_), Some(lower)) => {
+          let base =
+            self.try_type_char(lower).map_err(|_| NoSuchChar { ch })?;
+          handstates.extend([*SHIFT, base]);
+        }
+        (Err(e), None) => return Err(e),
+      }
+    }
+    Ok(handstates)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+
+  use super::*;
+  use crate::keyboard::{
+    metric::{BigChords, FingerUsage, HandUsage, Metric},
+    LOWERCASE_CHARS,
+    UPPERCASE_CHARS,
+  };
+
+  #[test]
+  fn test_all_letters_unique() {
+    let kb = Artsey::new();
+    let hs_set: HashSet<HandsState<8>> = kb
+      .try_type_chars(LOWERCASE_CHARS.chars())
+      .unwrap()
+      .into_iter()
+      .collect();
+    assert_eq!(hs_set.len(), 26);
+    assert!(!hs_set.contains(&SHIFT));
+    let policy = kb.shift_policy();
+    for (lower, upper) in LOWERCASE_CHARS.chars().zip(UPPERCASE_CHARS.chars()) {
+      let lower = kb.try_type_char(lower).unwrap();
+      assert_eq!(
+        policy.shifted(&lower),
+        Some(kb.type_chars([upper].into_iter()))
+      );
+    }
+  }
+
+  #[test]
+  fn test_typing() {
+    let kb = Artsey::new();
+    assert_eq!(kb.type_chars("Do".chars()), vec![
+      [1, 1, 1, 0, 0, 0, 0, 1].into(),
+      [1, 1, 1, 0, 0, 0, 0, 0].into(),
+      [0, 0, 0, 0, 0, 0, 0, 1].into(),
+    ]);
+    assert_eq!(kb.try_type_char('!'), Err(NoSuchChar { ch: '!' }));
+    assert_eq!(kb.try_type_text("aÉ").unwrap_err().ch, 'É');
+  }
+
+  #[test]
+  fn test_metrics() {
+    let handstates = Artsey::new().type_chars("Art sea".chars());
+    let usage = FingerUsage::<8>::default().updated(&handstates);
+    assert_eq!(usage.breakdown(), None);
+    assert_eq!(usage.values(), [3, 2, 2, 1, 2, 1, 1, 2]);
+    let rows = HandUsage::<8>::default().updated(&handstates);
+    assert_eq!(rows.values(), [8, 6]);
+    // the shift and the space chords
+    let big_chords = BigChords::<8>::default().updated(&handstates);
+    assert_eq!(big_chords.triples().len(), 8);
+    assert_eq!(big_chords.values(), 2);
+  }
+}
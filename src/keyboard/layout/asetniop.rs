@@ -1,13 +1,17 @@
 //! Describes ASETNIOP keyboard layout.
 
-use std::{cell::Cell, collections::HashMap};
+use std::collections::HashMap;
 
 use lazy_static::lazy_static;
 
+use super::layered::{Layer, LayeredChordKeyboard};
 use crate::keyboard::{
   hands::{FingerState, HandsState},
+  shift::{Shift, ShiftPolicy},
   Keyboard,
   NoSuchChar,
+  TypingError,
+  BACKSPACE,
 };
 
 const SWITCH_COMBINATION: HandsState = HandsState([
@@ -81,6 +85,7 @@ static ref LETTERS_LAYOUT: HashMap<char, HandsState> = HashMap::from([
   ('Z', [1, 0, 0, 0, 1, 0, 0, 1, 0, 0].into()),
   // symbols (no shift)
   (' ', [0, 0, 0, 0, 0, 1, 0, 0, 0, 0].into()),
+  (BACKSPACE, [0, 0, 0, 0, 0, 1, 0, 0, 0, 1].into()),
   ('!', [0, 0, 0, 0, 0, 0, 0, 1, 0, 1].into()),
   ('\'', [0, 0, 1, 0, 0, 0, 0, 0, 0, 1].into()),
   (';', [0, 0, 0, 0, 0, 0, 0, 0, 1, 1].into()),
@@ -106,6 +111,7 @@ static ref LETTERS_LAYOUT: HashMap<char, HandsState> = HashMap::from([
 static ref SYMBOLS_LAYOUT: HashMap<char, HandsState> = HashMap::from([
   // no shift
   (' ', [0, 0, 0, 0, 0, 1, 0, 0, 0, 0].into()),
+  (BACKSPACE, [0, 0, 0, 0, 0, 1, 0, 0, 0, 1].into()),
   ('1', [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into()),
   ('`', [1, 0, 1, 0, 0, 0, 0, 0, 0, 0].into()),
   ('[', [1, 0, 0, 1, 0, 0, 0, 0, 0, 0].into()),
@@ -152,41 +158,165 @@ static ref SYMBOLS_LAYOUT: HashMap<char, HandsState> = HashMap::from([
 ]);
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum Layout {
-  Letters(&'static HashMap<char, HandsState>),
-  Symbols(&'static HashMap<char, HandsState>),
+/// Layer of [Asetniop].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AsetniopLayer {
+  Letters,
+  Symbols,
 }
 
-impl Layout {
-  fn new_letters() -> Layout {
-    Layout::Letters(&LETTERS_LAYOUT)
-  }
+/// ASETNIOP layout with letters and symbols layers. Both layers are switched
+/// to with the same chord. By default the symbols layer stays active until
+/// a letter is typed, see [Asetniop::set_one_shot_symbols] for the
+/// behavior of the real ASETNIOP.
+///
+/// Space is the right thumb and [BACKSPACE] is the right thumb with the
+/// right pinky in both layers. Shifted chars are typed with the left thumb
+/// added to the chord, or before it with [Asetniop::set_sticky_shift].
+///
+/// Like the real ASETNIOP, the layout may have a dictionary of words typed
+/// with a single chord of all their letters, see
+/// [Asetniop::add_dictionary].
+#[derive(Debug, Clone)]
+pub struct Asetniop {
+  keyboard: LayeredChordKeyboard,
+  sticky_shift: bool,
+}
 
-  fn new_symbols() -> Layout {
-    Layout::Symbols(&SYMBOLS_LAYOUT)
+impl Default for Asetniop {
+  fn default() -> Self {
+    let mut keyboard = LayeredChordKeyboard::new();
+    keyboard
+      .add_layer(Layer::new(LETTERS_LAYOUT.clone(), SWITCH_COMBINATION))
+      .add_layer(Layer::new(SYMBOLS_LAYOUT.clone(), SWITCH_COMBINATION));
+    Self {
+      keyboard,
+      sticky_shift: false,
+    }
   }
 }
 
-impl Default for Layout {
-  fn default() -> Self {
-    Self::new_letters()
+impl Asetniop {
+  /// Returns number of layer switches made while typing since creation or
+  /// the last [Asetniop::reset_switch_count].
+  pub fn switch_count(&self) -> u64 {
+    self.keyboard.switch_count()
+  }
+
+  pub fn reset_switch_count(&self) {
+    self.keyboard.reset_switch_count();
+  }
+
+  /// Returns the layer active after the last typed char.
+  pub fn current_layer(&self) -> AsetniopLayer {
+    match self.keyboard.current_layer() {
+      0 => AsetniopLayer::Letters,
+      _ => AsetniopLayer::Symbols,
+    }
+  }
+
+  /// Makes the letters layer active.
+  pub fn reset_layer(&self) {
+    self.keyboard.reset();
+  }
+
+  /// Sets whether the symbols layer reverts to letters after one symbol,
+  /// like in the real ASETNIOP, instead of staying active until a letter
+  /// is typed.
+  pub fn set_one_shot_symbols(&mut self, one_shot: bool) -> &mut Self {
+    if let Some(layer) = self.keyboard.layer_mut(1) {
+      layer.set_sticky(!one_shot);
+    }
+    self
+  }
+
+  pub fn is_one_shot_symbols(&self) -> bool {
+    !self.keyboard.layers()[1].is_sticky()
+  }
+
+  /// Sets whether shift is a chord of its own: the left thumb is pressed
+  /// alone before the unshifted chord instead of together with it.
+  pub fn set_sticky_shift(&mut self, sticky_shift: bool) -> &mut Self {
+    self.sticky_shift = sticky_shift;
+    self
+  }
+
+  pub fn is_sticky_shift(&self) -> bool {
+    self.sticky_shift
+  }
+
+  /// Adds `words` to the dictionary, the most frequent words first. A word
+  /// is typed with all fingers of its letters pressed at once, e.g. "the"
+  /// with both index and middle fingers, when it is a whole word typed from
+  /// the letters layer. Words whose chord
+  /// already types a letter, another word or switches layers are skipped,
+  /// as are words with chars other than lowercase letters.
+  ///
+  /// ```
+  /// use tenboard::keyboard::{layout::asetniop::Asetniop, Keyboard};
+  ///
+  /// let mut kb = Asetniop::default();
+  /// kb.add_dictionary(["the", "eth", "and"]);
+  /// assert_eq!(kb.dictionary().count(), 2);
+  /// assert_eq!(kb.type_chars("the end".chars()).len(), 5);
+  /// ```
+  pub fn add_dictionary<'a>(
+    &mut self,
+    words: impl IntoIterator<Item = &'a str>,
+  ) -> &mut Self {
+    for word in words {
+      let chord = word.chars().try_fold(HandsState::default(), |chord, ch| {
+        let hs = LETTERS_LAYOUT.get(&ch).filter(|_| ch.is_lowercase())?;
+        Some(chord | *hs)
+      });
+      let Some(chord) = chord else {
+        continue;
+      };
+      let layer = &self.keyboard.layers()[0];
+      let taken = chord == SWITCH_COMBINATION
+        || layer.chords().values().any(|hs| *hs == chord)
+        || layer.words().iter().any(|(w, hs)| hs == chord && w != word);
+      if !taken {
+        if let Some(layer) = self.keyboard.layer_mut(0) {
+          layer.add_word(word, chord);
+        }
+      }
+    }
+    self
+  }
+
+  /// Returns dictionary words with their chords, the longest words first.
+  pub fn dictionary(&self) -> impl Iterator<Item = (&str, HandsState)> {
+    self.keyboard.layers()[0].words().iter()
   }
 }
 
-#[derive(Default)]
-pub struct Asetniop {
-  layout: Cell<Layout>,
+/// Uppercase letters are typed with the left thumb added, or after it with
+/// sticky shift.
+impl Shift for Asetniop {
+  fn shift_policy(&self) -> ShiftPolicy {
+    match self.sticky_shift {
+      true => ShiftPolicy::Sticky(HandsState::left_thumb()),
+      false => ShiftPolicy::Modifier(HandsState::left_thumb()),
+    }
+  }
 }
 
 impl Asetniop {
-  /// Swaps the layout from letters to symbols.
-  fn swap_layout(&self) {
-    let layout = match self.layout.get() {
-      Layout::Letters(_) => Layout::new_symbols(),
-      Layout::Symbols(_) => Layout::new_letters(),
-    };
-    self.layout.set(layout);
+  /// Splits shifted chords into the shift and the rest with sticky shift.
+  fn apply_shift(&self, handstates: Vec<HandsState>) -> Vec<HandsState> {
+    if !self.sticky_shift {
+      return handstates;
+    }
+    let shift = HandsState::left_thumb();
+    let modifier = ShiftPolicy::Modifier(shift);
+    handstates
+      .into_iter()
+      .flat_map(|hs| match modifier.is_shift_press(&hs) {
+        true => vec![shift, hs - shift],
+        false => vec![hs],
+      })
+      .collect()
   }
 }
 
@@ -195,28 +325,127 @@ impl Keyboard for Asetniop {
     &self,
     chars: impl Iterator<Item = char>,
   ) -> Result<Vec<HandsState>, NoSuchChar> {
-    let mut handstates: Vec<HandsState> = Vec::new();
-    for ch in chars {
-      let maybe_hs = match self.layout.get() {
-        Layout::Letters(l) => l.get(&ch),
-        Layout::Symbols(l) => l.get(&ch),
-      };
-      if let Some(hs) = maybe_hs {
-        handstates.push(hs.to_owned());
-      } else {
-        self.swap_layout();
-        let maybe_hs = match self.layout.get() {
-          Layout::Letters(l) => l.get(&ch),
-          Layout::Symbols(l) => l.get(&ch),
-        };
-        if let Some(hs) = maybe_hs {
-          handstates.push(SWITCH_COMBINATION.to_owned());
-          handstates.push(hs.to_owned());
-        } else {
-          return Err(NoSuchChar { ch });
-        }
-      }
+    let handstates = self.keyboard.try_type_chars(chars)?;
+    Ok(self.apply_shift(handstates))
+  }
+
+  fn try_type_text(
+    &self,
+    text: &str,
+  ) -> Result<Vec<HandsState>, TypingError> {
+    let handstates = self.keyboard.try_type_text(text)?;
+    Ok(self.apply_shift(handstates))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_layer_switching() {
+    let kb = Asetniop::default();
+    assert_eq!(kb.type_chars("a1a".chars()), vec![
+      [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+      SWITCH_COMBINATION,
+      [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+      SWITCH_COMBINATION,
+      [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+    ]);
+    assert_eq!(kb.switch_count(), 2);
+    assert_eq!(kb.try_type_chars("é".chars()), Err(NoSuchChar { ch: 'é' }));
+    let error = kb.try_type_text("abc\né de fg").unwrap_err();
+    assert_eq!(
+      (error.ch, error.index, error.line, error.column),
+      ('é', 4, 2, 1)
+    );
+
+    kb.type_chars("1".chars());
+    assert_eq!(kb.current_layer(), AsetniopLayer::Symbols);
+    kb.reset_layer();
+    assert_eq!(kb.current_layer(), AsetniopLayer::Letters);
+  }
+
+  #[test]
+  fn test_one_shot_symbols() {
+    let mut kb = Asetniop::default();
+    assert!(!kb.is_one_shot_symbols());
+    kb.set_one_shot_symbols(true);
+    assert!(kb.is_one_shot_symbols());
+    assert_eq!(kb.type_chars("12a".chars()), vec![
+      SWITCH_COMBINATION,
+      [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+      SWITCH_COMBINATION,
+      [0, 1, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+      [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+    ]);
+    assert_eq!(kb.current_layer(), AsetniopLayer::Letters);
+  }
+
+  #[test]
+  fn test_space_and_backspace() {
+    let kb = Asetniop::default();
+    let space = HandsState::right_thumb();
+    let backspace: HandsState = [0, 0, 0, 0, 0, 1, 0, 0, 0, 1].into();
+    let text = "Hi, it's me!\u{8} 42";
+    let handstates = kb.type_chars(text.chars());
+    assert_eq!(handstates.iter().filter(|&&hs| hs == space).count(), 3);
+    assert_eq!(handstates.iter().filter(|&&hs| hs == backspace).count(), 1);
+    let handstates = kb.type_chars("1\u{8}".chars());
+    assert_eq!(handstates.last(), Some(&backspace));
+    assert!(kb
+      .keyboard
+      .collisions()
+      .iter()
+      .all(|c| c.chord != backspace));
+  }
+
+  #[test]
+  fn test_sticky_shift() {
+    let mut kb = Asetniop::default();
+    kb.set_sticky_shift(true);
+    let lt = HandsState::left_thumb();
+    let a: HandsState = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into();
+    assert_eq!(kb.type_chars("Aa ".chars()), vec![
+      lt,
+      a,
+      a,
+      HandsState::right_thumb()
+    ]);
+    assert_eq!(kb.shift_policy(), ShiftPolicy::Sticky(lt));
+    assert_eq!(kb.type_chars("@".chars()), vec![
+      lt,
+      [0, 0, 0, 0, 0, 0, 0, 1, 0, 1].into()
+    ]);
+  }
+
+  #[test]
+  fn test_dictionary() {
+    let mut kb = Asetniop::default();
+    kb.add_dictionary(["the", "eth", "pa", "a", "én", "and", "The"]);
+    let the: HandsState = [0, 0, 1, 1, 0, 0, 1, 1, 0, 0].into();
+    let and: HandsState = [1, 1, 1, 0, 0, 0, 1, 0, 0, 0].into();
+    assert_eq!(kb.dictionary().collect::<Vec<_>>(), [
+      ("the", the),
+      ("and", and)
+    ]);
+    let space = HandsState::right_thumb();
+    assert_eq!(kb.type_chars("the and".chars()), vec![the, space, and]);
+    assert_eq!(kb.type_chars("then".chars()).len(), 4);
+    assert_eq!(kb.type_chars("1 the".chars()).len(), 7);
+    kb.reset_layer();
+    assert_eq!(kb.type_chars("the".chars()), vec![the]);
+  }
+
+  #[test]
+  fn test_shift_policy() {
+    let kb = Asetniop::default();
+    let policy = kb.shift_policy();
+    for ch in "asetniop".chars() {
+      let lower = LETTERS_LAYOUT[&ch];
+      let upper = LETTERS_LAYOUT[&ch.to_ascii_uppercase()];
+      assert_eq!(policy.shifted(&lower), Some(vec![upper]));
+      assert!(policy.is_shift_press(&upper));
     }
-    Ok(handstates)
   }
 }
@@ -0,0 +1,130 @@
+//! Contains the map from chars to chords used by Tenboard layouts.
+
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::keyboard::hands::HandsState;
+
+/// Map from chars to chords with a dense array for ASCII chars, so typing
+/// usual text doesn't hash every char. Other chars are kept in a
+/// `HashMap`. Serialized like a `HashMap<char, HandsState>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ChordMap {
+  ascii: [Option<HandsState>; 128],
+  other: HashMap<char, HandsState>,
+}
+
+impl ChordMap {
+  pub fn new() -> Self {
+    Self {
+      ascii: [None; 128],
+      other: HashMap::new(),
+    }
+  }
+
+  #[inline]
+  pub fn get(&self, ch: char) -> Option<HandsState> {
+    match self.ascii.get(ch as usize) {
+      Some(hs) => *hs,
+      None => self.other.get(&ch).copied(),
+    }
+  }
+
+  /// Binds `ch` to `hs` and returns its previous chord.
+  pub fn insert(&mut self, ch: char, hs: HandsState) -> Option<HandsState> {
+    match self.ascii.get_mut(ch as usize) {
+      Some(slot) => slot.replace(hs),
+      None => self.other.insert(ch, hs),
+    }
+  }
+
+  pub fn contains_key(&self, ch: char) -> bool {
+    self.get(ch).is_some()
+  }
+
+  /// Returns chars with their chords, ASCII chars first in code order.
+  pub fn iter(&self) -> impl Iterator<Item = (char, HandsState)> + '_ {
+    let ascii = self
+      .ascii
+      .iter()
+      .enumerate()
+      .filter_map(|(i, hs)| hs.map(|hs| (i as u8 as char, hs)));
+    ascii.chain(self.other.iter().map(|(&ch, &hs)| (ch, hs)))
+  }
+
+  pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+    self.iter().map(|(ch, _)| ch)
+  }
+
+  pub fn values_mut(&mut self) -> impl Iterator<Item = &mut HandsState> {
+    self
+      .ascii
+      .iter_mut()
+      .flatten()
+      .chain(self.other.values_mut())
+  }
+}
+
+impl Default for ChordMap {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl FromIterator<(char, HandsState)> for ChordMap {
+  fn from_iter<T>(iter: T) -> Self
+  where
+    T: IntoIterator<Item = (char, HandsState)>,
+  {
+    let mut map = Self::new();
+    for (ch, hs) in iter {
+      map.insert(ch, hs);
+    }
+    map
+  }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for ChordMap {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(self.iter())
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for ChordMap {
+  fn deserialize<D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Self, D::Error> {
+    Ok(
+      HashMap::<char, HandsState>::deserialize(deserializer)?
+        .into_iter()
+        .collect(),
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_chord_map() {
+    let a: HandsState = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into();
+    let b: HandsState = [0, 1, 0, 0, 0, 0, 0, 0, 0, 0].into();
+    let mut map: ChordMap = [('a', a), ('ß', b)].into_iter().collect();
+    assert_eq!(map.get('a'), Some(a));
+    assert_eq!(map.get('ß'), Some(b));
+    assert_eq!(map.get('b'), None);
+    assert_eq!(map.chars().count(), 2);
+    assert_eq!(map.insert('a', b), Some(a));
+    assert_eq!(map.insert('\u{7f}', a), None);
+    assert_eq!(map.chars().collect::<String>(), "a\u{7f}ß");
+    for hs in map.values_mut() {
+      *hs = a;
+    }
+    assert!(map.iter().all(|(_, hs)| hs == a));
+  }
+}
@@ -0,0 +1,405 @@
+//! Describes user defined constraints for Tenboard layout generation and
+//! optimization.
+
+use std::{
+  collections::{HashMap, HashSet},
+  fmt::Display,
+};
+
+use rand::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::tenboard::Tenboard;
+use crate::keyboard::hands::{FingerState, HandsState, ThumbPolicy};
+
+/// Set of rules a layout must follow.
+///
+/// Constraints are honored by generators of
+/// [TenboardUnconstrained](super::tenboard::TenboardUnconstrained) taking
+/// them, by [Annealing](crate::keyboard::optimize::Annealing),
+/// [HillClimb](crate::keyboard::optimize::HillClimb) and
+/// [BeamSearch](crate::keyboard::optimize::BeamSearch). Other layouts fix
+/// chords of some chars themselves and don't support constraints, but
+/// [Constraints::violations] can check any of them.
+///
+/// ```
+/// use tenboard::keyboard::{
+///   hands::HandsState,
+///   layout::{constraints::Constraints, tenboard::TenboardUnconstrained},
+/// };
+///
+/// let mut constraints = Constraints::new();
+/// constraints
+///   .pin(' ', HandsState::right_thumb())
+///   .forbid([1, 1, 0, 0, 0, 0, 0, 0, 0, 0].into())
+///   .forbid([0, 0, 0, 0, 0, 0, 0, 0, 1, 1].into())
+///   .single_key("et")
+///   .reserve_thumbs();
+/// let layout =
+///   TenboardUnconstrained::new_random_with_constraints(&constraints).unwrap();
+/// assert!(constraints.violations(&layout).is_empty());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Constraints {
+  pinned: HashMap<char, HandsState>,
+  forbidden: Vec<HandsState>,
+  single_key: HashSet<char>,
+  reserved: HashSet<HandsState>,
+  thumb_policy: ThumbPolicy,
+  #[cfg_attr(feature = "serde", serde(default = "default_max_keys"))]
+  max_keys: usize,
+  #[cfg_attr(feature = "serde", serde(default))]
+  chord_filter: ChordFilter,
+}
+
+fn default_max_keys() -> usize {
+  2
+}
+
+impl Constraints {
+  /// Creates constraints that allow any one and two key chord permitted by
+  /// the default [ThumbPolicy].
+  pub fn new() -> Self {
+    Self {
+      pinned: HashMap::new(),
+      forbidden: Vec::new(),
+      single_key: HashSet::new(),
+      reserved: HashSet::new(),
+      thumb_policy: ThumbPolicy::default(),
+      max_keys: default_max_keys(),
+      chord_filter: ChordFilter::new(),
+    }
+  }
+
+  /// Binds `ch` to `handstate`. Pinned chords ignore other constraints.
+  pub fn pin(&mut self, ch: char, handstate: HandsState) -> &mut Self {
+    self.pinned.insert(ch, handstate);
+    self
+  }
+
+  /// Forbids chords that press all fingers pressed in `fingers`. For
+  /// example, `[1, 1, 0, 0, 0, 0, 0, 0, 0, 0]` forbids pressing left pinky
+  /// and ring fingers together.
+  pub fn forbid(&mut self, fingers: HandsState) -> &mut Self {
+    self.forbidden.push(fingers);
+    self
+  }
+
+  /// Restricts each of `chars` to single key chords.
+  pub fn single_key(&mut self, chars: &str) -> &mut Self {
+    self.single_key.extend(chars.chars());
+    self
+  }
+
+  /// Forbids assigning `handstate` to any char unless it's pinned.
+  pub fn reserve(&mut self, handstate: HandsState) -> &mut Self {
+    self.reserved.insert(handstate);
+    self
+  }
+
+  /// Reserves chords where thumbs are pressed alone.
+  pub fn reserve_thumbs(&mut self) -> &mut Self {
+    self
+      .reserve(HandsState::left_thumb())
+      .reserve(HandsState::right_thumb())
+      .reserve(HandsState::both_thumbs())
+  }
+
+  pub fn set_thumb_policy(&mut self, policy: ThumbPolicy) -> &mut Self {
+    self.thumb_policy = policy;
+    self
+  }
+
+  /// Allows chords of up to `max_keys` keys, not counting thumbs. Chords of
+  /// three and more keys are needed for char sets bigger than one and two
+  /// key chords can cover. Defaults to 2.
+  pub fn set_max_keys(&mut self, max_keys: usize) -> &mut Self {
+    self.max_keys = max_keys;
+    self
+  }
+
+  /// Allows only chords allowed by `filter`.
+  pub fn set_chord_filter(&mut self, filter: ChordFilter) -> &mut Self {
+    self.chord_filter = filter;
+    self
+  }
+
+  /// Returns the chord `ch` is pinned to.
+  pub fn pinned(&self, ch: char) -> Option<HandsState> {
+    self.pinned.get(&ch).copied()
+  }
+
+  /// Returns `true` if `handstate` may be assigned to some char.
+  pub fn allows_chord(&self, handstate: &HandsState) -> bool {
+    finger_keys(handstate) <= self.max_keys
+      && self.thumb_policy.allows(handstate)
+      && self.chord_filter.allows(handstate)
+      && !self.reserved.contains(handstate)
+      && !self.forbidden.iter().any(|f| f.is_subset_of(handstate))
+  }
+
+  /// Returns `true` if `handstate` may be assigned to `ch`.
+  pub fn allows(&self, ch: char, handstate: &HandsState) -> bool {
+    match self.pinned.get(&ch) {
+      Some(hs) => hs == handstate,
+      None => {
+        self.allows_chord(handstate)
+          && (!self.single_key.contains(&ch) || handstate.count_pressed() == 1)
+      }
+    }
+  }
+
+  /// Returns chars of `layout` whose chords break the constraints.
+  pub fn violations(&self, layout: &dyn Tenboard) -> Vec<char> {
+    layout
+      .charset()
+      .chars()
+      .filter(|&ch| {
+        layout
+          .try_type_char(ch)
+          .is_ok_and(|hs| !self.allows(ch, &hs))
+      })
+      .collect()
+  }
+
+  /// Randomly assigns chords allowed by the constraints to `chars`.
+  pub(crate) fn assign_random(
+    &self,
+    chars: impl Iterator<Item = char>,
+    rng: &mut impl Rng,
+  ) -> Result<HashMap<char, HandsState>, Unsatisfiable> {
+    let pinned: HashSet<_> = self.pinned.values().copied().collect();
+    let mut pool: Vec<_> = HandsState::iterate_one_two_key(ThumbPolicy::ANY)
+      .chain(
+        // chords have at most 8 finger keys and 2 thumbs
+        (3..=self.max_keys.min(8) + 2)
+          .flat_map(HandsState::iterate_n_key)
+          .filter(|hs| finger_keys(hs) >= 3),
+      )
+      .filter(|hs| !pinned.contains(hs) && self.allows_chord(hs))
+      .collect();
+    pool.shuffle(rng);
+    let mut chars: Vec<_> = chars.collect();
+    // the most constrained chars pick their chords first
+    chars.sort_by_key(|ch| {
+      (!self.pinned.contains_key(ch), !self.single_key.contains(ch))
+    });
+    let mut layout = HashMap::new();
+    for ch in chars {
+      let hs = match self.pinned.get(&ch) {
+        Some(hs) => *hs,
+        None => {
+          let idx = pool
+            .iter()
+            .position(|hs| self.allows(ch, hs))
+            .ok_or(Unsatisfiable { ch })?;
+          pool.swap_remove(idx)
+        }
+      };
+      layout.insert(ch, hs);
+    }
+    Ok(layout)
+  }
+}
+
+/// Returns number of pressed keys except thumbs.
+fn finger_keys(handstate: &HandsState) -> usize {
+  handstate.count_pressed()
+    - [handstate[4], handstate[5]]
+      .iter()
+      .filter(|fs| fs.is_pressed())
+      .count()
+}
+
+impl Default for Constraints {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Filter of chords a person can comfortably press, e.g. with limited
+/// mobility of some fingers. Layout generation and optimization honor it
+/// through [Constraints::set_chord_filter].
+///
+/// ```
+/// use tenboard::keyboard::{hands::HandsState, layout::constraints::ChordFilter};
+///
+/// let mut filter = ChordFilter::new();
+/// filter.exclude_finger(0).forbid_ring_pinky();
+/// assert!(!filter.allows(&[1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into()));
+/// assert!(!filter.allows(&[0, 0, 0, 0, 0, 0, 0, 0, 1, 1].into()));
+/// assert!(filter.allows(&[0, 0, 0, 0, 0, 0, 0, 1, 1, 0].into()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ChordFilter {
+  excluded: HandsState,
+  forbidden: Vec<HandsState>,
+  max_fingers_per_hand: Option<usize>,
+}
+
+impl ChordFilter {
+  /// Creates a filter that allows any chord.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Forbids chords that press `finger`. See
+  /// [FINGER_NAMES](crate::keyboard::hands::FINGER_NAMES) for finger
+  /// indices.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `finger` isn't less than 10.
+  pub fn exclude_finger(&mut self, finger: usize) -> &mut Self {
+    assert!(finger < 10, "no finger with index {finger}");
+    self.excluded[finger] = FingerState::Pressed;
+    self
+  }
+
+  /// Forbids chords that press all fingers pressed in `fingers` together.
+  pub fn forbid_together(&mut self, fingers: HandsState) -> &mut Self {
+    self.forbidden.push(fingers);
+    self
+  }
+
+  /// Forbids pressing ring and pinky fingers of the same hand together.
+  pub fn forbid_ring_pinky(&mut self) -> &mut Self {
+    self
+      .forbid_together([1, 1, 0, 0, 0, 0, 0, 0, 0, 0].into())
+      .forbid_together([0, 0, 0, 0, 0, 0, 0, 0, 1, 1].into())
+  }
+
+  /// Forbids chords that press more than `max` fingers of any hand, thumb
+  /// included.
+  pub fn set_max_fingers_per_hand(&mut self, max: usize) -> &mut Self {
+    self.max_fingers_per_hand = Some(max);
+    self
+  }
+
+  /// Returns `true` if `handstate` passes the filter.
+  pub fn allows(&self, handstate: &HandsState) -> bool {
+    let presses = |fingers: &HandsState| {
+      fingers.is_subset_of(handstate) && fingers.count_pressed() > 0
+    };
+    !handstate.overlaps(&self.excluded)
+      && !self.forbidden.iter().any(presses)
+      && self.max_fingers_per_hand.is_none_or(|max| {
+        handstate
+          .hand_iter()
+          .all(|hand| hand.iter().filter(|fs| fs.is_pressed()).count() <= max)
+      })
+  }
+}
+
+/// This error means that no chord satisfying constraints is left for a char.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Unsatisfiable {
+  pub ch: char,
+}
+
+impl Display for Unsatisfiable {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "no chord satisfies constraints for char '{}'", self.ch)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::keyboard::{
+    charset::CharSet,
+    layout::tenboard::TenboardUnconstrained,
+    TYPABLE_CHARS,
+  };
+
+  #[test]
+  fn test_allows() {
+    let mut constraints = Constraints::new();
+    constraints
+      .pin('a', HandsState::left_thumb())
+      .forbid([0, 0, 0, 0, 0, 0, 0, 0, 1, 1].into())
+      .single_key("e")
+      .reserve_thumbs();
+    assert!(constraints.allows('a', &HandsState::left_thumb()));
+    assert!(!constraints.allows('b', &HandsState::left_thumb()));
+    assert!(!constraints.allows('b', &[0, 0, 0, 0, 0, 0, 0, 0, 1, 1].into()));
+    assert!(!constraints.allows('b', &[0, 0, 0, 0, 1, 0, 0, 0, 1, 1].into()));
+    assert!(constraints.allows('b', &[0, 0, 0, 0, 0, 0, 0, 1, 0, 1].into()));
+    assert!(!constraints.allows('e', &[0, 0, 0, 0, 0, 0, 0, 1, 0, 1].into()));
+    assert!(constraints.allows('e', &[0, 0, 0, 0, 0, 0, 0, 1, 0, 0].into()));
+  }
+
+  #[test]
+  fn test_max_keys() {
+    let mut constraints = Constraints::new();
+    assert!(constraints.allows_chord(&[0, 1, 1, 0, 1, 0, 0, 0, 0, 0].into()));
+    assert!(!constraints.allows_chord(&[1, 1, 1, 0, 0, 0, 0, 0, 0, 0].into()));
+    constraints
+      .set_max_keys(3)
+      .set_thumb_policy(ThumbPolicy::ANY);
+    assert!(constraints.allows_chord(&[1, 1, 1, 0, 1, 1, 0, 0, 0, 0].into()));
+    let mut charset = CharSet::german();
+    charset.add_chars("абвгдеёжзийклмнопрстуфхцчшщъыьэюя");
+    let layout =
+      TenboardUnconstrained::new_random_with_charset_and_constraints(
+        &charset,
+        &constraints,
+        &mut thread_rng(),
+      )
+      .unwrap();
+    assert!(charset.missing(&layout).is_empty());
+    assert!(constraints.violations(&layout).is_empty());
+
+    constraints.set_max_keys(usize::MAX);
+    assert!(constraints.allows_chord(&[1; 10].into()));
+    assert!(
+      TenboardUnconstrained::new_random_with_charset_and_constraints(
+        &charset,
+        &constraints,
+        &mut thread_rng(),
+      )
+      .is_ok()
+    );
+  }
+
+  #[test]
+  fn test_chord_filter() {
+    let mut filter = ChordFilter::new();
+    assert!(HandsState::iterate_one_two_key(ThumbPolicy::ANY)
+      .all(|hs| filter.allows(&hs)));
+    filter
+      .exclude_finger(9)
+      .forbid_ring_pinky()
+      .set_max_fingers_per_hand(2);
+    assert!(!filter.allows(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 1].into()));
+    assert!(!filter.allows(&[1, 1, 0, 0, 0, 0, 0, 0, 0, 0].into()));
+    assert!(!filter.allows(&[0, 1, 1, 0, 1, 0, 0, 0, 0, 0].into()));
+    assert!(filter.allows(&[0, 1, 0, 0, 1, 0, 1, 1, 0, 0].into()));
+
+    let mut filter = ChordFilter::new();
+    filter.exclude_finger(9).forbid_ring_pinky();
+    let mut constraints = Constraints::new();
+    constraints.set_chord_filter(filter.clone()).set_max_keys(3);
+    let layout =
+      TenboardUnconstrained::new_random_with_constraints(&constraints).unwrap();
+    assert!(constraints.violations(&layout).is_empty());
+    assert!(TYPABLE_CHARS
+      .chars()
+      .all(|ch| filter.allows(&layout.try_type_char(ch).unwrap())));
+  }
+
+  #[test]
+  fn test_unsatisfiable() {
+    let mut constraints = Constraints::new();
+    constraints.single_key("abcdefghijk");
+    assert_eq!(
+      TenboardUnconstrained::new_random_with_constraints(&constraints)
+        .unwrap_err(),
+      Unsatisfiable { ch: 'k' }
+    );
+  }
+}
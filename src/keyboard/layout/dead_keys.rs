@@ -0,0 +1,231 @@
+//! Describes keyboards with dead keys: chords that type nothing themselves,
+//! but make the next chord type an accented char.
+
+use std::collections::HashMap;
+
+use crate::keyboard::{
+  hands::HandsState,
+  shift::{Shift, ShiftPolicy},
+  try_type_run,
+  Keyboard,
+  NoSuchChar,
+  TypingError,
+};
+
+/// Accent of a dead key. Its compositions cover Latin letters of Western
+/// European languages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Accent {
+  Acute,
+  Grave,
+  Circumflex,
+  Diaeresis,
+  Tilde,
+  Cedilla,
+}
+
+impl Accent {
+  /// Returns base chars with chars they compose into. A space composes
+  /// into the accent itself.
+  pub fn compositions(&self) -> Vec<(char, char)> {
+    let (accent, bases, composed) = match self {
+      Accent::Acute => ('´', "aeiouyAEIOUY", "áéíóúýÁÉÍÓÚÝ"),
+      Accent::Grave => ('`', "aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+      Accent::Circumflex => ('^', "aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+      Accent::Diaeresis => ('¨', "aeiouyAEIOUY", "äëïöüÿÄËÏÖÜŸ"),
+      Accent::Tilde => ('~', "anoANO", "ãñõÃÑÕ"),
+      Accent::Cedilla => ('¸', "cC", "çÇ"),
+    };
+    bases
+      .chars()
+      .zip(composed.chars())
+      .chain([(' ', accent)])
+      .collect()
+  }
+}
+
+/// Keyboard that extends another keyboard with dead keys. A char the
+/// underlying keyboard can't type is typed with a dead key chord followed
+/// by chords of its base char, e.g. ´ and then a for á. Metrics then see
+/// both chords.
+///
+/// ```
+/// use tenboard::keyboard::{
+///   hands::HandsState,
+///   layout::{
+///     dead_keys::{Accent, DeadKeyKeyboard},
+///     standard::Qwerty,
+///   },
+///   Keyboard,
+/// };
+///
+/// let mut kb = DeadKeyKeyboard::new(Qwerty::new());
+/// let acute = [0, 0, 0, 0, 1, 1, 0, 0, 0, 0].into();
+/// kb.add_dead_key(acute, Accent::Acute.compositions());
+/// let handstates = kb.type_chars("café".chars());
+/// assert_eq!(handstates.len(), 5);
+/// assert_eq!(handstates[3], acute);
+/// let (_, dead_keys) = kb.try_type_chars_counted("éé".chars()).unwrap();
+/// assert_eq!(dead_keys, 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DeadKeyKeyboard<K> {
+  keyboard: K,
+  /// Composed chars with dead key chords and base chars typing them.
+  compositions: HashMap<char, (HandsState, char)>,
+}
+
+impl<K: Keyboard> DeadKeyKeyboard<K> {
+  /// Creates a keyboard without dead keys. It types like `keyboard`.
+  pub fn new(keyboard: K) -> Self {
+    Self {
+      keyboard,
+      compositions: HashMap::new(),
+    }
+  }
+
+  /// Makes `chord` a dead key composing base chars with chars they compose
+  /// into, given as `(base, composed)` pairs. If a composed char already
+  /// has a dead key, it's replaced.
+  pub fn add_dead_key(
+    &mut self,
+    chord: HandsState,
+    compositions: impl IntoIterator<Item = (char, char)>,
+  ) -> &mut Self {
+    for (base, composed) in compositions {
+      self.compositions.insert(composed, (chord, base));
+    }
+    self
+  }
+
+  pub fn keyboard(&self) -> &K {
+    &self.keyboard
+  }
+
+  /// Returns the dead key chord and the base char typing `ch` or `None` if
+  /// `ch` isn't composed with a dead key.
+  pub fn composition(&self, ch: char) -> Option<(HandsState, char)> {
+    self.compositions.get(&ch).copied()
+  }
+
+  /// Same as [Keyboard::try_type_chars], but also returns the number of
+  /// dead key chords among typed chords.
+  pub fn try_type_chars_counted(
+    &self,
+    chars: impl Iterator<Item = char>,
+  ) -> Result<(Vec<HandsState>, u64), NoSuchChar> {
+    let chars: Vec<_> = chars.collect();
+    self.try_type_indexed(&chars).map_err(|(e, _)| e)
+  }
+
+  /// Types `chars` and counts dead key chords or returns an error with the
+  /// index of the char that couldn't be typed.
+  fn try_type_indexed(
+    &self,
+    chars: &[char],
+  ) -> Result<(Vec<HandsState>, u64), (NoSuchChar, usize)> {
+    let mut handstates = Vec::with_capacity(chars.len());
+    let mut dead_keys = 0;
+    // runs of chars are typed with the underlying keyboard at once and cut
+    // before the first composed char it fails at
+    let (mut start, mut end) = (0, chars.len());
+    let mut composed = None;
+    while start < chars.len() {
+      match try_type_run(&self.keyboard, &chars[start..end]) {
+        Ok(hs) => {
+          handstates.extend(hs);
+          if let Some((dead_key, base, error)) = composed.take() {
+            handstates.push(dead_key);
+            handstates.extend(
+              self
+                .keyboard
+                .try_type_chars(std::iter::once(base))
+                .map_err(|_| (error, end))?,
+            );
+            dead_keys += 1;
+          }
+          (start, end) = (end + 1, chars.len());
+        }
+        Err((error, i)) => {
+          let (dead_key, base) =
+            self.composition(error.ch).ok_or((error, start + i))?;
+          end = start + i;
+          composed = Some((dead_key, base, error));
+        }
+      }
+    }
+    Ok((handstates, dead_keys))
+  }
+}
+
+impl<K: Keyboard> Keyboard for DeadKeyKeyboard<K> {
+  fn try_type_chars(
+    &self,
+    chars: impl Iterator<Item = char>,
+  ) -> Result<Vec<HandsState>, NoSuchChar> {
+    self
+      .try_type_chars_counted(chars)
+      .map(|(handstates, _)| handstates)
+  }
+
+  fn try_type_text(
+    &self,
+    text: &str,
+  ) -> Result<Vec<HandsState>, TypingError> {
+    let chars: Vec<_> = text.chars().collect();
+    self
+      .try_type_indexed(&chars)
+      .map(|(handstates, _)| handstates)
+      .map_err(|(e, i)| TypingError::locate(e.ch, text, i))
+  }
+}
+
+impl<K: Keyboard + Shift> Shift for DeadKeyKeyboard<K> {
+  fn shift_policy(&self) -> ShiftPolicy {
+    self.keyboard.shift_policy()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testing::{CapsLockKeyboard, SyntheticKeyboard};
+
+  #[test]
+  fn test_dead_keys() {
+    let kb = SyntheticKeyboard::new_one_finger("ace ");
+    let mut kb = DeadKeyKeyboard::new(kb);
+    let acute = HandsState::left_thumb();
+    let cedilla = HandsState::right_thumb();
+    kb.add_dead_key(acute, Accent::Acute.compositions())
+      .add_dead_key(cedilla, Accent::Cedilla.compositions());
+    let a: HandsState = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into();
+    let c: HandsState = [0, 1, 0, 0, 0, 0, 0, 0, 0, 0].into();
+    let space: HandsState = [0, 0, 0, 1, 0, 0, 0, 0, 0, 0].into();
+    assert_eq!(kb.type_chars("áça".chars()), vec![
+      acute, a, cedilla, c, a
+    ]);
+    assert_eq!(kb.type_chars("¸".chars()), vec![cedilla, space]);
+    assert_eq!(kb.try_type_chars_counted("áça".chars()).unwrap().1, 2);
+    assert_eq!(kb.try_type_chars_counted("".chars()), Ok((vec![], 0)));
+    assert_eq!(kb.composition('é'), Some((acute, 'e')));
+    assert_eq!(kb.try_type_chars("ó".chars()), Err(NoSuchChar { ch: 'ó' }));
+    assert_eq!(kb.try_type_chars("x".chars()), Err(NoSuchChar { ch: 'x' }));
+    let error = kb.try_type_text("áa ó").unwrap_err();
+    assert_eq!((error.ch, error.index), ('ó', 3));
+  }
+
+  #[test]
+  fn test_stateful_keyboard() {
+    let kb = CapsLockKeyboard::new(
+      SyntheticKeyboard::new_one_finger("ab"),
+      HandsState::left_thumb(),
+    );
+    let mut kb = DeadKeyKeyboard::new(kb);
+    assert_eq!(kb.type_chars("AB".chars()).len(), 3);
+    kb.add_dead_key(HandsState::right_thumb(), Accent::Acute.compositions());
+    let (handstates, dead_keys) =
+      kb.try_type_chars_counted("ABáAB".chars()).unwrap();
+    assert_eq!((handstates.len(), dead_keys), (8, 1));
+  }
+}
@@ -0,0 +1,351 @@
+//! Describes keyboards with chords for grapheme clusters, i.e. typable
+//! units of several chars like decomposed letters or emoji sequences.
+
+use std::{cmp::Ordering, collections::HashMap, fmt::Display};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::keyboard::{
+  hands::HandsState,
+  shift::{Shift, ShiftPolicy},
+  try_type_run,
+  Keyboard,
+  NoSuchChar,
+  TypingError,
+};
+
+/// Maximum length of a [Grapheme] in bytes.
+pub const GRAPHEME_CAPACITY: usize = 31;
+
+/// Short string typed with a single chord, e.g. "e\u{301}" or "👍🏽". It's
+/// stored inline, so it's as cheap to copy and hash as a few chars.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Grapheme {
+  len: u8,
+  bytes: [u8; GRAPHEME_CAPACITY],
+}
+
+impl Grapheme {
+  /// Creates a grapheme of `s` or returns an error if `s` is empty or longer
+  /// than [GRAPHEME_CAPACITY] bytes.
+  pub fn new(s: &str) -> Result<Self, BadGrapheme> {
+    Self::from_chars(s.chars()).ok_or_else(|| BadGrapheme {
+      grapheme: s.to_owned(),
+    })
+  }
+
+  /// Creates a grapheme of `chars` or returns `None` if there are no chars
+  /// or they don't fit.
+  fn from_chars(chars: impl IntoIterator<Item = char>) -> Option<Self> {
+    let mut grapheme = Self {
+      len: 0,
+      bytes: [0; GRAPHEME_CAPACITY],
+    };
+    for ch in chars {
+      let len = grapheme.len as usize;
+      let end = len + ch.len_utf8();
+      if end > GRAPHEME_CAPACITY {
+        return None;
+      }
+      ch.encode_utf8(&mut grapheme.bytes[len..end]);
+      grapheme.len = end as u8;
+    }
+    (grapheme.len > 0).then_some(grapheme)
+  }
+
+  pub fn as_str(&self) -> &str {
+    std::str::from_utf8(&self.bytes[..self.len as usize])
+      .expect("grapheme is valid UTF-8")
+  }
+
+  /// Returns the number of chars of the grapheme.
+  pub fn char_count(&self) -> usize {
+    self.as_str().chars().count()
+  }
+}
+
+impl From<char> for Grapheme {
+  fn from(value: char) -> Self {
+    Self::from_chars([value]).expect("a char fits into a grapheme")
+  }
+}
+
+impl TryFrom<&str> for Grapheme {
+  type Error = BadGrapheme;
+
+  fn try_from(value: &str) -> Result<Self, Self::Error> {
+    Self::new(value)
+  }
+}
+
+impl PartialOrd for Grapheme {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for Grapheme {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.as_str().cmp(other.as_str())
+  }
+}
+
+impl std::fmt::Debug for Grapheme {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{:?}", self.as_str())
+  }
+}
+
+impl Display for Grapheme {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Grapheme {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(self.as_str())
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Grapheme {
+  fn deserialize<D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Self, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    Self::new(&s).map_err(serde::de::Error::custom)
+  }
+}
+
+/// This error means that a string can't be a [Grapheme].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BadGrapheme {
+  pub grapheme: String,
+}
+
+impl Display for BadGrapheme {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "grapheme {:?} must be non-empty and at most {GRAPHEME_CAPACITY} bytes \
+       long",
+      self.grapheme
+    )
+  }
+}
+
+/// Keyboard that extends another keyboard with chords typing graphemes.
+/// When typing, the longest grapheme matching the text is used, and chars
+/// not covered by graphemes are typed with the underlying keyboard. A
+/// single char grapheme overrides the chord of the char.
+///
+/// ```
+/// use tenboard::keyboard::{
+///   layout::{asetniop::Asetniop, grapheme::{Grapheme, GraphemeKeyboard}},
+///   Keyboard,
+/// };
+///
+/// let mut kb = GraphemeKeyboard::new(Asetniop::default());
+/// let e_acute = Grapheme::new("e\u{301}").unwrap();
+/// kb.add_grapheme(e_acute, [0, 0, 1, 0, 0, 0, 0, 0, 1, 0].into());
+/// assert_eq!(kb.type_chars("cafe\u{301}".chars()).len(), 4);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GraphemeKeyboard<K> {
+  keyboard: K,
+  graphemes: HashMap<Grapheme, HandsState>,
+}
+
+impl<K: Keyboard> GraphemeKeyboard<K> {
+  /// Creates a keyboard without grapheme chords. It types like `keyboard`.
+  pub fn new(keyboard: K) -> Self {
+    Self {
+      keyboard,
+      graphemes: HashMap::new(),
+    }
+  }
+
+  /// Makes `handstate` type `grapheme`, replacing its previous chord if
+  /// there was one.
+  pub fn add_grapheme(
+    &mut self,
+    grapheme: Grapheme,
+    handstate: HandsState,
+  ) -> &mut Self {
+    self.graphemes.insert(grapheme, handstate);
+    self
+  }
+
+  /// Removes chord of `grapheme` and returns it.
+  pub fn remove_grapheme(&mut self, grapheme: &Grapheme) -> Option<HandsState> {
+    self.graphemes.remove(grapheme)
+  }
+
+  pub fn get(&self, grapheme: &Grapheme) -> Option<HandsState> {
+    self.graphemes.get(grapheme).copied()
+  }
+
+  pub fn keyboard(&self) -> &K {
+    &self.keyboard
+  }
+
+  /// Returns graphemes with their chords in alphabetical order.
+  pub fn graphemes(&self) -> impl Iterator<Item = (Grapheme, HandsState)> {
+    let mut graphemes: Vec<_> =
+      self.graphemes.iter().map(|(&g, &hs)| (g, hs)).collect();
+    graphemes.sort_unstable_by_key(|&(g, _)| g);
+    graphemes.into_iter()
+  }
+}
+
+impl<K: Keyboard> GraphemeKeyboard<K> {
+  /// Types `chars` or returns an error with the index of the char that
+  /// couldn't be typed. Runs of chars between graphemes are typed with the
+  /// underlying keyboard at once, so its state, like a locked modifier,
+  /// lasts for the run.
+  fn try_type_indexed(
+    &self,
+    chars: &[char],
+  ) -> Result<Vec<HandsState>, (NoSuchChar, usize)> {
+    let max_len = self.graphemes.keys().map(Grapheme::char_count).max();
+    let mut handstates = Vec::with_capacity(chars.len());
+    let type_run = |start: usize, end: usize| {
+      try_type_run(&self.keyboard, &chars[start..end])
+        .map_err(|(e, i)| (e, start + i))
+    };
+    let (mut i, mut run_start) = (0, 0);
+    while i < chars.len() {
+      let max_len = max_len.unwrap_or(0).min(chars.len() - i);
+      let grapheme = (1..=max_len).rev().find_map(|len| {
+        let grapheme = Grapheme::from_chars(chars[i..i + len].iter().copied())?;
+        Some((len, *self.graphemes.get(&grapheme)?))
+      });
+      match grapheme {
+        Some((len, hs)) => {
+          handstates.extend(type_run(run_start, i)?);
+          handstates.push(hs);
+          i += len;
+          run_start = i;
+        }
+        None => i += 1,
+      }
+    }
+    handstates.extend(type_run(run_start, i)?);
+    Ok(handstates)
+  }
+}
+
+impl<K: Keyboard> Keyboard for GraphemeKeyboard<K> {
+  fn try_type_chars(
+    &self,
+    chars: impl Iterator<Item = char>,
+  ) -> Result<Vec<HandsState>, NoSuchChar> {
+    let chars: Vec<_> = chars.collect();
+    self.try_type_indexed(&chars).map_err(|(e, _)| e)
+  }
+
+  fn try_type_text(
+    &self,
+    text: &str,
+  ) -> Result<Vec<HandsState>, TypingError> {
+    let chars: Vec<_> = text.chars().collect();
+    self
+      .try_type_indexed(&chars)
+      .map_err(|(e, i)| TypingError::locate(e.ch, text, i))
+  }
+}
+
+impl<K: Keyboard + Shift> Shift for GraphemeKeyboard<K> {
+  fn shift_policy(&self) -> ShiftPolicy {
+    self.keyboard.shift_policy()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testing::{CapsLockKeyboard, SyntheticKeyboard};
+
+  #[test]
+  fn test_grapheme() {
+    let family = "👨‍👩‍👧";
+    let grapheme = Grapheme::new(family).unwrap();
+    assert_eq!(grapheme.as_str(), family);
+    assert_eq!(grapheme.char_count(), 5);
+    assert_eq!(Grapheme::from('é').to_string(), "é");
+    assert!(Grapheme::new("").is_err());
+    let long = "👍".repeat(8);
+    assert_eq!(
+      Grapheme::try_from(long.as_str()),
+      Err(BadGrapheme { grapheme: long })
+    );
+    assert!(Grapheme::from('b') > Grapheme::new("ab").unwrap());
+  }
+
+  #[test]
+  fn test_grapheme_keyboard() {
+    let mut kb = GraphemeKeyboard::new(SyntheticKeyboard::new_one_finger("ae"));
+    let e_acute = Grapheme::new("e\u{301}").unwrap();
+    let thumbs = HandsState::both_thumbs();
+    kb.add_grapheme(e_acute, thumbs)
+      .add_grapheme('a'.into(), HandsState::left_thumb());
+    assert_eq!(kb.type_chars("ae\u{301}e".chars()), vec![
+      HandsState::left_thumb(),
+      thumbs,
+      [0, 1, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+    ]);
+    assert_eq!(
+      kb.try_type_chars("\u{301}".chars()),
+      Err(NoSuchChar { ch: '\u{301}' })
+    );
+    assert_eq!(
+      kb.graphemes().next(),
+      Some(('a'.into(), HandsState::left_thumb()))
+    );
+    assert_eq!(kb.remove_grapheme(&e_acute), Some(thumbs));
+    assert_eq!(kb.get(&e_acute), None);
+  }
+
+  #[test]
+  fn test_typing_error_position() {
+    let mut kb =
+      GraphemeKeyboard::new(SyntheticKeyboard::new_one_finger("ae \n"));
+    let e_acute = Grapheme::new("e\u{301}").unwrap();
+    kb.add_grapheme(e_acute, HandsState::both_thumbs());
+    let text = "e\u{301}a\nae\u{301}x a";
+    assert_eq!(kb.try_type_text(text), Err(TypingError {
+      ch: 'x',
+      index: 7,
+      byte: 9,
+      line: 2,
+      column: 4,
+    }));
+  }
+
+  #[test]
+  fn test_stateful_keyboard() {
+    let kb = CapsLockKeyboard::new(
+      SyntheticKeyboard::new_one_finger("ab"),
+      HandsState::left_thumb(),
+    );
+    let mut kb = GraphemeKeyboard::new(kb);
+    assert_eq!(kb.type_chars("AB".chars()).len(), 3);
+    kb.add_grapheme('a'.into(), HandsState::right_thumb());
+    assert_eq!(kb.type_chars("ABaAB".chars()).len(), 7);
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_grapheme_serialization() -> Result<(), serde_json::Error> {
+    let grapheme = Grapheme::new("e\u{301}").unwrap();
+    let json = serde_json::to_string(&grapheme)?;
+    assert_eq!(json, "\"e\u{301}\"");
+    assert_eq!(serde_json::from_str::<Grapheme>(&json)?, grapheme);
+    assert!(serde_json::from_str::<Grapheme>("\"\"").is_err());
+    Ok(())
+  }
+}
@@ -0,0 +1,381 @@
+//! Describes generic chorded keyboard with multiple layers of chords.
+
+use std::{cell::Cell, collections::HashMap};
+
+use super::word_chords::WordDictionary;
+use crate::keyboard::{
+  hands::HandsState,
+  shift::Shift,
+  Keyboard,
+  NoSuchChar,
+  TypingError,
+};
+
+/// Layer of a [LayeredChordKeyboard]: a chord map and a chord that switches
+/// keyboard to this layer. A layer may also have a dictionary of word
+/// chords typing whole words while the layer is active.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layer {
+  chords: HashMap<char, HandsState>,
+  switch: HandsState,
+  sticky: bool,
+  words: WordDictionary,
+}
+
+impl Layer {
+  /// Creates a sticky layer. Once switched to, it stays active until another
+  /// layer is switched to.
+  pub fn new(chords: HashMap<char, HandsState>, switch: HandsState) -> Self {
+    let mut words = WordDictionary::new();
+    words.set_whole_words(true);
+    Self {
+      chords,
+      switch,
+      sticky: true,
+      words,
+    }
+  }
+
+  /// Sets whether the layer stays active after switching to it. A non-sticky
+  /// layer is used for one char only, then the previous layer is active
+  /// again.
+  pub fn set_sticky(&mut self, sticky: bool) -> &mut Self {
+    self.sticky = sticky;
+    self
+  }
+
+  pub fn chords(&self) -> &HashMap<char, HandsState> {
+    &self.chords
+  }
+
+  pub fn switch(&self) -> HandsState {
+    self.switch
+  }
+
+  pub fn is_sticky(&self) -> bool {
+    self.sticky
+  }
+
+  /// Makes `handstate` type `word` while the layer is active. Word chords
+  /// are used for whole words only. See [WordDictionary::add_word].
+  pub fn add_word(&mut self, word: &str, handstate: HandsState) -> &mut Self {
+    self.words.add_word(word, handstate);
+    self
+  }
+
+  pub fn words(&self) -> &WordDictionary {
+    &self.words
+  }
+}
+
+/// Chorded keyboard with multiple layers. When a char is missing in the
+/// active layer, the first layer containing it is switched to by emitting
+/// that layer's switch chord before the char's chord.
+#[derive(Debug, Clone, Default)]
+pub struct LayeredChordKeyboard {
+  layers: Vec<Layer>,
+  current: Cell<usize>,
+  switches: Cell<u64>,
+}
+
+impl LayeredChordKeyboard {
+  /// Creates a keyboard without layers. It can't type anything.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds `layer` after existing ones. The first added layer is active by
+  /// default.
+  pub fn add_layer(&mut self, layer: Layer) -> &mut Self {
+    self.layers.push(layer);
+    self
+  }
+
+  pub fn layers(&self) -> &[Layer] {
+    &self.layers
+  }
+
+  pub fn layer_mut(&mut self, index: usize) -> Option<&mut Layer> {
+    self.layers.get_mut(index)
+  }
+
+  /// Returns index of the active layer.
+  pub fn current_layer(&self) -> usize {
+    self.current.get()
+  }
+
+  /// Makes the first layer active.
+  pub fn reset(&self) {
+    self.current.set(0);
+  }
+
+  /// Returns number of switch chords emitted since creation or the last
+  /// [LayeredChordKeyboard::reset_switch_count].
+  pub fn switch_count(&self) -> u64 {
+    self.switches.get()
+  }
+
+  pub fn reset_switch_count(&self) {
+    self.switches.set(0);
+  }
+
+  /// Appends chords necessary to type `ch` to `handstates`, switching
+  /// layers if needed.
+  fn type_char(
+    &self,
+    ch: char,
+    handstates: &mut Vec<HandsState>,
+  ) -> Result<(), NoSuchChar> {
+    let current = self.current.get();
+    if let Some(hs) = self.layers.get(current).and_then(|l| l.chords.get(&ch)) {
+      handstates.push(*hs);
+      return Ok(());
+    }
+    let (idx, layer, hs) = self
+      .layers
+      .iter()
+      .enumerate()
+      .find_map(|(i, l)| l.chords.get(&ch).map(|hs| (i, l, *hs)))
+      .ok_or(NoSuchChar { ch })?;
+    handstates.push(layer.switch);
+    handstates.push(hs);
+    self.switches.set(self.switches.get() + 1);
+    if layer.sticky {
+      self.current.set(idx);
+    }
+    Ok(())
+  }
+}
+
+impl LayeredChordKeyboard {
+  /// Returns chords that can't be told apart once typed: chords bound to
+  /// several chars of a layer or to a char and a layer switch, and switch
+  /// chords shared by several layers. Collisions of layers come in layer
+  /// order, then switch collisions.
+  pub fn collisions(&self) -> Vec<LayerCollision> {
+    let switches = |chord: &HandsState| -> Vec<usize> {
+      (0..self.layers.len())
+        .filter(|&i| self.layers[i].switch == *chord)
+        .collect()
+    };
+    let mut collisions = Vec::new();
+    for (i, layer) in self.layers.iter().enumerate() {
+      let mut chords: Vec<(HandsState, Vec<char>)> = Vec::new();
+      let mut chars: Vec<_> = layer.chords.iter().collect();
+      chars.sort_unstable_by_key(|&(ch, _)| ch);
+      for (&ch, &chord) in chars {
+        match chords.iter_mut().find(|(hs, _)| *hs == chord) {
+          Some((_, chars)) => chars.push(ch),
+          None => chords.push((chord, vec![ch])),
+        }
+      }
+      for (chord, chars) in chords {
+        let switches = switches(&chord);
+        if chars.len() + switches.len() > 1 {
+          collisions.push(LayerCollision {
+            layer: Some(i),
+            chord,
+            chars,
+            switches,
+          });
+        }
+      }
+    }
+    for (i, layer) in self.layers.iter().enumerate() {
+      let switches = switches(&layer.switch);
+      if switches.len() > 1 && switches[0] == i {
+        collisions.push(LayerCollision {
+          layer: None,
+          chord: layer.switch,
+          chars: Vec::new(),
+          switches,
+        });
+      }
+    }
+    collisions
+  }
+}
+
+/// Chord of a [LayeredChordKeyboard] with several meanings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerCollision {
+  /// Index of the layer whose chars collide or `None` if only switch chords
+  /// collide.
+  pub layer: Option<usize>,
+  pub chord: HandsState,
+  /// Chars of the layer bound to the chord in alphabetical order.
+  pub chars: Vec<char>,
+  /// Indices of layers switched to with the chord.
+  pub switches: Vec<usize>,
+}
+
+impl LayeredChordKeyboard {
+  /// Types `chars` or returns an error with the index of the char that
+  /// couldn't be typed.
+  fn try_type_indexed(
+    &self,
+    chars: &[char],
+  ) -> Result<Vec<HandsState>, (NoSuchChar, usize)> {
+    let mut handstates: Vec<HandsState> = Vec::with_capacity(chars.len());
+    let mut i = 0;
+    while i < chars.len() {
+      let word = self
+        .layers
+        .get(self.current.get())
+        .and_then(|layer| layer.words.match_word(chars, i));
+      match word {
+        Some((_, len, hs)) => {
+          handstates.push(hs);
+          i += len;
+        }
+        None => {
+          self.type_char(chars[i], &mut handstates).map_err(|e| (e, i))?;
+          i += 1;
+        }
+      }
+    }
+    Ok(handstates)
+  }
+}
+
+impl Keyboard for LayeredChordKeyboard {
+  fn try_type_chars(
+    &self,
+    chars: impl Iterator<Item = char>,
+  ) -> Result<Vec<HandsState>, NoSuchChar> {
+    let chars: Vec<_> = chars.collect();
+    self.try_type_indexed(&chars).map_err(|(e, _)| e)
+  }
+
+  fn try_type_text(
+    &self,
+    text: &str,
+  ) -> Result<Vec<HandsState>, TypingError> {
+    let chars: Vec<_> = text.chars().collect();
+    self
+      .try_type_indexed(&chars)
+      .map_err(|(e, i)| TypingError::locate(e.ch, text, i))
+  }
+}
+
+impl Shift for LayeredChordKeyboard {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn test_keyboard(sticky: bool) -> LayeredChordKeyboard {
+    let mut numbers = Layer::new(
+      HashMap::from([('1', [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into())]),
+      HandsState::right_thumb(),
+    );
+    numbers.set_sticky(sticky);
+    let mut kb = LayeredChordKeyboard::new();
+    kb.add_layer(Layer::new(
+      HashMap::from([('a', [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into())]),
+      HandsState::left_thumb(),
+    ))
+    .add_layer(numbers);
+    kb
+  }
+
+  #[test]
+  fn test_sticky_layers() {
+    let kb = test_keyboard(true);
+    assert_eq!(kb.type_chars("a11a".chars()), vec![
+      [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+      HandsState::right_thumb(),
+      [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+      [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+      HandsState::left_thumb(),
+      [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+    ]);
+    assert_eq!(kb.current_layer(), 0);
+    assert_eq!(kb.switch_count(), 2);
+    kb.type_chars("1".chars());
+    assert_eq!(kb.current_layer(), 1);
+    assert_eq!(kb.switch_count(), 3);
+    kb.reset_switch_count();
+    assert_eq!(kb.switch_count(), 0);
+    kb.reset();
+    assert_eq!(kb.current_layer(), 0);
+  }
+
+  #[test]
+  fn test_one_shot_layers() {
+    let kb = test_keyboard(false);
+    assert_eq!(kb.type_chars("11a".chars()), vec![
+      HandsState::right_thumb(),
+      [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+      HandsState::right_thumb(),
+      [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+      [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+    ]);
+    assert_eq!(kb.current_layer(), 0);
+    assert_eq!(kb.try_type_chars("b".chars()), Err(NoSuchChar { ch: 'b' }));
+  }
+
+  #[test]
+  fn test_words() {
+    let mut kb = test_keyboard(true);
+    let aa = HandsState::both_thumbs();
+    kb.layer_mut(0)
+      .unwrap()
+      .add_word("aa", aa)
+      .add_word("a", HandsState::left_thumb());
+    assert_eq!(kb.layers()[0].words().iter().collect::<Vec<_>>(), [("aa", aa)]);
+    let a: HandsState = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into();
+    assert_eq!(kb.type_chars("aa".chars()), vec![aa]);
+    kb.type_chars("1".chars());
+    assert_eq!(kb.type_chars("aa".chars()), vec![
+      HandsState::left_thumb(),
+      a,
+      a
+    ]);
+    assert_eq!(kb.type_chars("aaa".chars()), vec![a, a, a]);
+  }
+
+  #[test]
+  fn test_typing_error_position() {
+    let kb = test_keyboard(true);
+    assert_eq!(kb.try_type_text("a1ab a11"), Err(TypingError {
+      ch: 'b',
+      index: 3,
+      byte: 3,
+      line: 1,
+      column: 4,
+    }));
+  }
+
+  #[test]
+  fn test_collisions() {
+    let kb = test_keyboard(true);
+    assert!(kb.collisions().is_empty());
+    let one: HandsState = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into();
+    let mut kb = test_keyboard(true);
+    kb.add_layer(Layer::new(
+      HashMap::from([('b', one), ('c', one), ('d', HandsState::left_thumb())]),
+      HandsState::right_thumb(),
+    ));
+    assert_eq!(kb.collisions(), [
+      LayerCollision {
+        layer: Some(2),
+        chord: one,
+        chars: vec!['b', 'c'],
+        switches: vec![],
+      },
+      LayerCollision {
+        layer: Some(2),
+        chord: HandsState::left_thumb(),
+        chars: vec!['d'],
+        switches: vec![0],
+      },
+      LayerCollision {
+        layer: None,
+        chord: HandsState::right_thumb(),
+        chars: vec![],
+        switches: vec![1, 2],
+      },
+    ]);
+  }
+}
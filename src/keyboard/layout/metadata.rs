@@ -0,0 +1,328 @@
+//! Contains metadata of layouts stored along with them, so shared layout
+//! files tell where they come from.
+
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  corpus::text_hash,
+  keyboard::{
+    hands::HandsState,
+    optimize::Annealing,
+    report::Report,
+    schedule::Geometric,
+    shift::{Shift, ShiftPolicy},
+    Keyboard,
+    NoSuchChar,
+    TypingError,
+  },
+};
+
+/// Describes a layout: who made it, when and how. All fields are optional.
+/// `S` is the [Schedule](crate::keyboard::schedule::Schedule) of the
+/// optimizer that generated the layout.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct LayoutMetadata<S = Geometric> {
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub name: Option<String>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub author: Option<String>,
+  /// When the layout was generated, in seconds since the Unix epoch.
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub generated_at: Option<u64>,
+  /// Hash of the corpus the layout was optimized for, see [corpus_hash].
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub corpus_hash: Option<String>,
+  /// Settings of the optimizer that generated the layout.
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub optimizer: Option<Annealing<S>>,
+  /// Scores per press of metrics by their names.
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "BTreeMap::is_empty")
+  )]
+  pub scores: BTreeMap<String, f32>,
+}
+
+impl LayoutMetadata {
+  /// Creates empty metadata of a layout generated with the default
+  /// schedule. Use [LayoutMetadata::default] for other schedules.
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+impl<S> LayoutMetadata<S> {
+  pub fn set_name(&mut self, name: &str) -> &mut Self {
+    self.name = Some(name.to_owned());
+    self
+  }
+
+  pub fn set_author(&mut self, author: &str) -> &mut Self {
+    self.author = Some(author.to_owned());
+    self
+  }
+
+  /// Sets when the layout was generated in seconds since the Unix epoch.
+  pub fn set_generated_at(&mut self, generated_at: u64) -> &mut Self {
+    self.generated_at = Some(generated_at);
+    self
+  }
+
+  /// Sets [corpus_hash] of `text`.
+  pub fn set_corpus(&mut self, text: &str) -> &mut Self {
+    self.corpus_hash = Some(corpus_hash(text));
+    self
+  }
+
+  pub fn set_optimizer(&mut self, optimizer: &Annealing<S>) -> &mut Self
+  where
+    S: Clone,
+  {
+    self.optimizer = Some(optimizer.clone());
+    self
+  }
+
+  /// Replaces scores with scores per press of metrics of `report`.
+  pub fn set_scores(&mut self, report: &Report) -> &mut Self {
+    self.scores = report
+      .metrics
+      .iter()
+      .map(|metric| (metric.name.clone(), metric.score_per_press))
+      .collect();
+    self
+  }
+}
+
+impl<S> Default for LayoutMetadata<S> {
+  fn default() -> Self {
+    Self {
+      name: None,
+      author: None,
+      generated_at: None,
+      corpus_hash: None,
+      optimizer: None,
+      scores: BTreeMap::new(),
+    }
+  }
+}
+
+/// Returns [text_hash] of `text` in hex, as stored in layout files.
+pub fn corpus_hash(text: &str) -> String {
+  format!("{:016x}", text_hash(text))
+}
+
+/// Layout carrying its [LayoutMetadata]. It types like the wrapped layout
+/// and is serialized like it with an optional `metadata` entry added, so
+/// layout files without metadata can be read as well.
+///
+/// ```
+/// use tenboard::keyboard::{
+///   layout::{
+///     metadata::{LayoutMetadata, WithMetadata},
+///     tenboard::{Tenboard, TenboardUnconstrained},
+///   },
+///   Keyboard,
+/// };
+///
+/// let mut layout = WithMetadata::new(TenboardUnconstrained::new_random());
+/// layout
+///   .metadata_mut()
+///   .set_name("random")
+///   .set_corpus("hello world");
+/// #[cfg(feature = "serde")]
+/// {
+///   let json = serde_json::to_string(&layout).unwrap();
+///   layout = serde_json::from_str(&json).unwrap();
+/// }
+/// assert_eq!(layout.metadata().unwrap().name.as_deref(), Some("random"));
+/// assert_eq!(layout.type_chars("hello".chars()).len(), 5);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WithMetadata<L, S = Geometric> {
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, skip_serializing_if = "Option::is_none")
+  )]
+  metadata: Option<LayoutMetadata<S>>,
+  #[cfg_attr(feature = "serde", serde(flatten))]
+  layout: L,
+}
+
+impl<L> WithMetadata<L> {
+  /// Wraps `layout` without metadata. Use [WithMetadata::new_with_metadata]
+  /// for metadata of other schedules.
+  pub fn new(layout: L) -> Self {
+    Self {
+      metadata: None,
+      layout,
+    }
+  }
+}
+
+impl<L, S> WithMetadata<L, S> {
+  pub fn new_with_metadata(layout: L, metadata: LayoutMetadata<S>) -> Self {
+    Self {
+      metadata: Some(metadata),
+      layout,
+    }
+  }
+
+  pub fn layout(&self) -> &L {
+    &self.layout
+  }
+
+  pub fn layout_mut(&mut self) -> &mut L {
+    &mut self.layout
+  }
+
+  pub fn metadata(&self) -> Option<&LayoutMetadata<S>> {
+    self.metadata.as_ref()
+  }
+
+  /// Returns metadata of the layout, adding empty metadata if it has none.
+  pub fn metadata_mut(&mut self) -> &mut LayoutMetadata<S> {
+    self.metadata.get_or_insert_with(LayoutMetadata::default)
+  }
+
+  pub fn set_metadata(
+    &mut self,
+    metadata: Option<LayoutMetadata<S>>,
+  ) -> &mut Self {
+    self.metadata = metadata;
+    self
+  }
+
+  /// Returns the wrapped layout and its metadata.
+  pub fn into_parts(self) -> (L, Option<LayoutMetadata<S>>) {
+    (self.layout, self.metadata)
+  }
+}
+
+impl<L: Default, S> Default for WithMetadata<L, S> {
+  fn default() -> Self {
+    Self {
+      metadata: None,
+      layout: L::default(),
+    }
+  }
+}
+
+impl<L: Keyboard, S> Keyboard for WithMetadata<L, S> {
+  fn try_type_chars(
+    &self,
+    chars: impl Iterator<Item = char>,
+  ) -> Result<Vec<HandsState>, NoSuchChar> {
+    self.layout.try_type_chars(chars)
+  }
+
+  fn try_type_text(
+    &self,
+    text: &str,
+  ) -> Result<Vec<HandsState>, TypingError> {
+    self.layout.try_type_text(text)
+  }
+}
+
+impl<L: Shift, S> Shift for WithMetadata<L, S> {
+  fn shift_policy(&self) -> ShiftPolicy {
+    self.layout.shift_policy()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_metadata() {
+    assert_eq!(corpus_hash(""), "cbf29ce484222325");
+    assert_eq!(corpus_hash("a"), "af63dc4c8601ec8c");
+    let report = Report::new(&[HandsState::left_thumb()]);
+    let mut metadata = LayoutMetadata::new();
+    metadata.set_scores(&report).set_author("me");
+    assert_eq!(metadata.scores.len(), report.metrics.len());
+    assert_eq!(metadata.author.as_deref(), Some("me"));
+
+    let mut layout = WithMetadata::new(HandsState::left_thumb());
+    assert_eq!(layout.metadata(), None);
+    layout.metadata_mut().set_generated_at(1);
+    let (_, metadata) = layout.into_parts();
+    assert_eq!(metadata.unwrap().generated_at, Some(1));
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_metadata_serialization() -> Result<(), serde_json::Error> {
+    use crate::keyboard::{
+      charset::CharSet,
+      hands::ThumbPolicy,
+      layout::{
+        rows::TenboardRows,
+        tenboard::{Tenboard, TenboardHomeRowConstrained},
+      },
+      schedule::Adaptive,
+    };
+
+    let mut metadata = LayoutMetadata::new();
+    metadata
+      .set_name("rows")
+      .set_author("me")
+      .set_generated_at(1_700_000_000)
+      .set_corpus("text")
+      .set_optimizer(&Annealing::new())
+      .set_scores(&Report::new(&[HandsState::left_thumb()]));
+    let layout = WithMetadata::new_with_metadata(
+      TenboardRows::new_random(2, &CharSet::german(), ThumbPolicy::default()),
+      metadata.clone(),
+    );
+    let json = serde_json::to_string(&layout)?;
+    assert_eq!(serde_json::from_str::<WithMetadata<_>>(&json)?, layout);
+
+    let layout = TenboardHomeRowConstrained::new_random();
+    let json = serde_json::to_string(&layout)?;
+    let mut with_metadata: WithMetadata<TenboardHomeRowConstrained> =
+      serde_json::from_str(&json)?;
+    assert_eq!(with_metadata.metadata(), None);
+    assert_eq!(serde_json::to_string(&with_metadata)?, json);
+    with_metadata.set_metadata(Some(metadata.clone()));
+    let json = serde_json::to_string(&with_metadata)?;
+    let with_metadata: WithMetadata<TenboardHomeRowConstrained> =
+      serde_json::from_str(&json)?;
+    assert_eq!(with_metadata.metadata(), Some(&metadata));
+
+    let mut adaptive = Adaptive::default();
+    adaptive.set_patience(7);
+    let mut metadata = LayoutMetadata::default();
+    metadata.set_optimizer(&Annealing::with_schedule(adaptive));
+    let layout = WithMetadata::new_with_metadata(
+      TenboardHomeRowConstrained::new_random(),
+      metadata,
+    );
+    let json = serde_json::to_string(&layout)?;
+    let read: WithMetadata<TenboardHomeRowConstrained, Adaptive> =
+      serde_json::from_str(&json)?;
+    assert_eq!(read.metadata(), layout.metadata());
+    Ok(())
+  }
+}
@@ -0,0 +1,198 @@
+//! Describes keyboards where modifiers are pressed as separate chords.
+
+use crate::keyboard::{
+  hands::HandsState,
+  shift::{Shift, ShiftPolicy},
+  Keyboard,
+  NoSuchChar,
+  TypingError,
+};
+
+/// How a modifier of a [ModifierKeyboard] applies to other chords.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModifierMode {
+  /// The modifier is pressed, released, then applied to the next chord
+  /// only, like sticky keys of many chorded firmwares.
+  OneShot,
+  /// The modifier is pressed once before a run of modified chords and once
+  /// more before the next unmodified chord to release it, like caps lock.
+  Locked,
+}
+
+/// Keyboard that types chords of another keyboard, but presses modifiers
+/// as separate chords instead of combining them with other fingers. A chord
+/// of the underlying keyboard is modified if it presses all fingers of a
+/// modifier and some others. Metrics then see every modifier press as a
+/// separate `HandsState`.
+///
+/// ```
+/// use tenboard::keyboard::{
+///   hands::HandsState,
+///   layout::{
+///     asetniop::Asetniop,
+///     modifiers::{ModifierKeyboard, ModifierMode},
+///   },
+///   Keyboard,
+/// };
+///
+/// let mut kb = ModifierKeyboard::new(Asetniop::default());
+/// kb.add_modifier(HandsState::left_thumb(), ModifierMode::OneShot);
+/// let handstates = kb.type_chars("Aa".chars());
+/// assert_eq!(handstates.len(), 3);
+/// assert_eq!(handstates[0], HandsState::left_thumb());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ModifierKeyboard<K> {
+  keyboard: K,
+  modifiers: Vec<(HandsState, ModifierMode)>,
+}
+
+impl<K: Keyboard> ModifierKeyboard<K> {
+  /// Creates a keyboard without separate modifiers. It types like
+  /// `keyboard`.
+  pub fn new(keyboard: K) -> Self {
+    Self {
+      keyboard,
+      modifiers: Vec::new(),
+    }
+  }
+
+  /// Makes `modifier` a separate chord applied in `mode`. If `modifier` is
+  /// already added, its mode is replaced.
+  pub fn add_modifier(
+    &mut self,
+    modifier: HandsState,
+    mode: ModifierMode,
+  ) -> &mut Self {
+    match self.modifiers.iter_mut().find(|(m, _)| *m == modifier) {
+      Some((_, m)) => *m = mode,
+      None => self.modifiers.push((modifier, mode)),
+    }
+    self
+  }
+
+  pub fn keyboard(&self) -> &K {
+    &self.keyboard
+  }
+
+  /// Returns modifiers with their modes in order they were added.
+  pub fn modifiers(&self) -> &[(HandsState, ModifierMode)] {
+    &self.modifiers
+  }
+}
+
+/// Returns `true` if `chord` presses all fingers of `modifier` and some
+/// others.
+fn is_modified(chord: &HandsState, modifier: &HandsState) -> bool {
+  modifier.is_subset_of(chord)
+    && chord.count_pressed() > modifier.count_pressed()
+}
+
+impl<K: Keyboard> ModifierKeyboard<K> {
+  /// Splits modifiers off `chords` of the underlying keyboard.
+  fn split_modifiers(&self, chords: Vec<HandsState>) -> Vec<HandsState> {
+    let mut locked = vec![false; self.modifiers.len()];
+    let mut handstates = Vec::new();
+    for chord in chords {
+      let mut rest = chord;
+      for (i, (modifier, mode)) in self.modifiers.iter().enumerate() {
+        let modified = is_modified(&rest, modifier);
+        if modified {
+          rest = rest - *modifier;
+        }
+        match mode {
+          ModifierMode::OneShot if modified => handstates.push(*modifier),
+          ModifierMode::OneShot => {}
+          ModifierMode::Locked => {
+            if modified != locked[i] {
+              handstates.push(*modifier);
+              locked[i] = modified;
+            }
+          }
+        }
+      }
+      handstates.push(rest);
+    }
+    handstates
+  }
+}
+
+impl<K: Keyboard> Keyboard for ModifierKeyboard<K> {
+  fn try_type_chars(
+    &self,
+    chars: impl Iterator<Item = char>,
+  ) -> Result<Vec<HandsState>, NoSuchChar> {
+    Ok(self.split_modifiers(self.keyboard.try_type_chars(chars)?))
+  }
+
+  fn try_type_text(
+    &self,
+    text: &str,
+  ) -> Result<Vec<HandsState>, TypingError> {
+    Ok(self.split_modifiers(self.keyboard.try_type_text(text)?))
+  }
+}
+
+/// Shift policy of the underlying keyboard, where a one-shot modifier
+/// becomes [ShiftPolicy::Sticky].
+impl<K: Keyboard + Shift> Shift for ModifierKeyboard<K> {
+  fn shift_policy(&self) -> ShiftPolicy {
+    match self.keyboard.shift_policy() {
+      ShiftPolicy::Modifier(modifier)
+        if self.modifiers.contains(&(modifier, ModifierMode::OneShot)) =>
+      {
+        ShiftPolicy::Sticky(modifier)
+      }
+      policy => policy,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    keyboard::{
+      layout::asetniop::Asetniop,
+      metric::{Metric, ShiftPresses},
+    },
+    testing::SyntheticKeyboard,
+  };
+
+  fn test_keyboard(mode: ModifierMode) -> ModifierKeyboard<SyntheticKeyboard> {
+    let kb = SyntheticKeyboard::new_one_finger("abcpqrsdef")
+      .with_chord('A', [1, 0, 0, 0, 1, 0, 0, 0, 0, 0])
+      .with_chord('B', [0, 1, 0, 0, 1, 0, 0, 0, 0, 0]);
+    let mut kb = ModifierKeyboard::new(kb);
+    kb.add_modifier(HandsState::left_thumb(), mode);
+    kb
+  }
+
+  #[test]
+  fn test_one_shot() {
+    let kb = test_keyboard(ModifierMode::OneShot);
+    let a: HandsState = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into();
+    let b: HandsState = [0, 1, 0, 0, 0, 0, 0, 0, 0, 0].into();
+    let lt = HandsState::left_thumb();
+    assert_eq!(kb.type_chars("AaB".chars()), vec![lt, a, a, lt, b]);
+    let policy = ShiftPolicy::Sticky(lt);
+    let presses =
+      ShiftPresses::new(policy).updated(&kb.type_chars("AB".chars()));
+    assert_eq!(presses.values(), 2);
+
+    let mut kb = ModifierKeyboard::new(Asetniop::default());
+    assert_eq!(kb.shift_policy(), ShiftPolicy::Modifier(lt));
+    kb.add_modifier(lt, ModifierMode::OneShot);
+    assert_eq!(kb.shift_policy(), policy);
+  }
+
+  #[test]
+  fn test_locked() {
+    let kb = test_keyboard(ModifierMode::Locked);
+    let a: HandsState = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into();
+    let b: HandsState = [0, 1, 0, 0, 0, 0, 0, 0, 0, 0].into();
+    let lt = HandsState::left_thumb();
+    assert_eq!(kb.type_chars("ABaA".chars()), vec![lt, a, b, lt, a, lt, a]);
+    assert_eq!(kb.try_type_chars("Ax".chars()), Err(NoSuchChar { ch: 'x' }));
+  }
+}
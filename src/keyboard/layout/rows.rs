@@ -0,0 +1,236 @@
+//! Describes Tenboard layouts where every finger owns keys in several rows.
+
+use std::collections::HashMap;
+
+use rand::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::tenboard::BadSwap;
+use crate::keyboard::{
+  charset::CharSet,
+  geometry::{Row, RowChord},
+  hands::{HandsState, ThumbPolicy},
+  shift::Shift,
+  Keyboard,
+  NoSuchChar,
+};
+
+/// Tenboard layout where each finger but thumbs owns a key in the home row
+/// and keys in the top and, optionally, the bottom row. A chord selects a
+/// row for every pressed finger, so there are far more one and two key
+/// chords than in [Tenboard](super::tenboard::Tenboard) layouts. Thumbs own
+/// a single key in the home row.
+///
+/// As a [Keyboard], the layout types chords without rows. Use
+/// [TenboardRows::try_type_row_chars] to keep them, e.g. for
+/// [TravelDistance](crate::keyboard::geometry::TravelDistance).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TenboardRows {
+  rows: usize,
+  layout: HashMap<char, RowChord>,
+}
+
+impl TenboardRows {
+  /// Creates a new layout with `rows` rows per finger where each char of
+  /// `charset` corresponds to a random chord allowed by `policy`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `rows` is not 2 or 3, or if there are less chords than chars
+  /// in `charset`.
+  pub fn new_random(
+    rows: usize,
+    charset: &CharSet,
+    policy: ThumbPolicy,
+  ) -> Self {
+    Self::new_random_with_rng(rows, charset, policy, &mut rand::thread_rng())
+  }
+
+  /// Same as [TenboardRows::new_random], but uses `rng` as a source of
+  /// randomness.
+  pub fn new_random_with_rng(
+    rows: usize,
+    charset: &CharSet,
+    policy: ThumbPolicy,
+    rng: &mut impl Rng,
+  ) -> Self {
+    let mut chords = Self::chords(rows, policy);
+    assert!(
+      chords.len() >= charset.len(),
+      "{} rows allow only {} chords for {} chars",
+      rows,
+      chords.len(),
+      charset.len()
+    );
+    chords.shuffle(rng);
+    Self {
+      rows,
+      layout: charset.chars().zip(chords).collect(),
+    }
+  }
+
+  /// Returns all one and two key chords of a layout with `rows` rows per
+  /// finger allowed by `policy`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `rows` is not 2 or 3.
+  pub fn chords(rows: usize, policy: ThumbPolicy) -> Vec<RowChord> {
+    assert!((2..=3).contains(&rows), "expected 2 or 3 rows, got {rows}");
+    let rows = match rows {
+      2 => &[Row::Home, Row::Top][..],
+      _ => &Row::ALL[..],
+    };
+    HandsState::iterate_one_two_key(policy)
+      .flat_map(|hs| {
+        let mut chords = vec![RowChord::default()];
+        for (finger, fs) in hs.iter().enumerate() {
+          if !fs.is_pressed() {
+            continue;
+          }
+          let choices = if matches!(finger, 4 | 5) {
+            &[Row::Home][..]
+          } else {
+            rows
+          };
+          chords = chords
+            .into_iter()
+            .flat_map(|chord| {
+              choices.iter().map(move |&row| {
+                let mut chord = chord;
+                chord.0[finger] = Some(row);
+                chord
+              })
+            })
+            .collect();
+        }
+        chords
+      })
+      .collect()
+  }
+
+  /// Returns the number of rows per finger.
+  pub fn rows(&self) -> usize {
+    self.rows
+  }
+
+  pub fn charset(&self) -> CharSet {
+    let mut chars: Vec<_> = self.layout.keys().copied().collect();
+    chars.sort_unstable();
+    chars.into_iter().collect()
+  }
+
+  /// Returns the chord of `ch` or an error if `ch` isn't in the layout.
+  pub fn try_type_row_char(&self, ch: char) -> Result<RowChord, NoSuchChar> {
+    self.layout.get(&ch).copied().ok_or(NoSuchChar { ch })
+  }
+
+  /// Returns chords typing `chars` with their rows.
+  pub fn try_type_row_chars(
+    &self,
+    chars: impl Iterator<Item = char>,
+  ) -> Result<Vec<RowChord>, NoSuchChar> {
+    chars.map(|ch| self.try_type_row_char(ch)).collect()
+  }
+
+  /// Swaps chords of `ch1` and `ch2`. Returns an error if any of them isn't
+  /// in the layout.
+  pub fn try_swap_states(
+    &mut self,
+    ch1: char,
+    ch2: char,
+  ) -> Result<(), BadSwap> {
+    match (
+      self.layout.get(&ch1).copied(),
+      self.layout.get(&ch2).copied(),
+    ) {
+      (Some(chord1), Some(chord2)) => {
+        self.layout.insert(ch1, chord2);
+        self.layout.insert(ch2, chord1);
+        Ok(())
+      }
+      _ => Err(BadSwap { ch1, ch2 }),
+    }
+  }
+}
+
+impl Keyboard for TenboardRows {
+  fn try_type_chars(
+    &self,
+    chars: impl Iterator<Item = char>,
+  ) -> Result<Vec<HandsState>, NoSuchChar> {
+    chars
+      .map(|ch| self.try_type_row_char(ch).map(|chord| chord.handstate()))
+      .collect()
+  }
+}
+
+impl Shift for TenboardRows {}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+
+  use rand::rngs::StdRng;
+
+  use super::*;
+  use crate::keyboard::geometry::TravelDistance;
+
+  #[test]
+  fn test_chords() {
+    let two = TenboardRows::chords(2, ThumbPolicy::default());
+    let three = TenboardRows::chords(3, ThumbPolicy::default());
+    assert!(two.len() > 110 * 2);
+    assert!(three.len() > two.len());
+    assert_eq!(three.iter().collect::<HashSet<_>>().len(), three.len());
+    assert!(three.iter().all(|chord| chord.0[4..6]
+      .iter()
+      .all(|row| matches!(row, None | Some(Row::Home)))));
+  }
+
+  #[test]
+  fn test_random_layout() {
+    let charset = CharSet::german();
+    let layout = TenboardRows::new_random_with_rng(
+      3,
+      &charset,
+      ThumbPolicy::default(),
+      &mut StdRng::seed_from_u64(0),
+    );
+    assert_eq!(layout.rows(), 3);
+    assert_eq!(layout.charset().len(), charset.len());
+    let chords: HashSet<_> = charset
+      .chars()
+      .map(|ch| layout.try_type_row_char(ch).unwrap())
+      .collect();
+    assert_eq!(chords.len(), charset.len());
+
+    let text = "Grüße".chars();
+    let handstates = layout.type_chars(text.clone());
+    let row_chords = layout.try_type_row_chars(text).unwrap();
+    assert!(row_chords
+      .iter()
+      .zip(&handstates)
+      .all(|(chord, hs)| chord.handstate() == *hs));
+    assert!(TravelDistance::default().updated_rows(&row_chords).score() > 0.0);
+
+    #[cfg(feature = "serde")]
+    {
+      let json = serde_json::to_string(&layout).unwrap();
+      assert_eq!(serde_json::from_str::<TenboardRows>(&json).unwrap(), layout);
+    }
+  }
+
+  #[test]
+  fn test_swap_states() {
+    let mut layout =
+      TenboardRows::new_random(2, &CharSet::ascii(), ThumbPolicy::default());
+    let (a, b) = (layout.try_type_row_char('a'), layout.try_type_row_char('b'));
+    layout.try_swap_states('a', 'b').unwrap();
+    assert_eq!(layout.try_type_row_char('a'), b);
+    assert_eq!(layout.try_type_row_char('b'), a);
+    assert!(layout.try_swap_states('a', 'é').is_err());
+  }
+}
@@ -0,0 +1,171 @@
+//! Describes conventional keyboard layouts typed with standard touch-typing
+//! fingering, so chorded layouts can be compared against them.
+//!
+//! Every char is typed with a single finger. Shifted chars additionally
+//! press the pinky of the other hand, space is typed with the right thumb,
+//! tab with the left pinky and newline with the right pinky. Rows are not
+//! modeled, so `e` and `d` are the same press in QWERTY.
+
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+
+use crate::keyboard::{hands::HandsState, shift::Shift, Keyboard, NoSuchChar};
+
+/// Unshifted and shifted chars of the number, top, home and bottom rows of
+/// an ANSI keyboard.
+type Rows = [(&'static str, &'static str); 4];
+
+const QWERTY_ROWS: Rows = [
+  ("`1234567890-=", "~!@#$%^&*()_+"),
+  ("qwertyuiop[]\\", "QWERTYUIOP{}|"),
+  ("asdfghjkl;'", "ASDFGHJKL:\""),
+  ("zxcvbnm,./", "ZXCVBNM<>?"),
+];
+
+const DVORAK_ROWS: Rows = [
+  ("`1234567890[]", "~!@#$%^&*(){}"),
+  ("',.pyfgcrl/=\\", "\"<>PYFGCRL?+|"),
+  ("aoeuidhtns-", "AOEUIDHTNS_"),
+  (";qjkxbmwvz", ":QJKXBMWVZ"),
+];
+
+const COLEMAK_ROWS: Rows = [
+  ("`1234567890-=", "~!@#$%^&*()_+"),
+  ("qwfpgjluy;[]\\", "QWFPGJLUY:{}|"),
+  ("arstdhneio'", "ARSTDHNEIO\""),
+  ("zxcvbkm,./", "ZXCVBKM<>?"),
+];
+
+/// Fingers of the first ten columns of letter rows. Columns further right
+/// are typed with the right pinky.
+const COLUMN_FINGERS: [usize; 10] = [0, 1, 2, 3, 3, 6, 6, 7, 8, 9];
+
+fn column_finger(column: usize) -> usize {
+  COLUMN_FINGERS.get(column).copied().unwrap_or(9)
+}
+
+fn press(fingers: &[usize]) -> HandsState {
+  let mut hs = HandsState::default();
+  for &finger in fingers {
+    hs[finger] = true.into();
+  }
+  hs
+}
+
+fn fingering(rows: &Rows) -> HashMap<char, HandsState> {
+  let mut layout = HashMap::from([
+    (' ', press(&[5])),
+    ('\t', press(&[0])),
+    ('\n', press(&[9])),
+  ]);
+  for (row, (unshifted, shifted)) in rows.iter().enumerate() {
+    for (column, (ch, shifted_ch)) in
+      unshifted.chars().zip(shifted.chars()).enumerate()
+    {
+      // The number row starts one column left of letter rows.
+      let finger = match (row, column) {
+        (0, 0) => 0,
+        (0, column) => column_finger(column - 1),
+        (_, column) => column_finger(column),
+      };
+      let shift = if finger < 5 { 9 } else { 0 };
+      layout.insert(ch, press(&[finger]));
+      layout.insert(shifted_ch, press(&[finger, shift]));
+    }
+  }
+  layout
+}
+
+lazy_static! {
+  static ref QWERTY: HashMap<char, HandsState> = fingering(&QWERTY_ROWS);
+  static ref DVORAK: HashMap<char, HandsState> = fingering(&DVORAK_ROWS);
+  static ref COLEMAK: HashMap<char, HandsState> = fingering(&COLEMAK_ROWS);
+}
+
+macro_rules! standard_layout {
+  ($(#[$doc:meta])* $name:ident, $layout:ident) => {
+    $(#[$doc])*
+    #[derive(Default, Debug, Clone, Copy)]
+    pub struct $name {}
+
+    impl $name {
+      pub fn new() -> Self {
+        Self {}
+      }
+
+      /// Returns a hand state that describes the finger pressing given
+      /// char.
+      pub fn try_type_char(
+        &self,
+        ch: char,
+      ) -> Result<HandsState, NoSuchChar> {
+        $layout.get(&ch).copied().ok_or(NoSuchChar { ch })
+      }
+    }
+
+    impl Keyboard for $name {
+      fn try_type_chars(
+        &self,
+        chars: impl Iterator<Item = char>,
+      ) -> Result<Vec<HandsState>, NoSuchChar> {
+        chars.map(|ch| self.try_type_char(ch)).collect()
+      }
+    }
+
+    /// Shifted chars have chords of their own that press a pinky of the
+    /// other hand.
+    impl Shift for $name {}
+  };
+}
+
+standard_layout!(
+  /// US QWERTY layout.
+  Qwerty,
+  QWERTY
+);
+standard_layout!(
+  /// US Dvorak layout.
+  Dvorak,
+  DVORAK
+);
+standard_layout!(
+  /// Colemak layout.
+  Colemak,
+  COLEMAK
+);
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::keyboard::TYPABLE_CHARS;
+
+  #[test]
+  fn test_all_chars_typable() {
+    assert!(Qwerty::new().try_type_chars(TYPABLE_CHARS.chars()).is_ok());
+    assert!(Dvorak::new().try_type_chars(TYPABLE_CHARS.chars()).is_ok());
+    assert!(Colemak::new().try_type_chars(TYPABLE_CHARS.chars()).is_ok());
+  }
+
+  #[test]
+  fn test_typing() {
+    assert_eq!(Qwerty::new().type_chars("Hi 6".chars()), vec![
+      [1, 0, 0, 0, 0, 0, 1, 0, 0, 0].into(),
+      [0, 0, 0, 0, 0, 0, 0, 1, 0, 0].into(),
+      [0, 0, 0, 0, 0, 1, 0, 0, 0, 0].into(),
+      [0, 0, 0, 0, 0, 0, 1, 0, 0, 0].into(),
+    ]);
+    assert_eq!(
+      Dvorak::new().try_type_char('s'),
+      Qwerty::new().try_type_char(';')
+    );
+    assert_eq!(
+      Colemak::new().try_type_char('T'),
+      Qwerty::new().try_type_char('F')
+    );
+    assert_eq!(
+      Qwerty::new().try_type_char('é'),
+      Err(NoSuchChar { ch: 'é' })
+    );
+  }
+}
@@ -6,22 +6,42 @@ use std::{
 };
 
 use rand::prelude::*;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::keyboard::{
-  hands::HandsState,
-  Keyboard,
-  NoSuchChar,
-  DIGIT_CHARS,
-  LOWERCASE_CHARS,
-  PUNCTUATION_CHARS,
-  TYPABLE_CHARS,
+use super::{
+  chord_map::ChordMap,
+  constraints::{Constraints, Unsatisfiable},
+};
+use crate::{
+  corpus::NgramTable,
+  keyboard::{
+    charset::CharSet,
+    effort::{EffortModel, FingerEffort},
+    hands::{HandsState, ThumbPolicy},
+    shift::{Shift, ShiftPolicy},
+    Keyboard,
+    NoSuchChar,
+    DIGIT_CHARS,
+    LOWERCASE_CHARS,
+    PUNCTUATION_CHARS,
+    TYPABLE_CHARS,
+  },
 };
 
 pub trait Tenboard {
   /// Creates a new Tenboard keyboard layout where each character
   /// corresponds to a random `HandsState`.
   fn new_random() -> Self
+  where
+    Self: Sized,
+  {
+    Self::new_random_with_rng(&mut rand::thread_rng())
+  }
+
+  /// Same as [Tenboard::new_random], but uses `rng` as a source of
+  /// randomness, so that layouts can be reproduced with a seeded `rng`.
+  fn new_random_with_rng(rng: &mut impl Rng) -> Self
   where
     Self: Sized;
 
@@ -29,6 +49,221 @@ pub trait Tenboard {
   /// for given char to be typed. If for some char no combination was found,
   /// returns an error.
   fn try_type_char(&self, ch: char) -> Result<HandsState, NoSuchChar>;
+
+  /// Swaps chords of `ch1` and `ch2`. If the swap breaks layout constraints
+  /// or any of the chars isn't in the layout, returns an error and leaves
+  /// the layout unchanged.
+  fn try_swap_states(&mut self, ch1: char, ch2: char) -> Result<(), BadSwap>;
+
+  /// Swaps chords of `ch1` and `ch2`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the chars can't be swapped. To avoid panic, use
+  /// [Tenboard::try_swap_states].
+  fn swap_states(&mut self, ch1: char, ch2: char) {
+    self
+      .try_swap_states(ch1, ch2)
+      .unwrap_or_else(|e| panic!("{e}"))
+  }
+
+  /// Returns chars the layout is made for. By default these are ASCII
+  /// [TYPABLE_CHARS].
+  fn charset(&self) -> CharSet {
+    CharSet::ascii()
+  }
+
+  /// Returns chars of the layout whose chords aren't allowed by `policy`.
+  fn thumb_policy_violations(&self, policy: ThumbPolicy) -> Vec<char> {
+    self
+      .charset()
+      .chars()
+      .filter(|&ch| self.try_type_char(ch).is_ok_and(|hs| !policy.allows(&hs)))
+      .collect()
+  }
+
+  /// Returns all layouts that differ from this one by a single swap of
+  /// chords of two chars of [Tenboard::charset]. Swaps rejected by
+  /// [Tenboard::try_swap_states] are skipped, so neighbors respect
+  /// constraints of the layout. Pairs of chars are enumerated in charset
+  /// order.
+  fn neighbors(&self) -> impl Iterator<Item = Self> + '_
+  where
+    Self: Sized + Clone,
+  {
+    let chars: Vec<_> = self
+      .charset()
+      .chars()
+      .filter(|&ch| self.try_type_char(ch).is_ok())
+      .collect();
+    let pairs: Vec<_> = chars
+      .iter()
+      .enumerate()
+      .flat_map(|(i, &ch1)| chars[i + 1..].iter().map(move |&ch2| (ch1, ch2)))
+      .collect();
+    pairs.into_iter().filter_map(|(ch1, ch2)| {
+      let mut neighbor = self.clone();
+      neighbor.try_swap_states(ch1, ch2).ok().map(|_| neighbor)
+    })
+  }
+
+  /// Compares chords of chars of the layout with `other` layout. Chars of
+  /// either charset typed by one layout only are added or removed.
+  fn diff(&self, other: &dyn Tenboard) -> LayoutDiff {
+    let mut diff = LayoutDiff::default();
+    let mut old_chars = HashMap::new();
+    let mut new_chars = HashMap::new();
+    let charset = self.charset();
+    let other_charset = other.charset();
+    let chars = charset
+      .chars()
+      .chain(other_charset.chars().filter(|&ch| !charset.contains(ch)));
+    for ch in chars {
+      let (old, new) = match (self.try_type_char(ch), other.try_type_char(ch))
+      {
+        (Ok(old), Ok(new)) => (old, new),
+        (Ok(old), Err(_)) => {
+          diff.removed.push((ch, old));
+          continue;
+        }
+        (Err(_), Ok(new)) => {
+          diff.added.push((ch, new));
+          continue;
+        }
+        (Err(_), Err(_)) => continue,
+      };
+      old_chars.insert(old, ch);
+      new_chars.insert(new, ch);
+      if old == new {
+        diff.unchanged.push((ch, old));
+      } else {
+        diff.changed.push((ch, old, new));
+      }
+    }
+    for (hs, &old_ch) in &old_chars {
+      if let Some(&new_ch) = new_chars.get(hs) {
+        if old_ch != new_ch {
+          diff.moved.push((*hs, old_ch, new_ch));
+        }
+      }
+    }
+    diff.moved.sort_by_key(|&(_, old_ch, _)| old_ch);
+    diff
+  }
+
+  /// Returns chords bound to several chars of [Tenboard::charset], e.g. a
+  /// letter combined with a modifier thumb that equals a punctuation chord.
+  /// Such chars can't be told apart once typed. Collisions are ordered by
+  /// their first chars in charset order.
+  fn collisions(&self) -> Vec<Collision> {
+    let mut collisions: Vec<Collision> = Vec::new();
+    let mut indices = HashMap::new();
+    for ch in self.charset().chars() {
+      let Ok(chord) = self.try_type_char(ch) else {
+        continue;
+      };
+      let i = *indices.entry(chord).or_insert_with(|| {
+        collisions.push(Collision {
+          chord,
+          chars: Vec::new(),
+        });
+        collisions.len() - 1
+      });
+      collisions[i].chars.push(ch);
+    }
+    collisions.retain(|c| c.chars.len() > 1);
+    collisions
+  }
+}
+
+/// Difference between two `Tenboard` layouts.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LayoutDiff {
+  /// Chars bound to the same chords in both layouts.
+  pub unchanged: Vec<(char, HandsState)>,
+  /// Chars bound to different chords: char, old chord and new chord.
+  pub changed: Vec<(char, HandsState, HandsState)>,
+  /// Chords bound to different chars in both layouts: chord, old char and
+  /// new char.
+  pub moved: Vec<(HandsState, char, char)>,
+  /// Chars typed by the new layout only with their new chords.
+  pub added: Vec<(char, HandsState)>,
+  /// Chars typed by the old layout only with their old chords.
+  pub removed: Vec<(char, HandsState)>,
+}
+
+impl LayoutDiff {
+  /// Returns `true` if layouts type the same chars with the same chords.
+  pub fn is_empty(&self) -> bool {
+    self.changed.is_empty()
+      && self.moved.is_empty()
+      && self.added.is_empty()
+      && self.removed.is_empty()
+  }
+}
+
+impl Display for LayoutDiff {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for (ch, old, new) in &self.changed {
+      writeln!(f, "{ch:?}\t{old} -> {new}")?;
+    }
+    for (ch, new) in &self.added {
+      writeln!(f, "{ch:?}\t+ {new}")?;
+    }
+    for (ch, old) in &self.removed {
+      writeln!(f, "{ch:?}\t- {old}")?;
+    }
+    Ok(())
+  }
+}
+
+/// Chord bound to several chars of a `Tenboard` layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+  pub chord: HandsState,
+  /// Chars bound to the chord in charset order.
+  pub chars: Vec<char>,
+}
+
+impl Display for Collision {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let chars: Vec<_> = self.chars.iter().map(|ch| format!("{ch:?}")).collect();
+    write!(f, "chord {} is bound to {}", self.chord, chars.join(", "))
+  }
+}
+
+/// This error means that chords of two chars couldn't be swapped in a
+/// `Tenboard` layout.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BadSwap {
+  pub ch1: char,
+  pub ch2: char,
+}
+
+impl Display for BadSwap {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "chars '{}' and '{}' can't be swapped",
+      self.ch1, self.ch2
+    )
+  }
+}
+
+/// Swaps values of `ch1` and `ch2` keys if both are present in `layout`.
+fn swap_in_layout(
+  layout: &mut ChordMap,
+  ch1: char,
+  ch2: char,
+) -> Result<(), BadSwap> {
+  match (layout.get(ch1), layout.get(ch2)) {
+    (Some(hs1), Some(hs2)) => {
+      layout.insert(ch1, hs2);
+      layout.insert(ch2, hs1);
+      Ok(())
+    }
+    _ => Err(BadSwap { ch1, ch2 }),
+  }
 }
 
 impl<T: Tenboard> Keyboard for T {
@@ -42,15 +277,9 @@ impl<T: Tenboard> Keyboard for T {
 
 impl Debug for dyn Tenboard {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    TYPABLE_CHARS.chars().try_for_each(|ch| {
+    self.charset().chars().try_for_each(|ch| {
       let hs = self.try_type_char(ch);
-      let ch = match ch {
-        '\n' => '⤶',
-        '\t' => '⇆',
-        ' ' => '⎵',
-        _ => ch,
-      };
-      write!(f, "{ch}\t")?;
+      write!(f, "{}\t", char_symbol(ch))?;
       match hs {
         Ok(hs) => write!(f, "{hs}")?,
         Err(_) => write!(f, "no match!")?,
@@ -60,31 +289,276 @@ impl Debug for dyn Tenboard {
   }
 }
 
+/// Chord groups of [Display for dyn Tenboard](trait.Tenboard.html) in
+/// order they are shown.
+const CHORD_GROUPS: [&str; 6] =
+  ["thumbs", "one key", "left", "right", "both", "3+ keys"];
+
+/// Returns a chord group of a chord pressing `fingers` besides thumbs.
+fn chord_group(fingers: &[usize]) -> &'static str {
+  match fingers {
+    [] => "thumbs",
+    [_] => "one key",
+    [_, b] if *b < 5 => "left",
+    [a, _] if *a > 4 => "right",
+    [_, _] => "both",
+    _ => "3+ keys",
+  }
+}
+
+/// Number of chords shown in a line of [Display for dyn
+/// Tenboard](trait.Tenboard.html).
+const CHORDS_PER_LINE: usize = 6;
+
+/// Shows chords grouped by thumb modifiers as layers. Within a layer,
+/// chords are grouped by size and hand. Each chord is shown as pressed
+/// fingers without thumbs followed by its char.
+///
+/// ```text
+/// [no thumbs]
+///   one key  |... .... e   .|.. .... t   ...
+///   left     ||.. .... h   |.|. .... r   ...
+/// [left thumb]
+///   ...
+/// ```
 impl Display for dyn Tenboard {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    Debug::fmt(&self, f) // TODO: reimplement later
+    let mut chords: Vec<_> = self
+      .charset()
+      .chars()
+      .filter_map(|ch| Some((ch, self.try_type_char(ch).ok()?)))
+      .collect();
+    chords.sort_by_key(|(_, hs)| std::cmp::Reverse(hs.map(u32::from)));
+    let layers = [
+      ("no thumbs", false, false),
+      ("left thumb", true, false),
+      ("right thumb", false, true),
+      ("both thumbs", true, true),
+    ];
+    for (name, left, right) in layers {
+      let layer: Vec<_> = chords
+        .iter()
+        .filter(|(_, hs)| hs[4].is_pressed() == left)
+        .filter(|(_, hs)| hs[5].is_pressed() == right)
+        .collect();
+      if layer.is_empty() {
+        continue;
+      }
+      writeln!(f, "[{name}]")?;
+      for group in CHORD_GROUPS {
+        let entries: Vec<_> = layer
+          .iter()
+          .filter(|(_, hs)| {
+            let fingers: Vec<_> = [0, 1, 2, 3, 6, 7, 8, 9]
+              .into_iter()
+              .filter(|&i| hs[i].is_pressed())
+              .collect();
+            chord_group(&fingers) == group
+          })
+          .map(|(ch, hs)| {
+            let fingers: String = hs.iter().map(|fs| fs.to_string()).collect();
+            format!(
+              "{} {} {}",
+              &fingers[0..4],
+              &fingers[6..10],
+              char_symbol(*ch)
+            )
+          })
+          .collect();
+        for (i, line) in entries.chunks(CHORDS_PER_LINE).enumerate() {
+          let group = if i == 0 { group } else { "" };
+          writeln!(f, "  {group:8} {}", line.join("   "))?;
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Returns a visible symbol that stands for `ch`.
+fn char_symbol(ch: char) -> char {
+  match ch {
+    '\n' => '⤶',
+    '\t' => '⇆',
+    ' ' => '⎵',
+    _ => ch,
+  }
+}
+
+macro_rules! impl_display {
+  ($($layout:ty),*) => {
+    $(
+      impl Display for $layout {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+          Display::fmt(self as &dyn Tenboard, f)
+        }
+      }
+    )*
+  };
+}
+
+impl_display!(
+  TenboardUnconstrained,
+  TenboardThumbConstrained,
+  TenboardModifierConstrained,
+  TenboardHomeRowConstrained
+);
+
+impl Shift for TenboardUnconstrained {}
+impl Shift for TenboardThumbConstrained {}
+impl Shift for TenboardHomeRowConstrained {}
+
+/// Uppercase letters are typed with the whitespace thumb added.
+impl Shift for TenboardModifierConstrained {
+  fn shift_policy(&self) -> ShiftPolicy {
+    ShiftPolicy::Modifier(self.whitespace_hs)
   }
 }
 
 /// Unconstrained Tenboard layout. Any symbol can be mapped to any combination.
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TenboardUnconstrained {
-  #[serde(flatten)]
-  layout: HashMap<char, HandsState>,
+  #[cfg_attr(feature = "serde", serde(flatten))]
+  layout: ChordMap,
 }
 
 impl TenboardUnconstrained {
-  pub fn swap_states(&mut self, ch1: char, ch2: char) {
-    let hs1 = self
-      .layout
-      .remove(&ch1)
-      .unwrap_or_else(|| panic!("'{}' wasn't found", ch1));
-    let hs2 = self
-      .layout
-      .remove(&ch2)
-      .unwrap_or_else(|| panic!("'{}' wasn't found", ch2));
-    self.layout.insert(ch1, hs2);
-    self.layout.insert(ch2, hs1);
+  /// Creates a new layout where each character corresponds to a random
+  /// `HandsState` allowed by `policy`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `policy` allows less chords than there are typable chars.
+  pub fn new_random_with_policy(policy: ThumbPolicy) -> Self {
+    Self::new_random_with_policy_and_rng(policy, &mut rand::thread_rng())
+  }
+
+  /// Same as [TenboardUnconstrained::new_random_with_policy], but uses `rng`
+  /// as a source of randomness.
+  pub fn new_random_with_policy_and_rng(
+    policy: ThumbPolicy,
+    rng: &mut impl Rng,
+  ) -> Self {
+    Self::new_random_with_charset_and_rng(&CharSet::ascii(), policy, rng)
+  }
+
+  /// Creates a new layout where each char of `charset` corresponds to a
+  /// random `HandsState` allowed by `policy`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `policy` allows less chords than there are chars in
+  /// `charset`.
+  pub fn new_random_with_charset(
+    charset: &CharSet,
+    policy: ThumbPolicy,
+  ) -> Self {
+    Self::new_random_with_charset_and_rng(
+      charset,
+      policy,
+      &mut rand::thread_rng(),
+    )
+  }
+
+  /// Same as [TenboardUnconstrained::new_random_with_charset], but uses
+  /// `rng` as a source of randomness.
+  pub fn new_random_with_charset_and_rng(
+    charset: &CharSet,
+    policy: ThumbPolicy,
+    rng: &mut impl Rng,
+  ) -> Self {
+    let mut handsstates: Vec<_> =
+      HandsState::iterate_one_two_key(policy).collect();
+    assert!(
+      handsstates.len() >= charset.len(),
+      "thumb policy allows only {} chords for {} chars",
+      handsstates.len(),
+      charset.len()
+    );
+    handsstates.shuffle(rng);
+    Self {
+      layout: charset.chars().zip(handsstates).collect(),
+    }
+  }
+
+  /// Creates a new layout where the most frequent chars of `freq` get the
+  /// cheapest chords according to [FingerEffort]. Chars missing in `freq`
+  /// get the most expensive chords. Such layout is a better starting point
+  /// for optimization than a random one.
+  pub fn new_frequency_guided(freq: &NgramTable) -> Self {
+    Self::new_frequency_guided_with_model(
+      &CharSet::ascii(),
+      freq,
+      &FingerEffort::new(),
+    )
+  }
+
+  /// Same as [TenboardUnconstrained::new_frequency_guided], but lays out
+  /// chars of `charset` and takes chord costs from `model`.
+  pub fn new_frequency_guided_with_model(
+    charset: &CharSet,
+    freq: &NgramTable,
+    model: &impl EffortModel,
+  ) -> Self {
+    let mut handsstates: Vec<_> =
+      HandsState::iterate_one_two_key(ThumbPolicy::default()).collect();
+    assert!(
+      handsstates.len() >= charset.len(),
+      "thumb policy allows only {} chords for {} chars",
+      handsstates.len(),
+      charset.len()
+    );
+    handsstates.sort_by(|a, b| model.cost(a).total_cmp(&model.cost(b)));
+    let mut chars: Vec<_> = charset.chars().collect();
+    chars.sort_by(|&a, &b| {
+      freq
+        .char_frequency(b)
+        .total_cmp(&freq.char_frequency(a))
+        .then(a.cmp(&b))
+    });
+    Self {
+      layout: chars.into_iter().zip(handsstates).collect(),
+    }
+  }
+
+  /// Creates a new layout where each character corresponds to a random
+  /// `HandsState` allowed by `constraints`. Returns an error if there are
+  /// not enough allowed chords.
+  pub fn new_random_with_constraints(
+    constraints: &Constraints,
+  ) -> Result<Self, Unsatisfiable> {
+    Self::new_random_with_constraints_and_rng(
+      constraints,
+      &mut rand::thread_rng(),
+    )
+  }
+
+  /// Same as [TenboardUnconstrained::new_random_with_constraints], but uses
+  /// `rng` as a source of randomness.
+  pub fn new_random_with_constraints_and_rng(
+    constraints: &Constraints,
+    rng: &mut impl Rng,
+  ) -> Result<Self, Unsatisfiable> {
+    Self::new_random_with_charset_and_constraints(
+      &CharSet::ascii(),
+      constraints,
+      rng,
+    )
+  }
+
+  /// Creates a new layout where each char of `charset` corresponds to a
+  /// random `HandsState` allowed by `constraints`. Big char sets may need
+  /// [Constraints::set_max_keys] to have enough chords.
+  pub fn new_random_with_charset_and_constraints(
+    charset: &CharSet,
+    constraints: &Constraints,
+    rng: &mut impl Rng,
+  ) -> Result<Self, Unsatisfiable> {
+    let layout = constraints.assign_random(charset.chars(), rng)?;
+    Ok(Self {
+      layout: layout.into_iter().collect(),
+    })
   }
 }
 
@@ -93,63 +567,125 @@ impl FromIterator<(char, HandsState)> for TenboardUnconstrained {
   where
     T: IntoIterator<Item = (char, HandsState)>,
   {
-    let layout = HashMap::from_iter(iter);
-    assert!(TYPABLE_CHARS.chars().all(|ch| layout.contains_key(&ch)));
+    let layout = ChordMap::from_iter(iter);
+    assert!(TYPABLE_CHARS.chars().all(|ch| layout.contains_key(ch)));
     Self { layout }
   }
 }
 
 impl Tenboard for TenboardUnconstrained {
-  fn new_random() -> Self {
-    let mut handsstates: Vec<_> =
-      HandsState::iterate_one_two_key_all_states().collect();
-    handsstates.shuffle(&mut rand::thread_rng());
-    Self {
-      layout: HashMap::from_iter(TYPABLE_CHARS.chars().zip(handsstates)),
-    }
+  fn new_random_with_rng(rng: &mut impl Rng) -> Self {
+    Self::new_random_with_policy_and_rng(ThumbPolicy::default(), rng)
   }
 
   fn try_type_char(&self, ch: char) -> Result<HandsState, NoSuchChar> {
-    self.layout.get(&ch).copied().ok_or(NoSuchChar { ch })
+    self.layout.get(ch).ok_or(NoSuchChar { ch })
+  }
+
+  fn try_swap_states(&mut self, ch1: char, ch2: char) -> Result<(), BadSwap> {
+    swap_in_layout(&mut self.layout, ch1, ch2)
+  }
+
+  /// Returns ASCII chars of the layout in [TYPABLE_CHARS] order followed by
+  /// other chars in alphabetical order.
+  fn charset(&self) -> CharSet {
+    let mut other: Vec<_> = self
+      .layout
+      .chars()
+      .filter(|&ch| !TYPABLE_CHARS.contains(ch))
+      .collect();
+    other.sort_unstable();
+    TYPABLE_CHARS
+      .chars()
+      .filter(|&ch| self.layout.contains_key(ch))
+      .chain(other)
+      .collect()
   }
 }
 
 /// Constrained Tenboard layout.
 /// 'whitespace' and 'enter' are bound to single key thumb chords.
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TenboardThumbConstrained {
-  #[serde(rename = " ")]
+  #[cfg_attr(feature = "serde", serde(rename = " "))]
   whitespace_hs: HandsState,
-  #[serde(rename = "\n")]
+  #[cfg_attr(feature = "serde", serde(rename = "\n"))]
   newline_hs: HandsState,
-  #[serde(flatten)]
-  layout: HashMap<char, HandsState>,
+  #[cfg_attr(feature = "serde", serde(flatten))]
+  layout: ChordMap,
 }
 
-impl Tenboard for TenboardThumbConstrained {
-  fn new_random() -> Self {
-    let (whitespace_hs, newline_hs) = if rand::thread_rng().gen_bool(0.5) {
+impl TenboardThumbConstrained {
+  /// Creates a new layout where 'whitespace' and 'enter' are bound to single
+  /// thumb chords and other characters correspond to random `HandsState`s
+  /// allowed by `policy`. Thumbs alone are always reserved, so
+  /// `policy.alone` is ignored.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `policy` allows less chords than there are typable chars.
+  pub fn new_random_with_policy(policy: ThumbPolicy) -> Self {
+    Self::new_random_with_policy_and_rng(policy, &mut rand::thread_rng())
+  }
+
+  /// Same as [TenboardThumbConstrained::new_random_with_policy], but uses
+  /// `rng` as a source of randomness.
+  pub fn new_random_with_policy_and_rng(
+    policy: ThumbPolicy,
+    rng: &mut impl Rng,
+  ) -> Self {
+    let (whitespace_hs, newline_hs) = if rng.gen_bool(0.5) {
       (HandsState::left_thumb(), HandsState::right_thumb())
     } else {
       (HandsState::right_thumb(), HandsState::left_thumb())
     };
+    let policy = ThumbPolicy {
+      alone: false,
+      ..policy
+    };
     let mut handsstates: Vec<_> =
-      HandsState::iterate_one_two_key_with_thumbs().collect();
-    handsstates.shuffle(&mut rand::thread_rng());
+      HandsState::iterate_one_two_key(policy).collect();
+    assert!(
+      handsstates.len() + 2 >= TYPABLE_CHARS.len(),
+      "thumb policy allows only {} chords",
+      handsstates.len()
+    );
+    handsstates.shuffle(rng);
     let chars_iter =
       TYPABLE_CHARS.chars().filter(|&ch| ch != ' ' && ch != '\n');
     Self {
       whitespace_hs,
       newline_hs,
-      layout: HashMap::from_iter(chars_iter.zip(handsstates)),
+      layout: chars_iter.zip(handsstates).collect(),
     }
   }
+}
+
+impl Tenboard for TenboardThumbConstrained {
+  fn new_random_with_rng(rng: &mut impl Rng) -> Self {
+    Self::new_random_with_policy_and_rng(ThumbPolicy::MODIFIERS, rng)
+  }
 
   fn try_type_char(&self, ch: char) -> Result<HandsState, NoSuchChar> {
     match ch {
       ' ' => Ok(self.whitespace_hs),
       '\n' => Ok(self.newline_hs),
-      _ => self.layout.get(&ch).ok_or(NoSuchChar { ch }).copied(),
+      _ => self.layout.get(ch).ok_or(NoSuchChar { ch }),
+    }
+  }
+
+  fn try_swap_states(&mut self, ch1: char, ch2: char) -> Result<(), BadSwap> {
+    match (ch1, ch2) {
+      (' ', '\n') | ('\n', ' ') => {
+        std::mem::swap(&mut self.whitespace_hs, &mut self.newline_hs);
+        Ok(())
+      }
+      (' ' | '\n', _) | (_, ' ' | '\n') if ch1 != ch2 => {
+        Err(BadSwap { ch1, ch2 })
+      }
+      (' ' | '\n', _) => Ok(()),
+      _ => swap_in_layout(&mut self.layout, ch1, ch2),
     }
   }
 }
@@ -159,22 +695,23 @@ impl Tenboard for TenboardThumbConstrained {
 /// lowercase letters and digits are bound to other 8 single key chords.
 /// uppercase characters are bound to lowercase chords + one of the thumbs,
 /// punctuiation characters are bound to other chords + the other
-/// thumb.
-#[derive(Serialize, Deserialize)]
+/// thumb. Thumb usage is defined by the layout itself and corresponds to
+/// [ThumbPolicy::default].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TenboardModifierConstrained {
-  #[serde(rename = " ")]
+  #[cfg_attr(feature = "serde", serde(rename = " "))]
   whitespace_hs: HandsState,
-  #[serde(rename = "\n")]
+  #[cfg_attr(feature = "serde", serde(rename = "\n"))]
   newline_hs: HandsState,
-  #[serde(flatten)]
-  lowercase_digit_layout: HashMap<char, HandsState>,
-  #[serde(flatten)]
-  punctuation_layout: HashMap<char, HandsState>,
+  #[cfg_attr(feature = "serde", serde(flatten))]
+  lowercase_digit_layout: ChordMap,
+  #[cfg_attr(feature = "serde", serde(flatten))]
+  punctuation_layout: ChordMap,
 }
 
 impl Tenboard for TenboardModifierConstrained {
-  fn new_random() -> Self {
-    let mut rng = rand::thread_rng();
+  fn new_random_with_rng(rng: &mut impl Rng) -> Self {
     let (whitespace_hs, newline_hs) = if rng.gen_bool(0.5) {
       (HandsState::left_thumb(), HandsState::right_thumb())
     } else {
@@ -186,23 +723,21 @@ impl Tenboard for TenboardModifierConstrained {
       HandsState::iterate_one_two_key_no_thumbs()
         .map(|hs| hs.combine(&newline_hs))
         .collect();
-    lowercase_digit_hs.shuffle(&mut rng);
-    punctuation_hs.shuffle(&mut rng);
+    lowercase_digit_hs.shuffle(rng);
+    punctuation_hs.shuffle(rng);
     Self {
       whitespace_hs,
       newline_hs,
-      lowercase_digit_layout: HashMap::from_iter(
-        LOWERCASE_CHARS
-          .chars()
-          .chain(DIGIT_CHARS.chars())
-          .zip(lowercase_digit_hs),
-      ),
-      punctuation_layout: HashMap::from_iter(
-        PUNCTUATION_CHARS
-          .chars()
-          .filter(|&ch| ch != ' ' && ch != '\n')
-          .zip(punctuation_hs),
-      ),
+      lowercase_digit_layout: LOWERCASE_CHARS
+        .chars()
+        .chain(DIGIT_CHARS.chars())
+        .zip(lowercase_digit_hs)
+        .collect(),
+      punctuation_layout: PUNCTUATION_CHARS
+        .chars()
+        .filter(|&ch| ch != ' ' && ch != '\n')
+        .zip(punctuation_hs)
+        .collect(),
     }
   }
 
@@ -210,17 +745,317 @@ impl Tenboard for TenboardModifierConstrained {
     match ch {
       ' ' => Some(self.whitespace_hs),
       '\n' => Some(self.newline_hs),
-      _ if ch.is_lowercase() || ch.is_ascii_digit() => {
-        self.lowercase_digit_layout.get(&ch).copied()
+      _ if ch.is_ascii_lowercase() || ch.is_ascii_digit() => {
+        self.lowercase_digit_layout.get(ch)
       }
-      _ if ch.is_uppercase() => self
+      _ if ch.is_ascii_uppercase() => self
         .lowercase_digit_layout
-        .get(&ch.to_ascii_lowercase())
+        .get(ch.to_ascii_lowercase())
         .map(|hs| hs.combine(&self.whitespace_hs)),
-      _ => self.punctuation_layout.get(&ch).copied(),
+      _ => self.punctuation_layout.get(ch),
     }
     .ok_or(NoSuchChar { ch })
   }
+
+  fn try_swap_states(&mut self, ch1: char, ch2: char) -> Result<(), BadSwap> {
+    let is_lowercase_digit =
+      |ch: char| ch.is_ascii_lowercase() || ch.is_ascii_digit();
+    let is_thumb = |ch: char| ch == ' ' || ch == '\n';
+    match (ch1, ch2) {
+      _ if is_thumb(ch1) && is_thumb(ch2) => {
+        if ch1 != ch2 {
+          // punctuation chords include newline thumb, so they follow it
+          std::mem::swap(&mut self.whitespace_hs, &mut self.newline_hs);
+          for hs in self.punctuation_layout.values_mut() {
+            hs.swap(4, 5);
+          }
+        }
+        Ok(())
+      }
+      _ if is_lowercase_digit(ch1) && is_lowercase_digit(ch2) => {
+        swap_in_layout(&mut self.lowercase_digit_layout, ch1, ch2)
+      }
+      _ if [ch1, ch2].iter().all(|&ch| {
+        !is_thumb(ch) && !is_lowercase_digit(ch) && !ch.is_ascii_uppercase()
+      }) =>
+      {
+        swap_in_layout(&mut self.punctuation_layout, ch1, ch2)
+      }
+      _ => Err(BadSwap { ch1, ch2 }),
+    }
+  }
+}
+
+/// Eight most frequent English letters. [TenboardHomeRowConstrained] binds
+/// them to single key chords.
+pub const HOME_ROW_CHARS: &str = "etaoinsh";
+
+/// This error means that a char of [HOME_ROW_CHARS] isn't bound to its own
+/// single key chord without thumbs.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct NotOnHomeRow {
+  pub ch: char,
+}
+
+impl Display for NotOnHomeRow {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "char '{}' must be bound to its own single key chord without thumbs",
+      self.ch
+    )
+  }
+}
+
+/// Constrained Tenboard layout.
+/// [HOME_ROW_CHARS] are bound to the 8 single key chords without thumbs,
+/// like the home row letters of ASETNIOP. Other characters are bound to
+/// other chords.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "HashMap<char, HandsState>"))]
+pub struct TenboardHomeRowConstrained {
+  #[cfg_attr(feature = "serde", serde(flatten))]
+  layout: ChordMap,
+}
+
+impl TenboardHomeRowConstrained {
+  /// Creates a new layout where [HOME_ROW_CHARS] are bound to single key
+  /// chords without thumbs in random order and other characters correspond
+  /// to random `HandsState`s allowed by `policy`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `policy` allows less chords than there are typable chars.
+  pub fn new_random_with_policy(policy: ThumbPolicy) -> Self {
+    Self::new_random_with_policy_and_rng(policy, &mut rand::thread_rng())
+  }
+
+  /// Same as [TenboardHomeRowConstrained::new_random_with_policy], but uses
+  /// `rng` as a source of randomness.
+  pub fn new_random_with_policy_and_rng(
+    policy: ThumbPolicy,
+    rng: &mut impl Rng,
+  ) -> Self {
+    let mut home_hs: Vec<_> = HandsState::iterate_one_key_no_thumbs().collect();
+    let mut handsstates: Vec<_> = HandsState::iterate_one_two_key(policy)
+      .filter(|hs| !is_home_row_chord(hs))
+      .collect();
+    assert!(
+      handsstates.len() + home_hs.len() >= TYPABLE_CHARS.len(),
+      "thumb policy allows only {} chords",
+      handsstates.len() + home_hs.len()
+    );
+    home_hs.shuffle(rng);
+    handsstates.shuffle(rng);
+    let other_chars = TYPABLE_CHARS
+      .chars()
+      .filter(|&ch| !HOME_ROW_CHARS.contains(ch));
+    Self {
+      layout: HOME_ROW_CHARS
+        .chars()
+        .zip(home_hs)
+        .chain(other_chars.zip(handsstates))
+        .collect(),
+    }
+  }
+
+  /// Returns an error with the first char of [HOME_ROW_CHARS] that is
+  /// missing in the layout, is bound to some other chord or shares its
+  /// chord with another char.
+  pub fn validate(&self) -> Result<(), NotOnHomeRow> {
+    for ch in HOME_ROW_CHARS.chars() {
+      let shared = |hs| self.layout.iter().any(|(c, h)| c != ch && h == hs);
+      match self.layout.get(ch) {
+        Some(hs) if is_home_row_chord(&hs) && !shared(hs) => {}
+        _ => return Err(NotOnHomeRow { ch }),
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Returns `true` if `hs` is a single key chord without thumbs.
+fn is_home_row_chord(hs: &HandsState) -> bool {
+  hs.count_pressed() == 1 && hs[4].is_released() && hs[5].is_released()
+}
+
+impl TryFrom<HashMap<char, HandsState>> for TenboardHomeRowConstrained {
+  type Error = NotOnHomeRow;
+
+  fn try_from(layout: HashMap<char, HandsState>) -> Result<Self, Self::Error> {
+    let layout = Self {
+      layout: layout.into_iter().collect(),
+    };
+    layout.validate()?;
+    Ok(layout)
+  }
+}
+
+impl Tenboard for TenboardHomeRowConstrained {
+  fn new_random_with_rng(rng: &mut impl Rng) -> Self {
+    Self::new_random_with_policy_and_rng(ThumbPolicy::default(), rng)
+  }
+
+  fn try_type_char(&self, ch: char) -> Result<HandsState, NoSuchChar> {
+    self.layout.get(ch).ok_or(NoSuchChar { ch })
+  }
+
+  fn try_swap_states(&mut self, ch1: char, ch2: char) -> Result<(), BadSwap> {
+    if HOME_ROW_CHARS.contains(ch1) != HOME_ROW_CHARS.contains(ch2) {
+      return Err(BadSwap { ch1, ch2 });
+    }
+    swap_in_layout(&mut self.layout, ch1, ch2)
+  }
+}
+
+/// Chars one-handed layouts are generated for by default: lowercase
+/// letters, space, newline and the most frequent punctuation, one per each
+/// of 31 chords of a hand.
+pub const ONE_HAND_CHARS: &str = "abcdefghijklmnopqrstuvwxyz \n.,'";
+
+/// Returns all 31 chords of `hand` from one key to the whole hand.
+fn one_hand_chords(hand: usize) -> impl Iterator<Item = HandsState> {
+  (1..=5).flat_map(move |n| HandsState::iterate_n_key_one_hand(hand, n))
+}
+
+/// Binds chars of `charset` to random chords of `hand`.
+fn new_random_one_hand(
+  hand: usize,
+  charset: &CharSet,
+  rng: &mut impl Rng,
+) -> ChordMap {
+  let mut handsstates: Vec<_> = one_hand_chords(hand).collect();
+  assert!(
+    charset.len() <= handsstates.len(),
+    "one hand has only {} chords, but charset has {} chars",
+    handsstates.len(),
+    charset.len()
+  );
+  handsstates.shuffle(rng);
+  charset.chars().zip(handsstates).collect()
+}
+
+/// One-handed Tenboard layout for the left hand if `HAND` is 0 or for the
+/// right hand if it's 1. Chars are bound to chords of up to five keys of the
+/// hand, thumb included, so 31 chars at most can be typed.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(try_from = "ChordMap", into = "ChordMap")
+)]
+pub struct TenboardOneHand<const HAND: usize> {
+  layout: ChordMap,
+}
+
+/// One-handed Tenboard layout for the left hand.
+pub type TenboardLeftHand = TenboardOneHand<0>;
+/// One-handed Tenboard layout for the right hand.
+pub type TenboardRightHand = TenboardOneHand<1>;
+
+impl<const HAND: usize> TenboardOneHand<HAND> {
+  /// Creates a new layout where each char of `charset` corresponds to a
+  /// random chord of the hand.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `charset` has more than 31 chars.
+  pub fn new_random_with_charset(charset: &CharSet) -> Self {
+    Self::new_random_with_charset_and_rng(charset, &mut rand::thread_rng())
+  }
+
+  /// Same as [TenboardOneHand::new_random_with_charset], but uses `rng` as
+  /// a source of randomness.
+  pub fn new_random_with_charset_and_rng(
+    charset: &CharSet,
+    rng: &mut impl Rng,
+  ) -> Self {
+    const { assert!(HAND < 2, "HAND must be 0 or 1") };
+    Self {
+      layout: new_random_one_hand(HAND, charset, rng),
+    }
+  }
+}
+
+impl<const HAND: usize> Tenboard for TenboardOneHand<HAND> {
+  /// Creates a layout for [ONE_HAND_CHARS].
+  fn new_random_with_rng(rng: &mut impl Rng) -> Self {
+    Self::new_random_with_charset_and_rng(
+      &ONE_HAND_CHARS.chars().collect(),
+      rng,
+    )
+  }
+
+  fn try_type_char(&self, ch: char) -> Result<HandsState, NoSuchChar> {
+    self.layout.get(ch).ok_or(NoSuchChar { ch })
+  }
+
+  fn try_swap_states(&mut self, ch1: char, ch2: char) -> Result<(), BadSwap> {
+    swap_in_layout(&mut self.layout, ch1, ch2)
+  }
+
+  fn charset(&self) -> CharSet {
+    self.layout.chars().collect()
+  }
+}
+
+impl<const HAND: usize> Display for TenboardOneHand<HAND> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    Display::fmt(self as &dyn Tenboard, f)
+  }
+}
+
+impl<const HAND: usize> Shift for TenboardOneHand<HAND> {}
+
+impl<const HAND: usize> TryFrom<ChordMap> for TenboardOneHand<HAND> {
+  type Error = WrongHand;
+
+  /// Creates a layout of `layout` chords. Returns an error for the first
+  /// chord that presses no keys of the hand or keys of the other hand.
+  fn try_from(layout: ChordMap) -> Result<Self, Self::Error> {
+    const { assert!(HAND < 2, "HAND must be 0 or 1") };
+    let wrong = layout.iter().find(|(_, hs)| {
+      let hands: Vec<_> = hs.hand_iter().collect();
+      !hands[HAND].iter().any(|fs| fs.is_pressed())
+        || hands[1 - HAND].iter().any(|fs| fs.is_pressed())
+    });
+    match wrong {
+      Some((ch, hs)) => Err(WrongHand {
+        ch,
+        handstate: hs,
+        hand: HAND,
+      }),
+      None => Ok(Self { layout }),
+    }
+  }
+}
+
+impl<const HAND: usize> From<TenboardOneHand<HAND>> for ChordMap {
+  fn from(value: TenboardOneHand<HAND>) -> Self {
+    value.layout
+  }
+}
+
+/// This error means that a chord of a one-handed layout isn't a chord of its
+/// hand.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct WrongHand {
+  pub ch: char,
+  pub handstate: HandsState,
+  /// Index of the hand of the layout: 0 for the left hand, 1 for the right.
+  pub hand: usize,
+}
+
+impl Display for WrongHand {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let hand = ["left", "right"][self.hand];
+    write!(
+      f,
+      "chord {} of char '{}' isn't a chord of the {hand} hand",
+      self.handstate, self.ch
+    )
+  }
 }
 
 #[cfg(test)]
@@ -229,6 +1064,121 @@ mod tests {
 
   use super::*;
 
+  #[test]
+  fn test_seeded_layouts() {
+    fn assert_reproducible<T: Tenboard>() {
+      let first = T::new_random_with_rng(&mut StdRng::seed_from_u64(42));
+      let second = T::new_random_with_rng(&mut StdRng::seed_from_u64(42));
+      for ch in TYPABLE_CHARS.chars() {
+        assert_eq!(first.try_type_char(ch), second.try_type_char(ch));
+      }
+    }
+    assert_reproducible::<TenboardUnconstrained>();
+    assert_reproducible::<TenboardThumbConstrained>();
+    assert_reproducible::<TenboardModifierConstrained>();
+    assert_reproducible::<TenboardHomeRowConstrained>();
+    assert_reproducible::<TenboardLeftHand>();
+    assert_reproducible::<TenboardRightHand>();
+  }
+
+  #[test]
+  fn test_diff() {
+    let old = TenboardUnconstrained::new_random();
+    let mut new = old.clone();
+    assert!(old.diff(&new).is_empty());
+    new.swap_states('a', 'b');
+    let (hs_a, hs_b) = (
+      old.try_type_char('a').unwrap(),
+      old.try_type_char('b').unwrap(),
+    );
+    let diff = old.diff(&new);
+    assert_eq!(diff.changed, vec![('a', hs_a, hs_b), ('b', hs_b, hs_a)]);
+    assert_eq!(diff.moved, vec![(hs_a, 'a', 'b'), (hs_b, 'b', 'a')]);
+    assert_eq!(diff.unchanged.len(), TYPABLE_CHARS.len() - 2);
+    assert_eq!(
+      diff.to_string(),
+      format!("'a'\t{hs_a} -> {hs_b}\n'b'\t{hs_b} -> {hs_a}\n")
+    );
+
+    let mut new = old.clone();
+    new.layout.insert('é', hs_a | hs_b);
+    let diff = old.diff(&new);
+    assert!(!diff.is_empty());
+    assert!(diff.changed.is_empty());
+    assert_eq!(diff.added, vec![('é', hs_a | hs_b)]);
+    assert_eq!(diff.to_string(), format!("'é'\t+ {}\n", hs_a | hs_b));
+    let diff = new.diff(&old);
+    assert!(!diff.is_empty());
+    assert_eq!(diff.removed, vec![('é', hs_a | hs_b)]);
+    assert_eq!(diff.to_string(), format!("'é'\t- {}\n", hs_a | hs_b));
+  }
+
+  #[test]
+  fn test_collisions() {
+    assert!(TenboardModifierConstrained::new_random()
+      .collisions()
+      .is_empty());
+    let mut tb = TenboardModifierConstrained::new_random();
+    let a = tb.lowercase_digit_layout.get('a').unwrap();
+    tb.punctuation_layout
+      .insert('%', a.combine(&tb.whitespace_hs));
+    let collisions = tb.collisions();
+    assert_eq!(collisions, [Collision {
+      chord: a.combine(&tb.whitespace_hs),
+      chars: vec!['A', '%'],
+    }]);
+    assert_eq!(
+      collisions[0].to_string(),
+      format!("chord {} is bound to 'A', '%'", collisions[0].chord)
+    );
+  }
+
+  #[test]
+  fn test_neighbors() {
+    let n = TYPABLE_CHARS.len();
+    let tb = TenboardUnconstrained::new_random();
+    assert_eq!(tb.neighbors().count(), n * (n - 1) / 2);
+    let tb = TenboardModifierConstrained::new_random();
+    let mut count = 0;
+    for neighbor in tb.neighbors() {
+      assert!(!tb.diff(&neighbor).is_empty());
+      count += 1;
+    }
+    assert!(0 < count && count < n * (n - 1) / 2);
+  }
+
+  #[test]
+  fn test_frequency_guided() {
+    let tb =
+      TenboardUnconstrained::new_frequency_guided(&NgramTable::english());
+    let model = FingerEffort::new();
+    let cost = |ch| model.cost(&tb.try_type_char(ch).unwrap());
+    assert_eq!(tb.layout.chars().count(), TYPABLE_CHARS.len());
+    assert!(cost('e') <= cost('t'));
+    assert!(cost('t') < cost('z'));
+    assert!(cost('z') <= cost('~'));
+    assert_eq!(cost('e'), 1.0);
+  }
+
+  #[test]
+  fn test_frequency_guided_charset() {
+    let mut charset = CharSet::new();
+    charset.add_chars("абвгд ");
+    let model = FingerEffort::new();
+    let tb = TenboardUnconstrained::new_frequency_guided_with_model(
+      &charset,
+      &NgramTable::from_text("дддд вв а"),
+      &model,
+    );
+    let cost = |ch| model.cost(&tb.try_type_char(ch).unwrap());
+    assert_eq!(tb.layout.chars().count(), charset.len());
+    assert!(tb.try_type_char('e').is_err());
+    assert!(tb.try_type_char(',').is_err());
+    assert_eq!(cost('д'), 1.0);
+    assert!(cost('в') <= cost('а'));
+    assert!(cost('а') <= cost('б'));
+  }
+
   #[test]
   fn test_random_unconstrained_all_chars() {
     let tb = TenboardUnconstrained::new_random();
@@ -238,7 +1188,7 @@ mod tests {
       .collect::<Result<_, _>>()
       .unwrap();
     assert_eq!(hs_set.len(), TYPABLE_CHARS.len());
-    assert!(tb.layout.values().all(|hs| hs.count_pressed() <= 3));
+    assert!(tb.layout.iter().all(|(_, hs)| hs.count_pressed() <= 3));
   }
 
   #[test]
@@ -250,7 +1200,7 @@ mod tests {
       .collect::<Result<_, _>>()
       .unwrap();
     assert_eq!(hs_set.len(), TYPABLE_CHARS.len());
-    assert!(tb.layout.values().all(|hs| hs.count_pressed() <= 3));
+    assert!(tb.layout.iter().all(|(_, hs)| hs.count_pressed() <= 3));
   }
 
   #[test]
@@ -264,36 +1214,240 @@ mod tests {
     assert_eq!(hs_set.len(), TYPABLE_CHARS.len());
     assert!(tb
       .lowercase_digit_layout
-      .values()
-      .all(|hs| hs.count_pressed() <= 2));
+      .iter()
+      .all(|(_, hs)| hs.count_pressed() <= 2));
+    assert!(tb
+      .punctuation_layout
+      .iter()
+      .all(|(_, hs)| matches!(hs.count_pressed(), 2 | 3)));
+  }
+
+  #[test]
+  fn test_random_home_row_constrained_all_chars() {
+    let tb = TenboardHomeRowConstrained::new_random();
+    let hs_set: HashSet<HandsState> = TYPABLE_CHARS
+      .chars()
+      .map(|ch| tb.try_type_char(ch))
+      .collect::<Result<_, _>>()
+      .unwrap();
+    assert_eq!(hs_set.len(), TYPABLE_CHARS.len());
+    assert_eq!(tb.validate(), Ok(()));
+    for ch in HOME_ROW_CHARS.chars() {
+      assert!(is_home_row_chord(&tb.try_type_char(ch).unwrap()));
+    }
+    assert!(tb
+      .thumb_policy_violations(ThumbPolicy::default())
+      .is_empty());
+  }
+
+  #[test]
+  fn test_random_one_hand() {
+    let left = TenboardLeftHand::new_random();
+    let right = TenboardRightHand::new_random();
+    for (tb, hand) in [(&left as &dyn Tenboard, 0), (&right, 1)] {
+      let hs_set: HashSet<HandsState> = ONE_HAND_CHARS
+        .chars()
+        .map(|ch| tb.try_type_char(ch))
+        .collect::<Result<_, _>>()
+        .unwrap();
+      assert_eq!(hs_set.len(), 31);
+      assert!(hs_set.iter().all(|hs| {
+        hs.hand_iter()
+          .nth(1 - hand)
+          .unwrap()
+          .iter()
+          .all(|fs| !fs.is_pressed())
+      }));
+      assert_eq!(tb.charset().len(), 31);
+    }
+    assert!(left.try_type_char('A').is_err());
+    let tb =
+      TenboardRightHand::new_random_with_charset(&"abc".chars().collect());
+    assert_eq!(tb.charset().len(), 3);
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_one_hand_serialization() -> Result<(), serde_json::Error> {
+    let left = TenboardLeftHand::new_random();
+    let json = serde_json::to_string(&left)?;
+    let de: TenboardLeftHand = serde_json::from_str(&json)?;
+    assert_eq!(de.to_string(), left.to_string());
+    let error = serde_json::from_str::<TenboardRightHand>(&json).unwrap_err();
+    assert!(error.to_string().contains("of the right hand"));
+    Ok(())
+  }
+
+  #[test]
+  fn test_one_hand_validation() {
+    let left: HandsState = [0, 1, 0, 0, 1, 0, 0, 0, 0, 0].into();
+    let chords = |hs: HandsState| ChordMap::from_iter([('a', left), ('b', hs)]);
+    assert!(TenboardLeftHand::try_from(chords(left)).is_ok());
+    let both: HandsState = [0, 1, 0, 0, 0, 1, 0, 0, 0, 0].into();
+    let error = TenboardLeftHand::try_from(chords(both)).unwrap_err();
+    assert_eq!(error, WrongHand {
+      ch: 'b',
+      handstate: both,
+      hand: 0,
+    });
+    assert_eq!(
+      error.to_string(),
+      format!("chord {both} of char 'b' isn't a chord of the left hand")
+    );
+    let none = HandsState::from([0; 10]);
+    assert!(TenboardLeftHand::try_from(chords(none)).is_err());
+    assert!(TenboardRightHand::try_from(chords(left)).is_err());
+  }
+
+  #[test]
+  #[should_panic(expected = "one hand has only 31 chords")]
+  fn test_random_one_hand_too_many_chars() {
+    TenboardLeftHand::new_random_with_charset(&CharSet::ascii());
+  }
+
+  #[test]
+  fn test_random_with_policy() {
+    let tb = TenboardUnconstrained::new_random_with_policy(ThumbPolicy::ANY);
+    assert!(tb.thumb_policy_violations(ThumbPolicy::ANY).is_empty());
+    let tb =
+      TenboardThumbConstrained::new_random_with_policy(ThumbPolicy::MODIFIERS);
+    assert_eq!(tb.thumb_policy_violations(ThumbPolicy::MODIFIERS), vec![
+      ' ', '\n'
+    ]);
+    let tb = TenboardModifierConstrained::new_random();
+    assert!(tb
+      .thumb_policy_violations(ThumbPolicy::default())
+      .is_empty());
+    assert!(!tb.thumb_policy_violations(ThumbPolicy::NONE).is_empty());
+  }
+
+  #[test]
+  #[should_panic(expected = "thumb policy allows only 36 chords")]
+  fn test_random_with_policy_not_enough_chords() {
+    TenboardUnconstrained::new_random_with_policy(ThumbPolicy::NONE);
+  }
+
+  #[test]
+  fn test_swap_states() {
+    let mut tb = TenboardUnconstrained::new_random();
+    let (a, b) = (tb.layout.get('a').unwrap(), tb.layout.get('\n').unwrap());
+    tb.swap_states('a', '\n');
+    assert_eq!(tb.try_type_char('a'), Ok(b));
+    assert_eq!(tb.try_type_char('\n'), Ok(a));
+    assert_eq!(
+      tb.try_swap_states('a', 'é'),
+      Err(BadSwap {
+        ch1: 'a', ch2: 'é'
+      })
+    );
+
+    let mut tb = TenboardThumbConstrained::new_random();
+    assert!(tb.try_swap_states(' ', 'a').is_err());
+    let (ws, nl) = (tb.whitespace_hs, tb.newline_hs);
+    tb.swap_states('\n', ' ');
+    assert_eq!((tb.whitespace_hs, tb.newline_hs), (nl, ws));
+    assert!(tb.try_swap_states('A', '%').is_ok());
+
+    let mut tb = TenboardModifierConstrained::new_random();
+    assert!(tb.try_swap_states('a', '%').is_err());
+    assert!(tb.try_swap_states('a', 'A').is_err());
+    assert!(tb.try_type_char('É').is_err());
+    assert!(tb.try_swap_states('é', 'a').is_err());
+    let policy = tb.shift_policy();
+    assert_eq!(
+      policy.shifted(&tb.try_type_char('a').unwrap()),
+      Some(vec![tb.try_type_char('A').unwrap()])
+    );
+    assert!(tb.try_swap_states('a', '1').is_ok());
+    assert!(tb.try_swap_states('&', '%').is_ok());
+    tb.swap_states(' ', '\n');
+    let hs_set: HashSet<HandsState> = TYPABLE_CHARS
+      .chars()
+      .map(|ch| tb.try_type_char(ch))
+      .collect::<Result<_, _>>()
+      .unwrap();
+    assert_eq!(hs_set.len(), TYPABLE_CHARS.len());
     assert!(tb
       .punctuation_layout
-      .values()
-      .all(|hs| matches!(hs.count_pressed(), 2 | 3)));
+      .iter()
+      .all(|(_, hs)| hs.combine(&tb.newline_hs) == hs));
+
+    let mut tb = TenboardHomeRowConstrained::new_random();
+    assert!(tb.try_swap_states('e', 'z').is_err());
+    assert!(tb.try_swap_states('e', 't').is_ok());
+    assert!(tb.try_swap_states('z', '%').is_ok());
+    assert_eq!(tb.validate(), Ok(()));
+  }
+
+  #[test]
+  fn test_display() {
+    let mut handstates = HandsState::iterate_one_two_key_all_states();
+    let tb: TenboardUnconstrained = TYPABLE_CHARS
+      .chars()
+      .map(|ch| (ch, handstates.next().unwrap()))
+      .collect();
+    let display = tb.to_string();
+    let lines: Vec<_> = display.lines().collect();
+    assert_eq!(lines[0], "[no thumbs]");
+    assert_eq!(
+      lines[1],
+      "  one key  |... .... a   .|.. .... b   ..|. .... c   ...| .... d   \
+       .... |... e   .... .|.. f"
+    );
+    assert_eq!(lines[2], "           .... ..|. g   .... ...| h");
+    assert!(lines[3].starts_with("  left     ||.. .... i   |.|. .... j"));
+    assert!(display.contains("[left thumb]\n  one key  |... .... "));
+    assert!(display.contains("[right thumb]\n"));
+    assert!(!display.contains("[both thumbs]"));
+    assert!(!display.contains("thumbs   "));
   }
 
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_finger_names_deserialization() {
+    let json = r#"{"a": ["LP", "RI"], "b": [0,1,0,0,0,0,0,0,0,0]}"#;
+    let tb: TenboardUnconstrained = serde_json::from_str(json).unwrap();
+    assert_eq!(
+      tb.layout.get('a').unwrap(),
+      [1, 0, 0, 0, 0, 0, 1, 0, 0, 0].into()
+    );
+    assert_eq!(
+      tb.layout.get('b').unwrap(),
+      [0, 1, 0, 0, 0, 0, 0, 0, 0, 0].into()
+    );
+    let json = "{\"a\": [\"LP\"],\n\"b\": [\"LP\", \"XX\"]}";
+    let err = serde_json::from_str::<TenboardUnconstrained>(json)
+      .unwrap_err()
+      .to_string();
+    assert!(err.starts_with("invalid chord [\"LP\",\"XX\"]"));
+    assert!(err.ends_with("at line 2 column 18"));
+  }
+
+  #[cfg(feature = "serde")]
   #[test]
   fn test_unconstrained_serialization() -> Result<(), serde_json::Error> {
     let tb = TenboardUnconstrained::new_random();
     let json = serde_json::to_string(&tb)?;
     let tb_de: TenboardUnconstrained = serde_json::from_str(&json)?;
-    for k in tb.layout.keys() {
+    for k in tb.layout.chars() {
       assert_eq!(tb.layout.get(k), tb_de.layout.get(k))
     }
     Ok(())
   }
 
+  #[cfg(feature = "serde")]
   #[test]
   fn test_thumb_constrained_serialization() -> Result<(), serde_json::Error> {
     let tb = TenboardThumbConstrained::new_random();
     let json = serde_json::to_string(&tb)?;
     let tb_de: TenboardThumbConstrained = serde_json::from_str(&json)?;
-    for k in tb.layout.keys() {
+    for k in tb.layout.chars() {
       assert_eq!(tb.layout.get(k), tb_de.layout.get(k))
     }
     Ok(())
   }
 
+  #[cfg(feature = "serde")]
   #[test]
   fn test_modifier_constrained_serialization() -> Result<(), serde_json::Error>
   {
@@ -302,13 +1456,13 @@ mod tests {
     let tb_de: TenboardModifierConstrained = serde_json::from_str(&json)?;
     assert_eq!(tb.whitespace_hs, tb_de.whitespace_hs);
     assert_eq!(tb.newline_hs, tb_de.newline_hs);
-    for k in tb.punctuation_layout.keys() {
+    for k in tb.punctuation_layout.chars() {
       assert_eq!(
         tb.punctuation_layout.get(k),
         tb_de.punctuation_layout.get(k)
       )
     }
-    for k in tb.lowercase_digit_layout.keys() {
+    for k in tb.lowercase_digit_layout.chars() {
       assert_eq!(
         tb.lowercase_digit_layout.get(k),
         tb_de.lowercase_digit_layout.get(k)
@@ -316,4 +1470,25 @@ mod tests {
     }
     Ok(())
   }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_home_row_constrained_serialization() -> Result<(), serde_json::Error>
+  {
+    let tb = TenboardHomeRowConstrained::new_random();
+    let json = serde_json::to_string(&tb)?;
+    let tb_de: TenboardHomeRowConstrained = serde_json::from_str(&json)?;
+    for k in tb.layout.chars() {
+      assert_eq!(tb.layout.get(k), tb_de.layout.get(k))
+    }
+    let (e, z) = (tb.layout.get('e').unwrap(), tb.layout.get('z').unwrap());
+    let mut layout: HashMap<_, _> = tb.layout.iter().collect();
+    layout.insert('e', z);
+    layout.insert('z', e);
+    let err = TenboardHomeRowConstrained::try_from(layout).unwrap_err();
+    assert_eq!(err, NotOnHomeRow { ch: 'e' });
+    let json = r#"{"e": ["LP"], "t": ["LP"]}"#;
+    assert!(serde_json::from_str::<TenboardHomeRowConstrained>(json).is_err());
+    Ok(())
+  }
 }
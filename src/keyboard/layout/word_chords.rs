@@ -0,0 +1,273 @@
+//! Describes keyboards with chords that type whole words or their parts.
+
+use crate::keyboard::{
+  hands::HandsState,
+  shift::{Shift, ShiftPolicy},
+  try_type_run,
+  Keyboard,
+  NoSuchChar,
+  TypingError,
+};
+
+/// Dictionary of word chords typing strings like "the" or "ing", used by
+/// [WordChordKeyboard] and [Layer](super::layered::Layer).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WordDictionary {
+  /// Word chords, the longest words first.
+  words: Vec<(String, Vec<char>, HandsState)>,
+  whole_words: bool,
+}
+
+impl WordDictionary {
+  /// Creates an empty dictionary matching words anywhere in text.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Makes `handstate` type `word`. If `word` already has a chord, it's
+  /// replaced. Words of less than two chars are ignored.
+  pub fn add_word(&mut self, word: &str, handstate: HandsState) -> &mut Self {
+    let chars: Vec<_> = word.chars().collect();
+    if chars.len() < 2 {
+      return self;
+    }
+    self.words.retain(|(w, _, _)| w != word);
+    let idx = self
+      .words
+      .partition_point(|(_, c, _)| c.len() >= chars.len());
+    self.words.insert(idx, (word.to_owned(), chars, handstate));
+    self
+  }
+
+  /// Sets whether words are matched only as whole words, i.e. when matched
+  /// text isn't surrounded by letters or digits.
+  pub fn set_whole_words(&mut self, whole_words: bool) -> &mut Self {
+    self.whole_words = whole_words;
+    self
+  }
+
+  pub fn is_whole_words(&self) -> bool {
+    self.whole_words
+  }
+
+  /// Returns words with their chords, the longest words first.
+  pub fn iter(&self) -> impl Iterator<Item = (&str, HandsState)> {
+    self.words.iter().map(|(word, _, hs)| (word.as_str(), *hs))
+  }
+
+  /// Returns the longest word starting at `chars[i]` with its length in
+  /// chars and its chord.
+  pub fn match_word(
+    &self,
+    chars: &[char],
+    i: usize,
+  ) -> Option<(&str, usize, HandsState)> {
+    let is_word_char = |i: usize| chars[i].is_alphanumeric();
+    if self.whole_words && i > 0 && is_word_char(i - 1) {
+      return None;
+    }
+    self.words.iter().find_map(|(word, word_chars, hs)| {
+      let end = i + word_chars.len();
+      (chars[i..].starts_with(word_chars)
+        && (!self.whole_words || end == chars.len() || !is_word_char(end)))
+      .then_some((word.as_str(), word_chars.len(), *hs))
+    })
+  }
+}
+
+/// Part of a text typed with a single chord. See
+/// [WordChordKeyboard::plan].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stroke<'a> {
+  /// A char typed with the underlying keyboard.
+  Char(char),
+  /// A string typed with a word chord.
+  Word(&'a str),
+}
+
+/// Keyboard that extends another keyboard with word chords emitting strings,
+/// like "the" or "ing". When typing, the longest word chord matching the
+/// text is used, and chars not covered by word chords are typed with the
+/// underlying keyboard.
+///
+/// ```
+/// use tenboard::keyboard::{
+///   hands::HandsState,
+///   layout::{asetniop::Asetniop, word_chords::WordChordKeyboard},
+///   Keyboard,
+/// };
+///
+/// let mut kb = WordChordKeyboard::new(Asetniop::default());
+/// kb.add_word("the", [1, 0, 0, 0, 1, 0, 0, 0, 0, 1].into());
+/// assert_eq!(kb.type_chars("then".chars()).len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct WordChordKeyboard<K> {
+  keyboard: K,
+  words: WordDictionary,
+}
+
+impl<K: Keyboard> WordChordKeyboard<K> {
+  /// Creates a keyboard without word chords. It types like `keyboard`.
+  pub fn new(keyboard: K) -> Self {
+    Self {
+      keyboard,
+      words: WordDictionary::new(),
+    }
+  }
+
+  /// Makes `handstate` type `word`. See [WordDictionary::add_word].
+  pub fn add_word(&mut self, word: &str, handstate: HandsState) -> &mut Self {
+    self.words.add_word(word, handstate);
+    self
+  }
+
+  /// Sets whether word chords are used only for whole words. See
+  /// [WordDictionary::set_whole_words].
+  pub fn set_whole_words(&mut self, whole_words: bool) -> &mut Self {
+    self.words.set_whole_words(whole_words);
+    self
+  }
+
+  pub fn keyboard(&self) -> &K {
+    &self.keyboard
+  }
+
+  pub fn words(&self) -> &WordDictionary {
+    &self.words
+  }
+
+  /// Greedily splits `chars` into strokes, preferring the longest word
+  /// chords.
+  pub fn plan(&self, chars: &[char]) -> Vec<Stroke<'_>> {
+    let mut strokes = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+      match self.words.match_word(chars, i) {
+        Some((word, len, _)) => {
+          strokes.push(Stroke::Word(word));
+          i += len;
+        }
+        None => {
+          strokes.push(Stroke::Char(chars[i]));
+          i += 1;
+        }
+      }
+    }
+    strokes
+  }
+}
+
+impl<K: Keyboard> WordChordKeyboard<K> {
+  /// Types `chars` or returns an error with the index of the char that
+  /// couldn't be typed. Runs of chars between word chords are typed with
+  /// the underlying keyboard at once.
+  fn try_type_indexed(
+    &self,
+    chars: &[char],
+  ) -> Result<Vec<HandsState>, (NoSuchChar, usize)> {
+    let mut handstates = Vec::with_capacity(chars.len());
+    let type_run = |start: usize, end: usize| {
+      try_type_run(&self.keyboard, &chars[start..end])
+        .map_err(|(e, i)| (e, start + i))
+    };
+    let (mut i, mut run_start) = (0, 0);
+    while i < chars.len() {
+      match self.words.match_word(chars, i) {
+        Some((_, len, hs)) => {
+          handstates.extend(type_run(run_start, i)?);
+          handstates.push(hs);
+          i += len;
+          run_start = i;
+        }
+        None => i += 1,
+      }
+    }
+    handstates.extend(type_run(run_start, i)?);
+    Ok(handstates)
+  }
+}
+
+impl<K: Keyboard> Keyboard for WordChordKeyboard<K> {
+  fn try_type_chars(
+    &self,
+    chars: impl Iterator<Item = char>,
+  ) -> Result<Vec<HandsState>, NoSuchChar> {
+    let chars: Vec<_> = chars.collect();
+    self.try_type_indexed(&chars).map_err(|(e, _)| e)
+  }
+
+  fn try_type_text(
+    &self,
+    text: &str,
+  ) -> Result<Vec<HandsState>, TypingError> {
+    let chars: Vec<_> = text.chars().collect();
+    self
+      .try_type_indexed(&chars)
+      .map_err(|(e, i)| TypingError::locate(e.ch, text, i))
+  }
+}
+
+impl<K: Keyboard + Shift> Shift for WordChordKeyboard<K> {
+  fn shift_policy(&self) -> ShiftPolicy {
+    self.keyboard.shift_policy()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testing::{CapsLockKeyboard, SyntheticKeyboard};
+
+  #[test]
+  fn test_plan() {
+    let mut kb = WordChordKeyboard::new(SyntheticKeyboard::new_one_finger(
+      "abcdefghijklmnopqrstuvwxyz ",
+    ));
+    kb.add_word("the", HandsState::both_thumbs())
+      .add_word("th", HandsState::left_thumb())
+      .add_word("ing", HandsState::right_thumb());
+    let chars: Vec<_> = "then thing".chars().collect();
+    assert_eq!(kb.plan(&chars), vec![
+      Stroke::Word("the"),
+      Stroke::Char('n'),
+      Stroke::Char(' '),
+      Stroke::Word("th"),
+      Stroke::Word("ing"),
+    ]);
+    assert_eq!(kb.type_chars("then thing".chars()).len(), 5);
+    kb.set_whole_words(true);
+    assert_eq!(kb.plan(&chars).len(), 10);
+    assert_eq!(
+      kb.try_type_chars("the!".chars()),
+      Err(NoSuchChar { ch: '!' })
+    );
+  }
+
+  #[test]
+  fn test_typing_error_position() {
+    let mut kb = WordChordKeyboard::new(SyntheticKeyboard::new_one_finger(
+      "abcdefghijklmnopqrstuvwxyz \n",
+    ));
+    kb.add_word("the", HandsState::both_thumbs());
+    assert_eq!(kb.try_type_text("the\nthé the"), Err(TypingError {
+      ch: 'é',
+      index: 6,
+      byte: 6,
+      line: 2,
+      column: 3,
+    }));
+  }
+
+  #[test]
+  fn test_stateful_keyboard() {
+    let kb = CapsLockKeyboard::new(
+      SyntheticKeyboard::new_one_finger("ab"),
+      HandsState::left_thumb(),
+    );
+    let mut kb = WordChordKeyboard::new(kb);
+    assert_eq!(kb.type_chars("AB".chars()).len(), 3);
+    kb.add_word("ab", HandsState::right_thumb());
+    assert_eq!(kb.type_chars("ABabAB".chars()).len(), 7);
+  }
+}
@@ -0,0 +1,329 @@
+//! Contains preprocessing of text that replaces chars a keyboard can't type
+//! with ones it can.
+
+#[cfg(feature = "unicode")]
+use std::iter::Filter;
+use std::{char::ToLowercase, collections::HashMap, str::Chars};
+
+#[cfg(feature = "unicode")]
+use unicode_normalization::{
+  char::is_combining_mark,
+  Decompositions,
+  Recompositions,
+  UnicodeNormalization,
+};
+
+/// Typographic chars and their ASCII replacements.
+const TYPOGRAPHIC: [(char, &str); 17] = [
+  ('\u{2018}', "'"),
+  ('\u{2019}', "'"),
+  ('\u{201a}', "'"),
+  ('\u{201b}', "'"),
+  ('\u{201c}', "\""),
+  ('\u{201d}', "\""),
+  ('\u{201e}', "\""),
+  ('\u{ab}', "\""),
+  ('\u{bb}', "\""),
+  ('\u{2013}', "-"),
+  ('\u{2014}', "-"),
+  ('\u{2015}', "-"),
+  ('\u{2212}', "-"),
+  ('\u{2026}', "..."),
+  ('\u{a0}', " "),
+  ('\u{2009}', " "),
+  ('\u{202f}', " "),
+];
+
+/// Latin letters with diacritics grouped by their base letters.
+const DIACRITICS: [(&str, &str); 30] = [
+  ("a", "àáâãäåāăą"),
+  ("A", "ÀÁÂÃÄÅĀĂĄ"),
+  ("c", "çćĉċč"),
+  ("C", "ÇĆĈĊČ"),
+  ("d", "ďđ"),
+  ("D", "ĎĐ"),
+  ("e", "èéêëēĕėęě"),
+  ("E", "ÈÉÊËĒĔĖĘĚ"),
+  ("g", "ĝğġģ"),
+  ("G", "ĜĞĠĢ"),
+  ("i", "ìíîïĩīĭįı"),
+  ("I", "ÌÍÎÏĨĪĬĮİ"),
+  ("l", "ĺļľŀł"),
+  ("L", "ĹĻĽĿŁ"),
+  ("n", "ñńņňŉ"),
+  ("N", "ÑŃŅŇ"),
+  ("o", "òóôõöøōŏő"),
+  ("O", "ÒÓÔÕÖØŌŎŐ"),
+  ("r", "ŕŗř"),
+  ("R", "ŔŖŘ"),
+  ("s", "śŝşš"),
+  ("S", "ŚŜŞŠ"),
+  ("t", "ţťŧ"),
+  ("T", "ŢŤŦ"),
+  ("u", "ùúûüũūŭůűų"),
+  ("U", "ÙÚÛÜŨŪŬŮŰŲ"),
+  ("y", "ýÿŷ"),
+  ("Y", "ÝŸŶ"),
+  ("z", "źżž"),
+  ("Z", "ŹŻŽ"),
+];
+
+/// Ligatures and letters written with several Latin letters.
+const LIGATURES: [(char, &str); 8] = [
+  ('ß', "ss"),
+  ('æ', "ae"),
+  ('Æ', "AE"),
+  ('œ', "oe"),
+  ('Œ', "OE"),
+  ('þ', "th"),
+  ('Þ', "TH"),
+  ('ð', "d"),
+];
+
+/// Replaces chars of a text before it's typed, so that texts with chars
+/// missing in a keyboard can be scored anyway.
+///
+/// ```
+/// use tenboard::keyboard::mapper::CharMapper;
+///
+/// let mut mapper = CharMapper::new();
+/// mapper.add_typographic().add_diacritics().set_lowercase(true);
+/// assert_eq!(mapper.map_str("Café — “Ça va”…"), "cafe - \"ca va\"...");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CharMapper {
+  substitutions: HashMap<char, String>,
+  lowercase: bool,
+  #[cfg(feature = "unicode")]
+  normalization: Option<Normalization>,
+}
+
+/// Unicode normalization form applied by a [CharMapper], so that texts
+/// with composed and decomposed forms of the same chars are typed the same.
+#[cfg(feature = "unicode")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Normalization {
+  /// Canonical composition, e.g. "e\u{301}" becomes "é".
+  Nfc,
+  /// Canonical decomposition, e.g. "é" becomes "e\u{301}".
+  Nfd,
+  /// Compatibility composition, which also replaces compatibility chars,
+  /// e.g. "ﬁ" becomes "fi" and "²" becomes "2".
+  Nfkc,
+  /// Compatibility decomposition.
+  Nfkd,
+  /// Compatibility decomposition with combining marks removed, e.g. "é"
+  /// becomes "e". It strips diacritics of any script.
+  Fold,
+}
+
+impl CharMapper {
+  /// Creates a mapper that leaves chars as they are.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Creates a mapper that replaces typographic chars and letters with
+  /// diacritics with their closest ASCII chars.
+  pub fn ascii() -> Self {
+    let mut mapper = Self::new();
+    mapper.add_typographic().add_diacritics();
+    mapper
+  }
+
+  /// Replaces `from` with `to`. An empty `to` removes `from` from text.
+  pub fn add_substitution(&mut self, from: char, to: &str) -> &mut Self {
+    self.substitutions.insert(from, to.to_owned());
+    self
+  }
+
+  /// Replaces smart quotes, dashes, ellipsis and non-breaking spaces with
+  /// ASCII chars.
+  pub fn add_typographic(&mut self) -> &mut Self {
+    for (from, to) in TYPOGRAPHIC {
+      self.add_substitution(from, to);
+    }
+    self
+  }
+
+  /// Strips diacritics from Latin letters and splits ligatures.
+  pub fn add_diacritics(&mut self) -> &mut Self {
+    for (base, letters) in DIACRITICS {
+      for from in letters.chars() {
+        self.add_substitution(from, base);
+      }
+    }
+    for (from, to) in LIGATURES {
+      self.add_substitution(from, to);
+    }
+    self
+  }
+
+  /// Makes the mapper convert uppercase chars to lowercase before other
+  /// substitutions.
+  pub fn set_lowercase(&mut self, lowercase: bool) -> &mut Self {
+    self.lowercase = lowercase;
+    self
+  }
+
+  /// Makes the mapper normalize text before other substitutions. The
+  /// substitutions then see normalized chars, e.g. only composed ones
+  /// with [Normalization::Nfc].
+  #[cfg(feature = "unicode")]
+  pub fn set_normalization(
+    &mut self,
+    normalization: Option<Normalization>,
+  ) -> &mut Self {
+    self.normalization = normalization;
+    self
+  }
+
+  #[cfg(feature = "unicode")]
+  pub fn normalization(&self) -> Option<Normalization> {
+    self.normalization
+  }
+
+  /// Returns chars that replace `ch`. Normalization isn't applied, since it
+  /// depends on neighbouring chars.
+  pub fn map_char(&self, ch: char) -> MappedChar<'_> {
+    let ch = if self.lowercase && ch.is_uppercase() {
+      let mut lower = ch.to_lowercase();
+      if lower.len() != 1 {
+        return MappedChar::Lowercase(lower);
+      }
+      lower.next().unwrap()
+    } else {
+      ch
+    };
+    match self.substitutions.get(&ch) {
+      Some(to) => MappedChar::Substitution(to.chars()),
+      None => MappedChar::Char(Some(ch)),
+    }
+  }
+
+  /// Lazily maps `chars`. The result can be passed directly to
+  /// [Keyboard::try_type_chars](super::Keyboard::try_type_chars).
+  pub fn map<'a>(
+    &'a self,
+    chars: impl Iterator<Item = char> + 'a,
+  ) -> impl Iterator<Item = char> + 'a {
+    #[cfg(feature = "unicode")]
+    let chars = Normalized::new(chars, self.normalization);
+    chars.flat_map(|ch| self.map_char(ch))
+  }
+
+  pub fn map_str(&self, text: &str) -> String {
+    self.map(text.chars()).collect()
+  }
+}
+
+/// Iterator over chars that replace a single char. See
+/// [CharMapper::map_char].
+#[derive(Debug, Clone)]
+pub enum MappedChar<'a> {
+  Char(Option<char>),
+  Substitution(Chars<'a>),
+  Lowercase(ToLowercase),
+}
+
+impl Iterator for MappedChar<'_> {
+  type Item = char;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    match self {
+      MappedChar::Char(ch) => ch.take(),
+      MappedChar::Substitution(chars) => chars.next(),
+      MappedChar::Lowercase(chars) => chars.next(),
+    }
+  }
+}
+
+/// Iterator over chars normalized with a [Normalization].
+#[cfg(feature = "unicode")]
+enum Normalized<I: Iterator<Item = char>> {
+  Unchanged(I),
+  Composed(Recompositions<I>),
+  Decomposed(Decompositions<I>),
+  Folded(Filter<Decompositions<I>, fn(&char) -> bool>),
+}
+
+#[cfg(feature = "unicode")]
+impl<I: Iterator<Item = char>> Normalized<I> {
+  fn new(chars: I, normalization: Option<Normalization>) -> Self {
+    match normalization {
+      None => Normalized::Unchanged(chars),
+      Some(Normalization::Nfc) => Normalized::Composed(chars.nfc()),
+      Some(Normalization::Nfd) => Normalized::Decomposed(chars.nfd()),
+      Some(Normalization::Nfkc) => Normalized::Composed(chars.nfkc()),
+      Some(Normalization::Nfkd) => Normalized::Decomposed(chars.nfkd()),
+      Some(Normalization::Fold) => {
+        Normalized::Folded(chars.nfkd().filter(|&ch| !is_combining_mark(ch)))
+      }
+    }
+  }
+}
+
+#[cfg(feature = "unicode")]
+impl<I: Iterator<Item = char>> Iterator for Normalized<I> {
+  type Item = char;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    match self {
+      Normalized::Unchanged(chars) => chars.next(),
+      Normalized::Composed(chars) => chars.next(),
+      Normalized::Decomposed(chars) => chars.next(),
+      Normalized::Folded(chars) => chars.next(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{keyboard::Keyboard, testing::SyntheticKeyboard};
+
+  #[test]
+  fn test_mapper() {
+    let mut mapper = CharMapper::ascii();
+    assert_eq!(mapper.map_str("Straße – Æsir"), "Strasse - AEsir");
+    mapper.set_lowercase(true).add_substitution('\r', "");
+    assert_eq!(mapper.map_str("Ñu\r\nİ"), "nu\ni\u{307}");
+    assert_eq!(CharMapper::new().map_str("É’"), "É’");
+  }
+
+  #[test]
+  fn test_mapped_typing() {
+    let kb = SyntheticKeyboard::new_one_finger("abc-");
+    let mut mapper = CharMapper::ascii();
+    mapper.set_lowercase(true);
+    assert_eq!(
+      kb.try_type_chars(mapper.map("Àb—c".chars())),
+      Ok(kb.type_chars("ab-c".chars()))
+    );
+  }
+
+  #[cfg(feature = "unicode")]
+  #[test]
+  fn test_normalization() {
+    let composed = "caf\u{e9} \u{fb01}n";
+    let decomposed = "cafe\u{301} \u{fb01}n";
+    let mut mapper = CharMapper::new();
+    mapper.set_normalization(Some(Normalization::Nfc));
+    assert_eq!(mapper.map_str(decomposed), composed);
+    mapper.set_normalization(Some(Normalization::Nfkc));
+    assert_eq!(mapper.map_str(decomposed), "caf\u{e9} fin");
+    mapper.set_normalization(Some(Normalization::Nfd));
+    assert_eq!(mapper.map_str(composed), decomposed);
+    mapper.set_normalization(Some(Normalization::Fold));
+    assert_eq!(mapper.map_str(composed), "cafe fin");
+    assert_eq!(mapper.map_str("Ελλάδα"), "Ελλαδα");
+
+    let kb = SyntheticKeyboard::new_one_finger("caf\u{e9}");
+    let mut mapper = CharMapper::new();
+    mapper.set_normalization(Some(Normalization::Nfc));
+    assert_eq!(
+      kb.try_type_chars(mapper.map("cafe\u{301}".chars())),
+      Ok(kb.type_chars(composed[..5].chars()))
+    );
+  }
+}
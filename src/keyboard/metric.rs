@@ -1,53 +1,573 @@
 //! Contains metrics that define efficency of a keyboard.
 
-use super::hands::{FingerState, HandsState};
-
-/// Describes metric used to measure keyboard layout efficiency.
-pub trait Metric: Sized {
+use alloc::{format, string::String, vec::Vec};
+use core::{cmp::Reverse, fmt::Display};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+  hands::{FingerState, HandsState, FINGER_NAMES, HAND_NAMES},
+  profile::FingerProfile,
+  shift::ShiftPolicy,
+};
+use crate::compat::{self, HashMap};
+
+/// Describes metric used to measure keyboard layout efficiency. `N` is the
+/// number of inputs of typed [HandsState]s.
+pub trait Metric<const N: usize = 10> {
   /// Updates metric's state with data from given `handstate`.
-  fn update_once(&mut self, handstate: &HandsState);
+  fn update_once(&mut self, handstate: &HandsState<N>);
 
   /// Updates metric's state with data from given `handstates`.
-  fn update(&mut self, handstates: &[HandsState]) {
+  fn update(&mut self, handstates: &[HandsState<N>]) {
     for hs in handstates {
       self.update_once(hs);
     }
   }
 
   /// Consumes `self`, then `update`s and returns it.
-  fn updated(mut self, handstates: &[HandsState]) -> Self {
+  fn updated(mut self, handstates: &[HandsState<N>]) -> Self
+  where
+    Self: Sized,
+  {
     self.update(handstates);
     self
   }
 
   /// Returns metric's score. The lower - the better.
   fn score(&self) -> f32;
+
+  /// Returns per-finger or per-hand values the score is made of, if the
+  /// metric tracks them. By default it doesn't.
+  fn breakdown(&self) -> Option<Breakdown> {
+    None
+  }
+
+  /// Returns metadata of the metric's score for tools that label and
+  /// normalize scores. By default the metric is named after its type, lower
+  /// scores are better and scores are unbounded.
+  fn info(&self) -> MetricInfo {
+    let name = core::any::type_name::<Self>();
+    let name = name.split('<').next().unwrap_or(name);
+    MetricInfo::new(name.rsplit("::").next().unwrap_or(name))
+  }
+
+  /// Wraps `self` into [PerPress] to get score normalized by number of typed
+  /// chords.
+  fn per_press(self) -> PerPress<Self>
+  where
+    Self: Sized,
+  {
+    PerPress::new(self)
+  }
+
+  /// Wraps `self` into [Sampled] to record its score every `every` chords.
+  fn sampled(self, every: u64) -> Sampled<Self>
+  where
+    Self: Sized,
+  {
+    Sampled::new(self, every)
+  }
+}
+
+/// Describes scores of a metric, see [Metric::info].
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct MetricInfo {
+  /// Name of the metric, e.g. `"finger_alternation"`.
+  pub name: &'static str,
+  /// Whether lower scores are better, which is true for most metrics.
+  /// `None` if neither lower nor higher scores are better, like for totals.
+  pub lower_is_better: Option<bool>,
+  /// The lowest possible score, if there is one.
+  pub min: Option<f32>,
+  /// The highest possible score, if there is one.
+  pub max: Option<f32>,
+}
+
+impl MetricInfo {
+  /// Creates info of an unbounded metric where lower scores are better.
+  pub fn new(name: &'static str) -> Self {
+    Self {
+      name,
+      lower_is_better: Some(true),
+      min: None,
+      max: None,
+    }
+  }
+
+  /// Creates info of a metric counting something: lower scores are better
+  /// and scores are never negative.
+  pub fn counter(name: &'static str) -> Self {
+    Self {
+      min: Some(0.0),
+      ..Self::new(name)
+    }
+  }
+
+  /// Creates info of a metric totalling something: scores are never
+  /// negative and neither lower nor higher scores are better.
+  pub fn total(name: &'static str) -> Self {
+    Self {
+      lower_is_better: None,
+      ..Self::counter(name)
+    }
+  }
+
+  /// Maps `score` to 0..=1, where 0 is the best possible score and 1 is the
+  /// worst one. Scores without a direction map 0 to the lowest one. Returns
+  /// `None` unless both bounds are known.
+  pub fn normalize(&self, score: f32) -> Option<f32> {
+    let (min, max) = (self.min?, self.max?);
+    let normalized = ((score - min) / (max - min)).clamp(0.0, 1.0);
+    Some(match self.lower_is_better {
+      Some(false) => 1.0 - normalized,
+      _ => normalized,
+    })
+  }
+}
+
+/// Per-finger or per-hand values of a metric, see [Metric::breakdown].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Breakdown {
+  /// Values of fingers indexed like in [HandsState].
+  Fingers([f32; 10]),
+  /// Values of the left and the right hands.
+  Hands([f32; 2]),
+}
+
+impl Breakdown {
+  pub fn values(&self) -> &[f32] {
+    match self {
+      Breakdown::Fingers(values) => values,
+      Breakdown::Hands(values) => values,
+    }
+  }
+
+  /// Returns labels of values: [FINGER_NAMES] or [HAND_NAMES].
+  pub fn labels(&self) -> &'static [&'static str] {
+    match self {
+      Breakdown::Fingers(_) => &FINGER_NAMES,
+      Breakdown::Hands(_) => &HAND_NAMES,
+    }
+  }
+
+  /// Returns values with their labels.
+  pub fn labeled(&self) -> impl Iterator<Item = (&'static str, f32)> + '_ {
+    self
+      .labels()
+      .iter()
+      .copied()
+      .zip(self.values().iter().copied())
+  }
+
+  /// Applies `f` to every value.
+  pub fn map(self, f: impl Fn(f32) -> f32) -> Self {
+    match self {
+      Breakdown::Fingers(values) => Breakdown::Fingers(values.map(f)),
+      Breakdown::Hands(values) => Breakdown::Hands(values.map(f)),
+    }
+  }
+
+  /// Divides values by the largest one, so the most loaded finger or hand
+  /// gets 1. Values are left as is if they are all zero.
+  pub fn normalized(self) -> Self {
+    let max = self.values().iter().copied().fold(0.0, f32::max);
+    if max > 0.0 {
+      self.map(|v| v / max)
+    } else {
+      self
+    }
+  }
+
+  /// Renders values in 0..=1, e.g. a heatmap of [FingerUsage::heatmap], as
+  /// a terminal bar chart with a labeled line per value. Bars are `width`
+  /// cells long at 1 and colored from green to red with ANSI escapes.
+  pub fn to_ansi_bars(&self, width: usize) -> String {
+    let mut bars = String::new();
+    for (label, value) in self.labeled() {
+      let value = value.clamp(0.0, 1.0);
+      let red = compat::round(255.0 * value) as u8;
+      let green = compat::round(255.0 * (1.0 - value)) as u8;
+      let bar = "█".repeat(compat::round(value * width as f32) as usize);
+      bars.push_str(&format!(
+        "{label:>2} \x1b[38;2;{red};{green};0m{bar:<width$}\x1b[0m {value:.2}\n"
+      ));
+    }
+    bars
+  }
+}
+
+/// Wraps a metric and counts typed chords, so scores are comparable across
+/// corpora of different lengths. `score` returns wrapped metric's score
+/// divided by number of chords, `raw_score` returns it as is.
+#[derive(Default, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PerPress<M> {
+  metric: M,
+  presses: u32,
+}
+
+impl<M> PerPress<M> {
+  pub fn new(metric: M) -> Self {
+    Self { metric, presses: 0 }
+  }
+
+  /// Returns number of chords the metric was updated with.
+  pub fn presses(&self) -> u32 {
+    self.presses
+  }
+
+  /// Returns score of the wrapped metric.
+  pub fn raw_score<const N: usize>(&self) -> f32
+  where
+    M: Metric<N>,
+  {
+    self.metric.score()
+  }
+
+  pub fn inner(&self) -> &M {
+    &self.metric
+  }
+
+  pub fn into_inner(self) -> M {
+    self.metric
+  }
+}
+
+impl<const N: usize, M: Metric<N>> Metric<N> for PerPress<M> {
+  fn update_once(&mut self, handstate: &HandsState<N>) {
+    self.metric.update_once(handstate);
+    self.presses += 1;
+  }
+
+  fn score(&self) -> f32 {
+    self.metric.score() / self.presses.max(1) as f32
+  }
+
+  fn breakdown(&self) -> Option<Breakdown> {
+    let presses = self.presses.max(1) as f32;
+    Some(self.metric.breakdown()?.map(|v| v / presses))
+  }
+
+  /// Same as the info of the wrapped metric, but bounds other than 0 don't
+  /// apply to scores per press.
+  fn info(&self) -> MetricInfo {
+    let info = self.metric.info();
+    MetricInfo {
+      min: info.min.filter(|&min| min == 0.0),
+      max: info.max.filter(|&max| max == 0.0),
+      ..info
+    }
+  }
+}
+
+/// Wraps a metric and records its score every `every` chords, so score
+/// curves along a corpus can be plotted after a single scoring run.
+#[derive(Default, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Sampled<M> {
+  metric: M,
+  every: u64,
+  presses: u64,
+  samples: Vec<(u64, f32)>,
+}
+
+impl<M> Sampled<M> {
+  /// # Panics
+  ///
+  /// Panics if `every` is 0.
+  pub fn new(metric: M, every: u64) -> Self {
+    assert!(every > 0, "sampling interval must be positive");
+    Self {
+      metric,
+      every,
+      presses: 0,
+      samples: Vec::new(),
+    }
+  }
+
+  /// Returns recorded numbers of chords and scores after them.
+  pub fn samples(&self) -> &[(u64, f32)] {
+    &self.samples
+  }
+
+  /// Returns number of chords the metric was updated with.
+  pub fn presses(&self) -> u64 {
+    self.presses
+  }
+
+  pub fn inner(&self) -> &M {
+    &self.metric
+  }
+
+  /// Returns the wrapped metric and recorded samples.
+  pub fn into_parts(self) -> (M, Vec<(u64, f32)>) {
+    (self.metric, self.samples)
+  }
+}
+
+impl<const N: usize, M: Metric<N>> Metric<N> for Sampled<M> {
+  fn update_once(&mut self, handstate: &HandsState<N>) {
+    self.metric.update_once(handstate);
+    self.presses += 1;
+    if self.presses.is_multiple_of(self.every) {
+      self.samples.push((self.presses, self.metric.score()));
+    }
+  }
+
+  fn score(&self) -> f32 {
+    self.metric.score()
+  }
+
+  fn breakdown(&self) -> Option<Breakdown> {
+    self.metric.breakdown()
+  }
+
+  fn info(&self) -> MetricInfo {
+    self.metric.info()
+  }
+}
+
+/// Wraps a metric and rescales its score into 0..=1, where 0 is the best
+/// score, so metrics returning big raw counts don't dominate weighted
+/// [Objective](super::objective::Objective)s. Bounds are taken from
+/// [Metric::info], given explicitly or measured on a baseline layout.
+/// Scores worse than a baseline aren't clamped. Counting metrics grow with
+/// corpus length, so a baseline must be typed from the scored corpus, or
+/// the metric wrapped into [PerPress].
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use tenboard::keyboard::{
+///   layout::standard::Qwerty,
+///   metric::{FingerAlternation, Metric, Normalized},
+///   Keyboard,
+/// };
+///
+/// let baseline = Qwerty::new().type_chars("hello world".chars());
+/// let metric =
+///   Normalized::new_with_baseline(FingerAlternation::new(), &baseline);
+/// // Scores as bad as Qwerty's are 1, worse ones are above 1.
+/// assert_eq!(metric.updated(&baseline).score(), 1.0);
+/// # }
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Normalized<M> {
+  metric: M,
+  min: f32,
+  max: f32,
+  /// Whether `max` is a score of a baseline rather than the worst one, so
+  /// higher scores aren't clamped.
+  #[cfg_attr(feature = "serde", serde(default))]
+  baseline: bool,
+}
+
+impl<M> Normalized<M> {
+  /// Normalizes `metric` with bounds of its [Metric::info].
+  ///
+  /// # Panics
+  ///
+  /// Panics if the metric doesn't have both bounds.
+  pub fn new<const N: usize>(metric: M) -> Self
+  where
+    M: Metric<N>,
+  {
+    let info = metric.info();
+    match (info.min, info.max) {
+      (Some(min), Some(max)) => Self::new_with_bounds(metric, min, max),
+      _ => panic!(
+        "metric {} isn't bounded, give bounds or a baseline explicitly",
+        info.name
+      ),
+    }
+  }
+
+  /// Normalizes `metric` so `min` and `max` scores are mapped to 0 and 1,
+  /// or to 1 and 0 if higher scores of the metric are better. Scores
+  /// outside of the bounds are clamped, and a score equal to both bounds is
+  /// the best one.
+  pub fn new_with_bounds(metric: M, min: f32, max: f32) -> Self {
+    Self {
+      metric,
+      min,
+      max,
+      baseline: false,
+    }
+  }
+
+  /// Normalizes `metric` with bounds from its lowest possible score, or 0
+  /// if it's unknown, to its score over `baseline` chords typed with a
+  /// reference layout, e.g. Qwerty. If lower scores are better, scores
+  /// worse than the baseline's are above 1, unless the baseline has the
+  /// lowest score.
+  pub fn new_with_baseline<const N: usize>(
+    metric: M,
+    baseline: &[HandsState<N>],
+  ) -> Self
+  where
+    M: Metric<N> + Clone,
+  {
+    let info = metric.info();
+    let min = info.min.unwrap_or(0.0);
+    let max = metric.clone().updated(baseline).score();
+    Self {
+      baseline: max > min && info.lower_is_better != Some(false),
+      ..Self::new_with_bounds(metric, min, max)
+    }
+  }
+
+  /// Returns scores mapped to 0 and 1.
+  pub fn bounds(&self) -> (f32, f32) {
+    (self.min, self.max)
+  }
+
+  pub fn inner(&self) -> &M {
+    &self.metric
+  }
+
+  pub fn into_inner(self) -> M {
+    self.metric
+  }
+}
+
+impl<const N: usize, M: Metric<N>> Metric<N> for Normalized<M> {
+  fn update_once(&mut self, handstate: &HandsState<N>) {
+    self.metric.update_once(handstate);
+  }
+
+  fn update(&mut self, handstates: &[HandsState<N>]) {
+    self.metric.update(handstates);
+  }
+
+  fn score(&self) -> f32 {
+    let info = MetricInfo {
+      min: Some(self.min),
+      max: Some(self.max),
+      ..self.metric.info()
+    };
+    let score = self.metric.score();
+    match info.normalize(score) {
+      Some(normalized) if normalized.is_nan() => 0.0,
+      // `normalize` clamps scores worse than the baseline
+      Some(1.0) if self.baseline => (score - self.min) / (self.max - self.min),
+      normalized => normalized.unwrap_or(0.0),
+    }
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo {
+      max: Some(1.0).filter(|_| !self.baseline),
+      ..MetricInfo::counter(self.metric.info().name)
+    }
+  }
+}
+
+/// Metric made of closures, for experiments that don't deserve a struct.
+///
+/// ```
+/// use tenboard::keyboard::{hands::HandsState, metric::{FnMetric, Metric}};
+///
+/// // Counts chords of more than two keys.
+/// let mut metric = FnMetric::new(
+///   0,
+///   |big: &mut u32, hs: &HandsState| *big += (hs.count_pressed() > 2) as u32,
+///   |big: &u32| *big as f32,
+/// );
+/// metric.update(&[[1, 1, 1, 0, 0, 0, 0, 0, 0, 0].into()]);
+/// assert_eq!(metric.score(), 1.0);
+/// ```
+#[derive(Clone)]
+pub struct FnMetric<S, U, F> {
+  state: S,
+  update_fn: U,
+  score_fn: F,
+}
+
+impl<S, U, F> FnMetric<S, U, F> {
+  /// Creates a metric starting with `state`, which is updated by
+  /// `update_fn` and scored by `score_fn`.
+  pub fn new<const N: usize>(state: S, update_fn: U, score_fn: F) -> Self
+  where
+    U: FnMut(&mut S, &HandsState<N>),
+    F: Fn(&S) -> f32,
+  {
+    Self {
+      state,
+      update_fn,
+      score_fn,
+    }
+  }
+
+  pub fn state(&self) -> &S {
+    &self.state
+  }
+
+  pub fn into_state(self) -> S {
+    self.state
+  }
 }
 
-/// Measures finger usage.
+impl<const N: usize, S, U, F> Metric<N> for FnMetric<S, U, F>
+where
+  U: FnMut(&mut S, &HandsState<N>),
+  F: Fn(&S) -> f32,
+{
+  fn update_once(&mut self, handstate: &HandsState<N>) {
+    (self.update_fn)(&mut self.state, handstate);
+  }
+
+  fn score(&self) -> f32 {
+    (self.score_fn)(&self.state)
+  }
+}
+
+/// Returns finger breakdown of `values` if there is one per finger of two
+/// hands. Metrics of other numbers of inputs have no breakdown.
+fn fingers_breakdown<const N: usize>(values: [u32; N]) -> Option<Breakdown> {
+  <[u32; 10]>::try_from(values.as_slice())
+    .ok()
+    .map(|values| Breakdown::Fingers(values.map(|v| v as f32)))
+}
+
+/// Measures finger usage. `N` is the number of inputs, use
+/// [FingerUsage::default] to create the metric for other inputs than ten
+/// fingers.
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
-pub struct FingerUsage {
-  presses: [u32; 10],
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FingerUsage<const N: usize = 10> {
+  #[cfg_attr(feature = "serde", serde(with = "finger_counts"))]
+  presses: [u32; N],
 }
 
 impl FingerUsage {
   pub fn new() -> Self {
-    Self { presses: [0; 10] }
+    Self::default()
+  }
+
+  /// Returns usage of fingers relative to the most used one, from 0 for
+  /// unused fingers to 1. See [Breakdown::to_ansi_bars] to print it.
+  pub fn heatmap(&self) -> Breakdown {
+    Breakdown::Fingers(self.presses.map(|v| v as f32)).normalized()
   }
+}
 
-  pub fn values(self) -> [u32; 10] {
+impl<const N: usize> FingerUsage<N> {
+  pub fn values(self) -> [u32; N] {
     self.presses
   }
 }
 
-impl Default for FingerUsage {
+impl<const N: usize> Default for FingerUsage<N> {
   fn default() -> Self {
-    Self::new()
+    Self { presses: [0; N] }
   }
 }
 
-impl Metric for FingerUsage {
-  fn update_once(&mut self, handstate: &HandsState) {
+impl<const N: usize> Metric<N> for FingerUsage<N> {
+  fn update_once(&mut self, handstate: &HandsState<N>) {
     for (fc, fs) in self.presses.iter_mut().zip(handstate.iter()) {
       *fc += u32::from(*fs);
     }
@@ -56,32 +576,48 @@ impl Metric for FingerUsage {
   fn score(&self) -> f32 {
     self.presses.map(|v| v as f32).iter().sum()
   }
+
+  fn breakdown(&self) -> Option<Breakdown> {
+    fingers_breakdown(self.presses)
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::total("finger_usage")
+  }
 }
 
 /// Measures hand usage.
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
-pub struct HandUsage {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HandUsage<const N: usize = 10> {
   presses: [u32; 2],
 }
 
 impl HandUsage {
   pub fn new() -> Self {
-    Self { presses: [0; 2] }
+    Self::default()
   }
+}
 
+impl<const N: usize> HandUsage<N> {
   pub fn values(self) -> [u32; 2] {
     self.presses
   }
+
+  /// Returns usage of hands relative to the most used one, from 0 to 1.
+  pub fn heatmap(&self) -> Breakdown {
+    Breakdown::Hands(self.presses.map(|v| v as f32)).normalized()
+  }
 }
 
-impl Default for HandUsage {
+impl<const N: usize> Default for HandUsage<N> {
   fn default() -> Self {
-    Self::new()
+    Self { presses: [0; 2] }
   }
 }
 
-impl Metric for HandUsage {
-  fn update_once(&mut self, handstate: &HandsState) {
+impl<const N: usize> Metric<N> for HandUsage<N> {
+  fn update_once(&mut self, handstate: &HandsState<N>) {
     for (hc, hs) in self.presses.iter_mut().zip(handstate.hand_iter()) {
       *hc += hs.iter().map(|fs| u32::from(*fs)).sum::<u32>();
     }
@@ -90,11 +626,19 @@ impl Metric for HandUsage {
   fn score(&self) -> f32 {
     self.presses.map(|v| v as f32).iter().sum()
   }
+
+  fn breakdown(&self) -> Option<Breakdown> {
+    Some(Breakdown::Hands(self.presses.map(|v| v as f32)))
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::total("hand_usage")
+  }
 }
 
-impl From<FingerUsage> for HandUsage {
-  fn from(value: FingerUsage) -> Self {
-    let (lh, rh) = value.presses.split_at(5);
+impl<const N: usize> From<FingerUsage<N>> for HandUsage<N> {
+  fn from(value: FingerUsage<N>) -> Self {
+    let (lh, rh) = value.presses.split_at(N.div_ceil(2));
     Self {
       presses: [lh.iter().sum(), rh.iter().sum()],
     }
@@ -103,32 +647,36 @@ impl From<FingerUsage> for HandUsage {
 
 /// Measures finger alternation.
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
-pub struct FingerAlternation {
-  last_handstate: HandsState,
-  consecutive_presses: [u32; 10],
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FingerAlternation<const N: usize = 10> {
+  last_handstate: HandsState<N>,
+  #[cfg_attr(feature = "serde", serde(with = "finger_counts"))]
+  consecutive_presses: [u32; N],
 }
 
 impl FingerAlternation {
   pub fn new() -> Self {
-    Self {
-      last_handstate: [0; 10].into(),
-      consecutive_presses: [0; 10],
-    }
+    Self::default()
   }
+}
 
-  pub fn values(self) -> [u32; 10] {
+impl<const N: usize> FingerAlternation<N> {
+  pub fn values(self) -> [u32; N] {
     self.consecutive_presses
   }
 }
 
-impl Default for FingerAlternation {
+impl<const N: usize> Default for FingerAlternation<N> {
   fn default() -> Self {
-    Self::new()
+    Self {
+      last_handstate: HandsState::default(),
+      consecutive_presses: [0; N],
+    }
   }
 }
 
-impl Metric for FingerAlternation {
-  fn update_once(&mut self, handstate: &HandsState) {
+impl<const N: usize> Metric<N> for FingerAlternation<N> {
+  fn update_once(&mut self, handstate: &HandsState<N>) {
     for (cp, (last_fs, curr_fs)) in self
       .consecutive_presses
       .iter_mut()
@@ -144,42 +692,161 @@ impl Metric for FingerAlternation {
   fn score(&self) -> f32 {
     self.consecutive_presses.map(|v| v as f32).iter().sum()
   }
+
+  fn breakdown(&self) -> Option<Breakdown> {
+    fingers_breakdown(self.consecutive_presses)
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::counter("finger_alternation")
+  }
+}
+
+/// Measures immediate repetitions of the exact same chord, e.g. double
+/// letters like "ll" and "ee". Unlike [FingerAlternation], it doesn't count
+/// consecutive chords that only share some fingers.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChordRepeats<const N: usize = 10> {
+  last_handstate: Option<HandsState<N>>,
+  repeats: u32,
+}
+
+impl ChordRepeats {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+impl<const N: usize> ChordRepeats<N> {
+  pub fn values(self) -> u32 {
+    self.repeats
+  }
+}
+
+impl<const N: usize> Default for ChordRepeats<N> {
+  fn default() -> Self {
+    Self {
+      last_handstate: None,
+      repeats: 0,
+    }
+  }
+}
+
+impl<const N: usize> Metric<N> for ChordRepeats<N> {
+  fn update_once(&mut self, handstate: &HandsState<N>) {
+    if self.last_handstate == Some(*handstate) {
+      self.repeats += 1;
+    }
+    self.last_handstate = Some(*handstate);
+  }
+
+  fn score(&self) -> f32 {
+    self.repeats as f32
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::counter("chord_repeats")
+  }
+}
+
+/// Measures skipgrams: presses of a finger two chords after its previous
+/// press with a chord not using it in between, e.g. `e`, `x`, `e` typed
+/// with the same finger in a row. Immediate repeats are counted by
+/// [FingerAlternation] instead.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Skipgrams<const N: usize = 10> {
+  last_handstates: [HandsState<N>; 2],
+  #[cfg_attr(feature = "serde", serde(with = "finger_counts"))]
+  skipgrams: [u32; N],
+}
+
+impl Skipgrams {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+impl<const N: usize> Skipgrams<N> {
+  pub fn values(self) -> [u32; N] {
+    self.skipgrams
+  }
+}
+
+impl<const N: usize> Default for Skipgrams<N> {
+  fn default() -> Self {
+    Self {
+      last_handstates: [HandsState::default(); 2],
+      skipgrams: [0; N],
+    }
+  }
+}
+
+impl<const N: usize> Metric<N> for Skipgrams<N> {
+  fn update_once(&mut self, handstate: &HandsState<N>) {
+    let [first, second] = &self.last_handstates;
+    for (i, sg) in self.skipgrams.iter_mut().enumerate() {
+      if first[i].is_pressed()
+        && !second[i].is_pressed()
+        && handstate[i].is_pressed()
+      {
+        *sg += 1;
+      }
+    }
+    self.last_handstates = [self.last_handstates[1], *handstate];
+  }
+
+  fn score(&self) -> f32 {
+    self.skipgrams.map(|v| v as f32).iter().sum()
+  }
+
+  fn breakdown(&self) -> Option<Breakdown> {
+    fingers_breakdown(self.skipgrams)
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::counter("skipgrams")
+  }
 }
 
 /// Measures hand alternation.
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
-pub struct HandAlternation {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HandAlternation<const N: usize = 10> {
   last_hands_used: [bool; 2],
   consecutive_presses: [u32; 2],
 }
 
 impl HandAlternation {
   pub fn new() -> Self {
-    Self {
-      last_hands_used: [false; 2],
-      consecutive_presses: [0; 2],
-    }
+    Self::default()
   }
+}
 
+impl<const N: usize> HandAlternation<N> {
   pub fn values(self) -> [u32; 2] {
     self.consecutive_presses
   }
 }
 
-impl Default for HandAlternation {
+impl<const N: usize> Default for HandAlternation<N> {
   fn default() -> Self {
-    Self::new()
+    Self {
+      last_hands_used: [false; 2],
+      consecutive_presses: [0; 2],
+    }
   }
 }
 
-impl Metric for HandAlternation {
-  fn update_once(&mut self, handstate: &HandsState) {
+impl<const N: usize> Metric<N> for HandAlternation<N> {
+  fn update_once(&mut self, handstate: &HandsState<N>) {
     for (cp, (last_hand_used, curr_hs)) in self
       .consecutive_presses
       .iter_mut()
       .zip(self.last_hands_used.iter_mut().zip(handstate.hand_iter()))
     {
-      let next_hand_used = curr_hs.iter().any(|fs| *fs == FingerState::Pressed);
+      let next_hand_used = curr_hs.contains(&FingerState::Pressed);
       if *last_hand_used && next_hand_used {
         *cp += 1;
       }
@@ -190,12 +857,633 @@ impl Metric for HandAlternation {
   fn score(&self) -> f32 {
     self.consecutive_presses.map(|v| v as f32).iter().sum()
   }
+
+  fn breakdown(&self) -> Option<Breakdown> {
+    Some(Breakdown::Hands(self.consecutive_presses.map(|v| v as f32)))
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::counter("hand_alternation")
+  }
 }
 
-/// Measures finger usage balance. Compares it to target balance ratio.
+/// Measures streaks of consecutive chords typed with the same hand, where
+/// every next chord of a streak costs more. By default, the second chord of
+/// a streak adds 1 to the score, the third adds 2 and so on up to `window`
+/// chords. Chords beyond the window cost as much as the last one, so
+/// streaks of three or four chords hurt much more than
+/// [HandAlternation]'s pairs.
 #[derive(Clone, PartialEq, Debug)]
-pub struct FingerBalance {
-  presses: [u32; 10],
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SameHandStreaks<const N: usize = 10> {
+  weights: Vec<f32>,
+  current_runs: [usize; 2],
+  penalties: [f32; 2],
+}
+
+impl SameHandStreaks {
+  /// Creates a metric that distinguishes streaks of up to `window` chords.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `window` is less than 2.
+  pub fn new(window: usize) -> Self {
+    Self::with_window(window)
+  }
+}
+
+impl<const N: usize> SameHandStreaks<N> {
+  fn with_window(window: usize) -> Self {
+    assert!(window >= 2, "window must span at least 2 chords");
+    Self {
+      weights: (1..window).map(|w| w as f32).collect(),
+      current_runs: [0; 2],
+      penalties: [0.0; 2],
+    }
+  }
+
+  /// Sets costs of the second, third and further chords of a streak. The
+  /// window is the number of weights plus one.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `weights` is empty.
+  pub fn set_weights(&mut self, weights: &[f32]) -> &mut Self {
+    assert!(!weights.is_empty(), "at least one weight is required");
+    self.weights = weights.to_vec();
+    self
+  }
+
+  /// Returns the longest streak distinguished by the metric.
+  pub fn window(&self) -> usize {
+    self.weights.len() + 1
+  }
+
+  /// Returns penalties of left and right hands.
+  pub fn values(self) -> [f32; 2] {
+    self.penalties
+  }
+}
+
+impl<const N: usize> Default for SameHandStreaks<N> {
+  /// Creates a metric with window of 3 chords.
+  fn default() -> Self {
+    Self::with_window(3)
+  }
+}
+
+impl<const N: usize> Metric<N> for SameHandStreaks<N> {
+  fn update_once(&mut self, handstate: &HandsState<N>) {
+    for ((run, penalty), hand) in self
+      .current_runs
+      .iter_mut()
+      .zip(self.penalties.iter_mut())
+      .zip(handstate.hand_iter())
+    {
+      if !hand.contains(&FingerState::Pressed) {
+        *run = 0;
+        continue;
+      }
+      *run += 1;
+      if *run >= 2 {
+        let idx = (*run - 2).min(self.weights.len() - 1);
+        *penalty += self.weights[idx];
+      }
+    }
+  }
+
+  fn score(&self) -> f32 {
+    self.penalties.iter().sum()
+  }
+
+  fn breakdown(&self) -> Option<Breakdown> {
+    Some(Breakdown::Hands(self.penalties))
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::counter("same_hand_streaks")
+  }
+}
+
+/// Measures redirects: one-hand sequences of three presses that change
+/// direction, e.g. index→ring→middle. Position of a hand in a chord is the
+/// mean index of its pressed fingers. Thumbs are ignored, so a chord that
+/// uses only a thumb of a hand interrupts that hand's sequence.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Redirects {
+  last_positions: [Option<f32>; 2],
+  /// Whether hands moved towards pinkies with the last press.
+  last_directions: [Option<bool>; 2],
+  redirects: [u32; 2],
+}
+
+impl Redirects {
+  pub fn new() -> Self {
+    Self {
+      last_positions: [None; 2],
+      last_directions: [None; 2],
+      redirects: [0; 2],
+    }
+  }
+
+  pub fn values(self) -> [u32; 2] {
+    self.redirects
+  }
+}
+
+impl Default for Redirects {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Metric for Redirects {
+  fn update_once(&mut self, handstate: &HandsState) {
+    let positions = [0..4, 6..10].map(|fingers| {
+      let (sum, count) = fingers
+        .filter(|&i| handstate[i].is_pressed())
+        .fold((0, 0), |(sum, count), i| (sum + i, count + 1));
+      (count > 0).then(|| sum as f32 / count as f32)
+    });
+    for (((rd, last_pos), last_dir), pos) in self
+      .redirects
+      .iter_mut()
+      .zip(self.last_positions.iter_mut())
+      .zip(self.last_directions.iter_mut())
+      .zip(positions)
+    {
+      let dir = last_pos
+        .zip(pos)
+        .and_then(|(lp, p)| p.partial_cmp(&lp))
+        .filter(|o| o.is_ne())
+        .map(|o| o.is_gt());
+      if let (Some(ld), Some(d)) = (*last_dir, dir) {
+        if ld != d {
+          *rd += 1;
+        }
+      }
+      *last_pos = pos;
+      *last_dir = dir;
+    }
+  }
+
+  fn score(&self) -> f32 {
+    self.redirects.map(|v| v as f32).iter().sum()
+  }
+
+  fn breakdown(&self) -> Option<Breakdown> {
+    Some(Breakdown::Hands(self.redirects.map(|v| v as f32)))
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::counter("redirects")
+  }
+}
+
+/// Measures distribution of consecutive same hand press runs. Unlike
+/// `HandAlternation`, long one-handed streaks are penalized more than
+/// occasional doubles: a run of `n` presses adds `(n - 1)²` to the score.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HandRuns<const N: usize = 10> {
+  current_runs: [u32; 2],
+  runs: [Vec<u32>; 2],
+}
+
+impl HandRuns {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+impl<const N: usize> HandRuns<N> {
+  /// Returns histograms of run lengths for left and right hands.
+  /// `n`-th element of a histogram is the number of runs of `n + 1` presses.
+  pub fn values(self) -> [Vec<u32>; 2] {
+    let mut runs = self.runs;
+    for (hist, run) in runs.iter_mut().zip(self.current_runs) {
+      Self::record_run(hist, run);
+    }
+    runs
+  }
+
+  fn record_run(hist: &mut Vec<u32>, run: u32) {
+    if run == 0 {
+      return;
+    }
+    let idx = run as usize - 1;
+    if hist.len() <= idx {
+      hist.resize(idx + 1, 0);
+    }
+    hist[idx] += 1;
+  }
+}
+
+impl<const N: usize> Default for HandRuns<N> {
+  fn default() -> Self {
+    Self {
+      current_runs: [0; 2],
+      runs: [Vec::new(), Vec::new()],
+    }
+  }
+}
+
+impl<const N: usize> Metric<N> for HandRuns<N> {
+  fn update_once(&mut self, handstate: &HandsState<N>) {
+    for ((run, hist), hand) in self
+      .current_runs
+      .iter_mut()
+      .zip(self.runs.iter_mut())
+      .zip(handstate.hand_iter())
+    {
+      if hand.contains(&FingerState::Pressed) {
+        *run += 1;
+      } else {
+        Self::record_run(hist, *run);
+        *run = 0;
+      }
+    }
+  }
+
+  fn score(&self) -> f32 {
+    self
+      .clone()
+      .values()
+      .iter()
+      .flat_map(|hist| hist.iter().enumerate())
+      .map(|(i, &count)| (i * i) as f32 * count as f32)
+      .sum()
+  }
+
+  fn breakdown(&self) -> Option<Breakdown> {
+    Some(Breakdown::Hands(self.clone().values().map(|hist| {
+      hist
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| (i * i) as f32 * count as f32)
+        .sum()
+    })))
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::counter("hand_runs")
+  }
+}
+
+/// Measures thumb usage. Thumbs are shared by space, enter and modifiers in
+/// Tenboard layouts, so overusing them hurts more than overusing other
+/// fingers. Score is the number of thumb presses that are either part of a
+/// chord of 3+ keys or repeat the thumb of the previous chord.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ThumbUsage {
+  presses: [u32; 2],
+  big_chord_presses: [u32; 2],
+  repeated_presses: [u32; 2],
+  last_pressed: [bool; 2],
+}
+
+impl ThumbUsage {
+  pub fn new() -> Self {
+    Self {
+      presses: [0; 2],
+      big_chord_presses: [0; 2],
+      repeated_presses: [0; 2],
+      last_pressed: [false; 2],
+    }
+  }
+
+  /// Returns numbers of left and right thumb presses.
+  pub fn values(self) -> [u32; 2] {
+    self.presses
+  }
+
+  /// Returns numbers of left and right thumb presses in chords of 3 or more
+  /// keys.
+  pub fn big_chord_presses(&self) -> [u32; 2] {
+    self.big_chord_presses
+  }
+
+  /// Returns numbers of left and right thumb presses right after a chord
+  /// with the same thumb.
+  pub fn repeated_presses(&self) -> [u32; 2] {
+    self.repeated_presses
+  }
+}
+
+impl Default for ThumbUsage {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Metric for ThumbUsage {
+  fn update_once(&mut self, handstate: &HandsState) {
+    let big_chord = handstate.count_pressed() >= 3;
+    for (thumb, i) in [4, 5].into_iter().enumerate() {
+      let pressed = handstate[i].is_pressed();
+      if pressed {
+        self.presses[thumb] += 1;
+        self.big_chord_presses[thumb] += u32::from(big_chord);
+        self.repeated_presses[thumb] += u32::from(self.last_pressed[thumb]);
+      }
+      self.last_pressed[thumb] = pressed;
+    }
+  }
+
+  fn score(&self) -> f32 {
+    (self.big_chord_presses.iter().sum::<u32>()
+      + self.repeated_presses.iter().sum::<u32>()) as f32
+  }
+
+  fn breakdown(&self) -> Option<Breakdown> {
+    Some(Breakdown::Hands(self.presses.map(|v| v as f32)))
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::counter("thumb_usage")
+  }
+}
+
+/// Measures usage of big chords of 3 or more keys, which are harder to
+/// press than small ones, so frequent chars are better kept away from
+/// them. Score is the number of typed big chords, breakdown is presses of
+/// fingers in them. Besides, it counts finger triples pressed together in
+/// big chords, e.g. a 4-key chord adds 4 triples.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BigChords<const N: usize = 10> {
+  min_size: usize,
+  chords: u32,
+  #[cfg_attr(feature = "serde", serde(with = "finger_counts"))]
+  presses: [u32; N],
+  #[cfg_attr(feature = "serde", serde(with = "chord_counts"))]
+  triples: HashMap<HandsState<N>, u32>,
+}
+
+impl BigChords {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+impl<const N: usize> BigChords<N> {
+  /// Sets the number of keys of a chord to be considered big. It's 3 by
+  /// default. Triples are only counted in chords of 3 or more keys.
+  pub fn set_min_size(&mut self, min_size: usize) -> &mut Self {
+    self.min_size = min_size;
+    self
+  }
+
+  /// Returns number of typed big chords.
+  pub fn values(self) -> u32 {
+    self.chords
+  }
+
+  /// Returns numbers of finger triples pressed together in big chords.
+  /// Triples are `HandsState`s of 3 pressed fingers.
+  pub fn triples(&self) -> &HashMap<HandsState<N>, u32> {
+    &self.triples
+  }
+
+  /// Returns up to `n` most frequent finger triples with their counts, the
+  /// most frequent first. Ties are ordered by triple.
+  pub fn top_triples(&self, n: usize) -> Vec<(HandsState<N>, u32)> {
+    let mut triples: Vec<_> =
+      self.triples.iter().map(|(&hs, &c)| (hs, c)).collect();
+    triples.sort_unstable_by_key(|(hs, c)| (Reverse(*c), hs.map(u32::from)));
+    triples.truncate(n);
+    triples
+  }
+}
+
+impl<const N: usize> Default for BigChords<N> {
+  fn default() -> Self {
+    Self {
+      min_size: 3,
+      chords: 0,
+      presses: [0; N],
+      triples: HashMap::new(),
+    }
+  }
+}
+
+impl<const N: usize> Metric<N> for BigChords<N> {
+  fn update_once(&mut self, handstate: &HandsState<N>) {
+    if handstate.count_pressed() < self.min_size.max(1) {
+      return;
+    }
+    self.chords += 1;
+    let pressed: Vec<_> =
+      (0..N).filter(|&i| handstate[i].is_pressed()).collect();
+    for &i in &pressed {
+      self.presses[i] += 1;
+    }
+    for (a, &i) in pressed.iter().enumerate() {
+      for (b, &j) in pressed.iter().enumerate().skip(a + 1) {
+        for &k in &pressed[b + 1..] {
+          let mut triple = HandsState::default();
+          for finger in [i, j, k] {
+            triple[finger] = FingerState::Pressed;
+          }
+          *self.triples.entry(triple).or_default() += 1;
+        }
+      }
+    }
+  }
+
+  fn score(&self) -> f32 {
+    self.chords as f32
+  }
+
+  fn breakdown(&self) -> Option<Breakdown> {
+    fingers_breakdown(self.presses)
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::counter("big_chords")
+  }
+}
+
+/// Counts shift presses according to a [ShiftPolicy], so layouts typing
+/// uppercase chars differently are compared consistently. Use
+/// [Shift::shift_policy](super::shift::Shift::shift_policy) of the layout.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ShiftPresses<const N: usize = 10> {
+  policy: ShiftPolicy<N>,
+  presses: u32,
+}
+
+impl<const N: usize> ShiftPresses<N> {
+  pub fn new(policy: ShiftPolicy<N>) -> Self {
+    Self { policy, presses: 0 }
+  }
+
+  pub fn values(self) -> u32 {
+    self.presses
+  }
+}
+
+impl<const N: usize> Metric<N> for ShiftPresses<N> {
+  fn update_once(&mut self, handstate: &HandsState<N>) {
+    self.presses += u32::from(self.policy.is_shift_press(handstate));
+  }
+
+  fn score(&self) -> f32 {
+    self.presses as f32
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::counter("shift_presses")
+  }
+}
+
+/// Class of a window of three consecutive presses. The terms follow the
+/// ones used by keyboard layout analyzers like oxeylyzer.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TrigramClass {
+  /// Two consecutive presses share a finger.
+  SameFinger,
+  /// Presses alternate hands: left, right, left or vice versa.
+  Alternation,
+  /// Two presses with one hand, one with the other.
+  Roll,
+  /// Three presses with one hand moving in one direction.
+  OneHand,
+  /// Three presses with one hand changing direction.
+  Redirect,
+  /// Presses with both hands at once or that don't fit other classes.
+  Other,
+}
+
+impl TrigramClass {
+  pub const ALL: [TrigramClass; 6] = [
+    TrigramClass::SameFinger,
+    TrigramClass::Alternation,
+    TrigramClass::Roll,
+    TrigramClass::OneHand,
+    TrigramClass::Redirect,
+    TrigramClass::Other,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      TrigramClass::SameFinger => "same_finger",
+      TrigramClass::Alternation => "alternation",
+      TrigramClass::Roll => "roll",
+      TrigramClass::OneHand => "one_hand",
+      TrigramClass::Redirect => "redirect",
+      TrigramClass::Other => "other",
+    }
+  }
+
+  /// Classifies presses `trigram`.
+  pub fn of(trigram: [&HandsState; 3]) -> Self {
+    if trigram[0].overlaps(trigram[1]) || trigram[1].overlaps(trigram[2]) {
+      return TrigramClass::SameFinger;
+    }
+    let hands = trigram.map(|hs| {
+      let mut hands = hs.hand_iter().map(|h| h.contains(&FingerState::Pressed));
+      match (hands.next(), hands.next()) {
+        (Some(true), Some(false)) => Some(0),
+        (Some(false), Some(true)) => Some(1),
+        _ => None,
+      }
+    });
+    let [Some(h0), Some(h1), Some(h2)] = hands else {
+      return TrigramClass::Other;
+    };
+    if h0 != h1 && h1 != h2 {
+      return TrigramClass::Alternation;
+    }
+    if h0 != h1 || h1 != h2 {
+      return TrigramClass::Roll;
+    }
+    let [p0, p1, p2] = trigram.map(|hs| {
+      let (sum, count) = (0..10)
+        .filter(|&i| hs[i].is_pressed())
+        .fold((0, 0), |(sum, count), i| (sum + i, count + 1));
+      sum as f32 / count as f32
+    });
+    match (p1.partial_cmp(&p0), p2.partial_cmp(&p1)) {
+      (Some(d1), Some(d2)) if d1.is_eq() || d2.is_eq() => TrigramClass::Other,
+      (Some(d1), Some(d2)) if d1 == d2 => TrigramClass::OneHand,
+      (Some(_), Some(_)) => TrigramClass::Redirect,
+      _ => TrigramClass::Other,
+    }
+  }
+}
+
+/// Measures distribution of trigram classes over every window of three
+/// consecutive presses. Score is the number of same finger and redirect
+/// trigrams.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Trigrams {
+  counts: [u32; 6],
+  last_handstates: [Option<HandsState>; 2],
+}
+
+impl Trigrams {
+  pub fn new() -> Self {
+    Self {
+      counts: [0; 6],
+      last_handstates: [None; 2],
+    }
+  }
+
+  /// Returns numbers of trigrams of each class in [TrigramClass::ALL]
+  /// order.
+  pub fn values(self) -> [u32; 6] {
+    self.counts
+  }
+
+  /// Returns number of trigrams of `class`.
+  pub fn count(&self, class: TrigramClass) -> u32 {
+    self.counts[class as usize]
+  }
+
+  /// Returns percentages of trigrams of each class in [TrigramClass::ALL]
+  /// order.
+  pub fn percentages(&self) -> [f32; 6] {
+    let total = self.counts.iter().sum::<u32>().max(1) as f32;
+    self.counts.map(|c| c as f32 / total * 100.0)
+  }
+}
+
+impl Default for Trigrams {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Metric for Trigrams {
+  fn update_once(&mut self, handstate: &HandsState) {
+    if let [Some(first), Some(second)] = &self.last_handstates {
+      let class = TrigramClass::of([first, second, handstate]);
+      self.counts[class as usize] += 1;
+    }
+    self.last_handstates = [self.last_handstates[1], Some(*handstate)];
+  }
+
+  fn score(&self) -> f32 {
+    (self.count(TrigramClass::SameFinger) + self.count(TrigramClass::Redirect))
+      as f32
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::counter("trigrams")
+  }
+}
+
+/// Measures finger usage balance. Compares it to target balance ratio.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FingerBalance {
+  presses: [u32; 10],
   target_ratio: [f32; 10],
 }
 
@@ -206,19 +1494,84 @@ impl FingerBalance {
     self
   }
 
-  pub fn new() -> Self {
-    Self {
-      presses: [0; 10],
-      target_ratio: [0.1; 10],
-    }
+  pub fn new() -> Self {
+    Self {
+      presses: [0; 10],
+      target_ratio: [0.1; 10],
+    }
+  }
+
+  pub fn new_with_ratio(target_ratio: [f32; 10]) -> Self {
+    let mut fb = Self::new();
+    fb.set_ratio(target_ratio);
+    fb
+  }
+
+  /// Creates a metric where target share of every finger is proportional
+  /// to its strength in `profile`.
+  pub fn new_with_profile(profile: &FingerProfile) -> Self {
+    Self::new_with_ratio(profile.shares())
+  }
+
+  /// Creates a metric targeting approximate finger loads of Colemak-DH on
+  /// English text with space on thumbs, in percents: 8, 9, 12, 15, 6 for
+  /// the left pinky to thumb, mirrored for the right hand.
+  pub fn colemak_dh_targets() -> Self {
+    Self::new_with_percentages([
+      8.0, 9.0, 12.0, 15.0, 6.0, 6.0, 15.0, 12.0, 9.0, 8.0,
+    ])
+  }
+
+  /// Creates a metric targeting equal load of all fingers but pinkies,
+  /// which shouldn't be used at all.
+  pub fn equal_no_pinkies() -> Self {
+    Self::new_with_ratio([0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0])
+  }
+
+  /// Creates a metric targeting equal load of all fingers but thumbs,
+  /// which shouldn't be used at all.
+  pub fn equal_no_thumbs() -> Self {
+    Self::new_with_ratio([1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0])
   }
 
-  pub fn new_with_ratio(target_ratio: [f32; 10]) -> Self {
+  /// Same as [FingerBalance::try_set_percentages].
+  ///
+  /// # Panics
+  ///
+  /// Panics if percentages are invalid.
+  pub fn new_with_percentages(percentages: [f32; 10]) -> Self {
     let mut fb = Self::new();
-    fb.set_ratio(target_ratio);
+    fb.set_percentages(percentages);
     fb
   }
 
+  /// Sets target shares of fingers in percents. Unlike
+  /// [FingerBalance::set_ratio], it returns an error if any percentage is
+  /// negative or they don't sum up to 100.
+  pub fn try_set_percentages(
+    &mut self,
+    percentages: [f32; 10],
+  ) -> Result<&mut Self, BadPercentages> {
+    check_percentages(&percentages)?;
+    Ok(self.set_ratio(percentages))
+  }
+
+  /// Same as [FingerBalance::try_set_percentages].
+  ///
+  /// # Panics
+  ///
+  /// Panics if percentages are invalid.
+  pub fn set_percentages(&mut self, percentages: [f32; 10]) -> &mut Self {
+    self
+      .try_set_percentages(percentages)
+      .unwrap_or_else(|e| panic!("{e}"))
+  }
+
+  /// Returns target shares of fingers summing to 1.
+  pub fn target_ratio(&self) -> [f32; 10] {
+    self.target_ratio
+  }
+
   pub fn values(self) -> [f32; 10] {
     let total_presses =
       (self.presses.iter().sum::<u32>() as usize + self.presses.len()) as f32;
@@ -249,6 +1602,17 @@ impl Metric for FingerBalance {
       .map(|(a, b)| (a - b).abs())
       .sum()
   }
+
+  fn breakdown(&self) -> Option<Breakdown> {
+    Some(Breakdown::Fingers(self.clone().values()))
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo {
+      max: Some(2.0),
+      ..MetricInfo::counter("finger_balance")
+    }
+  }
 }
 
 impl From<FingerUsage> for FingerBalance {
@@ -260,8 +1624,93 @@ impl From<FingerUsage> for FingerBalance {
   }
 }
 
+/// Measures load of the busiest finger. Score is the maximum share of
+/// presses made by a single finger, so minimizing it improves the worst
+/// finger rather than the average imbalance like `FingerBalance` does.
+/// Shares can be weighted by finger strengths: a finger twice as strong as
+/// the average may take twice as much load.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MaxFingerLoad {
+  presses: [u32; 10],
+  weights: [f32; 10],
+}
+
+impl MaxFingerLoad {
+  pub fn new() -> Self {
+    Self {
+      presses: [0; 10],
+      weights: [1.0; 10],
+    }
+  }
+
+  pub fn new_with_strengths(strengths: [f32; 10]) -> Self {
+    let mut ml = Self::new();
+    ml.set_strengths(strengths);
+    ml
+  }
+
+  pub fn new_with_profile(profile: &FingerProfile) -> Self {
+    Self::new_with_strengths(profile.strengths())
+  }
+
+  /// Sets relative finger strengths. Only their ratios matter.
+  ///
+  /// # Panics
+  ///
+  /// Panics if any strength is not positive.
+  pub fn set_strengths(&mut self, strengths: [f32; 10]) -> &mut Self {
+    assert!(
+      strengths.iter().all(|&s| s > 0.0),
+      "finger strengths must be positive"
+    );
+    let mean = strengths.iter().sum::<f32>() / strengths.len() as f32;
+    self.weights = strengths.map(|s| mean / s);
+    self
+  }
+
+  /// Returns shares of presses made by each finger.
+  pub fn values(self) -> [f32; 10] {
+    let total = self.presses.iter().sum::<u32>().max(1) as f32;
+    self.presses.map(|p| p as f32 / total)
+  }
+}
+
+impl Default for MaxFingerLoad {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Metric for MaxFingerLoad {
+  fn update_once(&mut self, handstate: &HandsState) {
+    for (fc, fs) in self.presses.iter_mut().zip(handstate.iter()) {
+      *fc += u32::from(*fs);
+    }
+  }
+
+  fn score(&self) -> f32 {
+    self
+      .clone()
+      .values()
+      .iter()
+      .zip(self.weights)
+      .map(|(share, w)| share * w)
+      .fold(0.0, f32::max)
+  }
+
+  fn breakdown(&self) -> Option<Breakdown> {
+    Some(Breakdown::Fingers(self.clone().values()))
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::counter("max_finger_load")
+  }
+}
+
 /// Measures hand usage balance. Compares it to target balance ratio.
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct HandBalance {
   presses: [u32; 2],
   target_ratio: [f32; 2],
@@ -287,6 +1736,54 @@ impl HandBalance {
     fb
   }
 
+  /// Creates a metric where the left hand takes 55% of load, e.g. for
+  /// left-handed typists.
+  pub fn left_dominant() -> Self {
+    Self::new_with_percentages([55.0, 45.0])
+  }
+
+  /// Creates a metric where the right hand takes 55% of load.
+  pub fn right_dominant() -> Self {
+    Self::new_with_percentages([45.0, 55.0])
+  }
+
+  /// Same as [HandBalance::try_set_percentages].
+  ///
+  /// # Panics
+  ///
+  /// Panics if percentages are invalid.
+  pub fn new_with_percentages(percentages: [f32; 2]) -> Self {
+    let mut hb = Self::new();
+    hb.set_percentages(percentages);
+    hb
+  }
+
+  /// Sets target shares of hands in percents. Returns an error if any
+  /// percentage is negative or they don't sum up to 100.
+  pub fn try_set_percentages(
+    &mut self,
+    percentages: [f32; 2],
+  ) -> Result<&mut Self, BadPercentages> {
+    check_percentages(&percentages)?;
+    Ok(self.set_ratio(percentages))
+  }
+
+  /// Same as [HandBalance::try_set_percentages].
+  ///
+  /// # Panics
+  ///
+  /// Panics if percentages are invalid.
+  pub fn set_percentages(&mut self, percentages: [f32; 2]) -> &mut Self {
+    self
+      .try_set_percentages(percentages)
+      .unwrap_or_else(|e| panic!("{e}"))
+  }
+
+  /// Returns target shares of hands summing to 1.
+  pub fn target_ratio(&self) -> [f32; 2] {
+    self.target_ratio
+  }
+
   pub fn values(self) -> [f32; 2] {
     let total_presses =
       (self.presses.iter().sum::<u32>() as usize + self.presses.len()) as f32;
@@ -317,6 +1814,45 @@ impl Metric for HandBalance {
       .map(|(a, b)| (a - b).abs())
       .sum()
   }
+
+  fn breakdown(&self) -> Option<Breakdown> {
+    Some(Breakdown::Hands(self.clone().values()))
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo {
+      max: Some(2.0),
+      ..MetricInfo::counter("hand_balance")
+    }
+  }
+}
+
+/// This error means that target percentages of [FingerBalance] or
+/// [HandBalance] are negative or don't sum up to 100.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BadPercentages {
+  pub percentages: Vec<f32>,
+}
+
+impl Display for BadPercentages {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(
+      f,
+      "target percentages {:?} must be non-negative and sum up to 100",
+      self.percentages
+    )
+  }
+}
+
+fn check_percentages(percentages: &[f32]) -> Result<(), BadPercentages> {
+  let sum = percentages.iter().sum::<f32>();
+  if percentages.iter().all(|&p| p >= 0.0) && (sum - 100.0).abs() < 1e-3 {
+    Ok(())
+  } else {
+    Err(BadPercentages {
+      percentages: percentages.to_vec(),
+    })
+  }
 }
 
 impl From<HandUsage> for HandBalance {
@@ -343,43 +1879,300 @@ impl From<FingerBalance> for HandBalance {
   }
 }
 
+/// Measures how evenly typing spreads over chords. Score is the Shannon
+/// entropy of the distribution of typed chords in bits. Unlike other
+/// metrics, a higher score means more even usage, so the metric is meant
+/// for analysis rather than optimization.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChordEntropy<const N: usize = 10> {
+  #[cfg_attr(feature = "serde", serde(with = "chord_counts"))]
+  counts: HashMap<HandsState<N>, u32>,
+}
+
+/// Serializes chord counts as a list of pairs, since chords can't be keys
+/// of JSON objects.
+#[cfg(feature = "serde")]
+mod chord_counts {
+  use alloc::vec::Vec;
+
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+  use crate::{compat::HashMap, keyboard::hands::HandsState};
+
+  pub fn serialize<S: Serializer, const N: usize>(
+    counts: &HashMap<HandsState<N>, u32>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    let mut pairs: Vec<_> = counts.iter().collect();
+    pairs.sort_unstable_by_key(|(hs, _)| hs.map(u32::from));
+    pairs.serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+    deserializer: D,
+  ) -> Result<HashMap<HandsState<N>, u32>, D::Error> {
+    Ok(
+      Vec::<(HandsState<N>, u32)>::deserialize(deserializer)?
+        .into_iter()
+        .collect(),
+    )
+  }
+}
+
+/// Serializes per-finger counts of any number of fingers as a list.
+#[cfg(feature = "serde")]
+mod finger_counts {
+  use alloc::{format, vec::Vec};
+
+  use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+  pub fn serialize<S: Serializer, const N: usize>(
+    counts: &[u32; N],
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    counts.as_slice().serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+    deserializer: D,
+  ) -> Result<[u32; N], D::Error> {
+    let counts = Vec::<u32>::deserialize(deserializer)?;
+    let len = counts.len();
+    counts.try_into().map_err(|_| {
+      de::Error::invalid_length(len, &format!("{N} finger counts").as_str())
+    })
+  }
+}
+
+impl ChordEntropy {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+impl<const N: usize> ChordEntropy<N> {
+  /// Returns numbers of presses of every typed chord.
+  pub fn values(self) -> HashMap<HandsState<N>, u32> {
+    self.counts
+  }
+
+  /// Returns entropy divided by its maximum for the number of distinct
+  /// typed chords: 1 means every typed chord was typed equally often.
+  pub fn uniformity(&self) -> f32 {
+    match self.counts.len() {
+      0 | 1 => 1.0,
+      n => Metric::<N>::score(self) / compat::log2(n as f32),
+    }
+  }
+}
+
+impl<const N: usize> Default for ChordEntropy<N> {
+  fn default() -> Self {
+    Self {
+      counts: HashMap::new(),
+    }
+  }
+}
+
+impl<const N: usize> Metric<N> for ChordEntropy<N> {
+  fn update_once(&mut self, handstate: &HandsState<N>) {
+    *self.counts.entry(*handstate).or_default() += 1;
+  }
+
+  fn score(&self) -> f32 {
+    let total = self.counts.values().sum::<u32>() as f32;
+    self
+      .counts
+      .values()
+      .map(|&c| c as f32 / total)
+      .map(|p| -p * compat::log2(p))
+      .sum()
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo {
+      lower_is_better: Some(false),
+      ..MetricInfo::counter("chord_entropy")
+    }
+  }
+}
+
+impl<const N: usize> From<ChordFrequency<N>> for ChordEntropy<N> {
+  fn from(value: ChordFrequency<N>) -> Self {
+    Self {
+      counts: value.counts,
+    }
+  }
+}
+
+/// Counts presses of every distinct chord, so the most frequent chords,
+/// which must be the most comfortable ones, can be listed with
+/// [ChordFrequency::top]. Score is the number of distinct typed chords.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChordFrequency<const N: usize = 10> {
+  #[cfg_attr(feature = "serde", serde(with = "chord_counts"))]
+  counts: HashMap<HandsState<N>, u32>,
+}
+
+impl ChordFrequency {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+impl<const N: usize> ChordFrequency<N> {
+  /// Returns number of presses of `chord`.
+  pub fn count(&self, chord: &HandsState<N>) -> u32 {
+    self.counts.get(chord).copied().unwrap_or(0)
+  }
+
+  /// Returns up to `n` most frequent chords with their numbers of presses,
+  /// the most frequent first. Ties are ordered by chord.
+  pub fn top(&self, n: usize) -> Vec<(HandsState<N>, u32)> {
+    let mut chords: Vec<_> =
+      self.counts.iter().map(|(&hs, &c)| (hs, c)).collect();
+    chords.sort_unstable_by_key(|(hs, c)| (Reverse(*c), hs.map(u32::from)));
+    chords.truncate(n);
+    chords
+  }
+
+  /// Returns numbers of presses of every typed chord.
+  pub fn values(self) -> HashMap<HandsState<N>, u32> {
+    self.counts
+  }
+}
+
+impl<const N: usize> Default for ChordFrequency<N> {
+  fn default() -> Self {
+    Self {
+      counts: HashMap::new(),
+    }
+  }
+}
+
+impl<const N: usize> Metric<N> for ChordFrequency<N> {
+  fn update_once(&mut self, handstate: &HandsState<N>) {
+    *self.counts.entry(*handstate).or_default() += 1;
+  }
+
+  fn score(&self) -> f32 {
+    self.counts.len() as f32
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::total("chord_frequency")
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
-  use crate::keyboard::{Keyboard, NoSuchChar};
-
-  struct TestKeyboard {}
-
-  impl TestKeyboard {
-    fn try_type_char(&self, ch: char) -> Result<HandsState, NoSuchChar> {
-      match ch {
-        'a' => Ok([1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into()),
-        'b' => Ok([0, 1, 0, 0, 0, 0, 0, 0, 0, 0].into()),
-        'c' => Ok([0, 0, 1, 0, 0, 0, 0, 0, 0, 0].into()),
-        'd' => Ok([0, 0, 0, 0, 0, 0, 0, 1, 0, 0].into()),
-        'e' => Ok([0, 0, 0, 0, 0, 0, 0, 0, 1, 0].into()),
-        'f' => Ok([0, 0, 0, 0, 0, 0, 0, 0, 0, 1].into()),
-        'p' => Ok([0, 0, 0, 1, 0, 0, 0, 0, 0, 0].into()),
-        'q' => Ok([0, 0, 0, 0, 1, 0, 0, 0, 0, 0].into()),
-        'r' => Ok([0, 0, 0, 0, 0, 1, 0, 0, 0, 0].into()),
-        's' => Ok([0, 0, 0, 0, 0, 0, 1, 0, 0, 0].into()),
-        _ => Err(NoSuchChar { ch }),
-      }
-    }
+  use crate::{keyboard::Keyboard, testing::SyntheticKeyboard};
+
+  fn test_keyboard() -> SyntheticKeyboard {
+    SyntheticKeyboard::new_one_finger("abcpqrsdef")
   }
 
-  impl Keyboard for TestKeyboard {
-    fn try_type_chars(
-      &self,
-      chars: impl Iterator<Item = char>,
-    ) -> Result<Vec<HandsState>, NoSuchChar> {
-      chars.map(|ch| self.try_type_char(ch)).collect()
-    }
+  #[test]
+  fn test_thumb_usage() {
+    let kb = test_keyboard()
+      .with_chord('x', [1, 1, 0, 0, 1, 0, 0, 0, 0, 0])
+      .with_chord('y', [0, 0, 0, 0, 1, 1, 0, 0, 0, 0]);
+    let tu = ThumbUsage::new().updated(&kb.type_chars("qqaxyrr".chars()));
+    assert_eq!(tu.big_chord_presses(), [1, 0]);
+    assert_eq!(tu.repeated_presses(), [2, 2]);
+    assert_eq!(tu.score(), 5.0);
+    assert_eq!(tu.values(), [4, 3]);
+  }
+
+  #[test]
+  fn test_big_chords() {
+    let kb = test_keyboard()
+      .with_chord('x', [1, 1, 1, 0, 0, 0, 0, 0, 0, 0])
+      .with_chord('y', [1, 1, 1, 1, 0, 0, 0, 0, 0, 0])
+      .with_chord('z', [1, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+    let hss = kb.type_chars("axyzxa".chars());
+    let bc = BigChords::new().updated(&hss);
+    assert_eq!(bc.score(), 3.0);
+    assert_eq!(
+      bc.breakdown(),
+      Some(Breakdown::Fingers([
+        3.0, 3.0, 3.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0
+      ]))
+    );
+    assert_eq!(bc.triples().len(), 4);
+    assert_eq!(bc.top_triples(1), [(hss[1], 3)]);
+    assert_eq!(bc.values(), 3);
+
+    let mut bc = BigChords::new();
+    bc.set_min_size(2);
+    assert_eq!(bc.updated(&hss).score(), 4.0);
+  }
+
+  #[test]
+  fn test_shift_presses() {
+    let kb = test_keyboard()
+      .with_chord('A', [1, 0, 0, 0, 1, 0, 0, 0, 0, 0])
+      .with_chord(' ', [0, 0, 0, 0, 1, 0, 0, 0, 0, 0]);
+    let handstates = kb.type_chars("Aa A".chars());
+    let policy = ShiftPolicy::Modifier(HandsState::left_thumb());
+    let sp = ShiftPresses::new(policy).updated(&handstates);
+    assert_eq!(sp.score(), 2.0);
+    let sp = ShiftPresses::new(ShiftPolicy::Dedicated).updated(&handstates);
+    assert_eq!(sp.values(), 0);
+  }
+
+  #[test]
+  fn test_max_finger_load() {
+    let kb = test_keyboard();
+    let handstates = kb.type_chars("aaabcd".chars());
+    let ml = MaxFingerLoad::new().updated(&handstates);
+    assert_eq!(ml.score(), 0.5);
+    let mut strengths = [1.0; 10];
+    strengths[0] = 2.0;
+    strengths[7] = 0.25;
+    let ml = MaxFingerLoad::new_with_strengths(strengths).updated(&handstates);
+    // mean strength is 1.025, so 'd' finger's share weighs 4.1 times more
+    assert!((ml.score() - 4.1 / 6.0).abs() < 1e-6);
+    assert_eq!(ml.values()[0], 0.5);
+    let ml = MaxFingerLoad::new_with_profile(&strengths.try_into().unwrap());
+    assert!((ml.updated(&handstates).score() - 4.1 / 6.0).abs() < 1e-6);
+  }
+
+  #[test]
+  #[should_panic(expected = "finger strengths must be positive")]
+  fn test_max_finger_load_zero_strength() {
+    let mut strengths = [1.0; 10];
+    strengths[4] = 0.0;
+    MaxFingerLoad::new_with_strengths(strengths);
+  }
+
+  #[test]
+  fn test_trigrams() {
+    let kb = test_keyboard().with_chord('x', [1, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    let classify = |text: &str| {
+      let hs = kb.type_chars(text.chars());
+      TrigramClass::of([&hs[0], &hs[1], &hs[2]])
+    };
+    assert_eq!(classify("aab"), TrigramClass::SameFinger);
+    assert_eq!(classify("ada"), TrigramClass::Alternation);
+    assert_eq!(classify("abd"), TrigramClass::Roll);
+    assert_eq!(classify("abc"), TrigramClass::OneHand);
+    assert_eq!(classify("acb"), TrigramClass::Redirect);
+    assert_eq!(classify("axb"), TrigramClass::SameFinger);
+    assert_eq!(classify("bxc"), TrigramClass::Other);
+
+    let tg = Trigrams::new().updated(&kb.type_chars("adaabc".chars()));
+    assert_eq!(tg.clone().values(), [2, 1, 0, 1, 0, 0]);
+    assert_eq!(tg.percentages()[TrigramClass::SameFinger as usize], 50.0);
+    assert_eq!(tg.score(), 2.0);
   }
 
   #[test]
   fn test_finger_usage() {
-    let kb = TestKeyboard {};
+    let kb = test_keyboard();
     let text = "abcdefadab";
     let fu = FingerUsage::new().updated(&kb.type_chars(text.chars()));
     assert_eq!(fu.presses, [3, 2, 1, 0, 0, 0, 0, 2, 1, 1]);
@@ -388,7 +2181,7 @@ mod tests {
 
   #[test]
   fn test_hand_usage() {
-    let kb = TestKeyboard {};
+    let kb = test_keyboard();
     let text = "abcdefadab";
     let hu = HandUsage::new().updated(&kb.type_chars(text.chars()));
     assert_eq!(hu.presses, [6, 4]);
@@ -400,9 +2193,30 @@ mod tests {
     assert_eq!(hu.score(), 10.0);
   }
 
+  #[test]
+  fn test_heatmap() {
+    let kb = test_keyboard();
+    let text = "abcdefadab";
+    let fu = FingerUsage::new().updated(&kb.type_chars(text.chars()));
+    let heatmap = fu.heatmap();
+    assert_eq!(heatmap.values()[0], 1.0);
+    assert_eq!(heatmap.values()[3], 0.0);
+    assert_eq!(heatmap.values()[7], 2.0 / 3.0);
+    assert_eq!(FingerUsage::new().heatmap().values(), [0.0; 10]);
+
+    let bars = heatmap.to_ansi_bars(3);
+    let lines: Vec<_> = bars.lines().collect();
+    assert_eq!(lines.len(), 10);
+    assert_eq!(lines[0], "LP \x1b[38;2;255;0;0m███\x1b[0m 1.00");
+    assert_eq!(lines[3], "LI \x1b[38;2;0;255;0m   \x1b[0m 0.00");
+
+    let hu = HandUsage::from(fu);
+    assert_eq!(hu.heatmap(), Breakdown::Hands([1.0, 4.0 / 6.0]));
+  }
+
   #[test]
   fn test_finger_alternation() {
-    let kb = TestKeyboard {};
+    let kb = test_keyboard();
     let text = "abcdef";
     let fa = FingerAlternation::new().updated(&kb.type_chars(text.chars()));
     assert_eq!(fa.consecutive_presses, [0; 10]);
@@ -414,9 +2228,25 @@ mod tests {
     assert_eq!(fa.score(), 6.0);
   }
 
+  #[test]
+  fn test_chord_repeats() {
+    let kb = test_keyboard().with_chord('x', [1, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+    let text = "aaxbbbax";
+    let cr = ChordRepeats::new().updated(&kb.type_chars(text.chars()));
+    assert_eq!(cr.score(), 3.0);
+    let fa = FingerAlternation::new().updated(&kb.type_chars(text.chars()));
+    assert_eq!(fa.score(), 6.0);
+    assert_eq!(
+      ChordRepeats::new()
+        .updated(&[HandsState::default()])
+        .values(),
+      0
+    );
+  }
+
   #[test]
   fn test_hand_alternation() {
-    let kb = TestKeyboard {};
+    let kb = test_keyboard();
     let text = "adbecf";
     let ha = HandAlternation::new().updated(&kb.type_chars(text.chars()));
     assert_eq!(ha.consecutive_presses, [0; 2]);
@@ -428,13 +2258,279 @@ mod tests {
     assert_eq!(ha.score(), 7.0);
   }
 
+  #[test]
+  fn test_redirects() {
+    let kb = test_keyboard();
+    let text = "pcbsde";
+    let rd = Redirects::new().updated(&kb.type_chars(text.chars()));
+    assert_eq!(rd.redirects, [0; 2]);
+    assert_eq!(rd.score(), 0.0);
+
+    let text = "pbcsedpbqcaba";
+    let rd = Redirects::new().updated(&kb.type_chars(text.chars()));
+    assert_eq!(rd.redirects, [3, 1]);
+    assert_eq!(rd.score(), 4.0);
+  }
+
+  #[test]
+  fn test_hand_runs() {
+    let kb = test_keyboard();
+    let text = "adbecf";
+    let hr = HandRuns::new().updated(&kb.type_chars(text.chars()));
+    assert_eq!(hr.score(), 0.0);
+    assert_eq!(hr.values(), [vec![3], vec![3]]);
+
+    let text = "abcdaabbd";
+    let hr = HandRuns::new().updated(&kb.type_chars(text.chars()));
+    assert_eq!(hr.score(), 4.0 + 9.0);
+    assert_eq!(hr.values(), [vec![0, 0, 1, 1], vec![2]]);
+  }
+
+  #[test]
+  fn test_breakdown() {
+    let kb = test_keyboard();
+    let hss = kb.type_chars("aacffeddaaaaba".chars());
+    let fa = FingerAlternation::new().updated(&hss);
+    let breakdown = fa.breakdown().unwrap();
+    assert_eq!(breakdown.values().iter().sum::<f32>(), fa.score());
+    assert_eq!(breakdown.labeled().next(), Some(("LP", 4.0)));
+    let pp = FingerAlternation::new().per_press().updated(&hss);
+    let breakdown = pp.breakdown().unwrap();
+    assert_eq!(breakdown.values()[0], 4.0 / 14.0);
+
+    let hr = HandRuns::new().updated(&kb.type_chars("abcdaabbd".chars()));
+    assert_eq!(hr.breakdown(), Some(Breakdown::Hands([13.0, 0.0])));
+    assert_eq!(hr.breakdown().unwrap().labels(), HAND_NAMES);
+    assert_eq!(Trigrams::new().breakdown(), None);
+  }
+
+  #[test]
+  fn test_metric_info() {
+    assert_eq!(
+      FingerAlternation::new().info(),
+      MetricInfo::counter("finger_alternation")
+    );
+    let info = FingerBalance::new().info();
+    assert_eq!((info.min, info.max), (Some(0.0), Some(2.0)));
+    assert_eq!(info.normalize(0.5), Some(0.25));
+    let info = FingerBalance::new().per_press().info();
+    assert_eq!(
+      (info.name, info.min, info.max),
+      ("finger_balance", Some(0.0), None)
+    );
+    assert_eq!(info.normalize(0.5), None);
+    assert_eq!(ChordEntropy::new().info().lower_is_better, Some(false));
+    assert_eq!(FingerUsage::new().info().lower_is_better, None);
+    let info = MetricInfo {
+      lower_is_better: Some(false),
+      max: Some(4.0),
+      ..MetricInfo::counter("entropy")
+    };
+    assert_eq!(info.normalize(1.0), Some(0.75));
+    assert_eq!(info.normalize(5.0), Some(0.0));
+    let metric = FnMetric::new(0, |_: &mut u32, _: &HandsState| {}, |_| 0.0);
+    assert_eq!(metric.info(), MetricInfo::new("FnMetric"));
+  }
+
+  #[test]
+  fn test_per_press() {
+    let kb = test_keyboard();
+    let text = "aacffeddaaaaba";
+    let fa = FingerAlternation::new()
+      .per_press()
+      .updated(&kb.type_chars(text.chars()));
+    assert_eq!(fa.presses(), 14);
+    assert_eq!(fa.raw_score(), 6.0);
+    assert_eq!(fa.score(), 6.0 / 14.0);
+    assert_eq!(fa.into_inner().values(), [4, 0, 0, 0, 0, 0, 0, 1, 0, 1]);
+
+    let fa = FingerAlternation::new()
+      .per_press()
+      .updated(&kb.type_chars(text.repeat(3).chars()));
+    assert_eq!(fa.score(), 20.0 / 42.0);
+    assert_eq!(FingerAlternation::new().per_press().score(), 0.0);
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_state_serialization() -> Result<(), serde_json::Error> {
+    fn assert_resumable<M>(metric: M, handstates: &[HandsState])
+    where
+      M: Metric + Clone + Serialize + for<'de> Deserialize<'de>,
+    {
+      let (first, second) = handstates.split_at(handstates.len() / 2);
+      let whole = metric.clone().updated(handstates);
+      let json = serde_json::to_string(&metric.updated(first)).unwrap();
+      let resumed = serde_json::from_str::<M>(&json).unwrap().updated(second);
+      assert!((resumed.score() - whole.score()).abs() < 1e-5);
+    }
+
+    let kb = test_keyboard();
+    let handstates = kb.type_chars("aacffeddaaaabacbqpsr".chars());
+    assert_resumable(FingerUsage::new(), &handstates);
+    assert_resumable(FingerAlternation::new().per_press(), &handstates);
+    assert_resumable(Redirects::new(), &handstates);
+    assert_resumable(HandRuns::new(), &handstates);
+    assert_resumable(Trigrams::new().sampled(3), &handstates);
+    assert_resumable(ChordEntropy::new(), &handstates);
+    assert_resumable(SameHandStreaks::default(), &handstates);
+    assert_resumable(FingerBalance::new(), &handstates);
+
+    let json = serde_json::to_string(&FingerUsage::new().updated(&handstates))?;
+    assert!(json.starts_with("{\"presses\":[7,"));
+    let err = serde_json::from_str::<FingerUsage<8>>(&json).unwrap_err();
+    assert!(err.to_string().contains("8 finger counts"));
+    Ok(())
+  }
+
+  #[test]
+  fn test_fn_metric() {
+    let kb = test_keyboard();
+    let handstates = kb.type_chars("aacffeddaaaaba".chars());
+    let fa = FnMetric::new(
+      (None, 0),
+      |(prev, repeats): &mut (Option<HandsState>, u32), hs: &HandsState| {
+        *repeats += (*prev == Some(*hs)) as u32;
+        *prev = Some(*hs);
+      },
+      |(_, repeats)| *repeats as f32,
+    );
+    assert_eq!(fa.updated(&handstates).score(), 6.0);
+  }
+
+  #[test]
+  fn test_skipgrams() {
+    let kb = test_keyboard();
+    let sg = Skipgrams::new().updated(&kb.type_chars("abaaafdfcc".chars()));
+    assert_eq!(sg.score(), 2.0);
+    assert_eq!(sg.values(), [1, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+  }
+
+  #[test]
+  fn test_same_hand_streaks() {
+    let kb = test_keyboard();
+    let handstates = kb.type_chars("abcdpqrsd".chars());
+    let sh = SameHandStreaks::new(3).updated(&handstates);
+    assert_eq!(sh.clone().values(), [4.0, 3.0]);
+    assert_eq!(sh.window(), 3);
+    let mut sh = SameHandStreaks::new(2);
+    sh.set_weights(&[1.0, 4.0, 9.0]);
+    assert_eq!(sh.window(), 4);
+    assert_eq!(sh.updated(&handstates).values(), [6.0, 5.0]);
+    let ha = HandAlternation::new().updated(&handstates);
+    assert_eq!(SameHandStreaks::new(2).updated(&handstates).score(), 5.0);
+    assert_eq!(ha.score(), 5.0);
+  }
+
+  #[test]
+  fn test_chord_entropy() {
+    let kb = test_keyboard();
+    let ce = ChordEntropy::new().updated(&kb.type_chars("abcd".chars()));
+    assert_eq!(ce.score(), 2.0);
+    assert_eq!(ce.uniformity(), 1.0);
+    let ce = ChordEntropy::new().updated(&kb.type_chars("aaab".chars()));
+    assert!((ce.score() - 0.8113).abs() < 1e-4);
+    assert!(ce.uniformity() < 1.0);
+    assert_eq!(ce.values().len(), 2);
+    assert_eq!(ChordEntropy::new().score(), 0.0);
+  }
+
+  #[test]
+  fn test_chord_frequency() {
+    let kb = test_keyboard();
+    let hss = kb.type_chars("abacabad".chars());
+    let cf = ChordFrequency::new().updated(&hss);
+    assert_eq!(cf.score(), 4.0);
+    assert_eq!(cf.count(&hss[0]), 4);
+    assert_eq!(cf.count(&HandsState::left_thumb()), 0);
+    assert_eq!(cf.top(2), [(hss[0], 4), (hss[1], 2)]);
+    assert_eq!(cf.top(10).len(), 4);
+    let ce = ChordEntropy::from(cf);
+    assert_eq!(ce, ChordEntropy::new().updated(&hss));
+  }
+
+  #[test]
+  fn test_sampled() {
+    let kb = test_keyboard();
+    let fa = FingerAlternation::new()
+      .sampled(4)
+      .updated(&kb.type_chars("aacffeddaaaaba".chars()));
+    assert_eq!(fa.presses(), 14);
+    assert_eq!(fa.score(), 6.0);
+    assert_eq!(fa.samples(), [(4, 1.0), (8, 3.0), (12, 6.0)]);
+    let (fa, samples) = fa.into_parts();
+    assert_eq!(fa.score(), 6.0);
+    assert_eq!(samples.len(), 3);
+  }
+
+  #[test]
+  fn test_normalized() {
+    let kb = test_keyboard();
+    let hss = kb.type_chars("aacffeddaaaaba".chars());
+    let fb = Normalized::new(FingerBalance::new()).updated(&hss);
+    assert_eq!(fb.bounds(), (0.0, 2.0));
+    assert_eq!(fb.score(), fb.inner().score() / 2.0);
+    assert_eq!(fb.info(), MetricInfo {
+      max: Some(1.0),
+      ..MetricInfo::counter("finger_balance")
+    });
+
+    let fa = Normalized::new_with_bounds(FingerAlternation::new(), 0.0, 12.0);
+    assert_eq!(fa.updated(&hss).score(), 0.5);
+    let fa = Normalized::new_with_bounds(FingerAlternation::new(), 0.0, 3.0);
+    assert_eq!(fa.updated(&hss).score(), 1.0);
+    let baseline = kb.type_chars("aaaaa".chars());
+    let fa = Normalized::new_with_baseline(FingerAlternation::new(), &baseline);
+    assert_eq!(fa.bounds(), (0.0, 4.0));
+    assert_eq!(fa.info().max, None);
+    let fa = Normalized::new_with_bounds(FingerAlternation::new(), 0.0, 0.0);
+    assert_eq!(fa.score(), 0.0);
+    assert_eq!(fa.updated(&hss).score(), 1.0);
+  }
+
+  #[test]
+  fn test_normalized_baseline() {
+    let kb = test_keyboard();
+    let baseline = kb.type_chars("aabb".chars());
+    let fa = Normalized::new_with_baseline(FingerAlternation::new(), &baseline);
+    let fa = |text: &str| fa.clone().updated(&kb.type_chars(text.chars()));
+    // a longer corpus has more alternations than the baseline's
+    let longer = fa("aabbaabb");
+    assert_eq!(longer.score(), 2.0);
+    assert_eq!(fa("abab").score(), 0.0);
+
+    let fa = Normalized::new_with_baseline(
+      FingerAlternation::new().per_press(),
+      &baseline,
+    );
+    let fa = |text: &str| fa.clone().updated(&kb.type_chars(text.chars()));
+    assert_eq!(fa("aabbaabb").score(), 1.0);
+    assert_eq!(fa("aaaaaaab").score(), 1.5);
+    assert_eq!(fa("aaaaaaab").info().max, None);
+
+    let entropy = Normalized::new_with_baseline(ChordEntropy::new(), &baseline);
+    assert_eq!(entropy.info().max, Some(1.0));
+    let entropy = |text: &str| {
+      entropy.clone().updated(&kb.type_chars(text.chars())).score()
+    };
+    assert_eq!(entropy("aabbaabb"), 0.0);
+    assert_eq!(entropy("abcd"), 0.0);
+    assert_eq!(entropy("aaaa"), 1.0);
+  }
+
+  #[test]
+  #[should_panic(expected = "metric finger_usage isn't bounded")]
+  fn test_normalized_unbounded() {
+    Normalized::new(FingerUsage::new());
+  }
+
   #[test]
   fn test_finger_balance() {
     let fb = FingerBalance::new();
     assert_eq!(fb.presses, [0; 10]);
     assert_eq!(fb.score(), 0.0);
 
-    let kb = TestKeyboard {};
+    let kb = test_keyboard();
     let text = "abcdefpqrs";
     let fb = FingerBalance::new().updated(&kb.type_chars(text.chars()));
     assert_eq!(fb.presses, [1; 10]);
@@ -447,10 +2543,32 @@ mod tests {
     assert_eq!(fb.presses, [1; 10]);
     assert!(fb.score() - 1.6 < 1.0e-6);
 
+    let fb = FingerBalance::new_with_profile(&FingerProfile::even())
+      .updated(&kb.type_chars(text.chars()));
+    assert!(fb.score() < 1e-6);
+    let fb = FingerBalance::new_with_profile(&FingerProfile::new())
+      .updated(&kb.type_chars(text.chars()));
+    assert!(fb.score() > 0.0);
+
     let fu = FingerUsage::new().updated(&kb.type_chars(text.chars()));
     let fb = FingerBalance::from(fu);
     assert_eq!(fb.presses, [1; 10]);
     assert_eq!(fb.score(), 0.0);
+
+    let fb = FingerBalance::colemak_dh_targets();
+    assert_eq!(fb.target_ratio()[3], 0.15);
+    assert_eq!(FingerBalance::equal_no_pinkies().target_ratio()[0], 0.0);
+    assert_eq!(FingerBalance::equal_no_thumbs().target_ratio()[6], 0.125);
+    let mut fb = FingerBalance::new();
+    let err = fb
+      .try_set_percentages([10.0; 10].map(|p| p + 1.0))
+      .unwrap_err();
+    assert_eq!(err.percentages.len(), 10);
+    let mut percentages = [10.0; 10];
+    percentages[0] = -10.0;
+    percentages[1] = 30.0;
+    assert!(fb.try_set_percentages(percentages).is_err());
+    assert_eq!(fb.target_ratio(), [0.1; 10]);
   }
 
   #[test]
@@ -459,7 +2577,7 @@ mod tests {
     assert_eq!(hb.presses, [0, 0]);
     assert_eq!(hb.score(), 0.0);
 
-    let kb = TestKeyboard {};
+    let kb = test_keyboard();
     let text = "abcdefpqrs";
     let hb = HandBalance::new().updated(&kb.type_chars(text.chars()));
     assert_eq!(hb.presses, [5, 5]);
@@ -479,5 +2597,15 @@ mod tests {
     let hb = HandBalance::from(fb);
     assert_eq!(hb.presses, [5, 5]);
     assert_eq!(hb.score(), 0.0);
+
+    assert_eq!(HandBalance::left_dominant().target_ratio(), [0.55, 0.45]);
+    assert_eq!(HandBalance::right_dominant().target_ratio(), [0.45, 0.55]);
+    let err = HandBalance::new()
+      .try_set_percentages([50.0, 40.0])
+      .unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "target percentages [50.0, 40.0] must be non-negative and sum up to 100"
+    );
   }
 }
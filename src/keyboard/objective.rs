@@ -0,0 +1,205 @@
+//! Contains objectives: weighted combinations of metrics that describe
+//! keyboard layout efficiency with a single score.
+
+use std::{fmt::Display, str::FromStr};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+  hands::HandsState,
+  metric::{
+    FingerAlternation,
+    FingerBalance,
+    FingerUsage,
+    HandAlternation,
+    HandBalance,
+    HandRuns,
+    Metric,
+    MetricInfo,
+    Redirects,
+  },
+};
+
+/// Weighted sum of metrics. It is a metric itself, so it can be used anywhere
+/// a single metric is expected. Presets use [Metric::per_press] scores of
+/// counting metrics, so their scores don't depend on corpus length.
+#[derive(Default)]
+pub struct Objective {
+  terms: Vec<(f32, Box<dyn Metric>)>,
+}
+
+impl Objective {
+  /// Creates an objective without any metrics. Its score is always 0.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Creates an objective described by `preset`.
+  pub fn from_preset(preset: Preset) -> Self {
+    let mut objective = Self::new();
+    match preset {
+      Preset::Speed => objective
+        .add_metric(1.0, FingerAlternation::new().per_press())
+        .add_metric(0.5, Redirects::new().per_press())
+        .add_metric(0.25, HandAlternation::new().per_press())
+        .add_metric(0.25, FingerUsage::new().per_press()),
+      Preset::Comfort => objective
+        .add_metric(1.0, Redirects::new().per_press())
+        .add_metric(0.5, HandRuns::new().per_press())
+        .add_metric(0.5, FingerUsage::new().per_press())
+        .add_metric(0.5, FingerBalance::new())
+        .add_metric(0.25, FingerAlternation::new().per_press()),
+      Preset::Balanced => objective
+        .add_metric(1.0, FingerAlternation::new().per_press())
+        .add_metric(1.0, HandAlternation::new().per_press())
+        .add_metric(1.0, Redirects::new().per_press())
+        .add_metric(1.0, FingerBalance::new())
+        .add_metric(1.0, HandBalance::new()),
+      Preset::Learnability => objective
+        .add_metric(1.0, FingerUsage::new().per_press())
+        .add_metric(0.5, HandBalance::new())
+        .add_metric(0.25, FingerAlternation::new().per_press()),
+    };
+    objective
+  }
+
+  /// Adds `metric` to the objective. Its score is multiplied by `weight`.
+  pub fn add_metric(
+    &mut self,
+    weight: f32,
+    metric: impl Metric + 'static,
+  ) -> &mut Self {
+    self.terms.push((weight, Box::new(metric)));
+    self
+  }
+}
+
+impl Metric for Objective {
+  fn update_once(&mut self, handstate: &HandsState) {
+    for (_, metric) in self.terms.iter_mut() {
+      metric.update_once(handstate);
+    }
+  }
+
+  fn update(&mut self, handstates: &[HandsState]) {
+    for (_, metric) in self.terms.iter_mut() {
+      metric.update(handstates);
+    }
+  }
+
+  fn score(&self) -> f32 {
+    self.terms.iter().map(|(w, m)| w * m.score()).sum()
+  }
+
+  fn info(&self) -> MetricInfo {
+    MetricInfo::new("objective")
+  }
+}
+
+impl From<Preset> for Objective {
+  fn from(value: Preset) -> Self {
+    Self::from_preset(value)
+  }
+}
+
+/// Named objective with sensible metric weights.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Preset {
+  /// Minimizes same finger and same hand repetitions.
+  Speed,
+  /// Minimizes redirects, long one-handed runs and big chords.
+  Comfort,
+  /// Weights alternation and balance metrics equally.
+  Balanced,
+  /// Prefers small chords that are easy to remember and press.
+  Learnability,
+}
+
+impl Preset {
+  /// All available presets.
+  pub const ALL: [Preset; 4] = [
+    Preset::Speed,
+    Preset::Comfort,
+    Preset::Balanced,
+    Preset::Learnability,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Preset::Speed => "speed",
+      Preset::Comfort => "comfort",
+      Preset::Balanced => "balanced",
+      Preset::Learnability => "learnability",
+    }
+  }
+}
+
+impl Display for Preset {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+impl FromStr for Preset {
+  type Err = UnknownPreset;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Preset::ALL
+      .into_iter()
+      .find(|p| p.name().eq_ignore_ascii_case(s))
+      .ok_or_else(|| UnknownPreset { name: s.to_owned() })
+  }
+}
+
+/// This error means that there is no objective preset with such name.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UnknownPreset {
+  pub name: String,
+}
+
+impl Display for UnknownPreset {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "unknown objective preset '{}'", self.name)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{keyboard::Keyboard, testing::SyntheticKeyboard};
+
+  #[test]
+  fn test_objective_score() {
+    let kb = SyntheticKeyboard::new_one_finger("abcpqrsdef");
+    let hss = kb.type_chars("aacffeddaaaaba".chars());
+    let mut objective = Objective::new();
+    objective
+      .add_metric(2.0, FingerAlternation::new())
+      .add_metric(0.5, FingerUsage::new());
+    objective.update(&hss);
+    assert_eq!(objective.score(), 2.0 * 6.0 + 0.5 * 14.0);
+    assert_eq!(Objective::new().updated(&hss).score(), 0.0);
+  }
+
+  #[test]
+  fn test_preset_names() {
+    for preset in Preset::ALL {
+      assert_eq!(preset.to_string().parse(), Ok(preset));
+    }
+    assert_eq!("Speed".parse(), Ok(Preset::Speed));
+    assert_eq!(
+      "fast".parse::<Preset>(),
+      Err(UnknownPreset {
+        name: "fast".to_owned()
+      })
+    );
+    #[cfg(feature = "serde")]
+    assert_eq!(
+      serde_json::to_string(&Preset::Comfort).unwrap(),
+      "\"comfort\""
+    );
+  }
+}
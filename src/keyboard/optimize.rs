@@ -0,0 +1,1175 @@
+//! Contains optimizers that search for efficient Tenboard layouts.
+
+use std::{convert::Infallible, ops::ControlFlow};
+#[cfg(feature = "serde")]
+use std::{fmt::Display, fs, io, path::Path};
+
+use rand::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{
+  hands::{HandsState, ThumbPolicy},
+  incremental::{PairCost, UnaryCost},
+  layout::{
+    constraints::{ChordFilter, Constraints},
+    tenboard::{Tenboard, TenboardUnconstrained},
+  },
+  metric::Metric,
+  schedule::{Geometric, Schedule},
+  Keyboard,
+  TYPABLE_CHARS,
+};
+use crate::corpus::NgramTable;
+
+/// Simulated annealing optimizer. Each iteration it swaps chords of two
+/// random chars and keeps the swap if it improves the score or, with
+/// probability decreasing over time, even if it doesn't.
+///
+/// By default all typable chars are reassigned. Use
+/// [Annealing::set_chars] to reoptimize only a part of the layout and
+/// [Annealing::set_constraints] to keep the layout within [Constraints].
+///
+/// Besides running out of iterations, optimization stops early when the
+/// best score reaches [Annealing::set_target_score], when it doesn't
+/// improve for [Annealing::set_patience] iterations or when the observer
+/// passed to [Annealing::optimize_observed] breaks.
+///
+/// Temperature follows a [Schedule], which is [Geometric] by default. Use
+/// [Annealing::with_schedule] to pick another one.
+///
+/// Long runs can be checkpointed with [Annealing::optimize_checkpointed]
+/// and continued later with [AnnealingState::resume_from]. Checkpoints
+/// require the `serde` feature.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Annealing<S = Geometric> {
+  iterations: u32,
+  schedule: S,
+  chars: Vec<char>,
+  constraints: Option<Constraints>,
+  patience: Option<u32>,
+  target_score: Option<f32>,
+  seed: Option<u64>,
+}
+
+impl Annealing {
+  pub fn new() -> Self {
+    Self::with_schedule(Geometric::default())
+  }
+
+  /// Sets temperature of the first and the last iterations. Temperature
+  /// decreases geometrically between them.
+  pub fn set_temperature(
+    &mut self,
+    initial_temperature: f32,
+    final_temperature: f32,
+  ) -> &mut Self {
+    self.schedule = Geometric::new(initial_temperature, final_temperature);
+    self
+  }
+}
+
+impl<S: Schedule + Clone> Annealing<S> {
+  /// Creates an optimizer with default settings and `schedule`.
+  pub fn with_schedule(schedule: S) -> Self {
+    Self {
+      iterations: 10_000,
+      schedule,
+      chars: TYPABLE_CHARS.chars().collect(),
+      constraints: None,
+      patience: None,
+      target_score: None,
+      seed: None,
+    }
+  }
+
+  pub fn set_iterations(&mut self, iterations: u32) -> &mut Self {
+    self.iterations = iterations;
+    self
+  }
+
+  pub fn set_schedule(&mut self, schedule: S) -> &mut Self {
+    self.schedule = schedule;
+    self
+  }
+
+  /// Restricts optimization to `chars`. Only their chords are reassigned,
+  /// chords of other chars are left as they are.
+  pub fn set_chars(&mut self, chars: &str) -> &mut Self {
+    self.chars = chars.chars().collect();
+    self.chars.sort_unstable();
+    self.chars.dedup();
+    self
+  }
+
+  /// Makes the optimizer reject swaps that break `constraints`. The initial
+  /// layout is expected to satisfy them.
+  pub fn set_constraints(&mut self, constraints: Constraints) -> &mut Self {
+    self.constraints = Some(constraints);
+    self
+  }
+
+  /// Stops optimization after `patience` iterations in a row without
+  /// improvement of the best score.
+  pub fn set_patience(&mut self, patience: u32) -> &mut Self {
+    self.patience = Some(patience);
+    self
+  }
+
+  /// Stops optimization once the best score is less or equal to `score`.
+  pub fn set_target_score(&mut self, score: f32) -> &mut Self {
+    self.target_score = Some(score);
+    self
+  }
+
+  /// Seeds the random number generator, so that runs with the same
+  /// settings, initial layout and score function give the same result. By
+  /// default each run is seeded randomly.
+  pub fn set_seed(&mut self, seed: u64) -> &mut Self {
+    self.seed = Some(seed);
+    self
+  }
+
+  /// Returns the best layout found starting from `layout`. The lower
+  /// `score` of a layout - the better.
+  pub fn optimize<L: Tenboard + Clone>(
+    &self,
+    layout: L,
+    score: impl FnMut(&L) -> f32,
+  ) -> L {
+    self.optimize_observed(layout, score, |_, _, _| ControlFlow::Continue(()))
+  }
+
+  /// Same as [Annealing::optimize], but calls `on_iteration` with the
+  /// iteration number, the best score so far and the current layout after
+  /// every iteration. Optimization stops if `on_iteration` breaks.
+  pub fn optimize_observed<L: Tenboard + Clone>(
+    &self,
+    layout: L,
+    mut score: impl FnMut(&L) -> f32,
+    on_iteration: impl FnMut(u32, f32, &L) -> ControlFlow<()>,
+  ) -> L {
+    self
+      .start(layout, &mut score)
+      .resume_observed(score, on_iteration)
+  }
+
+  /// Same as [Annealing::optimize_observed], but saves the state of the run
+  /// to `path` every `interval` iterations, so that it can be continued
+  /// with [AnnealingState::resume_from]. Stops and returns an error if a
+  /// checkpoint can't be written.
+  #[cfg(feature = "serde")]
+  pub fn optimize_checkpointed<L: Tenboard + Clone + Serialize>(
+    &self,
+    layout: L,
+    mut score: impl FnMut(&L) -> f32,
+    on_iteration: impl FnMut(u32, f32, &L) -> ControlFlow<()>,
+    path: impl AsRef<Path>,
+    interval: u32,
+  ) -> Result<L, CheckpointError>
+  where
+    S: Serialize,
+  {
+    self.start(layout, &mut score).resume_checkpointed(
+      score,
+      on_iteration,
+      path,
+      interval,
+    )
+  }
+
+  /// Returns the state of a run starting from `layout`.
+  fn start<L: Tenboard + Clone>(
+    &self,
+    layout: L,
+    score: &mut impl FnMut(&L) -> f32,
+  ) -> AnnealingState<L, S> {
+    let current_score = score(&layout);
+    AnnealingState {
+      optimizer: self.clone(),
+      iteration: 0,
+      best: layout.clone(),
+      best_score: current_score,
+      current: layout,
+      current_score,
+      last_improvement: 0,
+      seed: self.seed.unwrap_or_else(|| rand::thread_rng().gen()),
+    }
+  }
+
+  fn reached_target(&self, score: f32) -> bool {
+    self.target_score.is_some_and(|target| score <= target)
+  }
+
+  fn satisfies_constraints(
+    &self,
+    layout: &impl Tenboard,
+    ch1: char,
+    ch2: char,
+  ) -> bool {
+    satisfies_constraints(self.constraints.as_ref(), layout, ch1, ch2)
+  }
+
+  fn temperature(&self, iteration: u32, stagnation: u32) -> f32 {
+    let progress = iteration as f32 / self.iterations.max(1) as f32;
+    self.schedule.temperature(progress, stagnation)
+  }
+}
+
+impl Default for Annealing {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Snapshot of an [Annealing] run that can be saved and resumed later.
+/// Resuming a run from a snapshot gives the same result as continuing it
+/// without interruption, as long as the score function is the same.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AnnealingState<L, S = Geometric> {
+  optimizer: Annealing<S>,
+  iteration: u32,
+  current: L,
+  #[cfg_attr(feature = "serde", serde(with = "optional_score"))]
+  current_score: f32,
+  best: L,
+  #[cfg_attr(feature = "serde", serde(with = "optional_score"))]
+  best_score: f32,
+  last_improvement: u32,
+  seed: u64,
+}
+
+/// Serializes scores as `Option<f32>`, where `None` stands for an infinite
+/// score, e.g. of a layout that can't type the corpus. JSON has no
+/// infinity, so such scores would be written as `null` and fail to load.
+#[cfg(feature = "serde")]
+mod optional_score {
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+  pub fn serialize<S: Serializer>(
+    score: &f32,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    score.is_finite().then_some(*score).serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<f32, D::Error> {
+    Ok(Option::deserialize(deserializer)?.unwrap_or(f32::INFINITY))
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<L, S> AnnealingState<L, S>
+where
+  L: Tenboard + Clone + Serialize,
+  S: Schedule + Clone + Serialize,
+{
+  /// Loads a state saved by [Annealing::optimize_checkpointed] or
+  /// [AnnealingState::save].
+  pub fn resume_from(path: impl AsRef<Path>) -> Result<Self, CheckpointError>
+  where
+    L: DeserializeOwned,
+    S: DeserializeOwned,
+  {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+  }
+
+  /// Writes the state to `path`. The file is replaced atomically, so an
+  /// interrupted write leaves the previous checkpoint intact.
+  pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CheckpointError> {
+    let path = path.as_ref();
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, serde_json::to_string(self)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+  }
+
+  /// Same as [AnnealingState::resume_observed], but saves the state to
+  /// `path` every `interval` iterations like
+  /// [Annealing::optimize_checkpointed] does.
+  pub fn resume_checkpointed(
+    self,
+    score: impl FnMut(&L) -> f32,
+    on_iteration: impl FnMut(u32, f32, &L) -> ControlFlow<()>,
+    path: impl AsRef<Path>,
+    interval: u32,
+  ) -> Result<L, CheckpointError> {
+    let interval = interval.max(1);
+    self.run(score, on_iteration, |state| {
+      if state.iteration.is_multiple_of(interval) {
+        state.save(path.as_ref())?;
+      }
+      Ok(())
+    })
+  }
+}
+
+impl<L, S> AnnealingState<L, S>
+where
+  L: Tenboard + Clone,
+  S: Schedule + Clone,
+{
+  pub fn optimizer(&self) -> &Annealing<S> {
+    &self.optimizer
+  }
+
+  /// Returns number of completed iterations.
+  pub fn iteration(&self) -> u32 {
+    self.iteration
+  }
+
+  pub fn best(&self) -> &L {
+    &self.best
+  }
+
+  pub fn best_score(&self) -> f32 {
+    self.best_score
+  }
+
+  /// Continues the run and returns the best layout found.
+  pub fn resume(self, score: impl FnMut(&L) -> f32) -> L {
+    self.resume_observed(score, |_, _, _| ControlFlow::Continue(()))
+  }
+
+  /// Same as [AnnealingState::resume], but calls `on_iteration` like
+  /// [Annealing::optimize_observed] does.
+  pub fn resume_observed(
+    self,
+    score: impl FnMut(&L) -> f32,
+    on_iteration: impl FnMut(u32, f32, &L) -> ControlFlow<()>,
+  ) -> L {
+    let no_checkpoints = |_: &Self| Ok::<_, Infallible>(());
+    match self.run(score, on_iteration, no_checkpoints) {
+      Ok(best) => best,
+      Err(never) => match never {},
+    }
+  }
+
+  /// Runs the remaining iterations and calls `checkpoint` after every one
+  /// of them. Stops if `checkpoint` fails.
+  fn run<E>(
+    mut self,
+    mut score: impl FnMut(&L) -> f32,
+    mut on_iteration: impl FnMut(u32, f32, &L) -> ControlFlow<()>,
+    mut checkpoint: impl FnMut(&Self) -> Result<(), E>,
+  ) -> Result<L, E> {
+    let mut rng = StdRng::seed_from_u64(self.seed);
+    if self.optimizer.chars.len() < 2
+      || self.optimizer.reached_target(self.best_score)
+    {
+      return Ok(self.best);
+    }
+    while self.iteration < self.optimizer.iterations {
+      let i = self.iteration;
+      let temperature =
+        self.optimizer.temperature(i, i - self.last_improvement);
+      let mut pair = self.optimizer.chars.choose_multiple(&mut rng, 2);
+      let (ch1, ch2) = (*pair.next().unwrap(), *pair.next().unwrap());
+      let current = &mut self.current;
+      if current.try_swap_states(ch1, ch2).is_ok() {
+        if self.optimizer.satisfies_constraints(current, ch1, ch2) {
+          let new_score = score(current);
+          let accept = new_score <= self.current_score
+            || rng.gen::<f32>()
+              < ((self.current_score - new_score) / temperature).exp();
+          if accept {
+            self.current_score = new_score;
+            if new_score < self.best_score {
+              self.best_score = new_score;
+              self.best = current.clone();
+              self.last_improvement = i;
+            }
+          } else {
+            current.swap_states(ch1, ch2);
+          }
+        } else {
+          current.swap_states(ch1, ch2);
+        }
+      }
+      self.iteration += 1;
+      // reseed, so that resuming from a checkpoint continues with the same
+      // random numbers
+      self.seed = rng.gen();
+      rng = StdRng::seed_from_u64(self.seed);
+      checkpoint(&self)?;
+      if on_iteration(i, self.best_score, &self.current).is_break()
+        || self.optimizer.reached_target(self.best_score)
+        || self
+          .optimizer
+          .patience
+          .is_some_and(|p| i - self.last_improvement >= p)
+      {
+        break;
+      }
+    }
+    Ok(self.best)
+  }
+}
+
+/// This error means that a checkpoint couldn't be read or written.
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub enum CheckpointError {
+  Io(io::Error),
+  Json(serde_json::Error),
+}
+
+#[cfg(feature = "serde")]
+impl Display for CheckpointError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      CheckpointError::Io(e) => write!(f, "{e}"),
+      CheckpointError::Json(e) => write!(f, "{e}"),
+    }
+  }
+}
+
+#[cfg(feature = "serde")]
+impl From<io::Error> for CheckpointError {
+  fn from(value: io::Error) -> Self {
+    CheckpointError::Io(value)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for CheckpointError {
+  fn from(value: serde_json::Error) -> Self {
+    CheckpointError::Json(value)
+  }
+}
+
+/// Greedy hill-climbing optimizer. Each iteration it tries swapping chords
+/// of every pair of chars and applies the swap that improves the score the
+/// most. It stops once no swap improves the score.
+///
+/// With [HillClimb::set_restarts] the climb is repeated from random
+/// shuffles of the initial layout and the best result is kept.
+///
+/// Every iteration scores a layout for each pair of chars, so it's best
+/// suited for small sets of chars or for polishing layouts found by
+/// [Annealing].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HillClimb {
+  max_iterations: u32,
+  restarts: u32,
+  chars: Vec<char>,
+  constraints: Option<Constraints>,
+  seed: Option<u64>,
+}
+
+impl HillClimb {
+  pub fn new() -> Self {
+    Self {
+      max_iterations: 1_000,
+      restarts: 0,
+      chars: TYPABLE_CHARS.chars().collect(),
+      constraints: None,
+      seed: None,
+    }
+  }
+
+  /// Limits the number of swaps made by every climb.
+  pub fn set_max_iterations(&mut self, max_iterations: u32) -> &mut Self {
+    self.max_iterations = max_iterations;
+    self
+  }
+
+  /// Sets the number of additional climbs starting from random shuffles of
+  /// the initial layout.
+  pub fn set_restarts(&mut self, restarts: u32) -> &mut Self {
+    self.restarts = restarts;
+    self
+  }
+
+  /// Restricts optimization to `chars`, like [Annealing::set_chars].
+  pub fn set_chars(&mut self, chars: &str) -> &mut Self {
+    self.chars = chars.chars().collect();
+    self.chars.sort_unstable();
+    self.chars.dedup();
+    self
+  }
+
+  /// Makes the optimizer skip swaps that break `constraints`. The initial
+  /// layout is expected to satisfy them.
+  pub fn set_constraints(&mut self, constraints: Constraints) -> &mut Self {
+    self.constraints = Some(constraints);
+    self
+  }
+
+  /// Seeds shuffles of restarts. By default they are seeded randomly.
+  pub fn set_seed(&mut self, seed: u64) -> &mut Self {
+    self.seed = Some(seed);
+    self
+  }
+
+  /// Returns the best layout found starting from `layout`. The lower
+  /// `score` of a layout - the better.
+  pub fn optimize<L: Tenboard + Clone>(
+    &self,
+    layout: L,
+    mut score: impl FnMut(&L) -> f32,
+  ) -> L {
+    let mut rng = match self.seed {
+      Some(seed) => StdRng::seed_from_u64(seed),
+      None => StdRng::from_entropy(),
+    };
+    let (mut best, mut best_score) = self.climb(layout.clone(), &mut score);
+    for _ in 0..self.restarts {
+      let (climbed, climbed_score) =
+        self.climb(self.shuffled(layout.clone(), &mut rng), &mut score);
+      if climbed_score < best_score {
+        best = climbed;
+        best_score = climbed_score;
+      }
+    }
+    best
+  }
+
+  /// Climbs from `layout` and returns the local optimum with its score.
+  fn climb<L: Tenboard + Clone>(
+    &self,
+    mut layout: L,
+    score: &mut impl FnMut(&L) -> f32,
+  ) -> (L, f32) {
+    let mut current_score = score(&layout);
+    for _ in 0..self.max_iterations {
+      let mut best_swap = None;
+      let mut best_score = current_score;
+      for (i, &ch1) in self.chars.iter().enumerate() {
+        for &ch2 in &self.chars[i + 1..] {
+          if layout.try_swap_states(ch1, ch2).is_err() {
+            continue;
+          }
+          if satisfies_constraints(self.constraints.as_ref(), &layout, ch1, ch2)
+          {
+            let new_score = score(&layout);
+            if new_score < best_score {
+              best_score = new_score;
+              best_swap = Some((ch1, ch2));
+            }
+          }
+          layout.swap_states(ch1, ch2);
+        }
+      }
+      let Some((ch1, ch2)) = best_swap else {
+        break;
+      };
+      layout.swap_states(ch1, ch2);
+      current_score = best_score;
+    }
+    (layout, current_score)
+  }
+
+  /// Applies a random swap for every char, skipping swaps that break
+  /// constraints.
+  fn shuffled<L: Tenboard>(&self, mut layout: L, rng: &mut impl Rng) -> L {
+    if self.chars.len() < 2 {
+      return layout;
+    }
+    for _ in 0..self.chars.len() {
+      let mut pair = self.chars.choose_multiple(rng, 2);
+      let (ch1, ch2) = (*pair.next().unwrap(), *pair.next().unwrap());
+      if layout.try_swap_states(ch1, ch2).is_ok()
+        && !satisfies_constraints(self.constraints.as_ref(), &layout, ch1, ch2)
+      {
+        layout.swap_states(ch1, ch2);
+      }
+    }
+    layout
+  }
+}
+
+impl Default for HillClimb {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Beam search layout generator. Chars are assigned chords one by one,
+/// the most frequent first. After each step only the best
+/// [BeamSearch::set_width] partial layouts are kept, scored by costs of
+/// their chords and bigrams weighted by frequencies, like
+/// [IncrementalScorer](super::incremental::IncrementalScorer) does for
+/// whole layouts.
+///
+/// Unlike optimizers, it doesn't use randomness, so the same settings and
+/// frequencies always give the same layout.
+///
+/// ```
+/// use tenboard::{
+///   corpus::NgramTable,
+///   keyboard::{
+///     effort::{EffortModel, FingerEffort},
+///     incremental::same_finger,
+///     optimize::BeamSearch,
+///   },
+/// };
+///
+/// let effort = FingerEffort::new();
+/// let layout = BeamSearch::new()
+///   .set_width(4)
+///   .add_unary_cost(1.0, move |hs| effort.cost(hs))
+///   .add_pair_cost(1.0, same_finger)
+///   .generate(&NgramTable::english());
+/// ```
+pub struct BeamSearch {
+  width: usize,
+  chords: Vec<HandsState>,
+  unary_costs: Vec<(f32, UnaryCost)>,
+  pair_costs: Vec<(f32, PairCost)>,
+  constraints: Option<Constraints>,
+}
+
+/// Partial layout of a [BeamSearch] with chord indices of assigned chars.
+#[derive(Clone)]
+struct Beam {
+  chords: Vec<usize>,
+  used: Vec<bool>,
+  score: f32,
+}
+
+impl BeamSearch {
+  /// Creates a generator with beam width of 16 that picks from one and two
+  /// key chords allowed by [ThumbPolicy::default]. It has no costs.
+  pub fn new() -> Self {
+    Self {
+      width: 16,
+      chords: HandsState::iterate_one_two_key(ThumbPolicy::default()).collect(),
+      unary_costs: Vec::new(),
+      pair_costs: Vec::new(),
+      constraints: None,
+    }
+  }
+
+  /// # Panics
+  ///
+  /// Panics if `width` is 0.
+  pub fn set_width(&mut self, width: usize) -> &mut Self {
+    assert!(width > 0, "beam width must be positive");
+    self.width = width;
+    self
+  }
+
+  /// Makes the generator pick from `chords`.
+  pub fn set_chords(
+    &mut self,
+    chords: impl IntoIterator<Item = HandsState>,
+  ) -> &mut Self {
+    self.chords = chords.into_iter().collect();
+    self
+  }
+
+  /// Drops chords that `filter` doesn't allow.
+  pub fn set_chord_filter(&mut self, filter: &ChordFilter) -> &mut Self {
+    self.chords.retain(|hs| filter.allows(hs));
+    self
+  }
+
+  /// Makes the generator assign chords allowed by `constraints` only.
+  /// Pinned chords are assigned even if they aren't among the chords to
+  /// pick from.
+  pub fn set_constraints(&mut self, constraints: Constraints) -> &mut Self {
+    self.constraints = Some(constraints);
+    self
+  }
+
+  /// Adds cost of typing a single chord multiplied by `weight`.
+  pub fn add_unary_cost(
+    &mut self,
+    weight: f32,
+    cost: impl Fn(&HandsState) -> f32 + 'static,
+  ) -> &mut Self {
+    self.unary_costs.push((weight, Box::new(cost)));
+    self
+  }
+
+  /// Adds cost of typing a chord right after another one multiplied by
+  /// `weight`.
+  pub fn add_pair_cost(
+    &mut self,
+    weight: f32,
+    cost: impl Fn(&HandsState, &HandsState) -> f32 + 'static,
+  ) -> &mut Self {
+    self.pair_costs.push((weight, Box::new(cost)));
+    self
+  }
+
+  /// Generates a layout of all [TYPABLE_CHARS] with frequencies of `freq`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if there are fewer chords than chars or if chars run out of
+  /// chords allowed by the constraints.
+  pub fn generate(&self, freq: &NgramTable) -> TenboardUnconstrained {
+    let mut chars: Vec<_> = TYPABLE_CHARS.chars().collect();
+    let mut chords = self.chords.clone();
+    let pinned = |ch| self.constraints.as_ref().and_then(|c| c.pinned(ch));
+    for hs in chars.iter().filter_map(|&ch| pinned(ch)) {
+      if !chords.contains(&hs) {
+        chords.push(hs);
+      }
+    }
+    assert!(chords.len() >= chars.len(), "not enough chords for all chars");
+    // pinned chars take their chords before anyone else does
+    chars.sort_by(|&a, &b| {
+      (pinned(a).is_none().cmp(&pinned(b).is_none()))
+        .then(freq.char_frequency(b).total_cmp(&freq.char_frequency(a)))
+        .then(a.cmp(&b))
+    });
+    let unary: Vec<_> = chords.iter().map(|hs| self.unary(hs)).collect();
+    let mut beams = vec![Beam {
+      chords: Vec::new(),
+      used: vec![false; chords.len()],
+      score: 0.0,
+    }];
+    for (k, &ch) in chars.iter().enumerate() {
+      // bigrams of `ch` with already assigned chars and with itself
+      let bigrams: Vec<_> = chars[..k]
+        .iter()
+        .enumerate()
+        .map(|(j, &other)| {
+          (
+            j,
+            freq.bigram_frequency([other, ch]),
+            freq.bigram_frequency([ch, other]),
+          )
+        })
+        .filter(|&(_, before, after)| before > 0.0 || after > 0.0)
+        .collect();
+      let repeated = freq.bigram_frequency([ch, ch]);
+      let char_freq = freq.char_frequency(ch);
+      let mut candidates = Vec::new();
+      for (b, beam) in beams.iter().enumerate() {
+        for (c, chord) in chords.iter().enumerate() {
+          let allowed = self
+            .constraints
+            .as_ref()
+            .is_none_or(|constraints| constraints.allows(ch, chord));
+          if beam.used[c] || !allowed {
+            continue;
+          }
+          let mut score = beam.score + unary[c] * char_freq;
+          if repeated > 0.0 {
+            score += self.pair(chord, chord) * repeated;
+          }
+          for &(j, before, after) in &bigrams {
+            let other = &chords[beam.chords[j]];
+            if before > 0.0 {
+              score += self.pair(other, chord) * before;
+            }
+            if after > 0.0 {
+              score += self.pair(chord, other) * after;
+            }
+          }
+          candidates.push((score, b, c));
+        }
+      }
+      candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+      beams = candidates
+        .into_iter()
+        .take(self.width)
+        .map(|(score, b, c)| {
+          let mut beam = beams[b].clone();
+          beam.chords.push(c);
+          beam.used[c] = true;
+          beam.score = score;
+          beam
+        })
+        .collect();
+      assert!(!beams.is_empty(), "no chord left for '{ch}'");
+    }
+    chars
+      .into_iter()
+      .zip(&beams[0].chords)
+      .map(|(ch, &c)| (ch, chords[c]))
+      .collect()
+  }
+
+  fn unary(&self, hs: &HandsState) -> f32 {
+    self.unary_costs.iter().map(|(w, c)| w * c(hs)).sum()
+  }
+
+  fn pair(&self, first: &HandsState, second: &HandsState) -> f32 {
+    self
+      .pair_costs
+      .iter()
+      .map(|(w, c)| w * c(first, second))
+      .sum()
+  }
+}
+
+impl Default for BeamSearch {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+fn satisfies_constraints(
+  constraints: Option<&Constraints>,
+  layout: &impl Tenboard,
+  ch1: char,
+  ch2: char,
+) -> bool {
+  let Some(constraints) = constraints else {
+    return true;
+  };
+  [ch1, ch2].into_iter().all(|ch| {
+    layout
+      .try_type_char(ch)
+      .is_ok_and(|hs| constraints.allows(ch, &hs))
+  })
+}
+
+/// Returns a score function that types `text` with a keyboard and returns
+/// score of a metric created by `new_metric`. If `text` can't be typed with
+/// the keyboard, the score is infinite.
+pub fn text_score<'a, K: Keyboard, M: Metric + 'a>(
+  text: &'a str,
+  new_metric: impl Fn() -> M + 'a,
+) -> impl Fn(&K) -> f32 + 'a {
+  move |keyboard| match keyboard.try_type_chars(text.chars()) {
+    Ok(handstates) => {
+      let mut metric = new_metric();
+      metric.update(&handstates);
+      metric.score()
+    }
+    Err(_) => f32::INFINITY,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+
+  use super::*;
+  use crate::keyboard::{
+    effort::{Effort, EffortModel, FingerEffort},
+    incremental::same_finger,
+    metric::FingerAlternation,
+    schedule::{Adaptive, Linear},
+  };
+
+  #[test]
+  fn test_partial_annealing() {
+    let text = "aabbccabcabc";
+    let score = text_score(text, FingerAlternation::new);
+    let layout = TenboardUnconstrained::new_random();
+    let initial_score = score(&layout);
+    let optimized = Annealing::new()
+      .set_iterations(500)
+      .set_chars("abcxyz")
+      .optimize(layout.clone(), &score);
+    assert!(score(&optimized) <= initial_score);
+    for ch in TYPABLE_CHARS.chars().filter(|ch| !"abcxyz".contains(*ch)) {
+      assert_eq!(optimized.try_type_char(ch), layout.try_type_char(ch));
+    }
+  }
+
+  #[test]
+  fn test_constrained_annealing() {
+    let mut constraints = Constraints::new();
+    constraints
+      .pin('a', HandsState::left_thumb())
+      .single_key("bc")
+      .forbid([0, 0, 0, 1, 0, 0, 1, 0, 0, 0].into());
+    let layout =
+      TenboardUnconstrained::new_random_with_constraints(&constraints).unwrap();
+    assert!(constraints.violations(&layout).is_empty());
+    let optimized = Annealing::new()
+      .set_iterations(500)
+      .set_constraints(constraints.clone())
+      .optimize(layout, text_score("abcabc", FingerAlternation::new));
+    assert!(constraints.violations(&optimized).is_empty());
+  }
+
+  #[test]
+  fn test_early_stopping() {
+    let score = text_score("abcabc", FingerAlternation::new);
+    let layout = TenboardUnconstrained::new_random();
+    let mut iterations = 0;
+    Annealing::new().optimize_observed(layout.clone(), &score, |i, _, _| {
+      iterations = i + 1;
+      if i == 9 {
+        ControlFlow::Break(())
+      } else {
+        ControlFlow::Continue(())
+      }
+    });
+    assert_eq!(iterations, 10);
+
+    let mut iterations = 0;
+    Annealing::new().set_patience(20).optimize_observed(
+      layout.clone(),
+      |_| 1.0,
+      |i, _, _| {
+        iterations = i + 1;
+        ControlFlow::Continue(())
+      },
+    );
+    assert_eq!(iterations, 21);
+
+    let mut iterations = 0;
+    Annealing::new()
+      .set_target_score(f32::INFINITY)
+      .optimize_observed(layout, &score, |_, _, _| {
+        iterations += 1;
+        ControlFlow::Continue(())
+      });
+    assert_eq!(iterations, 0);
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_checkpoint_resume() {
+    let path = std::env::temp_dir()
+      .join(format!("tenboard-checkpoint-{}.json", std::process::id()));
+    let score = text_score("the quick brown fox", FingerAlternation::new);
+    let mut optimizer = Annealing::new();
+    optimizer.set_iterations(200);
+    optimizer
+      .optimize_checkpointed(
+        TenboardUnconstrained::new_random(),
+        &score,
+        |i, _, _| {
+          if i == 70 {
+            ControlFlow::Break(())
+          } else {
+            ControlFlow::Continue(())
+          }
+        },
+        &path,
+        50,
+      )
+      .unwrap();
+    let state: AnnealingState<TenboardUnconstrained> =
+      AnnealingState::resume_from(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(state.iteration(), 50);
+    assert_eq!(state.optimizer(), &optimizer);
+    assert!(score(state.best()) <= state.best_score());
+    let first = state.clone().resume(&score);
+    let second = state
+      .resume_checkpointed(
+        &score,
+        |_, _, _| ControlFlow::Continue(()),
+        &path,
+        50,
+      )
+      .unwrap();
+    assert!(fs::metadata(&path).is_ok());
+    fs::remove_file(&path).unwrap();
+    for ch in TYPABLE_CHARS.chars() {
+      assert_eq!(first.try_type_char(ch), second.try_type_char(ch));
+    }
+    assert!(matches!(
+      AnnealingState::<TenboardUnconstrained>::resume_from(&path),
+      Err(CheckpointError::Io(_))
+    ));
+
+    let unwritable = path.join("checkpoint.json");
+    let result = optimizer.optimize_checkpointed(
+      TenboardUnconstrained::new_random(),
+      &score,
+      |_, _, _| ControlFlow::Continue(()),
+      &unwritable,
+      50,
+    );
+    assert!(matches!(result, Err(CheckpointError::Io(_))));
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_resumed_run_matches_uninterrupted() {
+    let path = std::env::temp_dir()
+      .join(format!("tenboard-resume-{}.json", std::process::id()));
+    let score = text_score("the quick brown fox", FingerAlternation::new);
+    let layout =
+      TenboardUnconstrained::new_random_with_rng(&mut StdRng::seed_from_u64(2));
+    let mut optimizer = Annealing::new();
+    optimizer.set_iterations(300).set_seed(11);
+    let uninterrupted = optimizer.optimize(layout.clone(), &score);
+    optimizer
+      .optimize_checkpointed(
+        layout,
+        &score,
+        |i, _, _| match i {
+          120 => ControlFlow::Break(()),
+          _ => ControlFlow::Continue(()),
+        },
+        &path,
+        100,
+      )
+      .unwrap();
+    let state: AnnealingState<TenboardUnconstrained> =
+      AnnealingState::resume_from(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(state.iteration(), 100);
+    let resumed = state.resume(&score);
+    assert!(resumed.diff(&uninterrupted).is_empty());
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_checkpoint_infinite_score() {
+    let path = std::env::temp_dir()
+      .join(format!("tenboard-infinite-{}.json", std::process::id()));
+    let mut optimizer = Annealing::new();
+    optimizer.set_iterations(10);
+    optimizer
+      .optimize_checkpointed(
+        TenboardUnconstrained::new_random(),
+        |_| f32::INFINITY,
+        |_, _, _| ControlFlow::Continue(()),
+        &path,
+        5,
+      )
+      .unwrap();
+    let json = fs::read_to_string(&path).unwrap();
+    assert!(json.contains("\"best_score\":null"));
+    let state: AnnealingState<TenboardUnconstrained> =
+      AnnealingState::resume_from(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(state.best_score(), f32::INFINITY);
+  }
+
+  #[test]
+  fn test_annealing_schedules() {
+    let score = text_score("the quick brown fox", FingerAlternation::new);
+    let layout = TenboardUnconstrained::new_random();
+    let initial_score = score(&layout);
+    let mut adaptive = Adaptive::default();
+    adaptive.set_patience(50);
+    let optimized = Annealing::with_schedule(adaptive)
+      .set_iterations(300)
+      .optimize(layout.clone(), &score);
+    assert!(score(&optimized) <= initial_score);
+    let optimized = Annealing::with_schedule(Linear::default())
+      .set_iterations(300)
+      .optimize(layout, &score);
+    assert!(score(&optimized) <= initial_score);
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_annealing_serialization() -> Result<(), serde_json::Error> {
+    fn round_trip<S>(schedule: S) -> Result<(), serde_json::Error>
+    where
+      S: Schedule + Clone + PartialEq + std::fmt::Debug,
+      S: Serialize + serde::de::DeserializeOwned,
+    {
+      let mut optimizer = Annealing::with_schedule(schedule);
+      optimizer.set_patience(50).set_seed(3);
+      let json = serde_json::to_string(&optimizer)?;
+      assert_eq!(serde_json::from_str::<Annealing<S>>(&json)?, optimizer);
+      Ok(())
+    }
+
+    round_trip(Geometric::default())?;
+    round_trip(Linear::default())?;
+    let mut adaptive = Adaptive::default();
+    adaptive.set_patience(7);
+    round_trip(adaptive)?;
+    round_trip(Adaptive::new(Linear::default()))?;
+    let json = serde_json::to_string(&Annealing::with_schedule(adaptive))?;
+    assert!(json.contains("\"schedule\":{\"base\":"));
+    Ok(())
+  }
+
+  #[test]
+  fn test_seeded_annealing() {
+    let score = text_score("the quick brown fox", FingerAlternation::new);
+    let layout =
+      TenboardUnconstrained::new_random_with_rng(&mut StdRng::seed_from_u64(1));
+    let mut optimizer = Annealing::new();
+    optimizer.set_iterations(200).set_seed(7);
+    let first = optimizer.optimize(layout.clone(), &score);
+    let second = optimizer.optimize(layout, &score);
+    for ch in TYPABLE_CHARS.chars() {
+      assert_eq!(first.try_type_char(ch), second.try_type_char(ch));
+    }
+  }
+
+  #[test]
+  fn test_hill_climb() {
+    let text = "abcabcaabbccxyzxyz";
+    let score = text_score(text, FingerAlternation::new);
+    let layout =
+      TenboardUnconstrained::new_random_with_rng(&mut StdRng::seed_from_u64(3));
+    let mut optimizer = HillClimb::new();
+    optimizer.set_chars("abcxyz").set_restarts(3).set_seed(1);
+    let optimized = optimizer.optimize(layout.clone(), &score);
+    assert!(score(&optimized) <= score(&layout));
+    for ch in TYPABLE_CHARS.chars().filter(|ch| !"abcxyz".contains(*ch)) {
+      assert_eq!(optimized.try_type_char(ch), layout.try_type_char(ch));
+    }
+    // no single swap improves a local optimum
+    let chars: Vec<_> = "abcxyz".chars().collect();
+    for (i, &ch1) in chars.iter().enumerate() {
+      for &ch2 in &chars[i + 1..] {
+        let mut swapped = optimized.clone();
+        swapped.swap_states(ch1, ch2);
+        assert!(score(&swapped) >= score(&optimized));
+      }
+    }
+    let again = optimizer.optimize(layout, &score);
+    for ch in TYPABLE_CHARS.chars() {
+      assert_eq!(again.try_type_char(ch), optimized.try_type_char(ch));
+    }
+  }
+
+  #[test]
+  fn test_beam_search() {
+    let freq = NgramTable::english();
+    let effort = FingerEffort::new();
+    let mut generator = BeamSearch::new();
+    generator
+      .set_width(2)
+      .add_unary_cost(1.0, move |hs| effort.cost(hs))
+      .add_pair_cost(1.0, same_finger);
+    let layout = generator.generate(&freq);
+    let chords: HashSet<_> = TYPABLE_CHARS
+      .chars()
+      .map(|ch| layout.try_type_char(ch).unwrap())
+      .collect();
+    assert_eq!(chords.len(), TYPABLE_CHARS.len());
+    let again = generator.generate(&freq);
+    assert!(layout.diff(&again).is_empty());
+    let mut filter = ChordFilter::new();
+    filter.forbid_ring_pinky();
+    let filtered = generator.set_chord_filter(&filter).generate(&freq);
+    assert!(TYPABLE_CHARS
+      .chars()
+      .all(|ch| filter.allows(&filtered.try_type_char(ch).unwrap())));
+    let mut constraints = Constraints::new();
+    constraints
+      .pin('q', [0, 0, 0, 1, 0, 0, 0, 0, 0, 0].into())
+      .pin('z', [1, 1, 1, 0, 0, 0, 0, 0, 0, 0].into())
+      .single_key("et")
+      .reserve_thumbs();
+    let constrained = generator.set_constraints(constraints.clone());
+    let layout = constrained.generate(&freq);
+    assert!(constraints.violations(&layout).is_empty());
+    assert_eq!(layout.try_type_char('z').ok(), constraints.pinned('z'));
+
+    let text = freq.sample_text(2000, &mut StdRng::seed_from_u64(0));
+    let score = |layout: &TenboardUnconstrained| {
+      let hss = layout.type_chars(text.chars());
+      Effort::default().updated(&hss).score()
+        + FingerAlternation::new().updated(&hss).score()
+    };
+    let random =
+      TenboardUnconstrained::new_random_with_rng(&mut StdRng::seed_from_u64(0));
+    assert!(score(&layout) < score(&random));
+  }
+
+  #[test]
+  fn test_text_score() {
+    let score = text_score("aé", FingerAlternation::new);
+    assert_eq!(score(&TenboardUnconstrained::new_random()), f32::INFINITY);
+  }
+}
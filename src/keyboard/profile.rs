@@ -0,0 +1,185 @@
+//! Contains relative finger strengths shared by metrics and effort models.
+
+use core::fmt::Display;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::hands::FINGER_NAMES;
+
+/// Relative strength of every finger. Only ratios between strengths matter:
+/// a finger twice as strong as another is expected to take twice as much
+/// load and press keys half as hard.
+///
+/// [FingerBalance](super::metric::FingerBalance),
+/// [MaxFingerLoad](super::metric::MaxFingerLoad) and
+/// [FingerEffort](super::effort::FingerEffort) can be created from a
+/// profile, so the same assumptions about hands apply to all of them.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "Strengths"))]
+pub struct FingerProfile {
+  strengths: [f32; 10],
+}
+
+impl FingerProfile {
+  /// Creates a profile where pinkies are half as strong as index fingers
+  /// and thumbs, ring fingers are two thirds as strong and middle fingers
+  /// are five sixths as strong.
+  pub fn new() -> Self {
+    let (pinky, ring, middle) = (0.5, 1.0 / 1.5, 1.0 / 1.2);
+    Self {
+      strengths: [pinky, ring, middle, 1.0, 1.0, 1.0, 1.0, middle, ring, pinky],
+    }
+  }
+
+  /// Creates a profile where all fingers are equally strong.
+  pub fn even() -> Self {
+    Self {
+      strengths: [1.0; 10],
+    }
+  }
+
+  /// Creates a profile for typists whose pinkies are much weaker than
+  /// usual: a third of index finger strength.
+  pub fn weak_pinkies() -> Self {
+    let mut profile = Self::new();
+    profile
+      .set_strength(0, 1.0 / 3.0)
+      .set_strength(9, 1.0 / 3.0);
+    profile
+  }
+
+  /// # Panics
+  ///
+  /// Panics if `strength` is not positive.
+  pub fn set_strength(&mut self, finger: usize, strength: f32) -> &mut Self {
+    assert!(strength > 0.0, "finger strength must be positive");
+    self.strengths[finger] = strength;
+    self
+  }
+
+  pub fn strength(&self, finger: usize) -> f32 {
+    self.strengths[finger]
+  }
+
+  pub fn strengths(&self) -> [f32; 10] {
+    self.strengths
+  }
+
+  /// Returns shares of load every finger should take, summing to 1.
+  pub fn shares(&self) -> [f32; 10] {
+    let sum = self.strengths.iter().sum::<f32>();
+    self.strengths.map(|s| s / sum)
+  }
+
+  /// Returns costs of pressing a key with every finger: the inverse of its
+  /// strength relative to the strongest finger.
+  pub fn costs(&self) -> [f32; 10] {
+    let max = self.strengths.iter().copied().fold(0.0, f32::max);
+    self.strengths.map(|s| max / s)
+  }
+}
+
+impl Default for FingerProfile {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl TryFrom<[f32; 10]> for FingerProfile {
+  type Error = BadStrength;
+
+  /// Creates a profile from finger strengths. Returns an error for the
+  /// first strength that is not positive.
+  fn try_from(strengths: [f32; 10]) -> Result<Self, Self::Error> {
+    match strengths.iter().position(|&s| s.is_nan() || s <= 0.0) {
+      Some(finger) => Err(BadStrength {
+        finger,
+        strength: strengths[finger],
+      }),
+      None => Ok(Self { strengths }),
+    }
+  }
+}
+
+/// Serialized form of [FingerProfile], validated on deserialization.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct Strengths {
+  strengths: [f32; 10],
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<Strengths> for FingerProfile {
+  type Error = BadStrength;
+
+  fn try_from(value: Strengths) -> Result<Self, Self::Error> {
+    value.strengths.try_into()
+  }
+}
+
+/// This error means that a finger strength is not positive.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct BadStrength {
+  pub finger: usize,
+  pub strength: f32,
+}
+
+impl Display for BadStrength {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(
+      f,
+      "strength of finger {} must be positive, got {}",
+      FINGER_NAMES[self.finger], self.strength
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_profile() {
+    let profile = FingerProfile::new();
+    let costs = profile.costs();
+    let expected = [2.0, 1.5, 1.2, 1.0, 1.0, 1.0, 1.0, 1.2, 1.5, 2.0];
+    assert!(costs
+      .iter()
+      .zip(expected)
+      .all(|(c, e)| (c - e).abs() < 1e-6));
+    assert!((profile.shares().iter().sum::<f32>() - 1.0).abs() < 1e-6);
+    assert_eq!(FingerProfile::even().shares(), [0.1; 10]);
+    assert!((FingerProfile::weak_pinkies().costs()[9] - 3.0).abs() < 1e-6);
+    let profile = FingerProfile::try_from([2.0; 10]).unwrap();
+    assert_eq!(profile.costs(), [1.0; 10]);
+    let mut strengths = [1.0; 10];
+    strengths[9] = 0.0;
+    let err = FingerProfile::try_from(strengths).unwrap_err();
+    assert_eq!(err, BadStrength {
+      finger: 9,
+      strength: 0.0,
+    });
+    assert_eq!(
+      err.to_string(),
+      "strength of finger RP must be positive, got 0"
+    );
+    strengths[9] = f32::NAN;
+    assert!(FingerProfile::try_from(strengths).is_err());
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_deserialize_profile() -> Result<(), serde_json::Error> {
+    let profile = FingerProfile::weak_pinkies();
+    let json = serde_json::to_string(&profile)?;
+    assert_eq!(serde_json::from_str::<FingerProfile>(&json)?, profile);
+    let json = r#"{"strengths":[1,1,1,1,-1,1,1,1,1,1]}"#;
+    let err = serde_json::from_str::<FingerProfile>(json).unwrap_err();
+    assert!(err
+      .to_string()
+      .starts_with("strength of finger LT must be positive, got -1"));
+    Ok(())
+  }
+}
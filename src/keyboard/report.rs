@@ -0,0 +1,332 @@
+//! Contains structured reports of metrics computed over a typed corpus.
+
+use std::io::{self, Write};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+  effort::Effort,
+  hands::{HandsState, FINGER_NAMES, HAND_NAMES},
+  metric::{
+    Breakdown,
+    FingerAlternation,
+    FingerBalance,
+    FingerUsage,
+    HandAlternation,
+    HandBalance,
+    HandRuns,
+    HandUsage,
+    MaxFingerLoad,
+    Metric,
+    MetricInfo,
+    Redirects,
+    ThumbUsage,
+    Trigrams,
+  },
+  objective::{Objective, Preset},
+};
+
+/// Scores of metrics computed over a typed corpus.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Report {
+  /// Number of typed chords.
+  pub presses: u32,
+  pub metrics: Vec<MetricReport>,
+}
+
+/// Score of a single metric with optional per-finger and per-hand values.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MetricReport {
+  pub name: String,
+  pub score: f32,
+  /// Score divided by number of typed chords.
+  pub score_per_press: f32,
+  /// Whether lower scores are better, see [MetricInfo::lower_is_better].
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, skip_serializing_if = "Option::is_none")
+  )]
+  pub lower_is_better: Option<bool>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, skip_serializing_if = "Option::is_none")
+  )]
+  pub fingers: Option<[f32; 10]>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, skip_serializing_if = "Option::is_none")
+  )]
+  pub hands: Option<[f32; 2]>,
+  /// Raw state of the metric, see [Report::add_metric_state].
+  #[cfg(feature = "serde")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub state: Option<serde_json::Value>,
+}
+
+impl Report {
+  /// Creates a report without any metrics for a corpus of `presses` chords.
+  pub fn empty(presses: u32) -> Self {
+    Self {
+      presses,
+      metrics: Vec::new(),
+    }
+  }
+
+  /// Creates a report with all built-in metrics computed over `handstates`
+  /// and the score of the [Preset::Balanced] objective named `balanced`.
+  pub fn new(handstates: &[HandsState]) -> Self {
+    let mut report = Self::empty(handstates.len() as u32);
+
+    report
+      .add_metric("finger_usage", &FingerUsage::new().updated(handstates))
+      .add_metric("hand_usage", &HandUsage::new().updated(handstates))
+      .add_metric(
+        "finger_alternation",
+        &FingerAlternation::new().updated(handstates),
+      )
+      .add_metric(
+        "hand_alternation",
+        &HandAlternation::new().updated(handstates),
+      )
+      .add_metric("redirects", &Redirects::new().updated(handstates))
+      .add_metric("hand_runs", &HandRuns::new().updated(handstates))
+      .add_metric("trigrams", &Trigrams::new().updated(handstates))
+      .add_metric("thumb_usage", &ThumbUsage::new().updated(handstates))
+      .add_metric("finger_balance", &FingerBalance::new().updated(handstates))
+      .add_metric("max_finger_load", &MaxFingerLoad::new().updated(handstates))
+      .add_metric("hand_balance", &HandBalance::new().updated(handstates))
+      .add_metric("effort", &Effort::default().updated(handstates))
+      .add_metric(
+        "balanced",
+        &Objective::from_preset(Preset::Balanced).updated(handstates),
+      );
+    report
+  }
+
+  /// Adds score of `metric` that was updated with the same corpus along
+  /// with its [Metric::breakdown].
+  pub fn add_metric(&mut self, name: &str, metric: &dyn Metric) -> &mut Self {
+    let (fingers, hands) = match metric.breakdown() {
+      Some(Breakdown::Fingers(values)) => (Some(values), None),
+      Some(Breakdown::Hands(values)) => (None, Some(values)),
+      None => (None, None),
+    };
+    self.push(name, metric.score(), metric.info(), fingers, hands);
+    self
+  }
+
+  /// Same as [Report::add_metric], but the metric is named after its
+  /// [Metric::info].
+  pub fn add(&mut self, metric: &dyn Metric) -> &mut Self {
+    self.add_metric(metric.info().name, metric)
+  }
+
+  /// Same as [Report::add_metric], but also stores serialized state of
+  /// `metric`, so raw counters are available along with the score.
+  #[cfg(feature = "serde")]
+  pub fn add_metric_state<M: Metric + Serialize>(
+    &mut self,
+    name: &str,
+    metric: &M,
+  ) -> &mut Self {
+    self.add_metric(name, metric);
+    let state = serde_json::to_value(metric).expect("metric is serializable");
+    if let Some(report) = self.metrics.last_mut() {
+      report.state = Some(state);
+    }
+    self
+  }
+
+  /// Returns report of a metric with given `name`.
+  pub fn get(&self, name: &str) -> Option<&MetricReport> {
+    self.metrics.iter().find(|m| m.name == name)
+  }
+
+  /// Writes the report as CSV with a header and a row per metric:
+  /// name, score, score per press, then per-finger and per-hand values,
+  /// which are empty if the metric doesn't track them.
+  pub fn to_csv(&self, mut writer: impl Write) -> io::Result<()> {
+    writeln!(writer, "{}", csv_header(&[]))?;
+    self.write_csv_rows(&mut writer, &[])
+  }
+
+  /// Writes a CSV row for every metric prefixed with `prefix` cells.
+  fn write_csv_rows(
+    &self,
+    writer: &mut impl Write,
+    prefix: &[&str],
+  ) -> io::Result<()> {
+    for metric in &self.metrics {
+      let fingers = metric.fingers.map(|v| v.to_vec());
+      let hands = metric.hands.map(|v| v.to_vec());
+      let values =
+        [(fingers, 10), (hands, 2)]
+          .into_iter()
+          .flat_map(|(values, len)| match values {
+            Some(values) => values.iter().map(f32::to_string).collect(),
+            None => vec![String::new(); len],
+          });
+      let row: Vec<_> = prefix
+        .iter()
+        .map(|cell| csv_escape(cell))
+        .chain([
+          csv_escape(&metric.name),
+          metric.score.to_string(),
+          metric.score_per_press.to_string(),
+        ])
+        .chain(values)
+        .collect();
+      writeln!(writer, "{}", row.join(","))?;
+    }
+    Ok(())
+  }
+
+  fn push(
+    &mut self,
+    name: &str,
+    score: f32,
+    info: MetricInfo,
+    fingers: Option<[f32; 10]>,
+    hands: Option<[f32; 2]>,
+  ) {
+    self.metrics.push(MetricReport {
+      name: name.to_owned(),
+      score,
+      score_per_press: score / self.presses.max(1) as f32,
+      lower_is_better: info.lower_is_better,
+      fingers,
+      hands,
+      #[cfg(feature = "serde")]
+      state: None,
+    });
+  }
+}
+
+/// Writes reports of several runs, e.g. of different layouts or optimizer
+/// runs, as a single CSV table. It's like [Report::to_csv], but every row
+/// starts with the name of its run.
+pub fn to_csv<'a>(
+  mut writer: impl Write,
+  runs: impl IntoIterator<Item = (&'a str, &'a Report)>,
+) -> io::Result<()> {
+  writeln!(writer, "{}", csv_header(&["run"]))?;
+  for (name, report) in runs {
+    report.write_csv_rows(&mut writer, &[name])?;
+  }
+  Ok(())
+}
+
+fn csv_header(prefix: &[&str]) -> String {
+  let columns: Vec<_> = prefix
+    .iter()
+    .chain(&["metric", "score", "score_per_press"])
+    .chain(&FINGER_NAMES)
+    .chain(&HAND_NAMES)
+    .copied()
+    .collect();
+  columns.join(",")
+}
+
+/// Quotes `cell` if it contains separators, quotes or line breaks.
+fn csv_escape(cell: &str) -> String {
+  if cell.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", cell.replace('"', "\"\""))
+  } else {
+    cell.to_owned()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{keyboard::Keyboard, testing::SyntheticKeyboard};
+
+  #[test]
+  fn test_report() {
+    let kb = SyntheticKeyboard::new_one_finger("abcpqrsdef");
+    let hss = kb.type_chars("aacffeddaaaaba".chars());
+    let mut report = Report::new(&hss);
+    report.add_metric("custom", &FingerAlternation::new().updated(&hss));
+    assert_eq!(report.presses, 14);
+    let fa = report.get("finger_alternation").unwrap();
+    assert_eq!(fa.score, 6.0);
+    assert_eq!(fa.score_per_press, 6.0 / 14.0);
+    assert_eq!(
+      fa.fingers,
+      Some([4.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0])
+    );
+    assert_eq!(fa.hands, None);
+    assert_eq!(fa.lower_is_better, Some(true));
+    assert_eq!(report.get("hand_usage").unwrap().lower_is_better, None);
+    assert_eq!(report.get("hand_usage").unwrap().hands, Some([9.0, 5.0]));
+    assert_eq!(report.get("custom").unwrap().score, 6.0);
+    assert!(report.get("missing").is_none());
+
+    let mut report = Report::empty(hss.len() as u32);
+    report.add(&FingerUsage::new().updated(&hss));
+    assert_eq!(report.get("finger_usage").unwrap().score, 14.0);
+  }
+
+  #[test]
+  fn test_to_csv() -> io::Result<()> {
+    let kb = SyntheticKeyboard::new_one_finger("abcpqrsdef");
+    let hss = kb.type_chars("aacffeddaaaaba".chars());
+    let mut report = Report::empty(hss.len() as u32);
+    report
+      .add_metric(
+        "finger_alternation",
+        &FingerAlternation::new().updated(&hss),
+      )
+      .add_metric("hand, usage", &HandUsage::new().updated(&hss));
+    let mut csv = Vec::new();
+    report.to_csv(&mut csv)?;
+    let csv = String::from_utf8(csv).unwrap();
+    let lines: Vec<_> = csv.lines().collect();
+    assert_eq!(
+      lines[0],
+      "metric,score,score_per_press,LP,LR,LM,LI,LT,RT,RI,RM,RR,RP,L,R"
+    );
+    assert_eq!(
+      lines[1],
+      format!(
+        "finger_alternation,6,{},4,0,0,0,0,0,0,1,0,1,,",
+        6.0f32 / 14.0
+      )
+    );
+    assert_eq!(lines[2], format!("\"hand, usage\",14,1,,,,,,,,,,,9,5"));
+
+    let mut csv = Vec::new();
+    to_csv(&mut csv, [("first", &report), ("second", &report)])?;
+    let csv = String::from_utf8(csv).unwrap();
+    assert_eq!(csv.lines().count(), 5);
+    assert!(csv.starts_with("run,metric,"));
+    assert!(csv
+      .lines()
+      .nth(4)
+      .unwrap()
+      .starts_with("second,\"hand, usage\","));
+    Ok(())
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_report_serialization() -> Result<(), serde_json::Error> {
+    let kb = SyntheticKeyboard::new_one_finger("abcpqrsdef");
+    let report = Report::new(&kb.type_chars("abcdef".chars()));
+    let json = serde_json::to_string(&report)?;
+    assert!(json.contains("\"name\":\"redirects\""));
+    assert_eq!(serde_json::from_str::<Report>(&json)?, report);
+
+    let hss = kb.type_chars("aacffeddaaaaba".chars());
+    let mut report = Report::new(&hss);
+    report.add_metric("custom", &FingerAlternation::new().updated(&hss));
+    assert_eq!(report.get("custom").unwrap().state, None);
+    report.add_metric_state("raw", &FingerUsage::new().updated(&hss));
+    let state = report.get("raw").unwrap().state.clone().unwrap();
+    assert_eq!(state["presses"][0], 7);
+    Ok(())
+  }
+}
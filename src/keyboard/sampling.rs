@@ -0,0 +1,248 @@
+//! Contains score statistics of random layouts, which put scores of
+//! optimized layouts in context.
+
+use super::{hands::HandsState, metric::Metric, Keyboard};
+
+type NewMetric<'a> = Box<dyn Fn(&[HandsState]) -> Box<dyn Metric> + 'a>;
+
+/// Generates random layouts, scores every one of them over a text with
+/// registered metrics and collects score distributions.
+///
+/// ```
+/// use tenboard::keyboard::{
+///   layout::tenboard::{Tenboard, TenboardUnconstrained},
+///   metric::{FingerAlternation, Metric},
+///   sampling::RandomSampling,
+///   Keyboard,
+/// };
+///
+/// let text = "the quick brown fox jumps over the lazy dog";
+/// let result = RandomSampling::new(20)
+///   .add_metric("finger_alternation", FingerAlternation::new)
+///   .run(TenboardUnconstrained::new_random, text);
+/// let distribution = result.get("finger_alternation").unwrap();
+/// assert_eq!(distribution.len(), 20);
+///
+/// let optimized = TenboardUnconstrained::new_random();
+/// let handstates = optimized.type_chars(text.chars());
+/// let score = FingerAlternation::new().updated(&handstates).score();
+/// let share = distribution.better_than(score);
+/// assert!((0.0..=1.0).contains(&share));
+/// ```
+pub struct RandomSampling<'a> {
+  samples: usize,
+  metrics: Vec<(String, bool, NewMetric<'a>)>,
+}
+
+impl<'a> RandomSampling<'a> {
+  /// Creates a sampling of `samples` random layouts without metrics.
+  pub fn new(samples: usize) -> Self {
+    Self {
+      samples,
+      metrics: Vec::new(),
+    }
+  }
+
+  /// Registers a metric created by `new_metric` for every layout. Its
+  /// [Metric::info] tells whether lower scores are better, which is assumed
+  /// for metrics without a direction.
+  pub fn add_metric<M: Metric + 'static>(
+    &mut self,
+    name: &str,
+    new_metric: impl Fn() -> M + 'a,
+  ) -> &mut Self {
+    let lower_is_better =
+      new_metric().info().lower_is_better.unwrap_or(true);
+    self.metrics.push((
+      name.to_owned(),
+      lower_is_better,
+      Box::new(move |handstates| Box::new(new_metric().updated(handstates))),
+    ));
+    self
+  }
+
+  /// Types `text` with layouts created by `new_layout` and scores them.
+  /// Layouts that can't type `text` are skipped and counted.
+  pub fn run<L: Keyboard>(
+    &self,
+    mut new_layout: impl FnMut() -> L,
+    text: &str,
+  ) -> SamplingResult {
+    let mut scores = vec![Vec::new(); self.metrics.len()];
+    let mut failures = 0;
+    for _ in 0..self.samples {
+      let Ok(handstates) = new_layout().try_type_text(text) else {
+        failures += 1;
+        continue;
+      };
+      for (scores, (_, _, new_metric)) in scores.iter_mut().zip(&self.metrics) {
+        scores.push(new_metric(&handstates).score());
+      }
+    }
+    let distributions = self
+      .metrics
+      .iter()
+      .zip(scores)
+      .map(|((name, lower_is_better, _), scores)| {
+        (name.clone(), Distribution::new(scores, *lower_is_better))
+      })
+      .collect();
+    SamplingResult {
+      distributions,
+      failures,
+    }
+  }
+}
+
+/// Score distributions of every metric of a [RandomSampling].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SamplingResult {
+  distributions: Vec<(String, Distribution)>,
+  failures: usize,
+}
+
+impl SamplingResult {
+  /// Returns distribution of scores of a metric with given `name`.
+  pub fn get(&self, name: &str) -> Option<&Distribution> {
+    self
+      .distributions
+      .iter()
+      .find(|(n, _)| n == name)
+      .map(|(_, distribution)| distribution)
+  }
+
+  /// Returns metric names with their distributions in order they were
+  /// added.
+  pub fn distributions(&self) -> &[(String, Distribution)] {
+    &self.distributions
+  }
+
+  /// Returns number of layouts that couldn't type the text.
+  pub fn failures(&self) -> usize {
+    self.failures
+  }
+}
+
+/// Distribution of scores of random layouts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution {
+  scores: Vec<f32>,
+  lower_is_better: bool,
+}
+
+impl Distribution {
+  /// Creates a distribution of `scores` of a metric, where lower scores are
+  /// better if `lower_is_better` is `true`.
+  pub fn new(mut scores: Vec<f32>, lower_is_better: bool) -> Self {
+    scores.sort_by(f32::total_cmp);
+    Self {
+      scores,
+      lower_is_better,
+    }
+  }
+
+  /// Returns scores from the lowest to the highest.
+  pub fn scores(&self) -> &[f32] {
+    &self.scores
+  }
+
+  pub fn len(&self) -> usize {
+    self.scores.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.scores.is_empty()
+  }
+
+  /// Returns the mean score or NaN if there are no scores.
+  pub fn mean(&self) -> f32 {
+    self.scores.iter().sum::<f32>() / self.scores.len() as f32
+  }
+
+  /// Returns the population standard deviation of scores or NaN if there
+  /// are no scores.
+  pub fn stddev(&self) -> f32 {
+    let mean = self.mean();
+    let variance = self.scores.iter().map(|s| (s - mean).powi(2)).sum::<f32>()
+      / self.scores.len() as f32;
+    variance.sqrt()
+  }
+
+  /// Returns the score below which `percentile` percents of scores lie,
+  /// interpolating between the closest scores. Returns NaN if there are no
+  /// scores.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `percentile` is not in 0..=100.
+  pub fn percentile(&self, percentile: f32) -> f32 {
+    assert!(
+      (0.0..=100.0).contains(&percentile),
+      "percentile must be in 0..=100"
+    );
+    let Some(last) = self.scores.len().checked_sub(1) else {
+      return f32::NAN;
+    };
+    let rank = percentile / 100.0 * last as f32;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    let fraction = rank - lower as f32;
+    self.scores[lower] + (self.scores[upper] - self.scores[lower]) * fraction
+  }
+
+  /// Returns share of random layouts `score` is strictly better than, e.g.
+  /// 0.999 for a score better than 99.9% of random layouts.
+  pub fn better_than(&self, score: f32) -> f32 {
+    let worse = match self.lower_is_better {
+      true => self.scores.iter().filter(|&&s| s > score).count(),
+      false => self.scores.iter().filter(|&&s| s < score).count(),
+    };
+    worse as f32 / self.scores.len().max(1) as f32
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    keyboard::metric::{ChordEntropy, FingerAlternation},
+    testing::SyntheticKeyboard,
+  };
+
+  #[test]
+  fn test_distribution() {
+    let distribution = Distribution::new(vec![4.0, 1.0, 3.0, 2.0], true);
+    assert_eq!(distribution.scores(), [1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(distribution.mean(), 2.5);
+    assert!((distribution.stddev() - 1.118034).abs() < 1e-6);
+    assert_eq!(distribution.percentile(0.0), 1.0);
+    assert_eq!(distribution.percentile(50.0), 2.5);
+    assert_eq!(distribution.percentile(100.0), 4.0);
+    assert_eq!(distribution.better_than(1.5), 0.75);
+    assert_eq!(distribution.better_than(0.0), 1.0);
+    let distribution = Distribution::new(vec![4.0, 1.0, 3.0, 2.0], false);
+    assert_eq!(distribution.better_than(1.5), 0.25);
+    assert!(Distribution::new(Vec::new(), true)
+      .percentile(50.0)
+      .is_nan());
+  }
+
+  #[test]
+  fn test_random_sampling() {
+    let layouts = ["abcpqrsdef", "aaaaaaaaaa", "fedcbapqrs"];
+    let mut layouts = layouts.iter().cycle();
+    let result = RandomSampling::new(5)
+      .add_metric("finger_alternation", FingerAlternation::new)
+      .add_metric("chord_entropy", ChordEntropy::new)
+      .run(
+        || SyntheticKeyboard::new_one_finger(layouts.next().unwrap()),
+        "abcabc",
+      );
+    assert_eq!(result.failures(), 2);
+    let fa = result.get("finger_alternation").unwrap();
+    assert_eq!(fa.len(), 3);
+    assert_eq!(fa.mean(), 0.0);
+    assert!(!result.get("chord_entropy").unwrap().lower_is_better);
+    assert_eq!(result.distributions().len(), 2);
+    assert!(result.get("missing").is_none());
+  }
+}
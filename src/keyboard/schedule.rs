@@ -0,0 +1,149 @@
+//! Contains cooling schedules of [Annealing](super::optimize::Annealing).
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Describes how temperature of simulated annealing changes over time.
+pub trait Schedule {
+  /// Returns temperature at `progress` of the run, going from 0 at the first
+  /// iteration to 1 at the last one. `stagnation` is the number of
+  /// iterations since the best score last improved.
+  fn temperature(&self, progress: f32, stagnation: u32) -> f32;
+}
+
+/// Schedule where temperature decreases geometrically from the initial to
+/// the final one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Geometric {
+  initial_temperature: f32,
+  final_temperature: f32,
+}
+
+impl Geometric {
+  pub fn new(initial_temperature: f32, final_temperature: f32) -> Self {
+    Self {
+      initial_temperature,
+      final_temperature,
+    }
+  }
+}
+
+impl Default for Geometric {
+  fn default() -> Self {
+    Self::new(1.0e-2, 1.0e-5)
+  }
+}
+
+impl Schedule for Geometric {
+  fn temperature(&self, progress: f32, _: u32) -> f32 {
+    self.initial_temperature
+      * (self.final_temperature / self.initial_temperature).powf(progress)
+  }
+}
+
+/// Schedule where temperature decreases linearly from the initial to the
+/// final one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Linear {
+  initial_temperature: f32,
+  final_temperature: f32,
+}
+
+impl Linear {
+  pub fn new(initial_temperature: f32, final_temperature: f32) -> Self {
+    Self {
+      initial_temperature,
+      final_temperature,
+    }
+  }
+}
+
+impl Default for Linear {
+  fn default() -> Self {
+    Self::new(1.0e-2, 0.0)
+  }
+}
+
+impl Schedule for Linear {
+  fn temperature(&self, progress: f32, _: u32) -> f32 {
+    self.initial_temperature
+      + (self.final_temperature - self.initial_temperature) * progress
+  }
+}
+
+/// Schedule that follows another one, but reheats when the search gets
+/// stuck: every [Adaptive::set_patience] iterations without improvement
+/// multiply temperature by [Adaptive::set_factor]. Temperature never
+/// exceeds the initial temperature of the base schedule.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Adaptive<S = Geometric> {
+  base: S,
+  patience: u32,
+  factor: f32,
+}
+
+impl<S: Schedule> Adaptive<S> {
+  /// Creates a schedule that doubles temperature of `base` every 500
+  /// iterations without improvement.
+  pub fn new(base: S) -> Self {
+    Self {
+      base,
+      patience: 500,
+      factor: 2.0,
+    }
+  }
+
+  /// # Panics
+  ///
+  /// Panics if `patience` is 0.
+  pub fn set_patience(&mut self, patience: u32) -> &mut Self {
+    assert!(patience > 0, "patience must be positive");
+    self.patience = patience;
+    self
+  }
+
+  pub fn set_factor(&mut self, factor: f32) -> &mut Self {
+    self.factor = factor;
+    self
+  }
+}
+
+impl Default for Adaptive {
+  fn default() -> Self {
+    Self::new(Geometric::default())
+  }
+}
+
+impl<S: Schedule> Schedule for Adaptive<S> {
+  fn temperature(&self, progress: f32, stagnation: u32) -> f32 {
+    let reheats = (stagnation / self.patience) as i32;
+    let temperature =
+      self.base.temperature(progress, stagnation) * self.factor.powi(reheats);
+    temperature.min(self.base.temperature(0.0, 0))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_schedules() {
+    let geometric = Geometric::new(1.0, 0.01);
+    assert_eq!(geometric.temperature(0.0, 0), 1.0);
+    assert!((geometric.temperature(0.5, 0) - 0.1).abs() < 1e-6);
+    assert!((geometric.temperature(1.0, 0) - 0.01).abs() < 1e-6);
+    let linear = Linear::new(1.0, 0.0);
+    assert_eq!(linear.temperature(0.25, 100), 0.75);
+
+    let mut adaptive = Adaptive::new(geometric);
+    adaptive.set_patience(10).set_factor(5.0);
+    let base = geometric.temperature(0.5, 0);
+    assert_eq!(adaptive.temperature(0.5, 9), base);
+    assert!((adaptive.temperature(0.5, 10) - 5.0 * base).abs() < 1e-6);
+    assert_eq!(adaptive.temperature(0.5, 1000), 1.0);
+  }
+}
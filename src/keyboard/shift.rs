@@ -0,0 +1,99 @@
+//! Describes how layouts type shifted chars, e.g. uppercase letters.
+
+use alloc::{vec, vec::Vec};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::hands::HandsState;
+
+/// Describes how shifted chars are derived from unshifted ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ShiftPolicy<const N: usize = 10> {
+  /// Shifted chars have chords of their own.
+  #[default]
+  Dedicated,
+  /// Shifted chars are typed with the chord of the unshifted char combined
+  /// with the modifier chord, e.g. a thumb.
+  Modifier(HandsState<N>),
+  /// The shift chord is typed before the chord of the unshifted char.
+  Sticky(HandsState<N>),
+}
+
+impl<const N: usize> ShiftPolicy<N> {
+  /// Returns chords typing a shifted char given chord `base` of its
+  /// unshifted char. Returns `None` for [ShiftPolicy::Dedicated].
+  pub fn shifted(&self, base: &HandsState<N>) -> Option<Vec<HandsState<N>>> {
+    match self {
+      ShiftPolicy::Dedicated => None,
+      ShiftPolicy::Modifier(modifier) => Some(vec![base.combine(modifier)]),
+      ShiftPolicy::Sticky(shift) => Some(vec![*shift, *base]),
+    }
+  }
+
+  /// Returns `true` if `handstate` presses the shift: a chord combined with
+  /// the modifier or the sticky shift chord itself.
+  pub fn is_shift_press(&self, handstate: &HandsState<N>) -> bool {
+    match self {
+      ShiftPolicy::Dedicated => false,
+      ShiftPolicy::Modifier(modifier) => {
+        modifier.is_subset_of(handstate)
+          && handstate.count_pressed() > modifier.count_pressed()
+      }
+      ShiftPolicy::Sticky(shift) => handstate == shift,
+    }
+  }
+}
+
+/// Implemented by layouts to tell how they type shifted chars.
+pub trait Shift<const N: usize = 10> {
+  /// Returns the shift policy of the layout. By default shifted chars have
+  /// chords of their own.
+  fn shift_policy(&self) -> ShiftPolicy<N> {
+    ShiftPolicy::Dedicated
+  }
+}
+
+/// Returns the char `ch` is a shifted form of, e.g. 'a' for 'A'. Returns
+/// `None` if `ch` isn't uppercase or its lowercase form isn't a single char.
+pub fn unshifted(ch: char) -> Option<char> {
+  if !ch.is_uppercase() {
+    return None;
+  }
+  let mut lower = ch.to_lowercase();
+  match (lower.next(), lower.next()) {
+    (Some(lower), None) => Some(lower),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_shift_policy() {
+    let base: HandsState = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into();
+    assert_eq!(ShiftPolicy::Dedicated.shifted(&base), None);
+    let modifier = ShiftPolicy::Modifier(HandsState::left_thumb());
+    assert_eq!(
+      modifier.shifted(&base),
+      Some(vec![[1, 0, 0, 0, 1, 0, 0, 0, 0, 0].into()])
+    );
+    assert!(modifier.is_shift_press(&[1, 0, 0, 0, 1, 0, 0, 0, 0, 0].into()));
+    assert!(!modifier.is_shift_press(&HandsState::left_thumb()));
+    assert!(!modifier.is_shift_press(&base));
+    let sticky = ShiftPolicy::Sticky(HandsState::both_thumbs());
+    assert_eq!(
+      sticky.shifted(&base),
+      Some(vec![HandsState::both_thumbs(), base])
+    );
+    assert!(sticky.is_shift_press(&HandsState::both_thumbs()));
+
+    assert_eq!(unshifted('A'), Some('a'));
+    assert_eq!(unshifted('Ж'), Some('ж'));
+    assert_eq!(unshifted('a'), None);
+    assert_eq!(unshifted('İ'), None);
+  }
+}
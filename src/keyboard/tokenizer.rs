@@ -0,0 +1,254 @@
+//! Contains preprocessing of text that splits it into tokens before it's
+//! typed, so that skipped parts and whitespace are handled the same way for
+//! every corpus.
+
+use alloc::{
+  borrow::{Cow, ToOwned},
+  string::String,
+  vec::Vec,
+};
+
+use super::SeparatorPolicy;
+
+/// Splits text into tokens typed with
+/// [Keyboard::try_type_tokens](super::Keyboard::try_type_tokens). Parts of
+/// text between tokens are not typed, instead tokens are joined with
+/// separators of [Tokenizer::separator_policy].
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use tenboard::keyboard::{
+///   layout::standard::Qwerty,
+///   tokenizer::{Tokenizer, WhitespaceTokenizer},
+///   Keyboard,
+/// };
+///
+/// let mut tokenizer = WhitespaceTokenizer::new();
+/// tokenizer.set_skip_urls(true);
+/// let text = "see  https://example.com\n\tfor details";
+/// assert_eq!(tokenizer.normalize(text), "see for details");
+/// let handstates = Qwerty::new().type_tokens(text, &tokenizer);
+/// assert_eq!(handstates.len(), 15);
+/// # }
+/// ```
+pub trait Tokenizer {
+  /// Returns tokens of `text` in order.
+  fn tokens<'a>(&self, text: &'a str) -> Vec<Cow<'a, str>>;
+
+  /// Returns what is typed between tokens. By default tokens are typed
+  /// back to back.
+  fn separator_policy(&self) -> SeparatorPolicy {
+    SeparatorPolicy::None
+  }
+
+  /// Returns `text` as it's typed: its tokens joined with separators.
+  fn normalize(&self, text: &str) -> String {
+    let policy = self.separator_policy();
+    let mut normalized = String::with_capacity(text.len());
+    for (i, token) in self.tokens(text).iter().enumerate() {
+      normalized.push_str(policy.separator(i));
+      normalized.push_str(token);
+    }
+    normalized
+  }
+}
+
+/// Returns `true` if `word` looks like a URL or an email address.
+fn is_url(word: &str) -> bool {
+  ["http://", "https://", "ftp://", "www.", "mailto:"]
+    .iter()
+    .any(|prefix| word.starts_with(prefix))
+    || word.contains('@') && word.contains('.')
+}
+
+/// Splits text into words at runs of whitespace, so that words are typed
+/// with single spaces between them.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WhitespaceTokenizer {
+  skip_urls: bool,
+}
+
+impl WhitespaceTokenizer {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sets whether URLs and email addresses are skipped.
+  pub fn set_skip_urls(&mut self, skip_urls: bool) -> &mut Self {
+    self.skip_urls = skip_urls;
+    self
+  }
+}
+
+impl Tokenizer for WhitespaceTokenizer {
+  fn tokens<'a>(&self, text: &'a str) -> Vec<Cow<'a, str>> {
+    text
+      .split_whitespace()
+      .filter(|word| !(self.skip_urls && is_url(word)))
+      .map(Cow::Borrowed)
+      .collect()
+  }
+
+  fn separator_policy(&self) -> SeparatorPolicy {
+    SeparatorPolicy::Space
+  }
+}
+
+/// Splits text into sentences ending with '.', '!' or '?' followed by
+/// whitespace. Whitespace inside sentences is collapsed into single spaces.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SentenceTokenizer {
+  skip_urls: bool,
+  one_per_line: bool,
+}
+
+impl SentenceTokenizer {
+  /// Creates a tokenizer typing sentences with spaces between them.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sets whether URLs and email addresses are skipped.
+  pub fn set_skip_urls(&mut self, skip_urls: bool) -> &mut Self {
+    self.skip_urls = skip_urls;
+    self
+  }
+
+  /// Sets whether sentences are typed on separate lines instead of being
+  /// separated with spaces.
+  pub fn set_one_per_line(&mut self, one_per_line: bool) -> &mut Self {
+    self.one_per_line = one_per_line;
+    self
+  }
+}
+
+impl Tokenizer for SentenceTokenizer {
+  fn tokens<'a>(&self, text: &'a str) -> Vec<Cow<'a, str>> {
+    let mut sentences = Vec::new();
+    let mut sentence: Vec<&str> = Vec::new();
+    for word in text.split_whitespace() {
+      if !(self.skip_urls && is_url(word)) {
+        sentence.push(word);
+      }
+      if word.ends_with(['.', '!', '?']) && !sentence.is_empty() {
+        sentences.push(Cow::Owned(sentence.join(" ")));
+        sentence.clear();
+      }
+    }
+    if !sentence.is_empty() {
+      sentences.push(Cow::Owned(sentence.join(" ")));
+    }
+    sentences
+  }
+
+  fn separator_policy(&self) -> SeparatorPolicy {
+    match self.one_per_line {
+      true => SeparatorPolicy::Newline,
+      false => SeparatorPolicy::Space,
+    }
+  }
+}
+
+/// Splits source code into lines. Trailing whitespace and blank lines are
+/// dropped, and so is indentation by default, since editors usually insert
+/// it automatically.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CodeTokenizer {
+  keep_indentation: bool,
+  line_comment: Option<String>,
+}
+
+impl CodeTokenizer {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sets whether leading whitespace of lines is typed.
+  pub fn set_keep_indentation(&mut self, keep_indentation: bool) -> &mut Self {
+    self.keep_indentation = keep_indentation;
+    self
+  }
+
+  /// Makes the tokenizer skip line comments starting with `prefix`, e.g.
+  /// "//" or "#". Comment markers inside string literals aren't told apart
+  /// from real comments.
+  pub fn set_line_comment(&mut self, prefix: Option<&str>) -> &mut Self {
+    self.line_comment = prefix.map(str::to_owned);
+    self
+  }
+}
+
+impl Tokenizer for CodeTokenizer {
+  fn tokens<'a>(&self, text: &'a str) -> Vec<Cow<'a, str>> {
+    text
+      .lines()
+      .map(|line| match &self.line_comment {
+        Some(prefix) => line.find(prefix.as_str()).map_or(line, |i| &line[..i]),
+        None => line,
+      })
+      .map(|line| match self.keep_indentation {
+        true => line.trim_end(),
+        false => line.trim(),
+      })
+      .filter(|line| !line.is_empty())
+      .map(Cow::Borrowed)
+      .collect()
+  }
+
+  fn separator_policy(&self) -> SeparatorPolicy {
+    SeparatorPolicy::Newline
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{keyboard::Keyboard, testing::SyntheticKeyboard};
+
+  #[test]
+  fn test_whitespace_tokenizer() {
+    let mut tokenizer = WhitespaceTokenizer::new();
+    let text = " mail me@example.com\n\nor visit www.example.com ";
+    assert_eq!(
+      tokenizer.normalize(text),
+      "mail me@example.com or visit www.example.com"
+    );
+    tokenizer.set_skip_urls(true);
+    assert_eq!(tokenizer.tokens(text), ["mail", "or", "visit"]);
+    assert_eq!(tokenizer.normalize(""), "");
+  }
+
+  #[test]
+  fn test_sentence_tokenizer() {
+    let mut tokenizer = SentenceTokenizer::new();
+    tokenizer.set_one_per_line(true).set_skip_urls(true);
+    let text = "It works.  Does it?\nSee\thttps://example.com. Yes";
+    assert_eq!(tokenizer.tokens(text), [
+      "It works.",
+      "Does it?",
+      "See",
+      "Yes"
+    ]);
+    assert_eq!(tokenizer.normalize(text), "It works.\nDoes it?\nSee\nYes");
+  }
+
+  #[test]
+  fn test_code_tokenizer() {
+    let mut tokenizer = CodeTokenizer::new();
+    let code = "fn main() {\n  // greet\n  hi(); // twice\n\n}\n";
+    assert_eq!(
+      tokenizer.normalize(code),
+      "fn main() {\n// greet\nhi(); // twice\n}"
+    );
+    tokenizer
+      .set_keep_indentation(true)
+      .set_line_comment(Some("//"));
+    assert_eq!(tokenizer.tokens(code), ["fn main() {", "  hi();", "}"]);
+
+    let kb = SyntheticKeyboard::new_one_finger("ab \n");
+    assert_eq!(
+      kb.try_type_tokens("a  b\n\n  ab", &CodeTokenizer::new()),
+      Ok(kb.type_chars("a  b\nab".chars()))
+    );
+  }
+}
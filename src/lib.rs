@@ -1 +1,27 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "cache")]
+pub mod cache;
+mod compat;
+#[cfg(feature = "std")]
+pub mod corpus;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod keyboard;
+#[cfg(feature = "std")]
+pub mod pipeline;
+pub mod prelude;
+#[cfg(feature = "svg")]
+pub mod render;
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod trainer;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "std")]
+pub use pipeline::{run, PipelineConfig, PipelineResult};
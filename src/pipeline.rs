@@ -0,0 +1,374 @@
+//! Contains one-call pipeline that covers the common path: load a corpus,
+//! load or generate a layout, optimize it, report its efficiency and export
+//! it for firmware.
+
+#[cfg(feature = "serde")]
+use std::fs;
+use std::{fmt::Display, io, path::PathBuf};
+
+use rand::prelude::*;
+
+#[cfg(feature = "serde")]
+use crate::keyboard::layout::metadata::WithMetadata;
+use crate::{
+  corpus,
+  export::{to_kanata, to_qmk, to_zmk, Board, NoKeycode},
+  keyboard::{
+    badge::Badge,
+    graph::TransitionGraph,
+    layout::{
+      constraints::{Constraints, Unsatisfiable},
+      metadata::LayoutMetadata,
+      tenboard::{Tenboard, TenboardUnconstrained},
+    },
+    mapper::CharMapper,
+    metric::Metric,
+    objective::{Objective, Preset},
+    optimize::{text_score, Annealing},
+    report::Report,
+    Keyboard,
+    TypingError,
+  },
+};
+
+/// Describes where the corpus comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CorpusSource {
+  Text(String),
+  File(PathBuf),
+}
+
+/// Describes where the initial layout comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutSource {
+  /// Random layout, respecting constraints if there are any.
+  Random,
+  /// JSON file with a serialized [TenboardUnconstrained], optionally with
+  /// [LayoutMetadata] like
+  /// [WithMetadata](crate::keyboard::layout::metadata::WithMetadata).
+  #[cfg(feature = "serde")]
+  File(PathBuf),
+  /// JSON string with a serialized [TenboardUnconstrained], optionally with
+  /// metadata.
+  #[cfg(feature = "serde")]
+  Json(String),
+}
+
+/// Settings of [run].
+#[derive(Debug, Clone)]
+pub struct PipelineConfig {
+  pub corpus: CorpusSource,
+  /// Preprocessing applied to the corpus before it's typed.
+  pub mapper: Option<CharMapper>,
+  pub layout: LayoutSource,
+  /// Objective used for optimization and the overall score.
+  pub objective: Preset,
+  /// Optimizer to run. If `None`, the layout is only scored.
+  pub optimizer: Option<Annealing>,
+  /// Constraints the layout must follow. Loaded layouts breaking them are
+  /// rejected.
+  pub constraints: Option<Constraints>,
+  /// Board to export the layout for. If `None`, nothing is exported.
+  pub board: Option<Board>,
+  /// Seed of random layout generation and optimization. If `None`, every
+  /// run is different.
+  pub seed: Option<u64>,
+}
+
+impl Default for PipelineConfig {
+  fn default() -> Self {
+    Self {
+      corpus: CorpusSource::Text(String::new()),
+      mapper: None,
+      layout: LayoutSource::Random,
+      objective: Preset::Balanced,
+      optimizer: None,
+      constraints: None,
+      board: None,
+      seed: None,
+    }
+  }
+}
+
+/// Output of [run].
+#[derive(Debug, Clone)]
+pub struct PipelineResult {
+  pub layout: TenboardUnconstrained,
+  /// Score of the configured objective.
+  pub score: f32,
+  pub report: Report,
+  pub badge: Badge,
+  pub graph: TransitionGraph,
+  /// Configs of the layout for [PipelineConfig::board].
+  pub exports: Option<Exports>,
+  /// Metadata of the loaded layout updated with the corpus hash, the
+  /// optimizer and scores of the report.
+  pub metadata: LayoutMetadata,
+}
+
+/// Layout converted into firmware and key remapper configs, see
+/// [export](crate::export).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exports {
+  pub qmk: String,
+  pub zmk: String,
+  pub kanata: String,
+}
+
+/// This error means that some stage of [run] failed.
+#[derive(Debug)]
+pub enum PipelineError {
+  Io(io::Error),
+  #[cfg(feature = "serde")]
+  Json(serde_json::Error),
+  Typing(TypingError),
+  Unsatisfiable(Unsatisfiable),
+  /// Chars of the loaded layout whose chords break the constraints.
+  Violations(Vec<char>),
+  NoKeycode(NoKeycode),
+}
+
+impl Display for PipelineError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      PipelineError::Io(e) => write!(f, "{e}"),
+      #[cfg(feature = "serde")]
+      PipelineError::Json(e) => write!(f, "{e}"),
+      PipelineError::Typing(e) => write!(f, "{e}"),
+      PipelineError::Unsatisfiable(e) => write!(f, "{e}"),
+      PipelineError::Violations(chars) => {
+        write!(f, "chords of {chars:?} break the constraints")
+      }
+      PipelineError::NoKeycode(e) => write!(f, "{e}"),
+    }
+  }
+}
+
+impl From<io::Error> for PipelineError {
+  fn from(value: io::Error) -> Self {
+    PipelineError::Io(value)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for PipelineError {
+  fn from(value: serde_json::Error) -> Self {
+    PipelineError::Json(value)
+  }
+}
+
+impl From<TypingError> for PipelineError {
+  fn from(value: TypingError) -> Self {
+    PipelineError::Typing(value)
+  }
+}
+
+impl From<Unsatisfiable> for PipelineError {
+  fn from(value: Unsatisfiable) -> Self {
+    PipelineError::Unsatisfiable(value)
+  }
+}
+
+impl From<NoKeycode> for PipelineError {
+  fn from(value: NoKeycode) -> Self {
+    PipelineError::NoKeycode(value)
+  }
+}
+
+/// Runs the whole pipeline described by `config`.
+pub fn run(config: PipelineConfig) -> Result<PipelineResult, PipelineError> {
+  let text = match config.corpus {
+    CorpusSource::Text(text) => text,
+    CorpusSource::File(path) => corpus::read_to_string(path)?,
+  };
+  let text = match &config.mapper {
+    Some(mapper) => mapper.map_str(&text),
+    None => text,
+  };
+  let mut rng = match config.seed {
+    Some(seed) => StdRng::seed_from_u64(seed),
+    None => StdRng::from_entropy(),
+  };
+  let (mut layout, metadata) = match config.layout {
+    LayoutSource::Random => match &config.constraints {
+      Some(c) => (
+        TenboardUnconstrained::new_random_with_constraints_and_rng(
+          c, &mut rng,
+        )?,
+        None,
+      ),
+      None => (TenboardUnconstrained::new_random_with_rng(&mut rng), None),
+    },
+    #[cfg(feature = "serde")]
+    LayoutSource::File(path) => {
+      serde_json::from_str::<WithMetadata<_>>(&fs::read_to_string(path)?)?
+        .into_parts()
+    }
+    #[cfg(feature = "serde")]
+    LayoutSource::Json(json) => {
+      serde_json::from_str::<WithMetadata<_>>(&json)?.into_parts()
+    }
+  };
+  if let Some(constraints) = &config.constraints {
+    let violations = constraints.violations(&layout);
+    if !violations.is_empty() {
+      return Err(PipelineError::Violations(violations));
+    }
+  }
+  let mut metadata: LayoutMetadata = metadata.unwrap_or_default();
+  metadata.set_corpus(&text);
+  if let Some(mut optimizer) = config.optimizer {
+    if let Some(constraints) = config.constraints {
+      optimizer.set_constraints(constraints);
+    }
+    if let Some(seed) = config.seed {
+      optimizer.set_seed(seed);
+    }
+    metadata.set_optimizer(&optimizer);
+    let objective = config.objective;
+    layout = optimizer.optimize(
+      layout,
+      text_score(&text, || Objective::from_preset(objective)),
+    );
+  }
+  let handstates = layout.try_type_text(&text)?;
+  let score = Objective::from_preset(config.objective)
+    .updated(&handstates)
+    .score();
+  let report = Report::new(&handstates);
+  metadata.set_scores(&report);
+  let badge = Badge::new(&layout, &text).expect("corpus is already typed");
+  let graph = TransitionGraph::new().updated(&handstates);
+  let exports = match &config.board {
+    Some(board) => Some(Exports {
+      qmk: to_qmk(&layout, board)?,
+      zmk: to_zmk(&layout, board)?,
+      kanata: to_kanata(&layout, board)?,
+    }),
+    None => None,
+  };
+  Ok(PipelineResult {
+    layout,
+    score,
+    report,
+    badge,
+    graph,
+    exports,
+    metadata,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::keyboard::{hands::HandsState, layout::metadata::corpus_hash};
+
+  #[test]
+  fn test_run() {
+    let mut constraints = Constraints::new();
+    constraints.pin('e', HandsState::left_thumb());
+    let mut optimizer = Annealing::new();
+    optimizer.set_iterations(100);
+    let result = run(PipelineConfig {
+      corpus: CorpusSource::Text("the quick brown fox".to_owned()),
+      optimizer: Some(optimizer),
+      constraints: Some(constraints),
+      board: Some(Board::home_row()),
+      ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(
+      result.layout.try_type_char('e'),
+      Ok(HandsState::left_thumb())
+    );
+    assert_eq!(result.report.presses, 19);
+    assert_eq!(result.graph.nodes().len(), 16);
+    let exports = result.exports.unwrap();
+    assert!(exports.qmk.contains("combo_t key_combos[] = {"));
+    assert!(exports.zmk.contains("compatible = \"zmk,combos\";"));
+    assert!(exports.kanata.starts_with("(defcfg concurrent-tap-hold yes)"));
+    assert!(result.metadata.optimizer.is_some());
+    assert_eq!(
+      result.metadata.corpus_hash,
+      Some(corpus_hash("the quick brown fox"))
+    );
+    assert_eq!(
+      result.metadata.scores.get("finger_alternation"),
+      Some(
+        &result
+          .report
+          .get("finger_alternation")
+          .unwrap()
+          .score_per_press
+      )
+    );
+  }
+
+  #[test]
+  fn test_run_mapped() {
+    let result = run(PipelineConfig {
+      corpus: CorpusSource::Text("“Déjà vu”".to_owned()),
+      mapper: Some(CharMapper::ascii()),
+      ..Default::default()
+    });
+    assert_eq!(result.unwrap().report.presses, 9);
+  }
+
+  #[test]
+  fn test_run_errors() {
+    let result = run(PipelineConfig {
+      corpus: CorpusSource::Text("é".to_owned()),
+      ..Default::default()
+    });
+    assert!(matches!(
+      result,
+      Err(PipelineError::Typing(TypingError { ch: 'é', .. }))
+    ));
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_run_violations() {
+    let mut constraints = Constraints::new();
+    constraints.pin('e', HandsState::left_thumb());
+    let layout =
+      TenboardUnconstrained::new_random_with_constraints(&constraints).unwrap();
+    constraints.pin('e', HandsState::right_thumb());
+    let result = run(PipelineConfig {
+      corpus: CorpusSource::Text("hello".to_owned()),
+      layout: LayoutSource::Json(serde_json::to_string(&layout).unwrap()),
+      constraints: Some(constraints),
+      ..Default::default()
+    });
+    assert!(matches!(
+      result,
+      Err(PipelineError::Violations(chars)) if chars == ['e']
+    ));
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_run_json_error() {
+    let result = run(PipelineConfig {
+      layout: LayoutSource::Json("{".to_owned()),
+      ..Default::default()
+    });
+    assert!(matches!(result, Err(PipelineError::Json(_))));
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_run_json_metadata() {
+    let mut layout = WithMetadata::new(TenboardUnconstrained::new_random());
+    layout.metadata_mut().set_name("shared");
+    let result = run(PipelineConfig {
+      corpus: CorpusSource::Text("hello".to_owned()),
+      layout: LayoutSource::Json(serde_json::to_string(&layout).unwrap()),
+      ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(result.metadata.name.as_deref(), Some("shared"));
+    assert_eq!(result.metadata.optimizer, None);
+    assert_eq!(result.report.presses, 5);
+  }
+}
@@ -0,0 +1,71 @@
+//! Re-exports the types needed by most scoring scripts, so a single
+//! `use tenboard::prelude::*;` is enough.
+//!
+//! ```
+//! # #[cfg(feature = "std")] {
+//! use tenboard::prelude::*;
+//!
+//! let layout = TenboardUnconstrained::new_random();
+//! let handstates = layout.type_chars("hello world".chars());
+//! let score = FingerAlternation::new().updated(&handstates).score();
+//! assert!(score >= 0.0);
+//! # }
+//! ```
+
+pub use crate::keyboard::{
+  hands::{FingerState, HandsState, ThumbPolicy},
+  metric::{
+    BigChords,
+    ChordEntropy,
+    ChordFrequency,
+    ChordRepeats,
+    FingerAlternation,
+    FingerBalance,
+    FingerUsage,
+    FnMetric,
+    HandAlternation,
+    HandBalance,
+    HandRuns,
+    HandUsage,
+    MaxFingerLoad,
+    Metric,
+    MetricInfo,
+    Normalized,
+    PerPress,
+    Redirects,
+    SameHandStreaks,
+    Sampled,
+    ShiftPresses,
+    Skipgrams,
+    ThumbUsage,
+    TrigramClass,
+    Trigrams,
+  },
+  profile::FingerProfile,
+  shift::{Shift, ShiftPolicy},
+  DynKeyboard,
+  Keyboard,
+  NoSuchChar,
+  SeparatorPolicy,
+};
+#[cfg(feature = "std")]
+pub use crate::keyboard::{
+  annotation::{Annotated, ChordAnnotations, ChordCost},
+  effort::{Effort, EffortModel, FingerEffort},
+  layout::{
+    artsey::Artsey,
+    asetniop::Asetniop,
+    rows::TenboardRows,
+    standard::{Colemak, Dvorak, Qwerty},
+    tenboard::{
+      Tenboard,
+      TenboardHomeRowConstrained,
+      TenboardLeftHand,
+      TenboardModifierConstrained,
+      TenboardOneHand,
+      TenboardRightHand,
+      TenboardThumbConstrained,
+      TenboardUnconstrained,
+    },
+  },
+};
@@ -0,0 +1,180 @@
+//! Contains rendering of Tenboard layouts into SVG diagrams.
+
+use std::{cmp::Reverse, fmt::Write};
+
+use crate::keyboard::{
+  hands::{HandsState, FINGER_NAMES},
+  layout::tenboard::Tenboard,
+  metric::FingerUsage,
+};
+
+const CELL: u32 = 24;
+const LABEL_WIDTH: u32 = 48;
+
+/// Draws a layout as a table with ten finger columns and a row per char,
+/// where fingers pressed by the char's chord are highlighted. Rows are
+/// ordered by chord size.
+///
+/// ```
+/// use tenboard::{
+///   keyboard::layout::tenboard::{Tenboard, TenboardUnconstrained},
+///   render::SvgRenderer,
+/// };
+///
+/// let svg = SvgRenderer::new().render(&TenboardUnconstrained::new_random());
+/// assert!(svg.starts_with("<svg"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SvgRenderer {
+  usage: Option<[u32; 10]>,
+}
+
+impl SvgRenderer {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Colors finger columns by `usage`, the more a finger is used, the
+  /// redder its column is.
+  pub fn set_finger_usage(&mut self, usage: FingerUsage) -> &mut Self {
+    self.usage = Some(usage.values());
+    self
+  }
+
+  /// Returns SVG document with a diagram of `layout`.
+  pub fn render(&self, layout: &dyn Tenboard) -> String {
+    let mut chords: Vec<_> = layout
+      .charset()
+      .chars()
+      .filter_map(|ch| Some((ch, layout.try_type_char(ch).ok()?)))
+      .collect();
+    chords.sort_by_key(|(_, hs)| (hs.count_pressed(), Reverse(sort_key(hs))));
+
+    let width = LABEL_WIDTH + 10 * CELL;
+    let height = (chords.len() as u32 + 1) * CELL;
+    let mut svg = String::new();
+    writeln!(
+      svg,
+      "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" \
+       height=\"{height}\" font-family=\"monospace\" font-size=\"12\">"
+    )
+    .unwrap();
+    writeln!(
+      svg,
+      "<rect width=\"{width}\" height=\"{height}\" fill=\"white\"/>"
+    )
+    .unwrap();
+    let max_usage = self
+      .usage
+      .and_then(|usage| usage.into_iter().max())
+      .filter(|&max| max > 0);
+    for (i, name) in FINGER_NAMES.iter().enumerate() {
+      let x = column_x(i);
+      if let (Some(usage), Some(max)) = (self.usage, max_usage) {
+        let green = 255 - 155 * u64::from(usage[i]) / u64::from(max);
+        writeln!(
+          svg,
+          "<rect x=\"{x}\" y=\"0\" width=\"{CELL}\" height=\"{height}\" \
+           fill=\"rgb(255,{green},{green})\"><title>{name}: {}</title></rect>",
+          usage[i]
+        )
+        .unwrap();
+      }
+      writeln!(
+        svg,
+        "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{name}</text>",
+        x + CELL / 2,
+        CELL * 2 / 3
+      )
+      .unwrap();
+    }
+    for (row, (ch, hs)) in chords.iter().enumerate() {
+      let y = (row as u32 + 1) * CELL;
+      writeln!(
+        svg,
+        "<text x=\"{}\" y=\"{}\">{}</text>",
+        CELL / 4,
+        y + CELL * 2 / 3,
+        escape(*ch)
+      )
+      .unwrap();
+      for (i, fs) in hs.iter().enumerate() {
+        let fill = if fs.is_pressed() { "black" } else { "none" };
+        writeln!(
+          svg,
+          "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{fill}\" \
+           stroke=\"gray\"/>",
+          column_x(i) + CELL / 2,
+          y + CELL / 2,
+          CELL / 3
+        )
+        .unwrap();
+      }
+    }
+    svg.push_str("</svg>\n");
+    svg
+  }
+}
+
+/// Returns x coordinate of the left edge of `finger` column. Hands are
+/// separated by a half of a cell.
+fn column_x(finger: usize) -> u32 {
+  let gap = if finger >= 5 { CELL / 2 } else { 0 };
+  LABEL_WIDTH - CELL / 2 + finger as u32 * CELL + gap
+}
+
+fn sort_key(handstate: &HandsState) -> [u32; 10] {
+  handstate.map(u32::from)
+}
+
+/// Returns `ch` as it's shown in a diagram, escaped for XML.
+fn escape(ch: char) -> String {
+  match ch {
+    '&' => "&amp;".to_owned(),
+    '<' => "&lt;".to_owned(),
+    '>' => "&gt;".to_owned(),
+    '"' => "&quot;".to_owned(),
+    '\'' => "&apos;".to_owned(),
+    '\n' => "⤶".to_owned(),
+    '\t' => "⇆".to_owned(),
+    ' ' => "⎵".to_owned(),
+    _ => ch.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::keyboard::{
+    layout::tenboard::TenboardUnconstrained,
+    metric::Metric,
+    Keyboard,
+    TYPABLE_CHARS,
+  };
+
+  #[test]
+  fn test_render() {
+    let layout = TenboardUnconstrained::new_random();
+    let svg = SvgRenderer::new().render(&layout);
+    assert_eq!(svg.matches("<circle").count(), TYPABLE_CHARS.len() * 10);
+    assert_eq!(svg.matches("fill=\"black\"").count(), {
+      let handstates = layout.type_chars(TYPABLE_CHARS.chars());
+      handstates
+        .iter()
+        .map(HandsState::count_pressed)
+        .sum::<usize>()
+    });
+    assert!(svg.contains(">&lt;</text>"));
+    assert!(!svg.contains("rgb("));
+
+    let usage = FingerUsage::new().updated(&layout.type_chars("hello".chars()));
+    let svg = SvgRenderer::new().set_finger_usage(usage).render(&layout);
+    assert_eq!(svg.matches("rgb(").count(), 10);
+
+    let mut renderer = SvgRenderer::new();
+    renderer.usage = Some([u32::MAX, 0, 0, 0, 0, 0, 0, 0, 0, u32::MAX / 2]);
+    let svg = renderer.render(&layout);
+    assert!(svg.contains("rgb(255,100,100)"));
+    assert!(svg.contains("rgb(255,178,178)"));
+  }
+}
@@ -0,0 +1,213 @@
+//! Contains helpers for testing metrics and other code that consumes
+//! `HandsState` sequences against predictable keyboards.
+
+use alloc::vec::Vec;
+
+use crate::{
+  compat::HashMap,
+  keyboard::{hands::HandsState, Keyboard, NoSuchChar},
+};
+
+/// Keyboard with user defined chords for each char. Chars without a chord
+/// can't be typed with it.
+///
+/// ```
+/// use tenboard::{keyboard::Keyboard, testing::SyntheticKeyboard};
+///
+/// let kb = SyntheticKeyboard::new()
+///   .with_chord('a', [1, 0, 0, 0, 0, 0, 0, 0, 0, 0])
+///   .with_chord('b', [0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+/// assert_eq!(kb.type_chars("ab".chars()), vec![
+///   [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+///   [0, 0, 0, 0, 0, 0, 0, 0, 0, 1].into(),
+/// ]);
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct SyntheticKeyboard {
+  layout: HashMap<char, HandsState>,
+}
+
+impl SyntheticKeyboard {
+  /// Creates a keyboard that can't type anything.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Creates a keyboard where n-th char of `chars` is typed by pressing
+  /// `n % 10`-th finger alone.
+  pub fn new_one_finger(chars: &str) -> Self {
+    chars
+      .chars()
+      .enumerate()
+      .map(|(i, ch)| {
+        let mut fs = [0; 10];
+        fs[i % 10] = 1;
+        (ch, fs.into())
+      })
+      .collect()
+  }
+
+  /// Creates a keyboard where n-th char of `chars` is typed with n-th
+  /// `HandsState` of `handstates`. Extra chars or states are ignored.
+  pub fn new_zipped(
+    chars: &str,
+    handstates: impl IntoIterator<Item = HandsState>,
+  ) -> Self {
+    chars.chars().zip(handstates).collect()
+  }
+
+  /// Binds `ch` to `handstate`, replacing previous binding if there was one.
+  pub fn set_chord(
+    &mut self,
+    ch: char,
+    handstate: impl Into<HandsState>,
+  ) -> &mut Self {
+    self.layout.insert(ch, handstate.into());
+    self
+  }
+
+  /// Consumes `self`, then binds `ch` to `handstate` and returns it.
+  pub fn with_chord(
+    mut self,
+    ch: char,
+    handstate: impl Into<HandsState>,
+  ) -> Self {
+    self.set_chord(ch, handstate);
+    self
+  }
+
+  /// Returns a hand state bound to `ch` or an error if there is none.
+  pub fn try_type_char(&self, ch: char) -> Result<HandsState, NoSuchChar> {
+    self.layout.get(&ch).copied().ok_or(NoSuchChar { ch })
+  }
+}
+
+impl FromIterator<(char, HandsState)> for SyntheticKeyboard {
+  fn from_iter<T>(iter: T) -> Self
+  where
+    T: IntoIterator<Item = (char, HandsState)>,
+  {
+    Self {
+      layout: HashMap::from_iter(iter),
+    }
+  }
+}
+
+impl Keyboard for SyntheticKeyboard {
+  fn try_type_chars(
+    &self,
+    chars: impl Iterator<Item = char>,
+  ) -> Result<Vec<HandsState>, NoSuchChar> {
+    chars.map(|ch| self.try_type_char(ch)).collect()
+  }
+}
+
+/// Keyboard that keeps state between chars typed at once, like a locked
+/// modifier. Uppercase ASCII letters are typed with chords of lowercase
+/// ones, and the lock chord is typed whenever case changes, starting
+/// unlocked for every call. Keyboards that wrap others and type runs of
+/// chars with them must type each run at once to keep the lock.
+///
+/// ```
+/// use tenboard::{
+///   keyboard::{hands::HandsState, Keyboard},
+///   testing::{CapsLockKeyboard, SyntheticKeyboard},
+/// };
+///
+/// let kb = CapsLockKeyboard::new(
+///   SyntheticKeyboard::new_one_finger("ab"),
+///   HandsState::left_thumb(),
+/// );
+/// assert_eq!(kb.type_chars("ABa".chars()).len(), 5);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapsLockKeyboard {
+  keyboard: SyntheticKeyboard,
+  lock: HandsState,
+}
+
+impl CapsLockKeyboard {
+  /// Creates a keyboard typing lowercase chars with `keyboard` and toggling
+  /// case with `lock`.
+  pub fn new(keyboard: SyntheticKeyboard, lock: HandsState) -> Self {
+    Self { keyboard, lock }
+  }
+}
+
+impl Keyboard for CapsLockKeyboard {
+  fn try_type_chars(
+    &self,
+    chars: impl Iterator<Item = char>,
+  ) -> Result<Vec<HandsState>, NoSuchChar> {
+    let mut handstates = Vec::new();
+    let mut locked = false;
+    for ch in chars {
+      if ch.is_ascii_uppercase() != locked {
+        handstates.push(self.lock);
+        locked = !locked;
+      }
+      let hs = self.keyboard.try_type_char(ch.to_ascii_lowercase());
+      handstates.push(hs.map_err(|_| NoSuchChar { ch })?);
+    }
+    Ok(handstates)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_one_finger() {
+    let kb = SyntheticKeyboard::new_one_finger("abcdefghijk");
+    assert_eq!(
+      kb.try_type_char('a'),
+      Ok([1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into())
+    );
+    assert_eq!(
+      kb.try_type_char('j'),
+      Ok([0, 0, 0, 0, 0, 0, 0, 0, 0, 1].into())
+    );
+    assert_eq!(
+      kb.try_type_char('k'),
+      Ok([1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into())
+    );
+    assert_eq!(kb.try_type_char('l'), Err(NoSuchChar { ch: 'l' }));
+  }
+
+  #[test]
+  fn test_zipped_and_set_chord() {
+    let mut kb = SyntheticKeyboard::new_zipped("ab", [
+      HandsState::left_thumb(),
+      HandsState::right_thumb(),
+    ]);
+    assert_eq!(
+      kb.try_type_chars("ba".chars()),
+      Ok(vec![HandsState::right_thumb(), HandsState::left_thumb()])
+    );
+    kb.set_chord('a', [1, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(
+      kb.try_type_char('a'),
+      Ok([1, 1, 0, 0, 0, 0, 0, 0, 0, 0].into())
+    );
+  }
+
+  #[test]
+  fn test_caps_lock() {
+    let kb = CapsLockKeyboard::new(
+      SyntheticKeyboard::new_one_finger("ab"),
+      HandsState::left_thumb(),
+    );
+    assert_eq!(kb.type_chars("aBAb".chars()), vec![
+      [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+      HandsState::left_thumb(),
+      [0, 1, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+      [1, 0, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+      HandsState::left_thumb(),
+      [0, 1, 0, 0, 0, 0, 0, 0, 0, 0].into(),
+    ]);
+    // every call starts unlocked
+    assert_eq!(kb.type_chars("B".chars()).len(), 2);
+    assert_eq!(kb.try_type_chars("aC".chars()), Err(NoSuchChar { ch: 'C' }));
+  }
+}
@@ -0,0 +1,209 @@
+//! Contains the data layer of a typing trainer: the order chords are
+//! learned in and practice sequences made of already learned chords.
+
+use rand::prelude::*;
+
+use crate::keyboard::{
+  effort::{EffortModel, FingerEffort},
+  hands::HandsState,
+  layout::tenboard::Tenboard,
+};
+
+/// Chord of a char with its difficulty.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lesson {
+  pub ch: char,
+  pub handstate: HandsState,
+  pub difficulty: f32,
+}
+
+/// Introduces chords of a layout from the easiest to the hardest one and
+/// builds practice sequences from words typable with introduced chords.
+///
+/// ```
+/// use rand::{rngs::StdRng, SeedableRng};
+/// use tenboard::{
+///   keyboard::layout::tenboard::{Tenboard, TenboardUnconstrained},
+///   trainer::Trainer,
+/// };
+///
+/// let layout = TenboardUnconstrained::new_random();
+/// let mut trainer = Trainer::new(&layout, ["the", "tea", "eat", "ate"]);
+/// while trainer.practice_words().is_empty() {
+///   trainer.introduce_next();
+/// }
+/// let words = trainer.practice(10, &mut StdRng::seed_from_u64(0));
+/// assert_eq!(words.len(), 10);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Trainer {
+  /// Chords ordered by difficulty, the easiest first.
+  lessons: Vec<Lesson>,
+  introduced: usize,
+  /// Words with their difficulties.
+  words: Vec<(String, f32)>,
+}
+
+impl Trainer {
+  /// Creates a trainer where chord difficulty is their [FingerEffort].
+  /// No chords are introduced yet.
+  pub fn new<S: Into<String>>(
+    layout: &dyn Tenboard,
+    words: impl IntoIterator<Item = S>,
+  ) -> Self {
+    Self::new_with_model(layout, words, &FingerEffort::new())
+  }
+
+  /// Same as [Trainer::new], but chord difficulty is its cost in `model`.
+  pub fn new_with_model<S: Into<String>>(
+    layout: &dyn Tenboard,
+    words: impl IntoIterator<Item = S>,
+    model: &impl EffortModel,
+  ) -> Self {
+    let mut lessons: Vec<_> = layout
+      .charset()
+      .chars()
+      .filter_map(|ch| {
+        let handstate = layout.try_type_char(ch).ok()?;
+        let difficulty = model.cost(&handstate);
+        Some(Lesson {
+          ch,
+          handstate,
+          difficulty,
+        })
+      })
+      .collect();
+    lessons.sort_by(|a, b| {
+      a.difficulty.total_cmp(&b.difficulty).then(a.ch.cmp(&b.ch))
+    });
+    let words = words
+      .into_iter()
+      .map(Into::into)
+      .filter_map(|word: String| {
+        let difficulty = word
+          .chars()
+          .map(|ch| lessons.iter().find(|l| l.ch == ch).map(|l| l.difficulty))
+          .sum::<Option<f32>>()?;
+        Some((word, difficulty))
+      })
+      .collect();
+    Self {
+      lessons,
+      introduced: 0,
+      words,
+    }
+  }
+
+  /// Returns all chords in order they are introduced.
+  pub fn lessons(&self) -> &[Lesson] {
+    &self.lessons
+  }
+
+  /// Returns introduced chords.
+  pub fn introduced(&self) -> &[Lesson] {
+    &self.lessons[..self.introduced]
+  }
+
+  pub fn is_introduced(&self, ch: char) -> bool {
+    self.introduced().iter().any(|l| l.ch == ch)
+  }
+
+  /// Returns the chord that will be introduced next.
+  pub fn next(&self) -> Option<&Lesson> {
+    self.lessons.get(self.introduced)
+  }
+
+  /// Introduces the next chord and returns it. Returns `None` if all chords
+  /// are introduced.
+  pub fn introduce_next(&mut self) -> Option<&Lesson> {
+    let lesson = self.lessons.get(self.introduced)?;
+    self.introduced += 1;
+    Some(lesson)
+  }
+
+  /// Introduces chords until `ch` is introduced. Returns `false` if `ch`
+  /// isn't in the layout.
+  pub fn introduce_until(&mut self, ch: char) -> bool {
+    match self.lessons.iter().position(|l| l.ch == ch) {
+      Some(i) => {
+        self.introduced = self.introduced.max(i + 1);
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Returns numbers of introduced and all chords.
+  pub fn progress(&self) -> (usize, usize) {
+    (self.introduced, self.lessons.len())
+  }
+
+  /// Returns words made of introduced chords only, the easiest first.
+  /// Words with chars missing in the layout are never returned.
+  pub fn practice_words(&self) -> Vec<&str> {
+    let mut words: Vec<_> = self
+      .words
+      .iter()
+      .filter(|(word, _)| word.chars().all(|ch| self.is_introduced(ch)))
+      .collect();
+    words.sort_by(|(w1, d1), (w2, d2)| d1.total_cmp(d2).then(w1.cmp(w2)));
+    words.into_iter().map(|(word, _)| word.as_str()).collect()
+  }
+
+  /// Randomly picks `count` practice words. Words with the latest
+  /// introduced chord are picked three times as often as others. Returns
+  /// nothing if there are no practice words yet.
+  pub fn practice(&self, count: usize, rng: &mut impl Rng) -> Vec<&str> {
+    let words = self.practice_words();
+    let latest = self.introduced.checked_sub(1).map(|i| self.lessons[i].ch);
+    let weight = |word: &&str| match latest {
+      Some(ch) if word.contains(ch) => 3,
+      _ => 1,
+    };
+    (0..count)
+      .map_while(|_| words.choose_weighted(rng, weight).ok().copied())
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::keyboard::{
+    layout::tenboard::TenboardUnconstrained,
+    TYPABLE_CHARS,
+  };
+
+  fn layout() -> TenboardUnconstrained {
+    let mut chords = HandsState::iterate_one_two_key_all_states();
+    "eatnhs"
+      .chars()
+      .chain(TYPABLE_CHARS.chars().filter(|ch| !"eatnhs".contains(*ch)))
+      .map(|ch| (ch, chords.next().unwrap()))
+      .collect()
+  }
+
+  #[test]
+  fn test_introduction() {
+    let mut trainer = Trainer::new(&layout(), ["tea", "the", "eat", "été"]);
+    assert_eq!(trainer.progress(), (0, 97));
+    assert_eq!(trainer.next().map(|l| l.ch), Some('h'));
+    assert!(trainer.practice_words().is_empty());
+    assert!(trainer.practice(5, &mut thread_rng()).is_empty());
+    assert!(trainer.introduce_until('c'));
+    assert!(!trainer.introduce_until('é'));
+    assert_eq!(trainer.progress(), (7, 97));
+    assert!(trainer.practice_words().is_empty());
+    assert_eq!(trainer.introduce_next().map(|l| l.ch), Some('e'));
+    assert_eq!(trainer.practice_words(), vec!["the", "eat", "tea"]);
+    let practice = trainer.practice(100, &mut thread_rng());
+    assert_eq!(practice.len(), 100);
+    assert!(practice.iter().all(|w| ["the", "eat", "tea"].contains(w)));
+    while trainer.introduce_next().is_some() {}
+    assert_eq!(trainer.progress(), (97, 97));
+    assert!(trainer
+      .lessons()
+      .windows(2)
+      .all(|w| w[0].difficulty <= w[1].difficulty));
+  }
+}
@@ -0,0 +1,104 @@
+//! Contains JavaScript bindings for scoring layouts in a browser. Layouts
+//! and corpora are passed as strings, so nothing here touches the file
+//! system.
+
+use std::fmt::Display;
+
+use rand::{rngs::StdRng, SeedableRng};
+use wasm_bindgen::prelude::*;
+
+use crate::keyboard::{
+  layout::tenboard::{Tenboard, TenboardUnconstrained},
+  metric::Metric,
+  objective::{Objective, Preset},
+  report::Report,
+  Keyboard,
+};
+
+/// [TenboardUnconstrained] layout for JavaScript.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct Layout {
+  layout: TenboardUnconstrained,
+}
+
+#[wasm_bindgen]
+impl Layout {
+  /// Parses a layout from JSON.
+  #[wasm_bindgen(js_name = fromJson)]
+  pub fn from_json(json: &str) -> Result<Layout, JsError> {
+    let layout = serde_json::from_str(json).map_err(js_error)?;
+    Ok(Self { layout })
+  }
+
+  /// Creates a random layout. The same `seed` gives the same layout.
+  pub fn random(seed: u64) -> Layout {
+    let mut rng = StdRng::seed_from_u64(seed);
+    Self {
+      layout: TenboardUnconstrained::new_random_with_rng(&mut rng),
+    }
+  }
+
+  #[wasm_bindgen(js_name = toJson)]
+  pub fn to_json(&self) -> String {
+    serde_json::to_string(&self.layout).expect("layout is serializable")
+  }
+
+  /// Returns the chord of `ch` as ten 0 or 1 finger states.
+  pub fn chord(&self, ch: char) -> Option<Vec<u8>> {
+    let hs = self.layout.try_type_char(ch).ok()?;
+    Some(hs.iter().map(|fs| u32::from(*fs) as u8).collect())
+  }
+
+  /// Types `text` and returns its chords as ten finger states per chord.
+  #[wasm_bindgen(js_name = typeText)]
+  pub fn type_text(&self, text: &str) -> Result<Vec<u8>, JsError> {
+    let handstates = self.layout.try_type_text(text).map_err(js_error)?;
+    Ok(
+      handstates
+        .iter()
+        .flat_map(|hs| hs.map(|fs| u32::from(fs) as u8))
+        .collect(),
+    )
+  }
+
+  /// Returns score of the objective `preset` for typing `text`.
+  pub fn score(&self, text: &str, preset: &str) -> Result<f32, JsError> {
+    let preset: Preset = preset.parse().map_err(js_error)?;
+    let handstates = self.layout.try_type_text(text).map_err(js_error)?;
+    Ok(Objective::from_preset(preset).updated(&handstates).score())
+  }
+
+  /// Returns JSON [Report] of typing `text`.
+  pub fn report(&self, text: &str) -> Result<String, JsError> {
+    let handstates = self.layout.try_type_text(text).map_err(js_error)?;
+    serde_json::to_string(&Report::new(&handstates)).map_err(js_error)
+  }
+
+  /// Returns human readable view of the layout.
+  pub fn render(&self) -> String {
+    self.layout.to_string()
+  }
+}
+
+fn js_error(e: impl Display) -> JsError {
+  JsError::new(&e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_layout() {
+    let layout = Layout::random(7);
+    let same = Layout::from_json(&layout.to_json()).unwrap();
+    assert_eq!(layout.chord('a'), same.chord('a'));
+    assert_eq!(layout.chord('é'), None);
+    let chords = layout.type_text("abc").unwrap();
+    assert_eq!(chords.len(), 30);
+    assert_eq!(&chords[..10], &layout.chord('a').unwrap()[..]);
+    assert!(layout.score("abc", "speed").is_ok());
+    assert!(layout.report("abc").unwrap().starts_with("{\"presses\":3,"));
+  }
+}